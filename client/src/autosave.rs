@@ -0,0 +1,80 @@
+use std::cell::RefCell;
+
+use gloo_storage::Storage;
+use shared::game::{self, export};
+
+use crate::settings::AutoSaveSgf;
+use crate::share;
+use crate::state::GameView;
+
+/// Local storage key of the saved record. Only the latest game is kept.
+const KEY: &str = "autosaved_sgf";
+
+/// Positions of the game being recorded, by move number.
+struct Record {
+    room_id: u32,
+    size: (u8, u8),
+    positions: Vec<(u32, Vec<game::Color>)>,
+}
+
+thread_local! {
+    static RECORD: RefCell<Option<Record>> = RefCell::new(None);
+}
+
+/// Adds the position of `view` to the record of the room and saves the record as SGF.
+/// Only games the user holds a seat in are recorded, with `AutoSaveSgf` on. Moves made
+/// while the client wasn't watching are missing from the record, the position after
+/// them still has every stone. Games opened from a review link aren't played, so they
+/// would only replace the saved game with an empty board.
+pub(crate) fn record(room_id: u32, user_id: u64, view: &GameView) {
+    if !AutoSaveSgf::get().0 || !view.seats.iter().any(|s| s.player == Some(user_id)) {
+        return;
+    }
+    if room_id == share::SHARED_ROOM_ID {
+        return;
+    }
+
+    let sgf = RECORD.with(|record| {
+        let mut record = record.borrow_mut();
+        // Expanding boards change size, their earlier positions wouldn't line up anymore.
+        let same_game = matches!(
+            &*record,
+            Some(r) if r.room_id == room_id && r.size == view.size
+        );
+        if !same_game {
+            *record = Some(Record {
+                room_id,
+                size: view.size,
+                positions: Vec::new(),
+            });
+        }
+        let recorded = &mut record.as_mut().unwrap().positions;
+
+        // Undo takes moves back, so later positions go.
+        recorded.retain(|(move_number, _)| *move_number < view.move_number);
+        recorded.push((view.move_number, view.board.clone()));
+
+        let positions = recorded
+            .iter()
+            .map(|(_, board)| board.clone())
+            .collect::<Vec<_>>();
+        let size = (view.size.0 as u32, view.size.1 as u32);
+        export::positions_sgf(size, &positions)
+    });
+
+    if let Err(e) = gloo_storage::LocalStorage::set(KEY, sgf) {
+        log::warn!("Saving the game record failed: {:?}", e);
+    }
+}
+
+/// The record saved by an earlier visit, if any.
+pub(crate) fn saved() -> Option<String> {
+    gloo_storage::LocalStorage::get::<String>(KEY).ok()
+}
+
+/// Deletes the saved record unless a newer game replaced `sgf` in the meantime.
+pub(crate) fn forget(sgf: &str) {
+    if saved().as_deref() == Some(sgf) {
+        gloo_storage::LocalStorage::delete(KEY);
+    }
+}
@@ -1,9 +1,15 @@
-use shared::game::{GameStateView, Visibility};
+use shared::game::{
+    rotate_point, unrotate_point, Annotation, Color, GameStateView, LabelStyle, Visibility,
+};
+use shared::states::scoring::{self, influence_map, ScoringState, INFLUENCE_RANGE};
 use web_sys::wasm_bindgen::JsCast;
 use web_sys::DomRect;
-use web_sys::{wasm_bindgen::JsValue, HtmlCanvasElement};
+use web_sys::{
+    wasm_bindgen::JsValue, CanvasRenderingContext2d, HtmlAnchorElement, HtmlCanvasElement,
+};
 
-use crate::palette::Palette;
+use crate::palette::{Palette, PaletteOption};
+use crate::settings;
 use crate::state::{self, GameHistory};
 
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -23,6 +29,15 @@ pub enum Input {
     None,
 }
 
+/// Teacher drawings stand out from every palette.
+const ANNOTATION_COLOR: &str = "#2f80ed";
+
+/// Renders a game onto a canvas.
+///
+/// Every point of the board owns a square cell of the canvas and stones, markers and
+/// pointer input all use the center of the cell. Only the lines depend on
+/// `stones_in_cells`. Normally they run through the cell centers and meet at the edge
+/// intersections like on a real board. Otherwise they outline the cells.
 pub(crate) struct Board {
     pub(crate) palette: Palette,
     pub(crate) toroidal_edge_size: i32,
@@ -31,6 +46,29 @@ pub(crate) struct Board {
     pub(crate) input: Input,
     pub(crate) show_hidden: bool,
     pub(crate) edge_size: f64,
+    /// Pointer input further than this from an intersection is ignored. Measured in cells.
+    pub(crate) snap_radius: f64,
+    /// Draw the lines around the points instead of through them, see
+    /// `settings::StonePlacement`.
+    pub(crate) stones_in_cells: bool,
+    /// Draw a translucent stone under the pointer. Clicks place stones either way.
+    pub(crate) show_ghost: bool,
+    /// Draw a glyph on stones that tells the teams apart without relying on color.
+    pub(crate) stone_marks: bool,
+    /// Shade empty points by the nearest team during play, see `influence_map`.
+    pub(crate) show_influence: bool,
+    /// While scoring, draw the board with dead stones already taken off. Only the drawing
+    /// leaves them out, the view keeps them for when play is resumed.
+    pub(crate) remove_dead: bool,
+    /// Quarter turns clockwise the board is drawn with, see `seat_rotation`.
+    pub(crate) rotation: u8,
+    /// Draw the row and column labels around the board.
+    pub(crate) show_coordinates: bool,
+    pub(crate) label_style: LabelStyle,
+    /// Drawn by the teacher on top of everything else.
+    pub(crate) annotations: Vec<Annotation>,
+    /// Count of the live position drawn like scoring, see `hypothetical_count`.
+    pub(crate) hypothetical: Option<ScoringState>,
 }
 
 impl Input {
@@ -67,16 +105,34 @@ impl Input {
         p.0 -= edge_size;
         p.1 -= edge_size;
         let size = (game.size.0 as i32 + 2 * board.toroidal_edge_size) as f64;
-        let pos = match game.mods.pixel && !is_scoring {
-            true => (
-                (p.0 / (width / size) + 0.5) as i32,
-                (p.1 / (height / size) + 0.5) as i32,
-            ),
-            false => (
-                (p.0 / (width / size)) as i32,
-                (p.1 / (height / size)) as i32,
-            ),
+        // In pixel mode the blobs are centered on the cell corners.
+        let offset = match game.mods.pixel && !is_scoring {
+            true => 0.5,
+            false => 0.0,
         };
+        let cell = (
+            p.0 / (width / size) + offset,
+            p.1 / (height / size) + offset,
+        );
+        let pos = (cell.0 as i32, cell.1 as i32);
+
+        // Intersections are at cell centers, see `Board`.
+        // Ignore input too far from the intersection to avoid misclicks on touch screens.
+        // Stones drawn in cells take the whole cell.
+        let dx = cell.0 - pos.0 as f64 - 0.5;
+        let dy = cell.1 - pos.1 as f64 - 0.5;
+        if !board.stones_in_cells && (dx * dx + dy * dy).sqrt() > board.snap_radius {
+            return Input::None;
+        }
+
+        let rotation = board.view_rotation(game);
+        if rotation != 0 {
+            if pos.0 < 0 || pos.1 < 0 || pos.0 >= size as i32 || pos.1 >= size as i32 {
+                return Input::None;
+            }
+            let pos = unrotate_point((pos.0 as u32, pos.1 as u32), size as u32, rotation);
+            return Input::Place(pos, clicked);
+        }
 
         Input::Place((pos.0 as u32, pos.1 as u32), clicked)
     }
@@ -90,9 +146,45 @@ impl Input {
 }
 
 impl Board {
+    /// A board that only displays positions, without any pointer input.
+    pub(crate) fn display(edge_size: f64) -> Board {
+        Board {
+            palette: PaletteOption::get().to_palette(),
+            toroidal_edge_size: 0,
+            board_displacement: (0, 0),
+            selection_pos: None,
+            input: Input::None,
+            show_hidden: false,
+            edge_size,
+            snap_radius: 1.0,
+            stones_in_cells: settings::StonePlacement::get() == settings::StonePlacement::Cells,
+            show_ghost: false,
+            stone_marks: settings::StoneMarks::get().0,
+            show_influence: false,
+            remove_dead: false,
+            rotation: 0,
+            show_coordinates: true,
+            label_style: settings::CoordinateLabels::get().0,
+            annotations: Vec::new(),
+            hypothetical: None,
+        }
+    }
+
+    /// The rotation actually used. Boards that wrap, pixel blobs and non-square boards
+    /// are always drawn upright.
+    fn view_rotation(&self, game: &state::GameView) -> u8 {
+        let square = game.size.0 == game.size.1;
+        if !square || game.mods.toroidal.is_some() || game.mods.pixel {
+            return 0;
+        }
+        self.rotation % 4
+    }
+
+    /// Draws the board to `context`, which is assumed to be `width` x `height` pixels.
     pub(crate) fn render_gl(
         &self,
-        canvas: &HtmlCanvasElement,
+        context: &CanvasRenderingContext2d,
+        (width, height): (f64, f64),
         game: &state::GameView,
         history: Option<&GameHistory>,
     ) -> Result<(), JsValue> {
@@ -110,19 +202,27 @@ impl Board {
 
         // Setup //////////////////////////////////////////////////////////////
 
-        let context = canvas
-            .get_context("2d")
-            .unwrap()
-            .unwrap()
-            .dyn_into::<web_sys::CanvasRenderingContext2d>()
-            .unwrap();
-
         // let dpi = gloo_utils::window().device_pixel_ratio();
         // context.scale(dpi, dpi)?;
 
-        let board = match history {
-            Some(h) => &h.board,
-            None => &game.board,
+        let removing_dead = self.remove_dead
+            && history.is_none()
+            && matches!(game.state, GameStateView::Scoring(_));
+        let without_dead;
+        let board = match (history, &game.state) {
+            (Some(h), _) => &h.board,
+            (None, GameStateView::Scoring(scoring)) if removing_dead => {
+                let width = game.size.0 as usize;
+                let mut board = game.board.clone();
+                for group in scoring.groups.iter().filter(|g| !g.alive) {
+                    for &(x, y) in &group.points {
+                        board[y as usize * width + x as usize] = Color::empty();
+                    }
+                }
+                without_dead = board;
+                &without_dead
+            }
+            (None, _) => &game.board,
         };
         let board_visibility = match history {
             Some(h) => &h.board_visibility,
@@ -132,10 +232,9 @@ impl Board {
         // TODO: actually handle non-square boards
         let view_board_size = game.size.0 as usize + 2 * self.toroidal_edge_size as usize;
         let board_size = game.size.0 as usize;
-        let width = canvas.width() as f64;
-        let height = canvas.height() as f64;
-        let size = (canvas.width() as f64 - 2.0 * edge_size) / view_board_size as f64;
-        let turn = game.seats[game.turn as usize].team.0;
+        let size = (width - 2.0 * edge_size) / view_board_size as f64;
+        let rotation = self.view_rotation(game);
+        let ghost_colors = ghost_colors(palette, game);
 
         let draw_stone =
             |(x, y): (i32, i32), diameter: f64, fill: bool, stroke: bool| -> Result<(), JsValue> {
@@ -158,10 +257,15 @@ impl Board {
 
         // Clear canvas ///////////////////////////////////////////////////////
 
-        context.clear_rect(0.0, 0.0, canvas.width().into(), canvas.height().into());
+        context.clear_rect(0.0, 0.0, width, height);
 
         context.set_fill_style(&JsValue::from_str(palette.background));
-        context.fill_rect(0.0, 0.0, canvas.width().into(), canvas.height().into());
+        context.fill_rect(0.0, 0.0, width, height);
+
+        // Nothing to draw on until the view has a board of its size.
+        if board_size == 0 || board.len() != board_size * game.size.1 as usize {
+            return Ok(());
+        }
 
         // Toroidal edge scroll boxes /////////////////////////////////////////
 
@@ -185,47 +289,102 @@ impl Board {
             }
         }
 
+        // Influence //////////////////////////////////////////////////////////
+
+        // Drawn under the lines and stones, faint enough to not look like territory.
+        let is_play = matches!(game.state, GameStateView::Play(_));
+        if self.show_influence && is_play && history.is_none() {
+            let position = shared::game::Board {
+                width: game.size.0 as u32,
+                height: game.size.1 as u32,
+                toroidal: game.mods.toroidal.is_some(),
+                points: board.clone(),
+            };
+            let max_strength = (INFLUENCE_RANGE + 1) as f64;
+            for (idx, &(team, strength)) in influence_map(&position).iter().enumerate() {
+                if team.is_empty() {
+                    continue;
+                }
+                let x = idx % board_size;
+                let y = idx / board_size;
+                context.set_fill_style(&JsValue::from_str(stone_colors[team.as_usize() - 1]));
+                context.set_global_alpha(0.35 * strength as f64 / max_strength);
+                self.board_to_view_coord(game, (x as i32, y as i32), |(px, py)| {
+                    context.fill_rect(
+                        edge_size + px as f64 * size,
+                        edge_size + py as f64 * size,
+                        size,
+                        size,
+                    );
+                });
+            }
+            context.set_global_alpha(1.0);
+        }
+
+        // Final ownership ////////////////////////////////////////////////////
+
+        // Every point by the team it was scored for, living stones included.
+        if let (GameStateView::Done(scoring), None) = (&game.state, history) {
+            context.set_global_alpha(0.3);
+            for (idx, &owner) in scoring.points.points.iter().enumerate() {
+                if owner.is_empty() {
+                    continue;
+                }
+                let x = idx % board_size;
+                let y = idx / board_size;
+                context.set_fill_style(&JsValue::from_str(stone_colors[owner.as_usize() - 1]));
+                self.board_to_view_coord(game, (x as i32, y as i32), |(px, py)| {
+                    context.fill_rect(
+                        edge_size + px as f64 * size,
+                        edge_size + py as f64 * size,
+                        size,
+                        size,
+                    );
+                });
+            }
+            context.set_global_alpha(1.0);
+        }
+
         // Board lines ////////////////////////////////////////////////////////
 
+        // The outermost lines go through the centers of the edge cells, so the lines
+        // meet at the edge intersections. Toroidal boards continue past them instead.
+        // Cells are outlined by lines one half cell further out.
         context.set_line_width(1.0);
         context.set_stroke_style(&JsValue::from_str("#000000"));
         context.set_fill_style(&JsValue::from_str("#000000"));
 
-        let line_edge_size = if game.mods.toroidal.is_some() {
+        let (line_offset, line_count) = if self.stones_in_cells {
+            (0.0, view_board_size + 1)
+        } else {
+            (0.5, view_board_size)
+        };
+        let line_edge_size = if game.mods.toroidal.is_some() && !self.stones_in_cells {
             size / 2.0
         } else {
             0.0
         };
+        let line_start = edge_size - line_edge_size + size * line_offset;
+        let line_end = edge_size + line_edge_size + size * (view_board_size as f64 - line_offset);
 
-        for y in 0..view_board_size {
+        for y in 0..line_count {
             context.begin_path();
-            context.move_to(
-                edge_size - line_edge_size + size * 0.5,
-                edge_size + (y as f64 + 0.5) * size,
-            );
-            context.line_to(
-                edge_size + line_edge_size + size * (view_board_size as f64 - 0.5),
-                edge_size + (y as f64 + 0.5) * size,
-            );
+            context.move_to(line_start, edge_size + (y as f64 + line_offset) * size);
+            context.line_to(line_end, edge_size + (y as f64 + line_offset) * size);
             context.stroke();
         }
 
-        for x in 0..view_board_size {
+        for x in 0..line_count {
             context.begin_path();
-            context.move_to(
-                edge_size + (x as f64 + 0.5) * size,
-                edge_size - line_edge_size + size * 0.5,
-            );
-            context.line_to(
-                edge_size + (x as f64 + 0.5) * size,
-                edge_size + line_edge_size + size * (view_board_size as f64 - 0.5),
-            );
+            context.move_to(edge_size + (x as f64 + line_offset) * size, line_start);
+            context.line_to(edge_size + (x as f64 + line_offset) * size, line_end);
             context.stroke();
         }
 
         // Starpoints /////////////////////////////////////////////////////////
 
-        if game.mods.toroidal.is_none() {
+        // Star points mark intersections, which cells don't have.
+        if game.mods.toroidal.is_none() && !self.stones_in_cells {
             let points: &[(i32, i32)] = match game.size.0 {
                 19 => &[
                     (3, 3),
@@ -256,59 +415,85 @@ impl Board {
             for &(x, y) in points {
                 let x = (x - self.board_displacement.0).rem_euclid(game.size.0 as i32);
                 let y = (y - self.board_displacement.1).rem_euclid(game.size.1 as i32);
+                let (x, y) = rotate_point((x as u32, y as u32), board_size as u32, rotation);
                 draw_stone((x as _, y as _), size / 4., true, false)?;
             }
         }
 
         // Coordinates ////////////////////////////////////////////////////////
 
-        let from_edge = edge_size - 20.0;
+        if self.show_coordinates {
+            let from_edge = edge_size - 20.0;
 
-        context.set_font("bold 1.5em serif");
+            context.set_font("bold 1.5em serif");
 
-        context.set_text_align("center");
-        context.set_text_baseline("middle");
+            context.set_text_align("center");
+            context.set_text_baseline("middle");
 
-        for (i, y) in (0..game.size.1)
-            .cycle()
-            .skip(
-                self.board_displacement.1 as usize + board_size - self.toroidal_edge_size as usize,
-            )
-            .take(view_board_size)
-            .enumerate()
-        {
-            let text = (game.size.1 - y).to_string();
-            let i = i as f64 + 0.5;
-            context.fill_text(&text, from_edge, edge_size + i * size + 2.0)?;
-            context.fill_text(&text, width - from_edge, edge_size + i * size + 2.0)?;
-        }
+            if rotation != 0 {
+                // Every view row and column shows a single board row or column, which one
+                // depends on the rotation.
+                let n = board_size as u32;
+                let label = |point: (u32, u32), column: bool| match column {
+                    true => self.label_style.column(point.0),
+                    false => (n - point.1).to_string(),
+                };
+                let columns_across = rotation % 2 == 0;
+                for i in 0..n {
+                    let text = label(unrotate_point((0, i), n, rotation), !columns_across);
+                    let at = i as f64 + 0.5;
+                    context.fill_text(&text, from_edge, edge_size + at * size + 2.0)?;
+                    context.fill_text(&text, width - from_edge, edge_size + at * size + 2.0)?;
+                }
+                context.set_text_baseline("baseline");
+                for i in 0..n {
+                    let text = label(unrotate_point((i, 0), n, rotation), columns_across);
+                    let at = i as f64 + 0.5;
+                    context.fill_text(&text, edge_size + at * size, from_edge)?;
+                    context.fill_text(&text, edge_size + at * size, height - from_edge)?;
+                }
+            } else {
+                for (i, y) in (0..game.size.1)
+                    .cycle()
+                    .skip(
+                        self.board_displacement.1 as usize + board_size
+                            - self.toroidal_edge_size as usize,
+                    )
+                    .take(view_board_size)
+                    .enumerate()
+                {
+                    let text = (game.size.1 - y).to_string();
+                    let i = i as f64 + 0.5;
+                    context.fill_text(&text, from_edge, edge_size + i * size + 2.0)?;
+                    context.fill_text(&text, width - from_edge, edge_size + i * size + 2.0)?;
+                }
 
-        context.set_text_align("center");
-        context.set_text_baseline("baseline");
-
-        for (i, x) in (0..game.size.0)
-            .cycle()
-            .skip(
-                self.board_displacement.0 as usize + board_size - self.toroidal_edge_size as usize,
-            )
-            .take(view_board_size)
-            .enumerate()
-        {
-            let letter = ('A'..'I')
-                .chain('J'..='Z')
-                .nth(x as usize)
-                .unwrap()
-                .to_string();
-            let i = i as f64 + 0.5;
-            context.fill_text(&letter, edge_size + i * size, from_edge)?;
-            context.fill_text(&letter, edge_size + i * size, height - from_edge)?;
+                context.set_text_align("center");
+                context.set_text_baseline("baseline");
+
+                for (i, x) in (0..game.size.0)
+                    .cycle()
+                    .skip(
+                        self.board_displacement.0 as usize + board_size
+                            - self.toroidal_edge_size as usize,
+                    )
+                    .take(view_board_size)
+                    .enumerate()
+                {
+                    let text = self.label_style.column(x as u32);
+                    let i = i as f64 + 0.5;
+                    context.fill_text(&text, edge_size + i * size, from_edge)?;
+                    context.fill_text(&text, edge_size + i * size, height - from_edge)?;
+                }
+            }
         }
 
         // Mouse hover display ////////////////////////////////////////////////
 
         let is_scoring = matches!(game.state, GameStateView::Scoring(_));
-        if !is_scoring {
-            if let Some(selection_pos) = self.selection_pos {
+        if !is_scoring && self.show_ghost {
+            if let (Some(selection_pos), Some((fill, stroke))) = (self.selection_pos, ghost_colors)
+            {
                 let mut p = self.view_to_board_coord(game, selection_pos);
                 if game.mods.pixel {
                     p.0 -= 1;
@@ -326,11 +511,8 @@ impl Board {
                     ],
                     false => vec![p],
                 };
-                let color = turn;
-                // Teams start from 1
-                context.set_fill_style(&JsValue::from_str(shadow_stone_colors[color as usize - 1]));
-                context
-                    .set_stroke_style(&JsValue::from_str(shadow_border_colors[color as usize - 1]));
+                context.set_fill_style(&JsValue::from_str(fill));
+                context.set_stroke_style(&JsValue::from_str(stroke));
 
                 for p in points {
                     self.board_to_view_coord(game, p, |p| {
@@ -342,6 +524,15 @@ impl Board {
 
         // Board stones ///////////////////////////////////////////////////////
 
+        // Stones placed in free placement stay hidden from the others until everyone is
+        // ready, so they're drawn like ghosts until the placement resolves.
+        let pending = history.is_none() && matches!(game.state, GameStateView::FreePlacement(_));
+        let (fill_colors, stroke_colors) = if pending {
+            (shadow_stone_colors, shadow_border_colors)
+        } else {
+            (stone_colors, border_colors)
+        };
+
         for (idx, &color) in board.iter().enumerate() {
             let x = idx % board_size;
             let y = idx / board_size;
@@ -355,11 +546,19 @@ impl Board {
                 continue;
             }
 
-            context.set_fill_style(&JsValue::from_str(stone_colors[color.0 as usize - 1]));
-            context.set_stroke_style(&JsValue::from_str(border_colors[color.0 as usize - 1]));
+            context.set_fill_style(&JsValue::from_str(fill_colors[color.0 as usize - 1]));
+            context.set_stroke_style(&JsValue::from_str(stroke_colors[color.0 as usize - 1]));
 
             self.board_to_view_coord(game, (x as i32, y as i32), |(px, py)| {
                 draw_stone((px as _, py as _), size, true, true).unwrap();
+                if self.stone_marks {
+                    let center = (
+                        edge_size + (px as f64 + 0.5) * size,
+                        edge_size + (py as f64 + 0.5) * size,
+                    );
+                    let mark_color = dead_mark_color[color.0 as usize - 1];
+                    draw_team_mark(context, center, size / 8., color.0, mark_color);
+                }
             });
         }
 
@@ -415,13 +614,94 @@ impl Board {
             }
         }
 
+        // Captured stones ////////////////////////////////////////////////////
+
+        // Faint outlines where the last move took stones off, so captures don't go unseen.
+        let captured = match (&game.state, history) {
+            (_, Some(h)) => &h.captured[..],
+            (GameStateView::Play(state), _) => &state.last_captured[..],
+            _ => &[],
+        };
+
+        context.set_stroke_style(&JsValue::from_str("#888888"));
+        context.set_line_width(1.5);
+        context.set_global_alpha(0.6);
+        for &(x, y) in captured {
+            self.board_to_view_coord(game, (x as i32, y as i32), |(px, py)| {
+                draw_stone((px as _, py as _), size * 0.8, false, true).unwrap();
+            });
+        }
+        context.set_global_alpha(1.0);
+
+        // Dame ///////////////////////////////////////////////////////////////
+
+        if history.is_none() {
+            context.set_fill_style(&JsValue::from_str("#e0658f"));
+
+            for &(x, y) in &game.dame {
+                self.board_to_view_coord(game, (x as i32, y as i32), |(px, py)| {
+                    let side = size / 5.;
+                    context.fill_rect(
+                        edge_size + (px as f64 + 0.5) * size - side / 2.,
+                        edge_size + (py as f64 + 0.5) * size - side / 2.,
+                        side,
+                        side,
+                    );
+                });
+            }
+        }
+
+        // Restricted points //////////////////////////////////////////////////
+
+        if history.is_none() {
+            context.set_fill_style(&JsValue::from_str("#00000022"));
+
+            let width = game.size.0 as usize;
+            for &(x, y) in &game.restricted_points {
+                if !board[y as usize * width + x as usize].is_empty() {
+                    continue;
+                }
+                self.board_to_view_coord(game, (x as i32, y as i32), |(px, py)| {
+                    context.fill_rect(
+                        edge_size + px as f64 * size,
+                        edge_size + py as f64 * size,
+                        size,
+                        size,
+                    );
+                });
+            }
+        }
+
+        // Ko points //////////////////////////////////////////////////////////
+
+        if history.is_none() {
+            context.set_stroke_style(&JsValue::from_str("#00000088"));
+            context.set_line_width(2.0);
+
+            for &(x, y) in &game.ko_points {
+                self.board_to_view_coord(game, (x as i32, y as i32), |(px, py)| {
+                    let cx = edge_size + (px as f64 + 0.5) * size;
+                    let cy = edge_size + (py as f64 + 0.5) * size;
+                    let r = size / 5.;
+                    context.begin_path();
+                    context.move_to(cx - r, cy - r);
+                    context.line_to(cx + r, cy + r);
+                    context.move_to(cx + r, cy - r);
+                    context.line_to(cx - r, cy + r);
+                    context.stroke();
+                });
+            }
+        }
+
         // States /////////////////////////////////////////////////////////////
 
         if history.is_none() {
-            match &game.state {
-                GameStateView::Scoring(scoring) | GameStateView::Done(scoring) => {
+            match (&game.state, &self.hypothetical) {
+                (GameStateView::Scoring(scoring), _)
+                | (GameStateView::Done(scoring), _)
+                | (GameStateView::Play(_), Some(scoring)) => {
                     for group in &scoring.groups {
-                        if group.alive {
+                        if group.alive || removing_dead {
                             continue;
                         }
 
@@ -491,6 +771,75 @@ impl Board {
             }
         }
 
+        // Annotations ////////////////////////////////////////////////////////
+
+        context.set_stroke_style(&JsValue::from_str(ANNOTATION_COLOR));
+        context.set_fill_style(&JsValue::from_str(ANNOTATION_COLOR));
+        context.set_line_width(3.0);
+        context.set_font(&format!("bold {}px sans-serif", (size * 0.6) as u32));
+        context.set_text_align("center");
+        context.set_text_baseline("middle");
+
+        // Center of a board point on the canvas, if it's drawn at all.
+        let center = |p: shared::game::Point| {
+            let mut center = None;
+            self.board_to_view_coord(game, (p.0 as i32, p.1 as i32), |(px, py)| {
+                center = Some((
+                    edge_size + (px as f64 + 0.5) * size,
+                    edge_size + (py as f64 + 0.5) * size,
+                ));
+            });
+            center
+        };
+
+        for annotation in &self.annotations {
+            match *annotation {
+                Annotation::Circle(p) => {
+                    if let Some((x, y)) = center(p) {
+                        context.begin_path();
+                        context.arc(x, y, size * 0.3, 0.0, 2.0 * std::f64::consts::PI)?;
+                        context.stroke();
+                    }
+                }
+                Annotation::Triangle(p) => {
+                    if let Some((x, y)) = center(p) {
+                        let r = size * 0.32;
+                        context.begin_path();
+                        context.move_to(x, y - r);
+                        context.line_to(x + r * 0.87, y + r * 0.5);
+                        context.line_to(x - r * 0.87, y + r * 0.5);
+                        context.close_path();
+                        context.stroke();
+                    }
+                }
+                Annotation::Letter(p, letter) => {
+                    if let Some((x, y)) = center(p) {
+                        context.fill_text(&letter.to_string(), x, y)?;
+                    }
+                }
+                Annotation::Line(from, to) | Annotation::Arrow(from, to) => {
+                    let (Some((x1, y1)), Some((x2, y2))) = (center(from), center(to)) else {
+                        continue;
+                    };
+                    context.begin_path();
+                    context.move_to(x1, y1);
+                    context.line_to(x2, y2);
+                    if let Annotation::Arrow(..) = annotation {
+                        let angle = (y2 - y1).atan2(x2 - x1);
+                        let head = size * 0.35;
+                        for side in [-0.5, 0.5] {
+                            context.move_to(x2, y2);
+                            context.line_to(
+                                x2 - head * (angle + side).cos(),
+                                y2 - head * (angle + side).sin(),
+                            );
+                        }
+                    }
+                    context.stroke();
+                }
+            }
+        }
+
         // Toroidal edge grayout //////////////////////////////////////////////
 
         if game.mods.toroidal.is_some() {
@@ -568,6 +917,13 @@ impl Board {
             return;
         }
 
+        let rotation = self.view_rotation(game);
+        if rotation != 0 {
+            let (x, y) = rotate_point((board.0 as u32, board.1 as u32), size as u32, rotation);
+            cb((x as i32, y as i32));
+            return;
+        }
+
         let x = (board.0 - self.board_displacement.0).rem_euclid(game.size.0 as i32);
         let y = (board.1 - self.board_displacement.1).rem_euclid(game.size.1 as i32);
         cb((x + edge, y + edge));
@@ -598,3 +954,205 @@ impl Board {
         }
     }
 }
+
+/// Quarter turns that put the side of the user's seat at the bottom in four seat games.
+/// Seats go around the board counterclockwise from the bottom, so the second seat plays
+/// from the right side. Other games are drawn upright.
+pub(crate) fn seat_rotation(game: &state::GameView, user_id: u64) -> u8 {
+    if game.seats.len() != 4 {
+        return 0;
+    }
+    game.seats
+        .iter()
+        .position(|s| s.player == Some(user_id))
+        .map_or(0, |idx| idx as u8)
+}
+
+/// Counts the live position as if play ended now, with a guess at the dead stones. Only
+/// during play, and only ever computed locally.
+pub(crate) fn hypothetical_count(game: &state::GameView) -> Option<ScoringState> {
+    if !matches!(game.state, GameStateView::Play(_)) {
+        return None;
+    }
+    let board = shared::game::Board {
+        width: game.size.0 as u32,
+        height: game.size.1 as u32,
+        toroidal: game.mods.toroidal.is_some(),
+        points: game.board.clone(),
+    };
+    Some(scoring::hypothetical_count(
+        &board,
+        &game.points,
+        game.mods.seki,
+    ))
+}
+
+/// Draws the colorblind glyph of `team` around `center`: a dot, ring, cross or square.
+fn draw_team_mark(
+    context: &CanvasRenderingContext2d,
+    (cx, cy): (f64, f64),
+    radius: f64,
+    team: u8,
+    color: &str,
+) {
+    context.save();
+    context.set_fill_style(&JsValue::from_str(color));
+    context.set_stroke_style(&JsValue::from_str(color));
+    context.set_line_width(1.5);
+    context.begin_path();
+    match team {
+        1 => {
+            let _ = context.arc(cx, cy, radius / 2., 0.0, 2.0 * std::f64::consts::PI);
+            context.fill();
+        }
+        2 => {
+            let _ = context.arc(cx, cy, radius, 0.0, 2.0 * std::f64::consts::PI);
+            context.stroke();
+        }
+        3 => {
+            context.move_to(cx - radius, cy - radius);
+            context.line_to(cx + radius, cy + radius);
+            context.move_to(cx + radius, cy - radius);
+            context.line_to(cx - radius, cy + radius);
+            context.stroke();
+        }
+        _ => {
+            context.stroke_rect(cx - radius, cy - radius, 2. * radius, 2. * radius);
+        }
+    }
+    context.restore();
+}
+
+/// Side length of exported images in pixels.
+const EXPORT_SIZE: u32 = 2048;
+
+/// Renders the current position to an offscreen canvas and downloads it as a PNG.
+pub(crate) fn download_png(game: &state::GameView) -> Result<(), JsValue> {
+    let document = gloo_utils::document();
+
+    let canvas = document
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+    canvas.set_width(EXPORT_SIZE);
+    canvas.set_height(EXPORT_SIZE);
+
+    Board::display(80.0).render_gl(
+        &canvas_context(&canvas)?,
+        (canvas.width() as f64, canvas.height() as f64),
+        game,
+        None,
+    )?;
+
+    let url = canvas.to_data_url_with_type("image/png")?;
+    let link = document
+        .create_element("a")?
+        .dyn_into::<HtmlAnchorElement>()?;
+    link.set_href(&url);
+    link.set_download(&format!("position-{}.png", game.move_number));
+    link.click();
+
+    Ok(())
+}
+
+/// Downloads the per move statistics of a finished game, see `shared::game::export::stats_csv`.
+pub(crate) fn download_csv(name: &str, csv: &str) -> Result<(), JsValue> {
+    let url = format!(
+        "data:text/csv;charset=utf-8,{}",
+        String::from(web_sys::js_sys::encode_uri_component(csv))
+    );
+    let link = gloo_utils::document()
+        .create_element("a")?
+        .dyn_into::<HtmlAnchorElement>()?;
+    link.set_href(&url);
+    link.set_download(name);
+    link.click();
+
+    Ok(())
+}
+
+/// Copies the position as a text diagram with coordinates, for pasting into forums. Stones
+/// the last move captured are listed below it.
+pub(crate) fn copy_diagram(game: &state::GameView) -> Result<(), JsValue> {
+    let board = shared::game::Board {
+        width: game.size.0 as u32,
+        height: game.size.1 as u32,
+        toroidal: game.mods.toroidal.is_some(),
+        points: game.board.clone(),
+    };
+    let (last_move, captured) = match &game.state {
+        GameStateView::Play(state) => (
+            state.last_stone.as_ref().map_or(&[][..], |p| &p[..]),
+            &state.last_captured[..],
+        ),
+        _ => (&[][..], &[][..]),
+    };
+    let mut diagram = shared::game::export::text_diagram(&board, last_move, true);
+    if let Some(note) = shared::game::export::capture_note(captured, board.height) {
+        diagram.push_str(&note);
+        diagram.push('\n');
+    }
+
+    let clipboard = gloo_utils::window()
+        .navigator()
+        .clipboard()
+        .ok_or_else(|| JsValue::from_str("Clipboard not available"))?;
+    // The promise only reports whether the browser allowed the write.
+    let _ = clipboard.write_text(&diagram);
+
+    Ok(())
+}
+
+/// Fill and border of the ghost stone. `None` if the seat on turn can't be resolved or
+/// nobody sits there yet, like in a room that was just created, which skips the ghost.
+fn ghost_colors(palette: &Palette, game: &state::GameView) -> Option<(&'static str, &'static str)> {
+    let seat = game.seats.get(game.turn as usize)?;
+    seat.player?;
+    // Teams start from 1
+    let idx = seat.team.as_usize().checked_sub(1)?;
+    Some((
+        *palette.shadow_stone_colors.get(idx)?,
+        *palette.shadow_border_colors.get(idx)?,
+    ))
+}
+
+/// Fails instead of panicking, since some restricted browsers refuse to hand out a context.
+pub(crate) fn canvas_context(
+    canvas: &HtmlCanvasElement,
+) -> Result<CanvasRenderingContext2d, JsValue> {
+    canvas
+        .get_context("2d")?
+        .ok_or_else(|| JsValue::from_str("2d canvas context is unavailable"))?
+        .dyn_into::<CanvasRenderingContext2d>()
+        .map_err(|_| JsValue::from_str("canvas context is not a 2d context"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::game::{Game, GameModifier, GroupVec};
+
+    #[test]
+    fn ghost_colors_without_seats() {
+        // Built like the view of a room that was just created.
+        let game = Game::standard(
+            &[1, 2],
+            GroupVec::from(&[0, 15][..]),
+            (9, 9),
+            GameModifier::default(),
+            0,
+        )
+        .unwrap();
+        let mut view = state::GameView::from(game.get_view(0));
+        let palette = PaletteOption::Normal.to_palette();
+
+        assert_eq!(ghost_colors(&palette, &view), None);
+        view.seats[0].player = Some(100);
+        assert_eq!(
+            ghost_colors(&palette, &view),
+            Some(("#000000a0", "#bbbbbb"))
+        );
+
+        view.seats.clear();
+        assert_eq!(ghost_colors(&palette, &view), None);
+    }
+}
@@ -1,8 +1,13 @@
 #![allow(non_snake_case)]
+mod autosave;
 mod board;
 mod config;
 mod networking;
 mod palette;
+mod practice;
+mod settings;
+mod share;
+mod sound;
 mod state;
 mod views;
 mod window;
@@ -10,12 +15,18 @@ mod window;
 use std::rc::Rc;
 
 use dioxus::{
-    html::{geometry::euclid::Size2D, input_data::MouseButton},
+    html::{
+        geometry::euclid::Size2D,
+        input_data::{keyboard_types::Key, MouseButton},
+    },
     prelude::*,
 };
 use dioxus_router::prelude::*;
 use dioxus_signals::{use_selector, use_signal, ReadOnlySignal, Signal};
-use shared::{game::Seat, message::Profile};
+use shared::{
+    game::Seat,
+    message::{BoardPresentation, Profile},
+};
 use state::GameRoom;
 use web_sys::wasm_bindgen::JsCast;
 use window::DisplayMode;
@@ -29,8 +40,16 @@ enum Route {
     Home {},
     #[route("/game/:id")]
     GameRoute { id: u32 },
+    #[route("/game/:id/move/:move_number")]
+    GameMoveRoute { id: u32, move_number: u32 },
     #[route("/create")]
     CreateRoute {},
+    #[route("/practice")]
+    PracticeRoute {},
+    #[route("/problems")]
+    ProblemsRoute {},
+    #[route("/review")]
+    ReviewRoute {},
     #[route("/profile")]
     ProfileRoute {},
 }
@@ -47,6 +66,9 @@ fn App(cx: Scope) -> Element {
     cx.render(rsx! {
         sir::AppStyle {},
         Router::<Route> {},
+        views::ErrorToasts {},
+        views::InvitePrompts {},
+        views::SavedGamePrompt {},
     })
 }
 
@@ -84,6 +106,21 @@ fn ProfileRoute(cx: Scope) -> Element {
         div {
             class: "{class}",
             NickInput { profile: state.read().user }
+            SnapSetting {}
+            StonePlacementSetting {}
+            ScoreboardSetting {}
+            BoardSizeSetting {}
+            LabelSetting {}
+            SoundSetting {}
+            GhostSetting {}
+            StoneMarkSetting {}
+            ChatScrollSetting {}
+            UndoGraceSetting {}
+            AutoSaveSetting {}
+            AnnounceSetting {}
+            PresentationSetting {}
+            BlockedUsers {}
+            AboutInfo { info: state.read().server_info }
         }
     };
 
@@ -112,7 +149,11 @@ fn GameRoute(cx: Scope, id: u32) -> Element {
     let mode = window::use_display_mode(cx);
 
     let _ = use_memo(cx, (id,), move |(id,)| {
-        action.join_room(id);
+        // Watching a random game already joined it on the server.
+        let active = state.read().active_room().read().as_ref().map(|r| r.id);
+        if active != Some(id) {
+            action.join_room(id);
+        }
     });
 
     cx.render(rsx! {
@@ -128,6 +169,7 @@ fn GameRoute(cx: Scope, id: u32) -> Element {
                     rsx!(SeatCards {})
                 }
                 GamePanel { room: state.read().active_room() }
+                views::MoveAnnouncer {}
             }
             if mode.is_desktop() {
                 rsx!(RightPanel {})
@@ -167,6 +209,208 @@ fn CreateRoute(cx: Scope) -> Element {
         }
     })
 }
+/// Opens a room with the next problem of the server's problem set we haven't solved.
+#[component]
+fn ProblemsRoute(cx: Scope) -> Element {
+    let action = ActionSender::new(cx);
+    let state = state::use_state(cx);
+    let mode = window::use_display_mode(cx);
+
+    use_on_create(cx, move || {
+        action.leave_all_rooms();
+        action.next_problem();
+        async {}
+    });
+
+    use_game_switcher(cx);
+
+    let progress = match *state.read().problem_progress.read() {
+        Some((solved, total)) if solved == total => "Every problem is solved".to_string(),
+        Some((solved, total)) => format!("{solved} of {total} problems solved"),
+        None => "Finding a problem".to_string(),
+    };
+
+    cx.render(rsx! {
+        div {
+            class: "root {mode.class()} in-game",
+            if mode.is_desktop() {
+                rsx!(RoomList { rooms: state.read().rooms })
+            }
+            div {
+                class: "center-stack",
+                div { style: "padding: 10px;", "{progress}" }
+            }
+            if mode.is_desktop() {
+                rsx!(RightPanel {})
+            }
+        }
+    })
+}
+
+/// A game opened from a review link, see `share`. Links to rooms on the server go on to
+/// the room.
+#[component]
+fn ReviewRoute(cx: Scope) -> Element {
+    let action = ActionSender::new(cx);
+    let state = state::use_state(cx);
+    let mode = window::use_display_mode(cx);
+    let navigator = use_navigator(cx);
+    let error = use_signal(cx, || None::<String>);
+
+    use_on_create(cx, move || {
+        action.leave_all_rooms();
+        let fragment = gloo_utils::window().location().hash().unwrap_or_default();
+        let opened = match share::parse_fragment(&fragment) {
+            Ok(share::SharedGame::Room(id)) => {
+                navigator.replace(Route::GameRoute { id });
+                Ok(())
+            }
+            Ok(share::SharedGame::Sgf(sgf)) => {
+                let state = state.read();
+                let user_id = state.user.read().user_id;
+                share::open(&state, user_id, &sgf)
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = opened {
+            error.set(Some(e));
+        }
+        async {}
+    });
+
+    let error = error.read().clone();
+    cx.render(rsx! {
+        div {
+            class: "root {mode.class()} in-game",
+            if mode.is_desktop() {
+                rsx!(RoomList { rooms: state.read().rooms })
+            }
+            div {
+                class: "center-stack",
+                if let Some(error) = error {
+                    rsx!(div { style: "padding: 10px;", "{error}" })
+                } else {
+                    rsx!(
+                        GameNavBar { room: state.read().active_room() },
+                        GamePanel { room: state.read().active_room() }
+                        if mode.is_mobile() {
+                            rsx!(views::AnalysisPanel {})
+                        }
+                    )
+                }
+            }
+            if mode.is_desktop() {
+                rsx!(RightPanel {})
+            }
+        }
+    })
+}
+
+/// A game with the position after `move_number` shown next to the live board.
+#[component]
+fn GameMoveRoute(cx: Scope, id: u32, move_number: u32) -> Element {
+    let id = *id;
+    let move_number = *move_number;
+    let state = state::use_state(cx);
+    let requested = use_signal(cx, || false);
+    // `ActionSender` borrows the scope, which the effect can't hold on to.
+    let handle = use_coroutine_handle::<shared::message::ClientMessage>(cx)
+        .expect("use_websocket called outside of websocket provider")
+        .clone();
+
+    // The server only answers for the room once it's joined.
+    dioxus_signals::use_effect(cx, move || {
+        let active = state.read().active_room().read().as_ref().map(|r| r.id);
+        if active == Some(id) && !*requested.read() {
+            requested.set(true);
+            handle.send(shared::message::ClientMessage::GameAction {
+                room_id: Some(id),
+                action: shared::message::GameAction::BoardAt(move_number, move_number),
+            });
+        }
+    });
+
+    cx.render(rsx! {
+        GameRoute { id: id }
+    })
+}
+
+/// A local game against yourself, see `practice`.
+#[component]
+fn PracticeRoute(cx: Scope) -> Element {
+    let action = ActionSender::new(cx);
+    let state = state::use_state(cx);
+    let mode = window::use_display_mode(cx);
+
+    use_on_create(cx, move || {
+        action.leave_all_rooms();
+        async {}
+    });
+
+    // Our user id is only known once the server identifies us, the seats follow it.
+    let user_id = state.read().user.read().user_id;
+    let player = state.read().practice.read().as_ref().map(practice::player);
+    if player != Some(Some(user_id)) {
+        practice::start(&state.read(), user_id, 19);
+    } else {
+        let active = state.read().active_room().read().as_ref().map(|r| r.id);
+        if active != Some(practice::PRACTICE_ROOM_ID) {
+            practice::resume(&state.read());
+        }
+    }
+
+    cx.render(rsx! {
+        div {
+            class: "root {mode.class()} in-game",
+            if mode.is_desktop() {
+                rsx!(RoomList { rooms: state.read().rooms })
+            }
+            div {
+                class: "center-stack",
+                PracticeControls {}
+                GameNavBar { room: state.read().active_room() },
+                if mode.is_mobile() {
+                    rsx!(SeatCards {})
+                }
+                GamePanel { room: state.read().active_room() }
+                views::MoveAnnouncer {}
+            }
+            if mode.is_desktop() {
+                rsx!(RightPanel {})
+            }
+        }
+    })
+}
+
+#[component]
+fn PracticeControls(cx: Scope) -> Element {
+    let state = state::use_state(cx);
+    let size = state
+        .read()
+        .active_room()
+        .read()
+        .as_ref()
+        .map_or(19, |r| r.view.size.0);
+
+    cx.render(rsx! {
+        div {
+            style: "padding: 10px; display: flex; gap: 10px;",
+            span { "Practice, nothing is saved. New board:" }
+            for new_size in practice::PRACTICE_SIZES {
+                a {
+                    style: "cursor: pointer;",
+                    font_weight: if new_size == size { "bold" } else { "normal" },
+                    onclick: move |_| {
+                        let state = state.read();
+                        let user_id = state.user.read().user_id;
+                        practice::start(&state, user_id, new_size);
+                    },
+                    "{new_size}x{new_size}"
+                }
+            }
+        }
+    })
+}
 
 /// Switches to a room automatically if the active room changes.
 /// This is necessary as we don't know which room to switch to when we create a game.
@@ -200,6 +444,16 @@ fn RightPanel(cx: Scope) -> Element {
         div {
             class: "{class}",
             SeatCards {}
+            views::PauseNotice {}
+            views::ConsentPrompts {}
+            views::GameOverPanel {}
+            views::AnnotationPanel {}
+            views::PresentationPanel {}
+            views::ScoreEstimateBar {}
+            views::HypotheticalCount {}
+            views::ComparisonBoard {}
+            views::AnalysisPanel {}
+            views::ChatPanel {}
         }
     })
 }
@@ -208,6 +462,7 @@ fn RightPanel(cx: Scope) -> Element {
 fn GamePanel(cx: Scope, room: ReadOnlySignal<Option<state::ActiveRoom>>) -> Element {
     let outer_div = use_signal(cx, || None::<Rc<MountedData>>);
     let canvas_element = use_signal(cx, || None::<Rc<MountedData>>);
+    let render_error = use_signal(cx, || None::<String>);
     let size = use_signal(cx, Size2D::default);
     let set_size = move || async move {
         let Some(data) = outer_div.read().clone() else {
@@ -215,11 +470,17 @@ fn GamePanel(cx: Scope, room: ReadOnlySignal<Option<state::ActiveRoom>>) -> Elem
         };
         let rect = data.get_client_rect().await.unwrap_or_default();
         let mut div_size = rect.size;
-        div_size.width = f64::min(div_size.width, div_size.height);
-        div_size.height = f64::min(div_size.width, div_size.height);
+        let mut side = f64::min(div_size.width, div_size.height);
+        if let Some(pixels) = settings::BoardSizing::get().pixels() {
+            side = side.min(pixels);
+        }
+        div_size.width = side;
+        div_size.height = side;
 
         // Resize the canvas instantly to allow rendering
-        let canvas = get_canvas();
+        let Some(canvas) = get_canvas() else {
+            return;
+        };
         let pixel_ratio = gloo_utils::window().device_pixel_ratio();
         let unscaled = div_size.width as u32;
         let scaled = (div_size.width * pixel_ratio) as u32;
@@ -255,6 +516,78 @@ fn GamePanel(cx: Scope, room: ReadOnlySignal<Option<state::ActiveRoom>>) -> Elem
         input: board::Input::None,
         show_hidden: false,
         edge_size: 40.0,
+        snap_radius: settings::SnapTolerance::get().radius(),
+        stones_in_cells: settings::StonePlacement::get() == settings::StonePlacement::Cells,
+        show_ghost: settings::ShowGhost::get().0,
+        stone_marks: settings::StoneMarks::get().0,
+        show_influence: settings::Influence::get().0,
+        remove_dead: false,
+        rotation: 0,
+        show_coordinates: true,
+        label_style: settings::CoordinateLabels::get().0,
+        annotations: Vec::new(),
+        hypothetical: None,
+    });
+
+    let state = state::use_state(cx);
+
+    // Each player of a four seat game sees the board from their own side, unless the room
+    // owner turns everyone's board the same way.
+    dioxus_signals::use_effect(cx, move || {
+        let me = state.read().user.read().user_id;
+        let shared = presented(state).rotation;
+        let rotation = view.read().as_ref().map_or(0, |view| {
+            shared.unwrap_or_else(|| board::seat_rotation(view, me))
+        });
+        if board.read().rotation != rotation {
+            board.write().rotation = rotation;
+        }
+    });
+
+    dioxus_signals::use_effect(cx, move || {
+        let presentation = presented(state);
+        let palette = presentation
+            .palette
+            .map_or_else(palette::PaletteOption::get, Into::into)
+            .to_palette();
+        let coordinates = presentation.coordinates.unwrap_or(true);
+        if board.read().palette != palette || board.read().show_coordinates != coordinates {
+            let mut board = board.write();
+            board.palette = palette;
+            board.show_coordinates = coordinates;
+        }
+    });
+
+    dioxus_signals::use_effect(cx, move || {
+        let show = *state.read().influence.read();
+        if board.read().show_influence != show {
+            board.write().show_influence = show;
+        }
+    });
+
+    dioxus_signals::use_effect(cx, move || {
+        let annotations = state.read().annotations.read().clone();
+        if board.read().annotations != annotations {
+            board.write().annotations = annotations;
+        }
+    });
+
+    dioxus_signals::use_effect(cx, move || {
+        let shown = *state.read().hypothetical.read();
+        let count = match view.read().as_ref() {
+            Some(view) if shown => board::hypothetical_count(view),
+            _ => None,
+        };
+        if board.read().hypothetical != count {
+            board.write().hypothetical = count;
+        }
+    });
+
+    dioxus_signals::use_effect(cx, move || {
+        let remove = *state.read().remove_dead.read();
+        if board.read().remove_dead != remove {
+            board.write().remove_dead = remove;
+        }
     });
 
     dioxus_signals::use_effect(cx, move || {
@@ -266,28 +599,107 @@ fn GamePanel(cx: Scope, room: ReadOnlySignal<Option<state::ActiveRoom>>) -> Elem
         let Some(view) = view.read().clone() else {
             return;
         };
-        let canvas = get_canvas();
+        let Some(canvas) = get_canvas() else {
+            return;
+        };
         let board = board.read();
-        board.render_gl(&canvas, &*view, None).unwrap();
+        let review = state.read().shown_history();
+        let view = with_review_size(view, review.as_ref());
+        // Rendering is retried on every change, so a transient failure clears itself.
+        let result = board::canvas_context(&canvas).and_then(|context| {
+            board.render_gl(
+                &context,
+                (canvas.width() as f64, canvas.height() as f64),
+                &view,
+                review.as_ref(),
+            )
+        });
+        match result {
+            Ok(()) => {
+                if render_error.read().is_some() {
+                    render_error.set(None);
+                }
+            }
+            Err(e) => {
+                log::error!("Failed to render board: {:?}", e);
+                if render_error.read().is_none() {
+                    render_error.set(Some(
+                        "The board could not be drawn. Your browser may have canvas rendering disabled."
+                            .to_string(),
+                    ));
+                }
+            }
+        }
     });
 
     let action = ActionSender::new(cx);
 
+    let place = move |view: &state::GameView, pos: (u32, u32)| {
+        let tool = *state.read().annotation_tool.read();
+        if let Some(tool) = tool {
+            let start = state.read().annotation_start;
+            let annotation = views::annotations::click_annotation(
+                tool,
+                &mut start.write(),
+                &state.read().annotations.read(),
+                pos,
+            );
+            if let Some(annotation) = annotation {
+                action.annotate(shared::game::AnnotationAction::Add(annotation));
+            }
+            return;
+        }
+
+        // Finished games can only be reviewed, or studied locally.
+        if matches!(view.state, shared::game::GameStateView::Done(_)) {
+            let analysis = state.read().analysis;
+            let mut analysis = analysis.write();
+            if let Some(analysis) = analysis.as_mut() {
+                if let Ok(node) = analysis.tree.play(analysis.selected, pos) {
+                    analysis.selected = node;
+                }
+            } else {
+                action.review(shared::game::ReviewAction::Place(pos.0, pos.1));
+            }
+        } else if matches!(view.state, shared::game::GameStateView::Setup(_)) {
+            // Cycle the point through empty and every team.
+            let idx = (pos.1 * view.size.0 as u32 + pos.0) as usize;
+            let current = view.board.get(idx).map_or(0, |c| c.0);
+            let next = (current + 1) % (view.points.len() as u8 + 1);
+            action.setup_stones(vec![(pos.0, pos.1, next)]);
+        } else {
+            action.place_stone(pos.0, pos.1);
+        }
+    };
+
     let update_mouse = move |e: MouseEvent, clicked: bool| {
         let Some(view) = view.read().clone() else {
             return;
         };
-        let canvas = get_canvas();
+        let view = with_review_size(view, state.read().shown_history().as_ref());
+        let Some(canvas) = get_canvas() else {
+            return;
+        };
         let coord = e.client_coordinates();
         let bounding_rect = canvas.get_bounding_client_rect();
-        let mut board = board.write();
-        let input =
-            board::Input::from_pointer(&board, &view, coord.to_tuple(), bounding_rect, clicked);
-        board.input = input;
-        board.selection_pos = input.into_selection();
+        let input = board::Input::from_pointer(
+            &board.read(),
+            &view,
+            coord.to_tuple(),
+            bounding_rect,
+            clicked,
+        );
+
+        // Rendering is driven by the board signal, so only touch it when the hover state
+        // actually changes. Otherwise every mouse move over the same point redraws the board.
+        if board.read().input != input {
+            let mut board = board.write();
+            board.input = input;
+            board.selection_pos = input.into_selection();
+        }
 
         if let board::Input::Place(pos, true) = input {
-            action.place_stone(pos.0, pos.1);
+            place(&view, pos);
         }
     };
 
@@ -296,6 +708,56 @@ fn GamePanel(cx: Scope, room: ReadOnlySignal<Option<state::ActiveRoom>>) -> Elem
         update_mouse(e, clicked);
     };
 
+    let show_keys = use_signal(cx, || false);
+
+    // The board takes focus when clicked, see `tabindex` below.
+    let on_key = move |e: KeyboardEvent| {
+        let Some(view) = view.read().clone() else {
+            return;
+        };
+        let view = with_review_size(view, state.read().shown_history().as_ref());
+        let me = state.read().user.read().user_id;
+        let is_play = matches!(view.state, shared::game::GameStateView::Play(_));
+        let is_teacher = view
+            .mods
+            .teaching
+            .as_ref()
+            .map_or(false, |t| t.teacher == me);
+        let own_turn = view
+            .seats
+            .get(view.turn as usize)
+            .map_or(false, |s| s.player == Some(me))
+            || is_teacher;
+        match e.key() {
+            Key::Character(c) if c.eq_ignore_ascii_case("p") => {
+                if is_play && own_turn {
+                    action.pass();
+                }
+            }
+            Key::Character(c) if c == "?" => {
+                let shown = *show_keys.read();
+                show_keys.set(!shown);
+            }
+            Key::Enter => {
+                let selection = board.read().selection_pos;
+                if let Some(pos) = selection {
+                    if !is_play || own_turn {
+                        place(&view, pos);
+                    }
+                }
+            }
+            Key::Escape => {
+                if board.read().selection_pos.is_some() {
+                    let mut board = board.write();
+                    board.input = board::Input::None;
+                    board.selection_pos = None;
+                }
+                show_keys.set(false);
+            }
+            _ => {}
+        }
+    };
+
     #[rustfmt::skip]
     let class = sir::css!("
         width: 100%;
@@ -305,11 +767,41 @@ fn GamePanel(cx: Scope, room: ReadOnlySignal<Option<state::ActiveRoom>>) -> Elem
         canvas {
             position: absolute;
         }
+        outline: none;
+        .render-error {
+            position: absolute;
+            padding: 20px;
+            text-align: center;
+        }
+        .keys {
+            position: absolute;
+            z-index: 1;
+            padding: 10px 20px;
+            background-color: rgba(0, 0, 0, 0.8);
+            color: white;
+        }
     ");
     cx.render(rsx! {
         div {
             class: "{class}",
             onmounted: onmounted,
+            tabindex: "0",
+            onkeydown: on_key,
+            if let Some(error) = render_error.read().as_deref() {
+                rsx!(div { class: "render-error", "{error}" })
+            }
+            if *show_keys.read() {
+                rsx!(div {
+                    class: "keys",
+                    p { "Keyboard shortcuts" }
+                    ul {
+                        li { "Enter: play on the highlighted point" }
+                        li { "Escape: clear the highlighted point" }
+                        li { "P: pass on your turn" }
+                        li { "?: show or hide this help" }
+                    }
+                })
+            }
             canvas {
                 onmounted: move |e| {
                     canvas_element.set(Some(e.inner().clone()));
@@ -413,28 +905,128 @@ fn GameNavBar(cx: Scope, room: ReadOnlySignal<Option<state::ActiveRoom>>) -> Ele
         is_own_turn: bool,
         is_play: bool,
         is_scoring: bool,
+        final_scoring: bool,
+        is_done: bool,
+        is_setup: bool,
+        move_number: u32,
+        max_moves: u32,
+        waiting_for_player: bool,
+        players_missing: u32,
+        pass_suggested: bool,
+        swap_available: bool,
+        problem_solved: bool,
+        repetition: bool,
+        no_result: bool,
+        capture_winner: Option<shared::game::Color>,
+        tiebreak_winner: Option<shared::game::Color>,
+        abandoned_winner: Option<shared::game::Color>,
+        skipped: Option<shared::game::Color>,
+        pass_streak: u32,
+        teaching: bool,
+        no_undo: bool,
+        scoring_timeout: Option<shared::game::clock::Millisecond>,
+        size: (u8, u8),
+        dame_count: usize,
     }
 
     let Info {
         is_own_turn,
         is_play,
         is_scoring,
+        final_scoring,
+        is_done,
+        is_setup,
+        move_number,
+        max_moves,
+        waiting_for_player,
+        players_missing,
+        pass_suggested,
+        swap_available,
+        problem_solved,
+        repetition,
+        no_result,
+        capture_winner,
+        tiebreak_winner,
+        abandoned_winner,
+        skipped,
+        pass_streak,
+        teaching,
+        no_undo,
+        scoring_timeout,
+        size,
+        dame_count,
     } = *dioxus_signals::use_selector(cx, move || {
         let view = view.read();
         let Some(view) = view.as_ref() else {
             return Info::default();
         };
         let me = state.read().user.read().user_id;
-        let seat = &view.seats[view.turn as usize];
+        let seat = view.seats.get(view.turn as usize);
+        let is_teacher = view
+            .mods
+            .teaching
+            .as_ref()
+            .map_or(false, |t| t.teacher == me);
         Info {
-            is_own_turn: seat.player == Some(me),
+            is_own_turn: seat.map_or(false, |s| s.player == Some(me)) || is_teacher,
             is_play: matches!(view.state, shared::game::GameStateView::Play(_)),
             is_scoring: matches!(view.state, shared::game::GameStateView::Scoring(_)),
+            final_scoring: matches!(
+                &view.state,
+                shared::game::GameStateView::Scoring(scoring) if scoring.final_round
+            ),
+            is_done: matches!(view.state, shared::game::GameStateView::Done(_)),
+            is_setup: matches!(view.state, shared::game::GameStateView::Setup(_)),
+            move_number: view.move_number,
+            max_moves: view.max_moves,
+            waiting_for_player: view.waiting_for_player,
+            players_missing: view.players_missing,
+            pass_suggested: view.pass_suggested,
+            swap_available: view.swap_available,
+            problem_solved: view.problem_solved,
+            repetition: view.repetition,
+            no_result: view.result == Some(shared::game::GameResult::NoResult),
+            capture_winner: match view.result {
+                Some(shared::game::GameResult::CaptureGoal(team)) => Some(team),
+                _ => None,
+            },
+            tiebreak_winner: match view.result {
+                Some(shared::game::GameResult::Tiebreak(team)) => Some(team),
+                _ => None,
+            },
+            abandoned_winner: match view.result {
+                Some(shared::game::GameResult::Abandoned(team)) => Some(team),
+                _ => None,
+            },
+            // Only the last move, earlier skips are in the history.
+            skipped: view
+                .skips
+                .last()
+                .filter(|&&(move_number, _)| move_number == view.move_number)
+                .and_then(|&(_, seat)| view.seats.get(seat as usize))
+                .map(|s| s.team),
+            pass_streak: view.pass_streak,
+            teaching: view.mods.teaching.is_some(),
+            no_undo: view.mods.no_undo,
+            scoring_timeout: view.scoring_timeout,
+            size: view.size,
+            dame_count: view.dame.len(),
         }
     })
     .read();
 
     let action = ActionSender::new(cx);
+    let problem_progress = *state.read().problem_progress.read();
+    let share_link = state.read().share_link.read().clone();
+    let is_local = room.read().as_ref().map_or(true, |r| share::is_local(r.id));
+
+    // Reviewing starts from the final position.
+    let review_move = state
+        .read()
+        .review
+        .read()
+        .as_ref()
+        .map_or(move_number, |r| r.move_number);
 
     cx.render(rsx! {
         div {
@@ -446,25 +1038,334 @@ fn GameNavBar(cx: Scope, room: ReadOnlySignal<Option<state::ActiveRoom>>) -> Ele
                 })
             }
             div { class: "pad" }
+            if is_done {
+                rsx!(
+                    span {
+                        style: "padding: 10px;",
+                        "Review: move {review_move}"
+                    }
+                    a {
+                        onclick: move |_| {
+                            let previous = review_move.saturating_sub(1);
+                            action.review(shared::game::ReviewAction::Navigate(previous))
+                        },
+                        "◀"
+                    }
+                    a {
+                        onclick: move |_| {
+                            action.review(shared::game::ReviewAction::Navigate(review_move + 1))
+                        },
+                        "▶"
+                    }
+                    a {
+                        onclick: move |_| action.review(shared::game::ReviewAction::Undo),
+                        "Undo variation"
+                    }
+                )
+            }
+            if waiting_for_player {
+                rsx!(span {
+                    style: "padding: 10px;",
+                    "Waiting for a player to take the seat"
+                })
+            }
+            if players_missing > 0 {
+                let plural = if players_missing == 1 { "" } else { "s" };
+                rsx!(span {
+                    style: "padding: 10px;",
+                    "Waiting for {players_missing} more player{plural}"
+                })
+            }
+            if is_play && max_moves.saturating_sub(move_number) <= 20 {
+                let left = max_moves.saturating_sub(move_number);
+                rsx!(span {
+                    style: "padding: 10px;",
+                    "Scoring in {left} moves (move limit)"
+                })
+            }
+            if let Some(deadline) = scoring_timeout {
+                rsx!(span {
+                    style: "padding: 10px;",
+                    if final_scoring { "Final scoring, accepted in " } else { "Resuming play in " }
+                    views::Countdown { deadline: deadline }
+                })
+            }
+            if teaching {
+                rsx!(span {
+                    style: "padding: 10px;",
+                    "Teaching game"
+                })
+            }
+            if no_result {
+                rsx!(span {
+                    style: "padding: 10px;",
+                    "No result (repetition)"
+                })
+            }
+            if let Some(team) = capture_winner {
+                let name = shared::game::Color::name(team);
+                rsx!(span {
+                    style: "padding: 10px;",
+                    "{name} reached the capture goal"
+                })
+            }
+            if let Some(team) = tiebreak_winner {
+                let name = shared::game::Color::name(team);
+                rsx!(span {
+                    style: "padding: 10px;",
+                    "Scores tied, {name} wins the tiebreak"
+                })
+            }
+            if let Some(team) = abandoned_winner {
+                let name = shared::game::Color::name(team);
+                rsx!(span {
+                    style: "padding: 10px;",
+                    "{name} wins, the opponent didn't return"
+                })
+            }
+            if let Some(team) = skipped {
+                let name = shared::game::Color::name(team);
+                rsx!(span {
+                    style: "padding: 10px;",
+                    "{name} was skipped for being away"
+                })
+            }
+            if is_play && pass_streak > 0 {
+                rsx!(span {
+                    style: "padding: 10px;",
+                    title: "Scoring starts once every team has passed in a row",
+                    if pass_streak == 1 { "1 pass" } else { "{pass_streak} passes in a row" }
+                })
+            }
+            if repetition {
+                rsx!(span {
+                    style: "padding: 10px;",
+                    "Position repeated"
+                })
+            }
+            if problem_solved {
+                rsx!(span {
+                    style: "padding: 10px;",
+                    "Solved!"
+                    if let Some((solved, total)) = problem_progress {
+                        rsx!(" {solved} of {total} problems done.")
+                    }
+                })
+                rsx!(Link {
+                    to: Route::ProblemsRoute {},
+                    "Next problem"
+                })
+            } else if *state.read().wrong_move.read() {
+                rsx!(span {
+                    style: "padding: 10px;",
+                    "Wrong move, try again"
+                })
+            }
             if is_own_turn && is_play {
+                rsx!(CoordinateInput { size: size })
+            }
+            a {
+                onclick: move |_| {
+                    if let Some(view) = view.read().as_ref() {
+                        if let Err(e) = board::download_png(view) {
+                            log::error!("PNG export failed: {:?}", e);
+                        }
+                    }
+                },
+                "PNG"
+            }
+            a {
+                title: "Copy the position as text",
+                onclick: move |_| {
+                    if let Some(view) = view.read().as_ref() {
+                        if let Err(e) = board::copy_diagram(view) {
+                            log::error!("Copying the diagram failed: {:?}", e);
+                        }
+                    }
+                },
+                "Text"
+            }
+            if is_done {
                 rsx!(a {
-                    onclick: move |_| action.undo(),
-                    "Undo"
+                    title: "Download captures and score estimates of every move as CSV",
+                    onclick: move |_| action.request_stats(),
+                    "Stats"
                 })
             }
-            if is_own_turn && is_play {
+            if is_done && !is_local {
                 rsx!(a {
-                    onclick: move |_| action.pass(),
-                    "Pass"
+                    title: "Copy a link that opens the game for review",
+                    onclick: move |_| action.share_game(),
+                    "Share"
                 })
             }
-            if is_scoring {
+            if let Some(link) = share_link {
+                rsx!(input {
+                    readonly: true,
+                    value: "{link}",
+                })
+            }
+            if is_play {
                 rsx!(a {
-                    onclick: move |_| action.pass(),
-                    "Accept"
+                    title: "Shade the board by which stones are nearest. A rough guide, not the score",
+                    onclick: move |_| {
+                        let influence = state.read().influence;
+                        let value = !*influence.read();
+                        settings::Influence(value).save();
+                        influence.set(value);
+                        action.sync_settings();
+                    },
+                    "Influence"
                 })
             }
-        }
+            if is_own_turn && is_play && !no_undo {
+                rsx!(a {
+                    onclick: move |_| action.undo(),
+                    "Undo"
+                })
+            }
+            if !is_own_turn && is_play && !no_undo {
+                rsx!(MisclickUndo {})
+            }
+            if is_own_turn && is_play {
+                rsx!(a {
+                    onclick: move |_| action.pass(),
+                    if pass_suggested { "Pass (suggested)" } else { "Pass" }
+                })
+            }
+            if is_own_turn && swap_available {
+                rsx!(a {
+                    title: "Take the first move as your own and let your opponent answer it",
+                    onclick: move |_| action.swap(),
+                    "Swap"
+                })
+            }
+            if is_scoring && !final_scoring && dame_count > 0 {
+                rsx!(
+                    span {
+                        style: "padding: 10px;",
+                        "{dame_count} dame left"
+                    }
+                    a {
+                        title: "Dame count under area scoring, go back to play to fill them",
+                        onclick: move |_| action.undo(),
+                        "Fill dame"
+                    }
+                )
+            }
+            if is_scoring {
+                rsx!(
+                    a {
+                        title: "Preview the board with dead stones taken off, as it will be counted",
+                        onclick: move |_| {
+                            let remove_dead = state.read().remove_dead;
+                            let value = !*remove_dead.read();
+                            remove_dead.set(value);
+                        },
+                        if *state.read().remove_dead.read() { "Show dead" } else { "Remove dead" }
+                    }
+                    a {
+                        onclick: move |_| action.pass(),
+                        "Accept"
+                    }
+                )
+            }
+            if is_setup {
+                rsx!(a {
+                    onclick: move |_| action.pass(),
+                    "Start game"
+                })
+            }
+        }
+    })
+}
+
+/// Offers to take back our stone for a moment after placing it, while the opponent
+/// hasn't answered. See `settings::UndoGrace`.
+#[component]
+fn MisclickUndo(cx: Scope) -> Element {
+    let state = state::use_state(cx);
+    let action = ActionSender::new(cx);
+    let until = state.read().undo_grace_until;
+    let tick = use_signal(cx, || 0u32);
+    use_future(cx, (), move |_| async move {
+        loop {
+            gloo_timers::future::TimeoutFuture::new(500).await;
+            *tick.write() += 1;
+        }
+    });
+
+    let _ = tick.read();
+    let open = until
+        .read()
+        .map_or(false, |until| web_sys::js_sys::Date::now() < until);
+    if !open {
+        return None;
+    }
+
+    cx.render(rsx! {
+        a {
+            title: "Take back the stone you just placed",
+            onclick: move |_| {
+                until.set(None);
+                action.undo();
+            },
+            "Undo?"
+        }
+    })
+}
+
+/// Places a stone by typing a coordinate like "D4", or "4-4" with numbered columns.
+#[component]
+fn CoordinateInput(cx: Scope, size: (u8, u8)) -> Element {
+    let size = *size;
+    let action = ActionSender::new(cx);
+    let text = use_signal(cx, String::new);
+    let invalid = use_signal(cx, || false);
+    let style = settings::CoordinateLabels::get().0;
+
+    let on_change = move |e: FormEvent| {
+        let value = e.inner().value.clone();
+        match style.parse(&value, (size.0 as u32, size.1 as u32)) {
+            Some((x, y)) => {
+                action.place_stone(x, y);
+                text.set(String::new());
+                invalid.set(false);
+            }
+            None => {
+                text.set(value);
+                invalid.set(true);
+            }
+        }
+    };
+
+    #[rustfmt::skip]
+    let class = sir::css!("
+        display: flex;
+        align-items: center;
+        padding: 0 10px;
+        input {
+            width: 4em;
+        }
+        .invalid {
+            margin-left: 5px;
+            color: #e0658f;
+        }
+    ");
+
+    cx.render(rsx! {
+        div {
+            class: "{class}",
+            input {
+                r#type: "text",
+                placeholder: "{style.point((3, 3), 7)}",
+                value: "{text}",
+                onchange: on_change,
+            }
+            if *invalid.read() {
+                rsx!(span { class: "invalid", "Not a point on the board" })
+            }
+        }
     })
 }
 
@@ -474,6 +1375,11 @@ fn SeatCards(cx: Scope) -> Element {
     let mode = window::use_display_mode(cx);
     let room = state.read().active_room();
     let seats = use_selector(cx, move || Some(room.read().as_ref()?.view.seats.clone()));
+    let handicap = use_selector(cx, move || {
+        let room = room.read();
+        let handicap = room.as_ref()?.view.mods.handicap.as_ref()?;
+        Some(handicap.stones)
+    });
 
     #[rustfmt::skip]
     let class = sir::css!("
@@ -483,6 +1389,11 @@ fn SeatCards(cx: Scope) -> Element {
         &.mobile {
             display: grid;
         }
+
+        .handicap {
+            padding: 5px 10px;
+            grid-column: 1 / -1;
+        }
     ");
 
     let columns = match mode {
@@ -503,6 +1414,16 @@ fn SeatCards(cx: Scope) -> Element {
                     seat_id: id as u32,
                 }
             }
+            if settings::ScoreboardLayout::get() == settings::ScoreboardLayout::Bar {
+                rsx!(ScoreBar {})
+            }
+            if let Some(stones) = *handicap.read() {
+                rsx!(div {
+                    class: "handicap",
+                    title: "Komi is shown on the seats during play",
+                    if stones > 1 { rsx!("Handicap {stones}") } else { rsx!("Handicap: komi only") }
+                })
+            }
         }
     })
 }
@@ -529,10 +1450,26 @@ fn SeatCard(cx: Scope, seat: Seat, seat_id: u32) -> Element {
     let nick = profile
         .as_ref()
         .map(|p| p.nick.as_deref().unwrap_or("Unknown"));
+    let rank = profile
+        .as_ref()
+        .map(|p| shared::message::rank_label(p.rating));
 
     let held_hy_self = seat
         .player
         .map_or(false, |p| p == state.read().user.read().user_id);
+    let following = seat.player.is_some() && *state.read().following.read() == seat.player;
+    // Players of other games can be asked over to a game we hold a seat in.
+    let invite_room = {
+        let active = state.read().active_room().read().as_ref().map(|r| r.id);
+        let seated_rooms = state.read().seated_rooms;
+        let seated_rooms = seated_rooms.read();
+        seated_rooms
+            .iter()
+            .map(|r| r.room_id)
+            .find(|&id| Some(id) != active)
+    };
+
+    let team_name = shared::game::Color::name(seat.team);
 
     let palette = palette::PaletteOption::get().to_palette();
     let bg_color = palette.stone_colors[seat.team.as_usize() - 1];
@@ -548,6 +1485,11 @@ fn SeatCard(cx: Scope, seat: Seat, seat_id: u32) -> Element {
         grid-template-rows: 40px 40px;
         height: 80px;
 
+        &.compact {
+            grid-template-rows: 40px;
+            height: 40px;
+        }
+
         &.is-turn {
             border: 2px solid #9ecaed;
             box-shadow: 0 0 10px #9ecaed;
@@ -573,6 +1515,23 @@ fn SeatCard(cx: Scope, seat: Seat, seat_id: u32) -> Element {
 
             &.empty {
                 box-shadow: inset 0 0 10px #ca8f00;
+                font-weight: bold;
+            }
+
+            .vacant {
+                font-style: italic;
+                opacity: 0.7;
+            }
+
+            .rank {
+                margin-left: 5px;
+                opacity: 0.7;
+            }
+
+            .follow {
+                margin-left: 5px;
+                opacity: 0.7;
+                text-decoration: underline;
             }
         }
 
@@ -598,6 +1557,9 @@ fn SeatCard(cx: Scope, seat: Seat, seat_id: u32) -> Element {
         shared::game::GameStateView::Play(play) => Some(play.clone()),
         _ => None,
     };
+    // The bar under the cards shows the clocks and captures instead.
+    let compact =
+        play.is_some() && settings::ScoreboardLayout::get() == settings::ScoreboardLayout::Bar;
 
     let scoring = match &view.state {
         shared::game::GameStateView::Scoring(scoring) => Some(scoring.clone()),
@@ -612,9 +1574,26 @@ fn SeatCard(cx: Scope, seat: Seat, seat_id: u32) -> Element {
     let scoring_div = if let Some(scoring) = &scoring {
         let score = scoring.scores[seat.team.as_usize() - 1] as f32 / 2.0;
         let accepted = scoring.players_accepted[seat_id as usize];
+        let pass_stones = scoring
+            .pass_stones
+            .get(seat.team.as_usize() - 1)
+            .copied()
+            .unwrap_or(0);
+        let marked_dead = scoring
+            .groups
+            .iter()
+            .zip(&scoring.marked_by)
+            .filter(|(group, &by)| !group.alive && by == Some(seat_id))
+            .count();
         rsx!(div {
             class: "scoring",
             "Score: {score}",
+            if pass_stones > 0 {
+                rsx!(" (+{pass_stones} from passes)")
+            }
+            if marked_dead > 0 {
+                rsx!(" - proposes {marked_dead} dead")
+            }
             if accepted {
                 " - (accepted)"
             } else {
@@ -628,15 +1607,54 @@ fn SeatCard(cx: Scope, seat: Seat, seat_id: u32) -> Element {
             class: "scoring",
             "Score: {score}"
         })
-    } else if let Some(play) = &play {
-        let passed = play.players_passed[seat.team.as_usize() - 1];
+    } else if let Some(play) = play.as_ref().filter(|_| !compact) {
+        let passed = play
+            .players_passed
+            .get(seat_id as usize)
+            .copied()
+            .unwrap_or(false);
+        let bonus = view
+            .capture_bonuses
+            .get(seat.team.as_usize() - 1)
+            .copied()
+            .filter(|&bonus| bonus != 0)
+            .map(|bonus| bonus as f32 / 2.0);
+        let capture_goal = view.mods.capture_goal.map(|goal| {
+            let captured = view.prisoners.get(seat.team.as_usize() - 1).copied();
+            format!("{}/{} captures", captured.unwrap_or(0), goal)
+        });
+        let komi = view
+            .komis
+            .get(seat.team.as_usize() - 1)
+            .copied()
+            .filter(|&komi| komi != 0)
+            .map(|komi| komi as f32 / 2.0);
+        let (time_left, deadline) = seat_clock(&view, seat_id);
         rsx!(div {
             class: "scoring",
+            if let Some(deadline) = deadline {
+                rsx!(views::Countdown { deadline: deadline })
+            } else if let Some(left) = time_left {
+                let text = views::format_time(left);
+                rsx!(span { "{text}" })
+            }
             if passed {
-                "Passed"
+                if time_left.is_some() { " - Passed" } else { "Passed" }
             } else {
                 ""
             }
+            if let Some(bonus) = bonus {
+                rsx!(span {
+                    style: "margin-left: 5px;",
+                    title: "Earned by capture bonuses",
+                    "+{bonus} bonus"
+                })
+            }
+            if let Some(capture_goal) = capture_goal {
+                rsx!(span { style: "margin-left: auto;", "{capture_goal}" })
+            } else if let Some(komi) = komi {
+                rsx!(span { style: "margin-left: auto;", "Komi {komi}" })
+            }
         })
     } else {
         rsx!(div {})
@@ -645,11 +1663,13 @@ fn SeatCard(cx: Scope, seat: Seat, seat_id: u32) -> Element {
     let can_take_seat = scoring.is_none() && done.is_none();
 
     let is_turn = view.turn == seat_id && play.is_some();
-    let class = if is_turn {
-        format!("{} is-turn", class)
-    } else {
-        class.to_string()
-    };
+    let mut class = class.to_string();
+    if is_turn {
+        class.push_str(" is-turn");
+    }
+    if compact {
+        class.push_str(" compact");
+    }
 
     cx.render(rsx! {
         div {
@@ -658,16 +1678,44 @@ fn SeatCard(cx: Scope, seat: Seat, seat_id: u32) -> Element {
             if seat.player.is_none() && can_take_seat {
                 rsx!(button {
                     class: "empty",
+                    title: "This seat is open, take it to play",
                     onclick: move |_| take_seat(),
-                    "Take Seat"
+                    "Sit as {team_name}"
                 })
             } else {
                 rsx!(button {
                     onclick: move |_| if held_hy_self && can_take_seat { leave_seat() },
                     if let Some(nick) = nick {
-                        rsx!("{nick}")
+                        rsx!(
+                            "{nick}"
+                            if let Some(rank) = rank {
+                                rsx!(span { class: "rank", title: "Rank, ? if unrated", "{rank}" })
+                            }
+                            if let Some(player) = seat.player.filter(|_| !held_hy_self) {
+                                rsx!(span {
+                                    class: "follow",
+                                    title: "Watch every game this player joins",
+                                    onclick: move |_| {
+                                        action.follow(if following { None } else { Some(player) })
+                                    },
+                                    if following { "Following" } else { "Follow" }
+                                })
+                            }
+                            if let Some((player, room_id)) = seat
+                                .player
+                                .filter(|_| !held_hy_self)
+                                .zip(invite_room)
+                            {
+                                rsx!(span {
+                                    class: "follow",
+                                    title: "Ask this player over to your game",
+                                    onclick: move |_| action.invite(player, room_id),
+                                    "Invite"
+                                })
+                            }
+                        )
                     } else if seat.player.is_none() {
-                        rsx!("<empty>")
+                        rsx!(span { class: "vacant", "Empty" })
                     }
                 })
             }
@@ -676,14 +1724,111 @@ fn SeatCard(cx: Scope, seat: Seat, seat_id: u32) -> Element {
     })
 }
 
-fn get_canvas() -> web_sys::HtmlCanvasElement {
-    let canvas = gloo_utils::document()
-        .get_element_by_id("game-canvas")
-        .unwrap();
-    canvas
+/// Time left on the clock of the seat, and when it runs out if it's running. The running
+/// clock counts down from when the server last started it.
+fn seat_clock(
+    view: &state::GameView,
+    seat_id: u32,
+) -> (
+    Option<shared::game::clock::Millisecond>,
+    Option<shared::game::clock::Millisecond>,
+) {
+    let time_left = view.time_remaining.get(seat_id as usize).copied();
+    let deadline = view
+        .clock
+        .as_ref()
+        .filter(|_| view.clock_running_for_seat == Some(seat_id))
+        .and_then(|clock| clock.clocks.get(seat_id as usize))
+        .zip(time_left)
+        .map(|(clock, left)| shared::game::clock::Millisecond(clock.last_time().0 + left.0));
+    (time_left, deadline)
+}
+
+/// Clocks and captures of every seat on one line, for `settings::ScoreboardLayout::Bar`.
+#[component]
+fn ScoreBar(cx: Scope) -> Element {
+    let state = state::use_state(cx);
+    let view = state.read().active_room().read().as_ref()?.view.clone();
+    let shared::game::GameStateView::Play(play) = &view.state else {
+        return None;
+    };
+
+    #[rustfmt::skip]
+    let class = sir::css!("
+        grid-column: 1 / -1;
+        display: flex;
+        flex-wrap: wrap;
+        gap: 5px 15px;
+        padding: 5px 10px;
+
+        .turn {
+            font-weight: bold;
+        }
+    ");
+
+    let entries = view.seats.iter().enumerate().map(|(seat_id, seat)| {
+        let seat_id = seat_id as u32;
+        let team = seat.team.as_usize() - 1;
+        let name = shared::game::Color::name(seat.team);
+        let (time_left, deadline) = seat_clock(&view, seat_id);
+        let captured = view.prisoners.get(team).copied().unwrap_or(0);
+        let captures = match view.mods.capture_goal {
+            Some(goal) => format!("{}/{}", captured, goal),
+            None => captured.to_string(),
+        };
+        let passed = play.players_passed.get(seat_id as usize) == Some(&true);
+        let class = if view.turn == seat_id { "turn" } else { "" };
+        rsx!(span {
+            class: "{class}",
+            title: "Clock and stones captured",
+            "{name}"
+            if let Some(deadline) = deadline {
+                rsx!(" · " views::Countdown { deadline: deadline })
+            } else if let Some(left) = time_left {
+                let text = views::format_time(left);
+                rsx!(" · {text}")
+            }
+            " · {captures} captured"
+            if passed { " (passed)" } else { "" }
+        })
+    });
+
+    cx.render(rsx! {
+        div {
+            class: "{class}",
+            entries
+        }
+    })
+}
+
+/// The board presentation of the room owner, or none at all if the user prefers their own.
+fn presented(state: Signal<state::ClientState>) -> BoardPresentation {
+    let presentation = state.read().presentation.read().clone();
+    match settings::FollowPresentation::get().0 {
+        true => presentation,
+        false => BoardPresentation::default(),
+    }
+}
+
+/// Expanding boards were smaller earlier in the game, so an earlier position may have
+/// another size.
+fn with_review_size(view: state::GameView, review: Option<&state::GameHistory>) -> state::GameView {
+    match review {
+        Some(review) if review.size != view.size => state::GameView {
+            size: review.size,
+            ..view
+        },
+        _ => view,
+    }
+}
+
+/// The board canvas, or `None` once the board is gone. Effects and pending resizes can
+/// still run while the game view is being torn down, eg. when navigating away.
+fn get_canvas() -> Option<web_sys::HtmlCanvasElement> {
+    gloo_utils::document()
+        .get_element_by_id("game-canvas")?
         .dyn_into::<web_sys::HtmlCanvasElement>()
-        .map_err(|_| ())
-        .unwrap()
+        .ok()
 }
 
 fn global_style() {
@@ -798,6 +1943,11 @@ fn RoomList(cx: Scope, rooms: Signal<Vec<GameRoom>>) -> Element {
             overflow-y: scroll;
         }
 
+        .continue {
+            padding: 2px;
+            border-bottom: 1px solid var(--text-color);
+        }
+
         a {
             display: flex;
             padding: 2px;
@@ -820,6 +1970,26 @@ fn RoomList(cx: Scope, rooms: Signal<Vec<GameRoom>>) -> Element {
                 flex-shrink: 0;
                 padding-right: 2px;
             }
+
+            .watching {
+                margin-left: auto;
+                padding-left: 4px;
+                flex-shrink: 0;
+                opacity: 0.7;
+            }
+
+            &.your-turn .turn {
+                margin-left: auto;
+                font-weight: bold;
+                color: #9ecaed;
+            }
+
+            svg {
+                width: 36px;
+                height: 36px;
+                flex-shrink: 0;
+                margin-right: 4px;
+            }
         }
     ");
 
@@ -827,6 +1997,20 @@ fn RoomList(cx: Scope, rooms: Signal<Vec<GameRoom>>) -> Element {
     let user = state.read().user.read().clone();
     let user_id = user.user_id;
     let user_name = user.nick.clone();
+    let seated_rooms = state.read().seated_rooms.read().clone();
+    let your_turn_count = seated_rooms.iter().filter(|r| r.your_turn).count();
+    let found_rooms = state.read().found_rooms.read().clone();
+    let shown_rooms = match &found_rooms {
+        Some((found, _)) => found.as_slice(),
+        None => rooms.as_slice(),
+    };
+    let action = ActionSender::new(cx);
+
+    let navigator = use_navigator(cx);
+    if let Some(id) = *state.read().random_game.read() {
+        state.read().random_game.set(None);
+        navigator.push(Route::GameRoute { id });
+    }
 
     cx.render(rsx! {
         div {
@@ -837,6 +2021,18 @@ fn RoomList(cx: Scope, rooms: Signal<Vec<GameRoom>>) -> Element {
                     to: Route::CreateRoute {},
                     div { "Create Game" },
                 }
+                a {
+                    onclick: move |_| action.spectate_random(),
+                    "Watch a game"
+                }
+                Link {
+                    to: Route::PracticeRoute {},
+                    "Practice"
+                }
+                Link {
+                    to: Route::ProblemsRoute {},
+                    "Problems"
+                }
                 Link {
                     to: Route::ProfileRoute {},
                     if let Some(user_name) = user_name.as_deref() {
@@ -848,14 +2044,53 @@ fn RoomList(cx: Scope, rooms: Signal<Vec<GameRoom>>) -> Element {
             }
             div {
                 class: "rooms",
+                if !seated_rooms.is_empty() {
+                    rsx!(div {
+                        class: "continue",
+                        if your_turn_count > 0 {
+                            rsx!("You have {your_turn_count} games to play")
+                        } else {
+                            rsx!("Continue playing")
+                        }
+                        for room in seated_rooms.iter() {
+                            Link {
+                                class: if room.your_turn { "game your-turn" } else { "game" },
+                                to: Route::GameRoute { id: room.room_id },
+                                key: "seated-{room.room_id}",
+                                div { "{room.room_id}" },
+                                if let Some(thumbnail) = &room.thumbnail {
+                                    rsx!(RoomThumbnail { thumbnail: Rc::new(thumbnail.clone()) })
+                                }
+                                div { "{room.name}" },
+                                if room.your_turn {
+                                    rsx!(div { class: "turn", "Your turn" })
+                                }
+                            }
+                        }
+                    })
+                }
+                views::RoomFilterBar {}
                 ul {
-                    for room in rooms.iter() {
+                    for room in shown_rooms.iter() {
                         Link {
                             class: "game",
                             to: Route::GameRoute { id: room.id },
                             key: "{room.id}",
                             div { "{room.id}" },
+                            if let Some(thumbnail) = &room.thumbnail {
+                                rsx!(RoomThumbnail { thumbnail: thumbnail.clone() })
+                            }
                             div { "{room.name}" },
+                            if room.no_spectators {
+                                rsx!(div {
+                                    class: "watching",
+                                    title: "Only players and invited users can join",
+                                    "No spectators"
+                                })
+                            }
+                            if room.spectators > 0 {
+                                rsx!(div { class: "watching", "{room.spectators} watching" })
+                            }
                         }
                     }
                 }
@@ -864,6 +2099,44 @@ fn RoomList(cx: Scope, rooms: Signal<Vec<GameRoom>>) -> Element {
     })
 }
 
+#[component]
+fn RoomThumbnail(cx: Scope, thumbnail: Rc<shared::game::BoardThumbnail>) -> Element {
+    let palette = palette::PaletteOption::get().to_palette();
+    let width = thumbnail.width as usize;
+    let view_box = format!("0 0 {} {}", thumbnail.width, thumbnail.height);
+    let stones = thumbnail
+        .points
+        .iter()
+        .enumerate()
+        .filter(|(_, &team)| team > 0)
+        .map(|(idx, &team)| {
+            let x = (idx % width) as f32 + 0.5;
+            let y = (idx / width) as f32 + 0.5;
+            let color = palette.stone_colors[team as usize - 1];
+            (idx, x, y, color)
+        });
+
+    cx.render(rsx! {
+        svg {
+            view_box: "{view_box}",
+            rect {
+                width: "{thumbnail.width}",
+                height: "{thumbnail.height}",
+                fill: palette.background,
+            }
+            for (idx, x, y, color) in stones {
+                circle {
+                    key: "{idx}",
+                    cx: "{x}",
+                    cy: "{y}",
+                    r: "0.45",
+                    fill: color,
+                }
+            }
+        }
+    })
+}
+
 #[component]
 fn NickInput(cx: Scope, profile: Signal<Profile>) -> Element {
     let action = ActionSender::new(cx);
@@ -883,3 +2156,417 @@ fn NickInput(cx: Scope, profile: Signal<Profile>) -> Element {
         }
     })
 }
+
+#[component]
+fn SnapSetting(cx: Scope) -> Element {
+    let action = ActionSender::new(cx);
+    let current = use_signal(cx, settings::SnapTolerance::get);
+    let on_change = move |e: FormEvent| {
+        let value = &e.inner().value;
+        if let Some(choice) = settings::SnapTolerance::ALL
+            .iter()
+            .find(|x| x.to_string() == *value)
+        {
+            choice.save();
+            current.set(*choice);
+            action.sync_settings();
+        }
+    };
+    cx.render(rsx! {
+        label {
+            style: "margin-top: 10px;",
+            "Snap stones to intersections:",
+        }
+        select {
+            onchange: on_change,
+            for choice in settings::SnapTolerance::ALL {
+                option {
+                    value: "{choice}",
+                    selected: *current.read() == choice,
+                    "{choice}"
+                }
+            }
+        }
+    })
+}
+
+#[component]
+fn StonePlacementSetting(cx: Scope) -> Element {
+    let action = ActionSender::new(cx);
+    let current = use_signal(cx, settings::StonePlacement::get);
+    let on_change = move |e: FormEvent| {
+        let value = &e.inner().value;
+        if let Some(choice) = settings::StonePlacement::ALL
+            .iter()
+            .find(|x| x.to_string() == *value)
+        {
+            choice.save();
+            current.set(*choice);
+            action.sync_settings();
+        }
+    };
+    cx.render(rsx! {
+        label {
+            style: "margin-top: 10px;",
+            "Place stones:",
+        }
+        select {
+            onchange: on_change,
+            for choice in settings::StonePlacement::ALL {
+                option {
+                    value: "{choice}",
+                    selected: *current.read() == choice,
+                    "{choice.label()}"
+                }
+            }
+        }
+    })
+}
+
+#[component]
+fn ScoreboardSetting(cx: Scope) -> Element {
+    let action = ActionSender::new(cx);
+    let current = use_signal(cx, settings::ScoreboardLayout::get);
+    let on_change = move |e: FormEvent| {
+        let value = &e.inner().value;
+        if let Some(choice) = settings::ScoreboardLayout::ALL
+            .iter()
+            .find(|x| x.to_string() == *value)
+        {
+            choice.save();
+            current.set(*choice);
+            action.sync_settings();
+        }
+    };
+    cx.render(rsx! {
+        label {
+            style: "margin-top: 10px;",
+            "Show clocks and captures:",
+        }
+        select {
+            onchange: on_change,
+            for choice in settings::ScoreboardLayout::ALL {
+                option {
+                    value: "{choice}",
+                    selected: *current.read() == choice,
+                    "{choice.label()}"
+                }
+            }
+        }
+    })
+}
+
+#[component]
+fn BoardSizeSetting(cx: Scope) -> Element {
+    let action = ActionSender::new(cx);
+    let current = use_signal(cx, settings::BoardSizing::get);
+    let on_change = move |e: FormEvent| {
+        let value = &e.inner().value;
+        if let Some(choice) = settings::BoardSizing::ALL
+            .iter()
+            .find(|x| x.to_string() == *value)
+        {
+            choice.save();
+            current.set(*choice);
+            action.sync_settings();
+        }
+    };
+    cx.render(rsx! {
+        label {
+            style: "margin-top: 10px;",
+            "Board size:",
+        }
+        select {
+            onchange: on_change,
+            for choice in settings::BoardSizing::ALL {
+                option {
+                    value: "{choice}",
+                    selected: *current.read() == choice,
+                    "{choice.label()}"
+                }
+            }
+        }
+    })
+}
+
+#[component]
+fn LabelSetting(cx: Scope) -> Element {
+    let action = ActionSender::new(cx);
+    let current = use_signal(cx, settings::CoordinateLabels::get);
+    let on_change = move |e: FormEvent| {
+        let value = &e.inner().value;
+        if let Some(choice) = settings::CoordinateLabels::ALL
+            .iter()
+            .find(|x| x.to_string() == *value)
+        {
+            choice.save();
+            current.set(*choice);
+            action.sync_settings();
+        }
+    };
+    cx.render(rsx! {
+        label {
+            style: "margin-top: 10px;",
+            "Coordinates:",
+        }
+        select {
+            onchange: on_change,
+            for choice in settings::CoordinateLabels::ALL {
+                option {
+                    value: "{choice}",
+                    selected: *current.read() == choice,
+                    "{choice.label()}"
+                }
+            }
+        }
+    })
+}
+
+#[component]
+fn SoundSetting(cx: Scope) -> Element {
+    let action = ActionSender::new(cx);
+    let enabled = use_signal(cx, || settings::SoundEnabled::get().0);
+    let toggle = move |_| {
+        let value = !*enabled.read();
+        settings::SoundEnabled(value).save();
+        enabled.set(value);
+        action.sync_settings();
+    };
+    cx.render(rsx! {
+        label {
+            style: "margin-top: 10px;",
+            input {
+                r#type: "checkbox",
+                checked: *enabled.read(),
+                onclick: toggle,
+            }
+            "Play sounds for moves"
+        }
+    })
+}
+
+#[component]
+fn GhostSetting(cx: Scope) -> Element {
+    let action = ActionSender::new(cx);
+    let enabled = use_signal(cx, || settings::ShowGhost::get().0);
+    let toggle = move |_| {
+        let value = !*enabled.read();
+        settings::ShowGhost(value).save();
+        enabled.set(value);
+        action.sync_settings();
+    };
+    cx.render(rsx! {
+        label {
+            style: "margin-top: 10px;",
+            input {
+                r#type: "checkbox",
+                checked: *enabled.read(),
+                onclick: toggle,
+            }
+            "Show a preview stone under the pointer"
+        }
+    })
+}
+
+#[component]
+fn StoneMarkSetting(cx: Scope) -> Element {
+    let action = ActionSender::new(cx);
+    let enabled = use_signal(cx, || settings::StoneMarks::get().0);
+    let toggle = move |_| {
+        let value = !*enabled.read();
+        settings::StoneMarks(value).save();
+        enabled.set(value);
+        action.sync_settings();
+    };
+    cx.render(rsx! {
+        label {
+            style: "margin-top: 10px;",
+            input {
+                r#type: "checkbox",
+                checked: *enabled.read(),
+                onclick: toggle,
+            }
+            "Mark stones with a shape per team"
+        }
+    })
+}
+
+#[component]
+fn ChatScrollSetting(cx: Scope) -> Element {
+    let action = ActionSender::new(cx);
+    let enabled = use_signal(cx, || settings::ChatAutoScroll::get().0);
+    let toggle = move |_| {
+        let value = !*enabled.read();
+        settings::ChatAutoScroll(value).save();
+        enabled.set(value);
+        action.sync_settings();
+    };
+    cx.render(rsx! {
+        label {
+            style: "margin-top: 10px;",
+            input {
+                r#type: "checkbox",
+                checked: *enabled.read(),
+                onclick: toggle,
+            }
+            "Follow new chat messages"
+        }
+    })
+}
+
+#[component]
+fn UndoGraceSetting(cx: Scope) -> Element {
+    let action = ActionSender::new(cx);
+    let seconds = use_signal(cx, || settings::UndoGrace::get().0);
+    let max = settings::UndoGrace::MAX;
+    let on_change = move |e: FormEvent| {
+        let value = e
+            .inner()
+            .value
+            .parse()
+            .unwrap_or(0)
+            .min(settings::UndoGrace::MAX);
+        settings::UndoGrace(value).save();
+        seconds.set(value);
+        action.sync_settings();
+    };
+    cx.render(rsx! {
+        label {
+            style: "margin-top: 10px;",
+            "Seconds to take back a misclick (0 for off):",
+        }
+        input {
+            r#type: "number",
+            min: "0",
+            max: "{max}",
+            value: "{seconds}",
+            onchange: on_change,
+        }
+    })
+}
+
+#[component]
+fn AutoSaveSetting(cx: Scope) -> Element {
+    let action = ActionSender::new(cx);
+    let enabled = use_signal(cx, || settings::AutoSaveSgf::get().0);
+    let toggle = move |_| {
+        let value = !*enabled.read();
+        settings::AutoSaveSgf(value).save();
+        enabled.set(value);
+        action.sync_settings();
+    };
+    cx.render(rsx! {
+        label {
+            style: "margin-top: 10px;",
+            input {
+                r#type: "checkbox",
+                checked: *enabled.read(),
+                onclick: toggle,
+            }
+            "Keep the record of your games in this browser"
+        }
+    })
+}
+
+#[component]
+fn AnnounceSetting(cx: Scope) -> Element {
+    let action = ActionSender::new(cx);
+    let enabled = use_signal(cx, || settings::AnnounceMoves::get().0);
+    let toggle = move |_| {
+        let value = !*enabled.read();
+        settings::AnnounceMoves(value).save();
+        enabled.set(value);
+        action.sync_settings();
+    };
+    cx.render(rsx! {
+        label {
+            style: "margin-top: 10px;",
+            input {
+                r#type: "checkbox",
+                checked: *enabled.read(),
+                onclick: toggle,
+            }
+            "Announce moves to screen readers"
+        }
+    })
+}
+
+#[component]
+fn PresentationSetting(cx: Scope) -> Element {
+    let action = ActionSender::new(cx);
+    let enabled = use_signal(cx, || settings::FollowPresentation::get().0);
+    let toggle = move |_| {
+        let value = !*enabled.read();
+        settings::FollowPresentation(value).save();
+        enabled.set(value);
+        action.sync_settings();
+    };
+    cx.render(rsx! {
+        label {
+            style: "margin-top: 10px;",
+            input {
+                r#type: "checkbox",
+                checked: *enabled.read(),
+                onclick: toggle,
+            }
+            "Show the board the way the room owner shares it"
+        }
+    })
+}
+
+/// Users whose chat and invites we don't get, with a way to take them back.
+#[component]
+fn BlockedUsers(cx: Scope) -> Element {
+    let state = state::use_state(cx);
+    let action = ActionSender::new(cx);
+    let blocked = state.read().blocked;
+    let profiles = state.read().profiles;
+    if blocked.read().0.is_empty() {
+        return None;
+    }
+
+    cx.render(rsx! {
+        div {
+            style: "margin-top: 10px;",
+            "Blocked users:"
+            for &user_id in blocked.read().0.iter() {
+                div {
+                    {
+                        let nick = profiles
+                            .read()
+                            .get(&user_id)
+                            .map(state::username)
+                            .unwrap_or_else(|| "Unknown".to_string());
+                        rsx!("{nick} ")
+                    }
+                    a {
+                        style: "cursor: pointer;",
+                        onclick: move |_| action.block(user_id, false),
+                        "Unblock"
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[component]
+fn AboutInfo(cx: Scope, info: Signal<Option<shared::message::ServerInfo>>) -> Element {
+    let info = info.read();
+    let Some(info) = info.as_ref() else {
+        return None;
+    };
+    let mismatch = info.protocol_version != shared::message::PROTOCOL_VERSION;
+    let uptime_hours = info.uptime_secs / 3600;
+
+    cx.render(rsx! {
+        div {
+            style: "margin-top: 20px;",
+            div { "Server version {info.version} (protocol {info.protocol_version})" }
+            div { "Up for {uptime_hours} hours, {info.room_count} rooms, {info.user_count} users online" }
+            if mismatch {
+                rsx!(div { "The server has been updated, please reload the page." })
+            }
+        }
+    })
+}
@@ -2,7 +2,7 @@ use dioxus::prelude::*;
 use futures::{select, stream::FusedStream, SinkExt, StreamExt};
 use gloo_net::websocket::futures::WebSocket;
 use gloo_timers::future::TimeoutFuture;
-use shared::message::{ClientMessage, ServerMessage};
+use shared::message::{self, ClientMessage, ServerMessage};
 
 use crate::config;
 
@@ -11,10 +11,75 @@ enum LoopFlow {
     Exit,
 }
 
+/// Everything that can go wrong with the connection or a request. Handled in one place,
+/// see `state::handle_error`, instead of each view looking at error messages.
+#[derive(Debug, Clone)]
+pub(crate) enum ClientError {
+    /// The server sent something this client can't read, usually because it's outdated.
+    Decode(String),
+    /// The server speaks another `PROTOCOL_VERSION`. Only a reload helps.
+    IncompatibleVersion {
+        server: u32,
+    },
+    /// Wait this many seconds before creating another game.
+    GameStartTimer(u64),
+    Game {
+        room_id: u32,
+        error: message::GameError,
+    },
+    RateLimit,
+    Other(String),
+}
+
+impl ClientError {
+    /// Shown as a dialog that stays up instead of a toast.
+    pub(crate) fn is_fatal(&self) -> bool {
+        matches!(self, ClientError::IncompatibleVersion { .. })
+    }
+}
+
+impl From<message::Error> for ClientError {
+    fn from(error: message::Error) -> Self {
+        match error {
+            message::Error::GameStartTimer(secs) => ClientError::GameStartTimer(secs),
+            message::Error::Game { room_id, error } => ClientError::Game { room_id, error },
+            message::Error::RateLimit => ClientError::RateLimit,
+            message::Error::Other(text) => ClientError::Other(text.into_owned()),
+        }
+    }
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Decode(_) => write!(f, "Couldn't read a message from the server"),
+            ClientError::IncompatibleVersion { .. } => {
+                write!(f, "The server has been updated, please reload the page")
+            }
+            ClientError::GameStartTimer(secs) => {
+                write!(f, "{}", message::Error::GameStartTimer(*secs))
+            }
+            ClientError::Game { error, .. } => write!(f, "{}", error),
+            ClientError::RateLimit => write!(f, "{}", message::Error::RateLimit),
+            ClientError::Other(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+/// Server errors are passed on as `ClientError`s, the other messages as they are.
+fn decode(bytes: &[u8]) -> Result<ServerMessage, ClientError> {
+    match serde_cbor::from_slice(bytes) {
+        Ok(ServerMessage::Error(error)) => Err(error.into()),
+        Ok(ServerMessage::MsgError(text)) => Err(ClientError::Other(text)),
+        Ok(msg) => Ok(msg),
+        Err(e) => Err(ClientError::Decode(e.to_string())),
+    }
+}
+
 pub(crate) fn use_websocket_provider<'a>(
     cx: &'a ScopeState,
     mut on_connect: impl FnMut() -> Vec<ClientMessage> + 'static,
-    mut receive: impl FnMut(ServerMessage) + 'static,
+    mut receive: impl FnMut(Result<ServerMessage, ClientError>) + 'static,
 ) -> impl Fn(ClientMessage) + 'a {
     use_coroutine(cx, move |rx: UnboundedReceiver<ClientMessage>| async move {
         log::info!("Connecting to WebSocket");
@@ -52,7 +117,7 @@ pub(crate) fn use_websocket_provider<'a>(
 async fn connection_loop(
     ws: WebSocket,
     rx: &mut (impl FusedStream<Item = ClientMessage> + Unpin),
-    receive: &mut impl FnMut(ServerMessage),
+    receive: &mut impl FnMut(Result<ServerMessage, ClientError>),
 ) -> LoopFlow {
     let (mut ws_sender, ws_recv) = ws.split();
     use gloo_net::websocket::Message;
@@ -65,13 +130,14 @@ async fn connection_loop(
                     log::warn!("WebSocket closed");
                     return LoopFlow::Reconnect;
                 };
-                let msg: ServerMessage = match msg {
+                let msg = match msg {
                     Message::Text(text) => {
                         log::warn!("Received text message: {}", text);
-                        serde_cbor::from_slice(text.as_bytes()).unwrap()
+                        decode(text.as_bytes())
                     }
                     Message::Bytes(bytes) => {
-                        serde_cbor::from_slice(&bytes).unwrap()
+                        // Reads both the named and the packed encoding.
+                        decode(&bytes)
                     }
                 };
                 receive(msg);
@@ -23,6 +23,15 @@ impl std::fmt::Display for PaletteOption {
     }
 }
 
+impl From<shared::message::BoardPalette> for PaletteOption {
+    fn from(palette: shared::message::BoardPalette) -> Self {
+        match palette {
+            shared::message::BoardPalette::Normal => PaletteOption::Normal,
+            shared::message::BoardPalette::Colorblind => PaletteOption::Colorblind,
+        }
+    }
+}
+
 impl PaletteOption {
     pub(crate) fn get() -> PaletteOption {
         let val = gloo_storage::LocalStorage::get::<String>("palette").ok();
@@ -0,0 +1,101 @@
+//! Games played against yourself without a server. Both seats are held locally, nothing
+//! is stored and a reload starts over.
+
+use std::rc::Rc;
+
+use shared::game::{self, clock::Millisecond, ActionKind};
+use shared::message::{GameAction, GameError};
+
+use crate::networking::ClientError;
+use crate::state::{self, ActiveRoom, ClientState};
+
+/// Room id of the practice game. Server room ids never get this high.
+pub(crate) const PRACTICE_ROOM_ID: u32 = u32::MAX;
+
+/// Board sizes offered for practice.
+pub(crate) const PRACTICE_SIZES: [u8; 3] = [9, 13, 19];
+
+/// Starts a new practice game with both seats held by `user_id` and shows it.
+pub(crate) fn start(state: &ClientState, user_id: u64, size: u8) {
+    let mut game = game::Game::standard(
+        &[1, 2],
+        game::GroupVec::from(&[0, 15][..]),
+        (size, size),
+        game::GameModifier::default(),
+        0,
+    )
+    .expect("Practice game setup failed");
+    for seat in 0..2 {
+        game.take_seat(user_id, seat).expect("Practice seat taken");
+    }
+    show(state, &game);
+    state.practice.set(Some(game));
+}
+
+/// The user playing the practice game.
+pub(crate) fn player(game: &game::Game) -> Option<u64> {
+    game.shared.seats.iter().find_map(|s| s.player)
+}
+
+/// Shows the practice game again after visiting other rooms.
+pub(crate) fn resume(state: &ClientState) {
+    if let Some(game) = state.practice.read().as_ref() {
+        show(state, game);
+    }
+}
+
+fn show(state: &ClientState, game: &game::Game) {
+    let user_id = player(game).unwrap_or_default();
+    let room = ActiveRoom {
+        id: PRACTICE_ROOM_ID,
+        owner: user_id,
+        members: vec![user_id],
+        view: Rc::new(game.get_view(user_id).into()),
+    };
+    state::set_active_room(state, room);
+}
+
+/// Plays `action` in the practice game. Whatever needs other people is refused.
+pub(crate) fn apply(state: &ClientState, action: GameAction) {
+    let mut practice = state.practice.write();
+    let Some(game) = practice.as_mut() else {
+        return;
+    };
+    let user_id = player(game).unwrap_or_default();
+
+    let kind = match action {
+        GameAction::Place(x, y) => ActionKind::Place(x, y),
+        GameAction::Pass => ActionKind::Pass,
+        GameAction::Cancel => ActionKind::Cancel,
+        GameAction::Resign => ActionKind::Resign,
+        GameAction::BoardAt(start, _) => {
+            if let Some(view) = game.get_view_at(user_id, start) {
+                state.compare.set(Some(view.into()));
+            }
+            return;
+        }
+        GameAction::EstimateScore => {
+            let move_number = game.shared.board_history.len() as u32 - 1;
+            let scores = game.score_estimate(user_id);
+            state.estimate.set(Some((move_number, scores)));
+            return;
+        }
+        GameAction::Typing(_) => return,
+        _ => {
+            let error = ClientError::Other("Not available in practice".to_string());
+            state::handle_error(state, error);
+            return;
+        }
+    };
+
+    match game.make_action(user_id, kind, Millisecond(0)) {
+        Ok(()) => show(state, game),
+        Err(error) => state::handle_error(
+            state,
+            ClientError::Game {
+                room_id: PRACTICE_ROOM_ID,
+                error: GameError::Action(error),
+            },
+        ),
+    }
+}
@@ -0,0 +1,451 @@
+use gloo_storage::Storage;
+
+use shared::game::LabelStyle;
+
+use crate::palette::PaletteOption;
+
+/// How close to an intersection a click or tap has to land to place a stone there.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum SnapTolerance {
+    /// Anywhere in the cell counts.
+    Off,
+    Loose,
+    Strict,
+}
+
+impl std::fmt::Display for SnapTolerance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl SnapTolerance {
+    pub(crate) const ALL: [SnapTolerance; 3] = [
+        SnapTolerance::Off,
+        SnapTolerance::Loose,
+        SnapTolerance::Strict,
+    ];
+
+    pub(crate) fn get() -> SnapTolerance {
+        let val = gloo_storage::LocalStorage::get::<String>("snap_tolerance").ok();
+        match val.as_deref() {
+            Some("Off") => SnapTolerance::Off,
+            Some("Loose") => SnapTolerance::Loose,
+            Some("Strict") => SnapTolerance::Strict,
+            _ => SnapTolerance::Off,
+        }
+    }
+
+    pub(crate) fn save(&self) {
+        gloo_storage::LocalStorage::set("snap_tolerance", &format!("{:?}", self)).unwrap();
+    }
+
+    /// Accepted distance from the intersection, in cells.
+    pub(crate) fn radius(&self) -> f64 {
+        match self {
+            // Corners of a cell are ~0.71 cells away from the intersection.
+            SnapTolerance::Off => 1.0,
+            SnapTolerance::Loose => 0.6,
+            SnapTolerance::Strict => 0.4,
+        }
+    }
+}
+
+/// Where stones sit on the drawn grid.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum StonePlacement {
+    /// On the line intersections, like a real board. The default.
+    Intersections,
+    /// Inside the squares, with the lines around each point.
+    Cells,
+}
+
+impl std::fmt::Display for StonePlacement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl StonePlacement {
+    pub(crate) const ALL: [StonePlacement; 2] =
+        [StonePlacement::Intersections, StonePlacement::Cells];
+
+    pub(crate) fn get() -> StonePlacement {
+        let val = gloo_storage::LocalStorage::get::<String>("stone_placement").ok();
+        match val.as_deref() {
+            Some("Cells") => StonePlacement::Cells,
+            _ => StonePlacement::Intersections,
+        }
+    }
+
+    pub(crate) fn save(&self) {
+        gloo_storage::LocalStorage::set("stone_placement", &format!("{:?}", self)).unwrap();
+    }
+
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            StonePlacement::Intersections => "On the intersections",
+            StonePlacement::Cells => "Inside the cells",
+        }
+    }
+}
+
+/// Where the clocks and capture counts go during play.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum ScoreboardLayout {
+    /// On each seat card, under the player.
+    Panels,
+    /// One compact line under the seat cards.
+    Bar,
+}
+
+impl std::fmt::Display for ScoreboardLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl ScoreboardLayout {
+    pub(crate) const ALL: [ScoreboardLayout; 2] = [ScoreboardLayout::Panels, ScoreboardLayout::Bar];
+
+    pub(crate) fn get() -> ScoreboardLayout {
+        let val = gloo_storage::LocalStorage::get::<String>("scoreboard_layout").ok();
+        match val.as_deref() {
+            Some("Bar") => ScoreboardLayout::Bar,
+            _ => ScoreboardLayout::Panels,
+        }
+    }
+
+    pub(crate) fn save(&self) {
+        gloo_storage::LocalStorage::set("scoreboard_layout", &format!("{:?}", self)).unwrap();
+    }
+
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            ScoreboardLayout::Panels => "On each seat",
+            ScoreboardLayout::Bar => "In one bar",
+        }
+    }
+}
+
+/// How big the board is drawn. Fixed sizes still shrink to fit smaller windows.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum BoardSizing {
+    /// As large as the space next to the panels allows.
+    Fit,
+    Small,
+    Medium,
+    Large,
+}
+
+impl std::fmt::Display for BoardSizing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl BoardSizing {
+    pub(crate) const ALL: [BoardSizing; 4] = [
+        BoardSizing::Fit,
+        BoardSizing::Small,
+        BoardSizing::Medium,
+        BoardSizing::Large,
+    ];
+
+    pub(crate) fn get() -> BoardSizing {
+        let val = gloo_storage::LocalStorage::get::<String>("board_sizing").ok();
+        match val.as_deref() {
+            Some("Small") => BoardSizing::Small,
+            Some("Medium") => BoardSizing::Medium,
+            Some("Large") => BoardSizing::Large,
+            _ => BoardSizing::Fit,
+        }
+    }
+
+    pub(crate) fn save(&self) {
+        gloo_storage::LocalStorage::set("board_sizing", &format!("{:?}", self)).unwrap();
+    }
+
+    /// Side length of the board in CSS pixels, `None` to fill the space.
+    pub(crate) fn pixels(&self) -> Option<f64> {
+        match self {
+            BoardSizing::Fit => None,
+            BoardSizing::Small => Some(480.0),
+            BoardSizing::Medium => Some(600.0),
+            BoardSizing::Large => Some(800.0),
+        }
+    }
+
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            BoardSizing::Fit => "Fit to window",
+            BoardSizing::Small => "480 pixels",
+            BoardSizing::Medium => "600 pixels",
+            BoardSizing::Large => "800 pixels",
+        }
+    }
+}
+
+/// Labels drawn around the board and expected when typing a coordinate.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct CoordinateLabels(pub(crate) LabelStyle);
+
+impl std::fmt::Display for CoordinateLabels {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+impl CoordinateLabels {
+    pub(crate) const ALL: [CoordinateLabels; 2] = [
+        CoordinateLabels(LabelStyle::Letters),
+        CoordinateLabels(LabelStyle::Numbers),
+    ];
+
+    pub(crate) fn get() -> CoordinateLabels {
+        let val = gloo_storage::LocalStorage::get::<String>("coordinate_labels").ok();
+        match val.as_deref() {
+            Some("Numbers") => CoordinateLabels(LabelStyle::Numbers),
+            _ => CoordinateLabels(LabelStyle::Letters),
+        }
+    }
+
+    pub(crate) fn save(&self) {
+        gloo_storage::LocalStorage::set("coordinate_labels", &self.to_string()).unwrap();
+    }
+
+    pub(crate) fn label(&self) -> &'static str {
+        match self.0 {
+            LabelStyle::Letters => "Letters and numbers (D4)",
+            LabelStyle::Numbers => "Numbers only (4-4)",
+        }
+    }
+}
+
+/// Plays a tone whenever a stone is placed. Off by default.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct SoundEnabled(pub(crate) bool);
+
+impl SoundEnabled {
+    pub(crate) fn get() -> SoundEnabled {
+        let val = gloo_storage::LocalStorage::get::<bool>("sound_enabled").ok();
+        SoundEnabled(val.unwrap_or(false))
+    }
+
+    pub(crate) fn save(&self) {
+        gloo_storage::LocalStorage::set("sound_enabled", self.0).unwrap();
+    }
+}
+
+/// Draws a translucent preview stone under the pointer. On by default.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct ShowGhost(pub(crate) bool);
+
+impl ShowGhost {
+    pub(crate) fn get() -> ShowGhost {
+        let val = gloo_storage::LocalStorage::get::<bool>("show_ghost").ok();
+        ShowGhost(val.unwrap_or(true))
+    }
+
+    pub(crate) fn save(&self) {
+        gloo_storage::LocalStorage::set("show_ghost", self.0).unwrap();
+    }
+}
+
+/// Marks every stone with a small per-team glyph, so teams differ by more than color.
+/// Off by default.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct StoneMarks(pub(crate) bool);
+
+impl StoneMarks {
+    pub(crate) fn get() -> StoneMarks {
+        let val = gloo_storage::LocalStorage::get::<bool>("stone_marks").ok();
+        StoneMarks(val.unwrap_or(false))
+    }
+
+    pub(crate) fn save(&self) {
+        gloo_storage::LocalStorage::set("stone_marks", self.0).unwrap();
+    }
+}
+
+/// Shows the score estimate bar during play. Off by default.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct ScoreEstimate(pub(crate) bool);
+
+impl ScoreEstimate {
+    pub(crate) fn get() -> ScoreEstimate {
+        let val = gloo_storage::LocalStorage::get::<bool>("score_estimate").ok();
+        ScoreEstimate(val.unwrap_or(false))
+    }
+
+    pub(crate) fn save(&self) {
+        gloo_storage::LocalStorage::set("score_estimate", self.0).unwrap();
+    }
+}
+
+/// Keeps the chat scrolled to the newest line unless the user scrolled up. On by default.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct ChatAutoScroll(pub(crate) bool);
+
+impl ChatAutoScroll {
+    pub(crate) fn get() -> ChatAutoScroll {
+        let val = gloo_storage::LocalStorage::get::<bool>("chat_auto_scroll").ok();
+        ChatAutoScroll(val.unwrap_or(true))
+    }
+
+    pub(crate) fn save(&self) {
+        gloo_storage::LocalStorage::set("chat_auto_scroll", self.0).unwrap();
+    }
+}
+
+/// Shades the board by which team's stones are nearest during play. Off by default.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct Influence(pub(crate) bool);
+
+impl Influence {
+    pub(crate) fn get() -> Influence {
+        let val = gloo_storage::LocalStorage::get::<bool>("influence").ok();
+        Influence(val.unwrap_or(false))
+    }
+
+    pub(crate) fn save(&self) {
+        gloo_storage::LocalStorage::set("influence", self.0).unwrap();
+    }
+}
+
+/// Seconds a misclicked stone can be taken back before the opponent answers. 0 turns
+/// the offer off. 3 by default.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct UndoGrace(pub(crate) u32);
+
+impl UndoGrace {
+    pub(crate) const MAX: u32 = 10;
+
+    pub(crate) fn get() -> UndoGrace {
+        let val = gloo_storage::LocalStorage::get::<u32>("undo_grace").ok();
+        UndoGrace(val.unwrap_or(3).min(Self::MAX))
+    }
+
+    pub(crate) fn save(&self) {
+        gloo_storage::LocalStorage::set("undo_grace", self.0).unwrap();
+    }
+}
+
+/// Keeps the record of the game being played in this browser, see `autosave`. Off by
+/// default.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct AutoSaveSgf(pub(crate) bool);
+
+impl AutoSaveSgf {
+    pub(crate) fn get() -> AutoSaveSgf {
+        let val = gloo_storage::LocalStorage::get::<bool>("autosave_sgf").ok();
+        AutoSaveSgf(val.unwrap_or(false))
+    }
+
+    pub(crate) fn save(&self) {
+        gloo_storage::LocalStorage::set("autosave_sgf", self.0).unwrap();
+    }
+}
+
+/// Reads each move out through a screen reader live region, see `views::MoveAnnouncer`.
+/// Off by default.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct AnnounceMoves(pub(crate) bool);
+
+impl AnnounceMoves {
+    pub(crate) fn get() -> AnnounceMoves {
+        let val = gloo_storage::LocalStorage::get::<bool>("announce_moves").ok();
+        AnnounceMoves(val.unwrap_or(false))
+    }
+
+    pub(crate) fn save(&self) {
+        gloo_storage::LocalStorage::set("announce_moves", self.0).unwrap();
+    }
+}
+
+/// Draws the board the way the room owner shares it, see `views::PresentationPanel`. On
+/// by default.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct FollowPresentation(pub(crate) bool);
+
+impl FollowPresentation {
+    pub(crate) fn get() -> FollowPresentation {
+        let val = gloo_storage::LocalStorage::get::<bool>("follow_presentation").ok();
+        FollowPresentation(val.unwrap_or(true))
+    }
+
+    pub(crate) fn save(&self) {
+        gloo_storage::LocalStorage::set("follow_presentation", self.0).unwrap();
+    }
+}
+
+/// The settings that are synced to the server so they follow the user across devices.
+pub(crate) fn to_json() -> serde_json::Value {
+    serde_json::json!({
+        "palette": PaletteOption::get().to_string(),
+        "snap_tolerance": SnapTolerance::get().to_string(),
+        "stone_placement": StonePlacement::get().to_string(),
+        "scoreboard_layout": ScoreboardLayout::get().to_string(),
+        "board_sizing": BoardSizing::get().to_string(),
+        "coordinate_labels": CoordinateLabels::get().to_string(),
+        "sound_enabled": SoundEnabled::get().0,
+        "show_ghost": ShowGhost::get().0,
+        "stone_marks": StoneMarks::get().0,
+        "score_estimate": ScoreEstimate::get().0,
+        "chat_auto_scroll": ChatAutoScroll::get().0,
+        "influence": Influence::get().0,
+        "undo_grace": UndoGrace::get().0,
+        "autosave_sgf": AutoSaveSgf::get().0,
+        "announce_moves": AnnounceMoves::get().0,
+        "follow_presentation": FollowPresentation::get().0,
+    })
+}
+
+/// Stores settings received from the server locally. Unknown keys are ignored.
+pub(crate) fn apply_json(settings: &serde_json::Value) {
+    for key in [
+        "palette",
+        "snap_tolerance",
+        "stone_placement",
+        "scoreboard_layout",
+        "board_sizing",
+        "coordinate_labels",
+    ] {
+        if let Some(value) = settings.get(key).and_then(|v| v.as_str()) {
+            let _ = gloo_storage::LocalStorage::set(key, value);
+        }
+    }
+    if let Some(value) = settings.get("sound_enabled").and_then(|v| v.as_bool()) {
+        SoundEnabled(value).save();
+    }
+    if let Some(value) = settings.get("show_ghost").and_then(|v| v.as_bool()) {
+        ShowGhost(value).save();
+    }
+    if let Some(value) = settings.get("stone_marks").and_then(|v| v.as_bool()) {
+        StoneMarks(value).save();
+    }
+    if let Some(value) = settings.get("score_estimate").and_then(|v| v.as_bool()) {
+        ScoreEstimate(value).save();
+    }
+    if let Some(value) = settings.get("chat_auto_scroll").and_then(|v| v.as_bool()) {
+        ChatAutoScroll(value).save();
+    }
+    if let Some(value) = settings.get("influence").and_then(|v| v.as_bool()) {
+        Influence(value).save();
+    }
+    if let Some(value) = settings.get("undo_grace").and_then(|v| v.as_u64()) {
+        UndoGrace(value.min(UndoGrace::MAX as u64) as u32).save();
+    }
+    if let Some(value) = settings.get("autosave_sgf").and_then(|v| v.as_bool()) {
+        AutoSaveSgf(value).save();
+    }
+    if let Some(value) = settings.get("announce_moves").and_then(|v| v.as_bool()) {
+        AnnounceMoves(value).save();
+    }
+    if let Some(value) = settings
+        .get("follow_presentation")
+        .and_then(|v| v.as_bool())
+    {
+        FollowPresentation(value).save();
+    }
+}
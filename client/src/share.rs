@@ -0,0 +1,106 @@
+//! Links that open a game for review in any browser. Short games carry their SGF in the
+//! link, longer ones point at the room on the server.
+
+use std::rc::Rc;
+
+use shared::game::{self, clock::Millisecond, export, variations::VariationTree, ActionKind};
+use web_sys::wasm_bindgen::JsValue;
+
+use crate::state::{self, ActiveRoom, Analysis, ClientState};
+
+/// Room id of a game opened from a link. Server room ids never get this high.
+pub(crate) const SHARED_ROOM_ID: u32 = u32::MAX - 1;
+
+/// Longest SGF put in a link once encoded. Longer links get cut off by some browsers and
+/// chat apps.
+pub(crate) const MAX_EMBEDDED_LENGTH: usize = 2000;
+
+/// What a review link opens.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum SharedGame {
+    Room(u32),
+    Sgf(String),
+}
+
+/// Rooms only known to this client, which other browsers can't open.
+pub(crate) fn is_local(room_id: u32) -> bool {
+    room_id >= SHARED_ROOM_ID
+}
+
+/// Link to review `sgf`, which goes in the fragment so it never reaches the server.
+/// Records that are too long or can't be read back link to `room_id` instead, if there
+/// is a room on the server.
+pub(crate) fn link(room_id: Option<u32>, sgf: &str) -> String {
+    let origin = gloo_utils::window().location().origin().unwrap_or_default();
+    let encoded = String::from(web_sys::js_sys::encode_uri_component(sgf));
+    let embeddable = encoded.len() <= MAX_EMBEDDED_LENGTH && export::parse_sgf(sgf).is_ok();
+    match room_id.filter(|&id| !is_local(id)) {
+        Some(room_id) if !embeddable => format!("{origin}/review#room={room_id}"),
+        _ => format!("{origin}/review#sgf={encoded}"),
+    }
+}
+
+pub(crate) fn copy_link(link: &str) -> Result<(), JsValue> {
+    let clipboard = gloo_utils::window()
+        .navigator()
+        .clipboard()
+        .ok_or_else(|| JsValue::from_str("Clipboard not available"))?;
+    // The promise only reports whether the browser allowed the write.
+    let _ = clipboard.write_text(link);
+    Ok(())
+}
+
+/// Reads the fragment of a review link, with or without the leading `#`. The error is
+/// shown to the user.
+pub(crate) fn parse_fragment(fragment: &str) -> Result<SharedGame, String> {
+    let fragment = fragment.trim_start_matches('#');
+    if let Some(room_id) = fragment.strip_prefix("room=") {
+        return match room_id.parse() {
+            Ok(room_id) => Ok(SharedGame::Room(room_id)),
+            Err(_) => Err("The link points at a room that doesn't exist".to_string()),
+        };
+    }
+    if let Some(encoded) = fragment.strip_prefix("sgf=") {
+        return match web_sys::js_sys::decode_uri_component(encoded) {
+            Ok(sgf) => Ok(SharedGame::Sgf(sgf.into())),
+            Err(_) => Err("The link is garbled, it may have been cut off".to_string()),
+        };
+    }
+    Err("The link doesn't contain a game".to_string())
+}
+
+/// Shows the game of a review link as a finished local game with its moves open for
+/// analysis. Nothing is sent to the server.
+pub(crate) fn open(state: &ClientState, user_id: u64, sgf: &str) -> Result<(), String> {
+    let tree = match export::parse_sgf(sgf) {
+        Ok(tree) => tree,
+        Err(e) => return Err(format!("The game in the link can't be read: {:?}", e)),
+    };
+
+    let size = (tree.board.width as u8, tree.board.height as u8);
+    let komis = game::GroupVec::from(&[0, 0][..]);
+    let mods = game::GameModifier::default();
+    let Some(mut game) = game::Game::standard(&[1, 2], komis, size, mods, 0) else {
+        return Err("The board in the link is too large".to_string());
+    };
+    for seat in 0..2 {
+        game.take_seat(user_id, seat).expect("Review seat taken");
+    }
+    // Both pass and accept the empty board, so the game is over and can be analyzed.
+    for _ in 0..3 {
+        let _ = game.make_action(user_id, ActionKind::Pass, Millisecond(0));
+    }
+
+    let room = ActiveRoom {
+        id: SHARED_ROOM_ID,
+        owner: user_id,
+        members: vec![user_id],
+        view: Rc::new(game.get_view(user_id).into()),
+    };
+    state::set_active_room(state, room);
+    state.analysis.set(Some(Analysis {
+        tree,
+        selected: VariationTree::ROOT,
+    }));
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use std::cell::RefCell;
+
+use web_sys::{AudioContext, OscillatorType};
+
+use crate::settings::SoundEnabled;
+
+/// Short tones for stones being placed, captures and games starting. Synthesized so no audio assets are needed.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum Sound {
+    /// A stone placed by one of your own seats.
+    OwnMove,
+    /// A stone placed by someone else, arriving from the server.
+    OpponentMove,
+    /// Play began in the room being watched.
+    GameStart,
+    /// A move captured this many stones. Bigger captures sound lower, longer and louder.
+    Capture(u32),
+}
+
+thread_local! {
+    // Browsers limit the number of audio contexts, so reuse one.
+    static CONTEXT: RefCell<Option<AudioContext>> = RefCell::new(None);
+}
+
+pub(crate) fn play(sound: Sound) {
+    if !SoundEnabled::get().0 {
+        return;
+    }
+    if let Err(e) = play_tone(sound) {
+        log::warn!("Failed to play sound: {:?}", e);
+    }
+}
+
+fn play_tone(sound: Sound) -> Result<(), web_sys::wasm_bindgen::JsValue> {
+    let context = CONTEXT.with(|c| -> Result<_, web_sys::wasm_bindgen::JsValue> {
+        let mut c = c.borrow_mut();
+        if c.is_none() {
+            *c = Some(AudioContext::new()?);
+        }
+        Ok(c.clone().unwrap())
+    })?;
+
+    let (frequency, length, volume) = match sound {
+        Sound::OwnMove => (660.0, 0.08, 0.3),
+        Sound::OpponentMove => (440.0, 0.15, 0.3),
+        Sound::GameStart => (880.0, 0.3, 0.3),
+        Sound::Capture(0..=1) => (520.0, 0.15, 0.3),
+        Sound::Capture(2..=5) => (390.0, 0.25, 0.4),
+        Sound::Capture(6..=14) => (290.0, 0.4, 0.5),
+        Sound::Capture(_) => (200.0, 0.6, 0.6),
+    };
+
+    let now = context.current_time();
+    let oscillator = context.create_oscillator()?;
+    oscillator.set_type(OscillatorType::Triangle);
+    oscillator.frequency().set_value(frequency);
+
+    let gain = context.create_gain()?;
+    gain.gain().set_value_at_time(volume, now)?;
+    gain.gain()
+        .exponential_ramp_to_value_at_time(0.001, now + length)?;
+
+    oscillator.connect_with_audio_node(&gain)?;
+    gain.connect_with_audio_node(&context.destination())?;
+    oscillator.start()?;
+    oscillator.stop_with_when(now + length)?;
+
+    Ok(())
+}
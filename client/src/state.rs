@@ -1,6 +1,7 @@
 use std::{collections::HashMap, rc::Rc};
 
-use crate::networking::use_websocket_provider;
+use crate::networking::{use_websocket_provider, ClientError};
+use crate::{autosave, practice, share, sound};
 use dioxus::prelude::*;
 use dioxus_signals::{ReadOnlySignal, Signal};
 use futures::StreamExt;
@@ -16,6 +17,71 @@ pub(crate) struct ClientState {
     pub(crate) user: Signal<Profile>,
     pub(crate) profiles: Signal<HashMap<u64, Profile>>,
     pub(crate) rooms: Signal<Vec<GameRoom>>,
+    /// Lobby filter picked by the user, `None` lists every room in `rooms`.
+    pub(crate) room_filter: Signal<Option<message::RoomFilter>>,
+    /// The page of rooms matching `room_filter` and how many match on every page.
+    /// Not kept up to date like `rooms`, changing the filter asks again.
+    pub(crate) found_rooms: Signal<Option<(Vec<GameRoom>, u32)>>,
+    pub(crate) server_info: Signal<Option<message::ServerInfo>>,
+    /// Rooms where we hold a seat, offered for rejoining after a reconnect.
+    pub(crate) seated_rooms: Signal<Vec<message::SeatedRoom>>,
+    /// Chat lines of the active room, both channels.
+    pub(crate) chat: Signal<Vec<message::ChatLine>>,
+    /// Others writing in the chat of the active room, with the local time of their last notice.
+    pub(crate) typing: Signal<HashMap<u64, (message::ChatChannel, f64)>>,
+    /// The last stone tried in a problem didn't follow the solution.
+    pub(crate) wrong_move: Signal<bool>,
+    /// Review link of the active room, shown in case the browser refused to copy it.
+    pub(crate) share_link: Signal<Option<String>>,
+    /// Problems of the server's problem set we solved, and how many there are.
+    pub(crate) problem_progress: Signal<Option<(u32, u32)>>,
+    /// Board of the post-game review of the active room, shown instead of the final position.
+    pub(crate) review: Signal<Option<GameHistory>>,
+    /// Local variation tree for studying the finished game in the active room.
+    /// Never sent to the server, the shared review is separate.
+    pub(crate) analysis: Signal<Option<Analysis>>,
+    /// Drawn by the teacher of the active room, on top of whatever board is shown.
+    pub(crate) annotations: Signal<Vec<game::Annotation>>,
+    /// How the owner of the active room wants the board drawn, see
+    /// `settings::FollowPresentation`.
+    pub(crate) presentation: Signal<message::BoardPresentation>,
+    /// Board clicks draw this instead of placing stones. Only for the teacher.
+    pub(crate) annotation_tool: Signal<Option<AnnotationTool>>,
+    /// First point of a line or arrow being drawn.
+    pub(crate) annotation_start: Signal<Option<game::Point>>,
+    /// Earlier position of the active room shown next to the live board.
+    pub(crate) compare: Signal<Option<GameHistory>>,
+    /// Score estimate of the active room from the server, with the move it was made at.
+    pub(crate) estimate: Signal<Option<(u32, game::GroupVec<i32>)>>,
+    /// Errors waiting to be dismissed, oldest first. See `handle_error`.
+    pub(crate) errors: Signal<Vec<ClientError>>,
+    /// An error nothing but a reload fixes.
+    pub(crate) fatal_error: Signal<Option<ClientError>>,
+    /// Shade the board by influence, see `settings::Influence`. Toggled during games.
+    pub(crate) influence: Signal<bool>,
+    /// Show the scoring board with dead stones taken off. Only changes what we draw.
+    pub(crate) remove_dead: Signal<bool>,
+    /// Draw `board::hypothetical_count` of the live position. Never leaves the client.
+    pub(crate) hypothetical: Signal<bool>,
+    /// Last move of the active room read out by `views::MoveAnnouncer`, kept empty
+    /// unless `settings::AnnounceMoves` is on.
+    pub(crate) announcement: Signal<String>,
+    /// Local game played against yourself, shown as the active room while practicing.
+    /// Lost on reload.
+    pub(crate) practice: Signal<Option<game::Game>>,
+    /// Room the server picked for us to watch, until we navigate there.
+    pub(crate) random_game: Signal<Option<u32>>,
+    /// Local time in milliseconds until which our last stone can be taken back, see
+    /// `settings::UndoGrace`.
+    pub(crate) undo_grace_until: Signal<Option<f64>>,
+    /// Invitations to rooms waiting for an answer, oldest first.
+    pub(crate) invites: Signal<Vec<Invite>>,
+    /// User we join as a spectator wherever they play. Forgotten by the server on reconnect.
+    pub(crate) following: Signal<Option<u64>>,
+    /// Users we don't get chat or invites from, kept by the server.
+    pub(crate) blocked: Signal<message::BlockList>,
+    /// Milliseconds to add to the local time to get the server time.
+    server_time_offset: Signal<f64>,
     active_room: Signal<Option<ActiveRoom>>,
 }
 
@@ -25,10 +91,55 @@ impl ClientState {
             user: Signal::new(Profile::default()),
             profiles: Signal::new(HashMap::new()),
             rooms: Signal::new(Vec::new()),
+            room_filter: Signal::new(None),
+            found_rooms: Signal::new(None),
+            server_info: Signal::new(None),
+            seated_rooms: Signal::new(Vec::new()),
+            chat: Signal::new(Vec::new()),
+            typing: Signal::new(HashMap::new()),
+            wrong_move: Signal::new(false),
+            share_link: Signal::new(None),
+            problem_progress: Signal::new(None),
+            review: Signal::new(None),
+            analysis: Signal::new(None),
+            annotations: Signal::new(Vec::new()),
+            presentation: Signal::new(message::BoardPresentation::default()),
+            annotation_tool: Signal::new(None),
+            annotation_start: Signal::new(None),
+            compare: Signal::new(None),
+            estimate: Signal::new(None),
+            errors: Signal::new(Vec::new()),
+            fatal_error: Signal::new(None),
+            practice: Signal::new(None),
+            influence: Signal::new(crate::settings::Influence::get().0),
+            remove_dead: Signal::new(false),
+            hypothetical: Signal::new(false),
+            announcement: Signal::new(String::new()),
+            random_game: Signal::new(None),
+            undo_grace_until: Signal::new(None),
+            invites: Signal::new(Vec::new()),
+            following: Signal::new(None),
+            blocked: Signal::new(message::BlockList::default()),
+            server_time_offset: Signal::new(0.0),
             active_room: Signal::new(None),
         }
     }
 
+    /// Current time on the server, as used by clocks and deadlines in `GameView`.
+    pub(crate) fn server_time(&self) -> game::clock::Millisecond {
+        let now = web_sys::js_sys::Date::now() + *self.server_time_offset.read();
+        game::clock::Millisecond(now as i128)
+    }
+
+    /// The position to show instead of the game board: the local analysis if one is open,
+    /// otherwise the shared review.
+    pub(crate) fn shown_history(&self) -> Option<GameHistory> {
+        if let Some(analysis) = self.analysis.read().as_ref() {
+            return Some(analysis.history());
+        }
+        self.review.read().clone()
+    }
+
     pub(crate) fn active_room(&self) -> ReadOnlySignal<Option<ActiveRoom>> {
         ReadOnlySignal::new(self.active_room)
     }
@@ -38,6 +149,10 @@ impl ClientState {
 pub(crate) struct GameRoom {
     pub(crate) id: u32,
     pub(crate) name: Rc<str>,
+    pub(crate) thumbnail: Option<Rc<game::BoardThumbnail>>,
+    pub(crate) spectators: u32,
+    /// Only players and invited users may join, see `game::GameModifier::no_spectators`.
+    pub(crate) no_spectators: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -48,6 +163,43 @@ pub(crate) struct ActiveRoom {
     pub(crate) view: Rc<GameView>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Invite {
+    pub(crate) from: u64,
+    pub(crate) room_id: u32,
+    pub(crate) room_name: String,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum AnnotationTool {
+    Circle,
+    Triangle,
+    /// Letters go A, B, C.. in the order they're drawn.
+    Letter,
+    Line,
+    Arrow,
+}
+
+impl AnnotationTool {
+    pub(crate) const ALL: [AnnotationTool; 5] = [
+        AnnotationTool::Circle,
+        AnnotationTool::Triangle,
+        AnnotationTool::Letter,
+        AnnotationTool::Line,
+        AnnotationTool::Arrow,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            AnnotationTool::Circle => "Circle",
+            AnnotationTool::Triangle => "Triangle",
+            AnnotationTool::Letter => "Letter",
+            AnnotationTool::Line => "Line",
+            AnnotationTool::Arrow => "Arrow",
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct GameView {
     pub(crate) state: game::GameStateView,
@@ -61,28 +213,131 @@ pub(crate) struct GameView {
     pub(crate) points: Vec<i32>,
     pub(crate) move_number: u32,
     pub(crate) clock: Option<game::clock::GameClock>,
+    pub(crate) waiting_for_player: bool,
+    pub(crate) pass_suggested: bool,
+    pub(crate) problem_solved: bool,
+    pub(crate) repetition: bool,
+    pub(crate) result: Option<game::GameResult>,
+    pub(crate) scoring_timeout: Option<game::clock::Millisecond>,
+    pub(crate) time_remaining: Vec<game::clock::Millisecond>,
+    pub(crate) clock_running_for_seat: Option<u32>,
+    pub(crate) ko_points: Vec<(u32, u32)>,
+    pub(crate) prisoners: game::GroupVec<u32>,
+    pub(crate) dame: Vec<(u32, u32)>,
+    pub(crate) restricted_points: Vec<(u32, u32)>,
+    pub(crate) swap_available: bool,
+    pub(crate) max_moves: u32,
+    pub(crate) komis: game::GroupVec<i32>,
+    pub(crate) skips: Vec<(u32, u32)>,
+    pub(crate) pause: Option<game::Pause>,
+    pub(crate) capture_bonuses: game::GroupVec<i32>,
+    pub(crate) players_missing: u32,
+    pub(crate) places: game::GroupVec<u32>,
+    pub(crate) consents: Vec<game::ConsentRequest>,
+    pub(crate) pass_streak: u32,
+}
+
+impl From<game::GameView> for GameView {
+    fn from(view: game::GameView) -> Self {
+        GameView {
+            state: view.state,
+            seats: view.seats.to_vec(),
+            turn: view.turn,
+            board: view.board,
+            board_visibility: view.board_visibility,
+            hidden_stones_left: view.hidden_stones_left,
+            size: view.size,
+            mods: view.mods,
+            points: view.points.to_vec(),
+            move_number: view.move_number,
+            clock: view.clock,
+            waiting_for_player: view.waiting_for_player,
+            pass_suggested: view.pass_suggested,
+            problem_solved: view.problem_solved,
+            repetition: view.repetition,
+            result: view.result,
+            scoring_timeout: view.scoring_timeout,
+            time_remaining: view.time_remaining,
+            clock_running_for_seat: view.clock_running_for_seat,
+            ko_points: view.ko_points,
+            prisoners: view.prisoners,
+            dame: view.dame,
+            restricted_points: view.restricted_points,
+            swap_available: view.swap_available,
+            max_moves: view.max_moves,
+            komis: view.komis,
+            skips: view.skips,
+            pause: view.pause,
+            capture_bonuses: view.capture_bonuses,
+            players_missing: view.players_missing,
+            places: view.places,
+            consents: view.consents,
+            pass_streak: view.pass_streak,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct GameHistory {
+    pub(crate) size: (u8, u8),
     pub(crate) board: Vec<game::Color>,
     pub(crate) board_visibility: Option<Vec<u16>>,
     pub(crate) last_stone: Option<game::GroupVec<(u32, u32)>>,
+    pub(crate) captured: Vec<(u32, u32)>,
     pub(crate) move_number: u32,
 }
 
+impl From<game::GameHistory> for GameHistory {
+    fn from(view: game::GameHistory) -> Self {
+        GameHistory {
+            size: view.size,
+            board: view.board.into_iter().map(game::Color).collect(),
+            board_visibility: view.board_visibility,
+            last_stone: view.last_stone,
+            captured: view.captured,
+            move_number: view.move_number,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Analysis {
+    pub(crate) tree: game::variations::VariationTree,
+    /// The node shown on the board.
+    pub(crate) selected: usize,
+}
+
+impl Analysis {
+    fn history(&self) -> GameHistory {
+        let board = self.tree.board_at(self.selected);
+        let stone = self.tree.nodes[self.selected].stone;
+        GameHistory {
+            size: (board.width as u8, board.height as u8),
+            board: board.points,
+            board_visibility: None,
+            last_stone: stone.map(|(p, _)| game::GroupVec::from(&[p][..])),
+            captured: Vec::new(),
+            move_number: self.tree.path(self.selected).len() as u32 - 1,
+        }
+    }
+}
+
 fn on_connect() -> Vec<ClientMessage> {
     vec![
+        // First so that every reply is already packed.
+        ClientMessage::Encoding(message::Encoding::PackedCbor),
         ClientMessage::Identify {
             token: get_token(),
             nick: None,
         },
         ClientMessage::GetGameList,
+        ClientMessage::GetInfo,
     ]
 }
 
 enum RoomEvent {
     Announce(GameRoom),
+    Spectators(u32, u32),
     Close(u32),
 }
 
@@ -125,6 +380,13 @@ pub(crate) fn use_state_provider(cx: &ScopeState) -> Signal<ClientState> {
     });
 
     let on_message = move |msg| {
+        let msg = match msg {
+            Ok(msg) => msg,
+            Err(error) => {
+                handle_error(&state.read(), error);
+                return;
+            }
+        };
         if !matches!(msg, ServerMessage::ServerTime(_)) {
             log::debug!("Received: {:?}", msg);
         }
@@ -134,67 +396,214 @@ pub(crate) fn use_state_provider(cx: &ScopeState) -> Signal<ClientState> {
                 token,
                 nick,
                 user_id,
+                settings,
+                blocked,
             } => {
                 set_token(&token);
-                state.user.set(Profile { user_id, nick });
+                crate::settings::apply_json(&settings);
+                state.blocked.set(blocked);
+                state.user.set(Profile {
+                    user_id,
+                    nick,
+                    rating: None,
+                });
+                // A new connection starts without follows.
+                state.following.set(None);
+            }
+            ServerMessage::ServerTime(time) => {
+                let offset = time.0 as f64 - web_sys::js_sys::Date::now();
+                state.server_time_offset.set(offset);
+            }
+            ServerMessage::RandomGame { room_id } => {
+                state.random_game.set(Some(room_id));
+            }
+            ServerMessage::Followed { room_id, .. } => {
+                state.random_game.set(Some(room_id));
+            }
+            ServerMessage::Invited {
+                from,
+                room_id,
+                room_name,
+            } => {
+                let mut invites = state.invites.write();
+                // A repeated invitation replaces the earlier one.
+                invites.retain(|i| i.from != from || i.room_id != room_id);
+                invites.push(Invite {
+                    from,
+                    room_id,
+                    room_name,
+                });
+            }
+            ServerMessage::ActiveRooms(rooms) => {
+                state.seated_rooms.set(rooms);
+            }
+            ServerMessage::ProblemProgress { solved, total } => {
+                state.problem_progress.set(Some((solved, total)));
             }
             ServerMessage::Profile(profile) => {
                 state.profiles.write().insert(profile.user_id, profile);
             }
-            ServerMessage::AnnounceGame { room_id, name } => {
+            ServerMessage::Chat { room_id, line } => {
+                let active_room = state.active_room.read().as_ref().map(|r| r.id);
+                if active_room == Some(room_id) {
+                    state.typing.write().remove(&line.user_id);
+                    state.chat.write().push(line);
+                }
+            }
+            ServerMessage::UserTyping {
+                room_id,
+                user_id,
+                channel,
+            } => {
+                let active_room = state.active_room.read().as_ref().map(|r| r.id);
+                if active_room == Some(room_id) {
+                    let now = web_sys::js_sys::Date::now();
+                    state.typing.write().insert(user_id, (channel, now));
+                }
+            }
+            ServerMessage::ChatHistory { room_id, lines } => {
+                let active_room = state.active_room.read().as_ref().map(|r| r.id);
+                if active_room == Some(room_id) {
+                    state.chat.set(lines);
+                }
+            }
+            ServerMessage::Info(info) => {
+                if info.protocol_version != message::PROTOCOL_VERSION {
+                    let server = info.protocol_version;
+                    handle_error(&state, ClientError::IncompatibleVersion { server });
+                }
+                state.server_info.set(Some(info));
+            }
+            ServerMessage::AnnounceGame {
+                room_id,
+                name,
+                thumbnail,
+                spectators,
+                no_spectators,
+            } => {
                 let new_room = GameRoom {
                     id: room_id,
                     name: name.into(),
+                    thumbnail: thumbnail.map(Rc::new),
+                    spectators,
+                    no_spectators,
                 };
                 room_debouncer(RoomEvent::Announce(new_room));
             }
+            ServerMessage::GameList { rooms, total } => {
+                // Dropped if the user went back to every room in the meantime.
+                if state.room_filter.read().is_some() {
+                    let rooms = rooms
+                        .into_iter()
+                        .map(|room| GameRoom {
+                            id: room.room_id,
+                            name: room.name.into(),
+                            thumbnail: room.thumbnail.map(Rc::new),
+                            spectators: room.spectators,
+                            no_spectators: room.no_spectators,
+                        })
+                        .collect();
+                    state.found_rooms.set(Some((rooms, total)));
+                }
+            }
+            ServerMessage::RoomUpdated {
+                room_id,
+                spectators,
+            } => {
+                room_debouncer(RoomEvent::Spectators(room_id, spectators));
+            }
             ServerMessage::CloseGame { room_id } => {
                 room_debouncer(RoomEvent::Close(room_id));
             }
+            ServerMessage::GameStarted { room_id, .. } => {
+                let active = state.active_room.read().as_ref().map(|r| r.id);
+                if active == Some(room_id) {
+                    sound::play(sound::Sound::GameStart);
+                }
+            }
+            ServerMessage::BoardAt { room_id, view } => {
+                let active = state.active_room.read().as_ref().map(|r| r.id);
+                if active == Some(room_id) {
+                    state.compare.set(Some(view.into()));
+                }
+            }
+            ServerMessage::ScoreEstimate {
+                room_id,
+                move_number,
+                scores,
+            } => {
+                let active = state.active_room.read().as_ref().map(|r| r.id);
+                if active == Some(room_id) {
+                    state.estimate.set(Some((move_number, scores)));
+                }
+            }
+            ServerMessage::SGF { room_id, sgf } => {
+                // Only asked for to share the game, see `ActionSender::share_game`.
+                let active = state.active_room.read().as_ref().map(|r| r.id);
+                if active == Some(room_id) {
+                    let link = share::link(Some(room_id), &sgf);
+                    if let Err(e) = share::copy_link(&link) {
+                        log::error!("Copying the link failed: {:?}", e);
+                    }
+                    state.share_link.set(Some(link));
+                }
+            }
+            ServerMessage::Stats { room_id, csv } => {
+                let active = state.active_room.read().as_ref().map(|r| r.id);
+                if active == Some(room_id) {
+                    let name = format!("game-{}-stats.csv", room_id);
+                    if let Err(e) = crate::board::download_csv(&name, &csv) {
+                        log::error!("Stats download failed: {:?}", e);
+                    }
+                }
+            }
+            ServerMessage::Annotations {
+                room_id,
+                annotations,
+            } => {
+                let active = state.active_room.read().as_ref().map(|r| r.id);
+                if active == Some(room_id) {
+                    state.annotations.set(annotations);
+                }
+            }
+            ServerMessage::Presentation {
+                room_id,
+                presentation,
+            } => {
+                let active = state.active_room.read().as_ref().map(|r| r.id);
+                if active == Some(room_id) {
+                    state.presentation.set(presentation);
+                }
+            }
+            ServerMessage::Review { room_id, view } => {
+                let active = state.active_room.read().as_ref().map(|r| r.id);
+                if active == Some(room_id) {
+                    state.review.set(Some(GameHistory {
+                        size: view.size,
+                        board: view.board.into_iter().map(game::Color).collect(),
+                        board_visibility: None,
+                        last_stone: view
+                            .variation
+                            .last()
+                            .map(|&p| game::GroupVec::from(&[p][..])),
+                        captured: Vec::new(),
+                        move_number: view.move_number,
+                    }));
+                }
+            }
             ServerMessage::GameStatus {
                 room_id,
                 owner,
                 members,
-                seats,
-                turn,
-                board,
-                board_visibility,
-                hidden_stones_left,
-                size,
-                state: game_state,
-                mods,
-                points,
-                move_number,
-                clock,
+                view,
             } => {
-                let view = GameView {
-                    state: game_state,
-                    seats: seats
-                        .into_iter()
-                        .map(|(player, team, resigned)| game::Seat {
-                            player,
-                            team: game::Color(team),
-                            resigned,
-                        })
-                        .collect(),
-                    turn,
-                    board: board.into_iter().map(game::Color).collect(),
-                    board_visibility,
-                    hidden_stones_left,
-                    size,
-                    mods,
-                    points,
-                    move_number,
-                    clock,
-                };
                 let room = ActiveRoom {
                     id: room_id,
-                    view: Rc::new(view),
+                    view: Rc::new(view.into()),
                     owner,
                     members,
                 };
-                *state.active_room.write() = Some(room);
-                log::debug!("{:?}", &*state.active_room.read());
+                set_active_room(&state, room);
             }
             _ => {}
         }
@@ -203,6 +612,88 @@ pub(crate) fn use_state_provider(cx: &ScopeState) -> Signal<ClientState> {
     state
 }
 
+/// Shows `room`, clearing whatever belonged to the previous one. Used for the server rooms
+/// and the local practice game alike.
+pub(crate) fn set_active_room(state: &ClientState, room: ActiveRoom) {
+    if let Some(previous) = state.active_room.read().as_ref() {
+        let same_room = previous.id == room.id;
+        let previous = &previous.view;
+        // Undo lowers the move number, so only sound when the game progressed.
+        if same_room && room.view.move_number > previous.move_number {
+            let me = state.user.read().user_id;
+            let seat = previous.seats.get(previous.turn as usize);
+            let own = seat.map_or(false, |s| s.player == Some(me));
+            // Prisoners only grow during play, so the difference is what the move took.
+            let captured = room
+                .view
+                .prisoners
+                .iter()
+                .sum::<u32>()
+                .saturating_sub(previous.prisoners.iter().sum());
+            sound::play(if captured > 0 {
+                sound::Sound::Capture(captured)
+            } else if own {
+                sound::Sound::OwnMove
+            } else {
+                sound::Sound::OpponentMove
+            });
+            if crate::settings::AnnounceMoves::get().0 {
+                if let Some(text) = crate::views::announcer::describe(previous, &room.view) {
+                    state.announcement.set(text);
+                }
+            }
+        }
+    }
+    let previous_room = state.active_room.read().as_ref().map(|r| r.id);
+    if previous_room != Some(room.id) {
+        state.chat.write().clear();
+        state.typing.write().clear();
+        state.wrong_move.set(false);
+        state.review.set(None);
+        state.analysis.set(None);
+        state.share_link.set(None);
+        state.annotations.write().clear();
+        state
+            .presentation
+            .set(message::BoardPresentation::default());
+        state.annotation_tool.set(None);
+        state.annotation_start.set(None);
+        state.compare.set(None);
+        state.estimate.set(None);
+        state.hypothetical.set(false);
+        state.announcement.set(String::new());
+    }
+    autosave::record(room.id, state.user.read().user_id, &room.view);
+    *state.active_room.write() = Some(room);
+    log::debug!("{:?}", &*state.active_room.read());
+}
+
+/// Most errors are shown as toasts. Only a few are kept, the oldest go first.
+const MAX_SHOWN_ERRORS: usize = 3;
+
+/// The one place errors from the server and the connection end up.
+pub(crate) fn handle_error(state: &ClientState, error: ClientError) {
+    match &error {
+        ClientError::Decode(details) => log::error!("Undecodable message: {}", details),
+        ClientError::IncompatibleVersion { server } => log::warn!(
+            "Protocol mismatch: server {}, client {}",
+            server,
+            message::PROTOCOL_VERSION
+        ),
+        _ => log::warn!("Server error: {:?}", error),
+    }
+
+    if error.is_fatal() {
+        state.fatal_error.set(Some(error));
+        return;
+    }
+    let mut errors = state.errors.write();
+    errors.push(error);
+    if errors.len() > MAX_SHOWN_ERRORS {
+        errors.remove(0);
+    }
+}
+
 pub(crate) fn use_state(cx: &ScopeState) -> Signal<ClientState> {
     *use_context(cx).expect("state not provided")
 }
@@ -220,6 +711,11 @@ fn apply_room_event(event: RoomEvent, rooms: &mut Vec<GameRoom>) {
             }
             Err(idx) => rooms.insert(idx, room),
         },
+        RoomEvent::Spectators(id, spectators) => {
+            if let Ok(idx) = rooms.binary_search_by(|r| id.cmp(&r.id)) {
+                rooms[idx].spectators = spectators;
+            }
+        }
         RoomEvent::Close(id) => match rooms.binary_search_by(|r| id.cmp(&r.id)) {
             Ok(idx) => {
                 rooms.remove(idx);
@@ -254,6 +750,19 @@ impl<'a> ActionSender<'a> {
     }
 
     fn send(&self, msg: ClientMessage) {
+        // Game actions in the practice game never reach the server.
+        if let ClientMessage::GameAction { action, .. } = &msg {
+            let state = self.state.read();
+            let active = state.active_room.read().as_ref().map(|r| r.id);
+            if active == Some(practice::PRACTICE_ROOM_ID) {
+                practice::apply(&state, action.clone());
+                return;
+            }
+            // The game of a review link is over, only its analysis changes.
+            if active == Some(share::SHARED_ROOM_ID) {
+                return;
+            }
+        }
         self.handle.send(msg);
     }
 
@@ -264,13 +773,56 @@ impl<'a> ActionSender<'a> {
         });
     }
 
+    /// Uploads the locally stored settings.
+    pub(crate) fn sync_settings(&self) {
+        self.send(ClientMessage::UpdateSettings(crate::settings::to_json()));
+    }
+
     pub(crate) fn join_room(&self, id: u32) {
         self.send(ClientMessage::JoinGame(id));
     }
 
+    pub(crate) fn spectate_random(&self) {
+        self.send(ClientMessage::SpectateRandomGame);
+    }
+
+    /// Lists the rooms matching `filter` instead of every room. `None` goes back to all.
+    pub(crate) fn find_games(&self, filter: Option<message::RoomFilter>) {
+        let state = self.state.read();
+        state.found_rooms.set(None);
+        state.room_filter.set(filter.clone());
+        if let Some(filter) = filter {
+            self.send(ClientMessage::FindGames(filter));
+        }
+    }
+
+    /// Stops or resumes chat and invites from `user_id`. What they already sent is hidden
+    /// right away.
+    pub(crate) fn block(&self, user_id: u64, blocked: bool) {
+        if blocked {
+            let state = self.state.read();
+            state.chat.write().retain(|line| line.user_id != user_id);
+            state
+                .invites
+                .write()
+                .retain(|invite| invite.from != user_id);
+        }
+        self.send(ClientMessage::Block { user_id, blocked });
+    }
+
+    pub(crate) fn follow(&self, user_id: Option<u64>) {
+        self.state.read().following.set(user_id);
+        self.send(ClientMessage::Follow(user_id));
+    }
+
+    pub(crate) fn invite(&self, user_id: u64, room_id: u32) {
+        self.send(ClientMessage::Invite { user_id, room_id });
+    }
+
     pub(crate) fn leave_all_rooms(&self) {
         let active_room = self.state.read().active_room;
         *active_room.write() = None;
+        self.state.read().chat.write().clear();
         self.send(ClientMessage::LeaveGame(None))
     }
 
@@ -289,14 +841,101 @@ impl<'a> ActionSender<'a> {
     }
 
     pub(crate) fn start_game(&self, start: message::StartGame) {
-        let msg = ClientMessage::StartGame(start);
+        let msg = ClientMessage::StartGame(Box::new(start));
         self.send(msg);
     }
 
+    pub(crate) fn next_problem(&self) {
+        self.send(ClientMessage::NextProblem);
+    }
+
+    pub(crate) fn start_game_preset(&self, preset: String) {
+        self.send(ClientMessage::StartGamePreset(preset));
+    }
+
     pub(crate) fn place_stone(&self, x: u32, y: u32) {
+        let state = self.state.read();
+        let expected = state.active_room.read().as_ref().and_then(|room| {
+            let problem = room.view.mods.problem.as_ref()?;
+            Some(problem.expected_move(room.view.move_number as usize))
+        });
+        if let Some(expected) = expected {
+            let wrong = expected != Some((x, y));
+            state.wrong_move.set(wrong);
+            if wrong {
+                return;
+            }
+        }
+
         self.send(ClientMessage::GameAction {
             room_id: None,
             action: shared::message::GameAction::Place(x, y),
+        });
+
+        let grace = crate::settings::UndoGrace::get().0;
+        if grace > 0 {
+            let until = web_sys::js_sys::Date::now() + grace as f64 * 1000.0;
+            state.undo_grace_until.set(Some(until));
+        }
+    }
+
+    /// Asks for the position after `move_number` moves, answered with `BoardAt`.
+    pub(crate) fn board_at(&self, move_number: u32) {
+        self.send(ClientMessage::GameAction {
+            room_id: None,
+            action: shared::message::GameAction::BoardAt(move_number, move_number),
+        })
+    }
+
+    /// Answered with `ScoreEstimate`, only to us.
+    /// Copies a review link of the finished game once the server sends its SGF.
+    pub(crate) fn share_game(&self) {
+        self.send(ClientMessage::GameAction {
+            room_id: None,
+            action: shared::message::GameAction::RequestSGF(Default::default()),
+        })
+    }
+
+    pub(crate) fn request_stats(&self) {
+        self.send(ClientMessage::GameAction {
+            room_id: None,
+            action: shared::message::GameAction::RequestStats,
+        })
+    }
+
+    pub(crate) fn estimate_score(&self) {
+        self.send(ClientMessage::GameAction {
+            room_id: None,
+            action: shared::message::GameAction::EstimateScore,
+        })
+    }
+
+    pub(crate) fn review(&self, action: game::ReviewAction) {
+        self.send(ClientMessage::GameAction {
+            room_id: None,
+            action: shared::message::GameAction::Review(action),
+        })
+    }
+
+    pub(crate) fn annotate(&self, action: game::AnnotationAction) {
+        self.send(ClientMessage::GameAction {
+            room_id: None,
+            action: shared::message::GameAction::Annotate(action),
+        })
+    }
+
+    /// Shows the board to the whole room this way. Only for the room owner.
+    pub(crate) fn present(&self, presentation: message::BoardPresentation) {
+        self.send(ClientMessage::GameAction {
+            room_id: None,
+            action: shared::message::GameAction::Present(presentation),
+        })
+    }
+
+    pub(crate) fn setup_stones(&self, stones: Vec<(u32, u32, u8)>) {
+        self.send(ClientMessage::GameAction {
+            room_id: None,
+            action: shared::message::GameAction::SetupStones(stones),
         })
     }
 
@@ -314,12 +953,47 @@ impl<'a> ActionSender<'a> {
         })
     }
 
+    pub(crate) fn send_chat(&self, channel: message::ChatChannel, text: String) {
+        self.send(ClientMessage::GameAction {
+            room_id: None,
+            action: shared::message::GameAction::Chat { channel, text },
+        })
+    }
+
+    pub(crate) fn typing(&self, channel: message::ChatChannel) {
+        self.send(ClientMessage::GameAction {
+            room_id: None,
+            action: shared::message::GameAction::Typing(channel),
+        })
+    }
+
+    pub(crate) fn swap(&self) {
+        self.send(ClientMessage::GameAction {
+            room_id: None,
+            action: shared::message::GameAction::Swap,
+        })
+    }
+
     pub(crate) fn resign(&self) {
         self.send(ClientMessage::GameAction {
             room_id: None,
             action: shared::message::GameAction::Resign,
         })
     }
+
+    pub(crate) fn resume(&self) {
+        self.send(ClientMessage::GameAction {
+            room_id: None,
+            action: shared::message::GameAction::Resume,
+        })
+    }
+
+    pub(crate) fn claim(&self) {
+        self.send(ClientMessage::GameAction {
+            room_id: None,
+            action: shared::message::GameAction::Claim,
+        })
+    }
 }
 
 pub(crate) fn username(profile: &Profile) -> String {
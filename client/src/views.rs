@@ -1,3 +1,35 @@
+pub mod analysis;
+pub mod annotations;
+pub mod announcer;
+pub mod chat;
+pub mod compare;
+pub mod consent;
+pub mod countdown;
 pub mod create_game;
+pub mod errors;
+pub mod estimate;
+pub mod game_over;
+pub mod hypothetical;
+pub mod invites;
+pub mod pause;
+pub mod presentation;
+pub mod room_filter;
+pub mod saved_game;
 
+pub use analysis::AnalysisPanel;
+pub use annotations::AnnotationPanel;
+pub use announcer::MoveAnnouncer;
+pub use chat::ChatPanel;
+pub use compare::ComparisonBoard;
+pub use consent::ConsentPrompts;
+pub use countdown::{format_time, Countdown};
 pub use create_game::CreateGamePanel;
+pub use errors::ErrorToasts;
+pub use estimate::ScoreEstimateBar;
+pub use game_over::GameOverPanel;
+pub use hypothetical::HypotheticalCount;
+pub use invites::InvitePrompts;
+pub use pause::PauseNotice;
+pub use presentation::PresentationPanel;
+pub use room_filter::RoomFilterBar;
+pub use saved_game::SavedGamePrompt;
@@ -0,0 +1,363 @@
+use dioxus::prelude::*;
+use dioxus_signals::*;
+
+use crate::palette;
+use crate::settings;
+use crate::share;
+use crate::state::{self, Analysis};
+use shared::game::{self, export, variations::VariationTree, GameStateView};
+
+/// Local variation tree of a finished game. Branches start from the position on the board.
+#[component]
+pub fn AnalysisPanel(cx: Scope) -> Element {
+    let state = state::use_state(cx);
+    let room = state.read().active_room();
+    let analysis = state.read().analysis;
+
+    let is_done = room.read().as_ref().map_or(false, |room| {
+        matches!(room.view.state, GameStateView::Done(_))
+    });
+    if !is_done {
+        return None;
+    }
+
+    let start = move || {
+        let state = state.read();
+        let room = room.read();
+        let Some(view) = room.as_ref().map(|r| &r.view) else {
+            return;
+        };
+        let (points, size, move_number) = match state.review.read().as_ref() {
+            Some(review) => (review.board.clone(), review.size, review.move_number),
+            None => (view.board.clone(), view.size, view.move_number),
+        };
+        let board = game::Board {
+            width: size.0 as u32,
+            height: size.1 as u32,
+            toroidal: view.mods.toroidal.is_some(),
+            points,
+        };
+        // Assume the seats alternated, so continue with the one after the last move.
+        let seat_count = view.seats.len();
+        let teams = (0..seat_count)
+            .map(|idx| view.seats[(move_number as usize + idx) % seat_count].team)
+            .collect();
+        analysis.set(Some(Analysis {
+            tree: VariationTree::new(board, teams),
+            selected: VariationTree::ROOT,
+        }));
+    };
+
+    #[rustfmt::skip]
+    let class = sir::css!("
+        padding: 10px;
+
+        .actions {
+            display: flex;
+            gap: 10px;
+            a {
+                cursor: pointer;
+                color: var(--text-color);
+            }
+        }
+
+        .graph {
+            max-height: 150px;
+            overflow: auto;
+            margin-top: 5px;
+            circle {
+                cursor: pointer;
+            }
+        }
+
+        .tree {
+            max-height: 200px;
+            overflow-y: auto;
+            padding: 5px 0;
+        }
+
+        .move {
+            cursor: pointer;
+            padding: 0 3px;
+            &.selected {
+                background: var(--bg-h-color);
+            }
+        }
+
+        details {
+            padding-left: 10px;
+        }
+
+        textarea {
+            width: 100%;
+        }
+    ");
+
+    let current = analysis.read();
+    let Some(current) = current.as_ref() else {
+        return cx.render(rsx! {
+            div {
+                class: "{class}",
+                div {
+                    class: "actions",
+                    a {
+                        title: "Branch into local variations from the position on the board",
+                        onclick: move |_| start(),
+                        "Analyze"
+                    }
+                }
+            }
+        });
+    };
+
+    let first_moves = current.tree.nodes[VariationTree::ROOT].children.clone();
+    let comment = current.tree.nodes[current.selected].comment.clone();
+    let sgf = export::variation_sgf(&current.tree);
+    let href = format!(
+        "data:application/x-go-sgf;charset=utf-8,{}",
+        String::from(web_sys::js_sys::encode_uri_component(&sgf))
+    );
+    let link = share::link(room.read().as_ref().map(|r| r.id), &sgf);
+    let copy_link = move || {
+        if let Err(e) = share::copy_link(&link) {
+            log::error!("Copying the link failed: {:?}", e);
+        }
+        state.read().share_link.set(Some(link.clone()));
+    };
+
+    let prune = move || {
+        if let Some(analysis) = analysis.write().as_mut() {
+            let parent = analysis.tree.nodes[analysis.selected].parent;
+            if let Some(parent) = parent {
+                analysis.tree.prune(analysis.selected);
+                analysis.selected = parent;
+            }
+        }
+    };
+
+    let set_comment = move |e: FormEvent| {
+        if let Some(analysis) = analysis.write().as_mut() {
+            let selected = analysis.selected;
+            analysis.tree.nodes[selected].comment = e.inner().value.clone();
+        }
+    };
+
+    cx.render(rsx! {
+        div {
+            class: "{class}",
+            div {
+                class: "actions",
+                a {
+                    onclick: move |_| {
+                        if let Some(analysis) = analysis.write().as_mut() {
+                            analysis.selected = VariationTree::ROOT;
+                        }
+                    },
+                    "Start"
+                }
+                a { onclick: move |_| prune(), "Delete move" }
+                a { href: "{href}", download: "variations.sgf", "SGF" }
+                a {
+                    title: "Copy a link that opens these variations for review",
+                    onclick: move |_| copy_link(),
+                    "Link"
+                }
+                a { onclick: move |_| analysis.set(None), "Close" }
+            }
+            MoveTree {}
+            div {
+                class: "tree",
+                for (idx, &node) in first_moves.iter().enumerate() {
+                    if idx == 0 {
+                        rsx!(VariationLine { start: node })
+                    } else {
+                        rsx!(details {
+                            open: true,
+                            summary { "Variation {idx}" }
+                            VariationLine { start: node }
+                        })
+                    }
+                }
+            }
+            textarea {
+                placeholder: "Notes on this move",
+                value: "{comment}",
+                oninput: set_comment,
+            }
+        }
+    })
+}
+
+/// A move and the main line after it. Other branches are listed after the move they
+/// branch from.
+#[component]
+fn VariationLine(cx: Scope, start: usize) -> Element {
+    let state = state::use_state(cx);
+    let analysis = state.read().analysis;
+
+    let moves = {
+        let analysis = analysis.read();
+        let Some(analysis) = analysis.as_ref() else {
+            return None;
+        };
+        let tree = &analysis.tree;
+        let height = tree.board.height;
+        let style = settings::CoordinateLabels::get().0;
+
+        let mut moves = Vec::new();
+        let mut depth = tree.path(*start).len() - 1;
+        let mut node = Some(*start);
+        while let Some(current) = node {
+            let label = match tree.nodes[current].stone {
+                Some(((x, y), team)) => format!(
+                    "{}. {} {}",
+                    depth,
+                    game::Color::name(team),
+                    style.point((x, y), height)
+                ),
+                None => depth.to_string(),
+            };
+            let children = &tree.nodes[current].children;
+            let branches = children.iter().skip(1).copied().collect::<Vec<_>>();
+            moves.push((current, label, branches, current == analysis.selected));
+
+            node = children.first().copied();
+            depth += 1;
+        }
+        moves
+    };
+
+    cx.render(rsx! {
+        div {
+            for (node, label, branches, selected) in moves {
+                div {
+                    class: if selected { "move selected" } else { "move" },
+                    onclick: move |_| {
+                        if let Some(analysis) = analysis.write().as_mut() {
+                            analysis.selected = node;
+                        }
+                    },
+                    "{label}"
+                }
+                for branch in branches {
+                    details {
+                        open: true,
+                        summary { "Variation" }
+                        VariationLine { start: branch }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Side length of a node's cell in the move tree, in pixels.
+const TREE_CELL: usize = 20;
+
+/// The variation tree drawn as nodes and branches, moves from left to right. Click a node
+/// to show its position. Nodes where the tree branches get a ring.
+#[component]
+fn MoveTree(cx: Scope) -> Element {
+    let state = state::use_state(cx);
+    let analysis = state.read().analysis;
+    let palette = palette::PaletteOption::get().to_palette();
+
+    let center = |cell: usize| (cell * TREE_CELL + TREE_CELL / 2) as f32;
+    let (nodes, lines, width, height) = {
+        let analysis = analysis.read();
+        let Some(analysis) = analysis.as_ref() else {
+            return None;
+        };
+        let tree = &analysis.tree;
+        let cells = tree.layout();
+        let style = settings::CoordinateLabels::get().0;
+
+        let mut nodes = Vec::new();
+        let mut lines = Vec::new();
+        let (mut columns, mut rows) = (1, 1);
+        for (node, cell) in cells.iter().enumerate() {
+            let Some((column, row)) = *cell else {
+                continue;
+            };
+            columns = columns.max(column + 1);
+            rows = rows.max(row + 1);
+
+            let data = &tree.nodes[node];
+            let color = match data.stone {
+                Some((_, team)) => palette.stone_colors[team.as_usize() - 1],
+                None => palette.background,
+            };
+            let label = match data.stone {
+                Some(((x, y), team)) => format!(
+                    "{}. {} {}",
+                    column,
+                    game::Color::name(team),
+                    style.point((x, y), tree.board.height)
+                ),
+                None => "Start".to_string(),
+            };
+            let (stroke, stroke_width) = if node == analysis.selected {
+                ("#9ecaed", 3.0)
+            } else if data.children.len() > 1 {
+                ("var(--text-color)", 1.5)
+            } else {
+                ("none", 0.0)
+            };
+            let (x, y) = (center(column), center(row));
+            nodes.push((node, x, y, color, stroke, stroke_width, label));
+
+            // Down the column of the parent, then over to the node.
+            if let Some((parent_column, parent_row)) = data.parent.and_then(|p| cells[p]) {
+                let bend = row.saturating_sub(1).max(parent_row);
+                let points = format!(
+                    "{},{} {},{} {},{}",
+                    center(parent_column),
+                    center(parent_row),
+                    center(parent_column),
+                    center(bend),
+                    center(column),
+                    center(row)
+                );
+                lines.push((node, points));
+            }
+        }
+        (nodes, lines, columns * TREE_CELL, rows * TREE_CELL)
+    };
+
+    let radius = TREE_CELL as f32 * 0.35;
+    cx.render(rsx! {
+        div {
+            class: "graph",
+            svg {
+                width: "{width}",
+                height: "{height}",
+                for (node, points) in lines {
+                    polyline {
+                        key: "line-{node}",
+                        points: "{points}",
+                        fill: "none",
+                        stroke: "var(--text-color)",
+                        stroke_width: "1",
+                    }
+                }
+                for (node, x, y, color, stroke, stroke_width, label) in nodes {
+                    circle {
+                        key: "{node}",
+                        cx: "{x}",
+                        cy: "{y}",
+                        r: "{radius}",
+                        fill: color,
+                        stroke: stroke,
+                        stroke_width: "{stroke_width}",
+                        onclick: move |_| {
+                            if let Some(analysis) = analysis.write().as_mut() {
+                                analysis.selected = node;
+                            }
+                        },
+                        title { "{label}" }
+                    }
+                }
+            }
+        }
+    })
+}
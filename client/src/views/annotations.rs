@@ -0,0 +1,120 @@
+use dioxus::prelude::*;
+use shared::game;
+
+use crate::state::{self, ActionSender, AnnotationTool};
+
+/// Drawing tools for the teacher of a teaching game. While a tool is picked, clicks on
+/// the board draw instead of placing stones. Everyone in the room sees the drawings.
+#[component]
+pub fn AnnotationPanel(cx: Scope) -> Element {
+    let state = state::use_state(cx);
+    let room = state.read().active_room();
+    let action = ActionSender::new(cx);
+
+    let me = state.read().user.read().user_id;
+    let is_teacher = room.read().as_ref().map_or(false, |room| {
+        room.view
+            .mods
+            .teaching
+            .as_ref()
+            .map_or(false, |t| t.teacher == me)
+    });
+    if !is_teacher {
+        return None;
+    }
+
+    let tool = state.read().annotation_tool;
+    let start = state.read().annotation_start;
+    let current = *tool.read();
+    let drawn = state.read().annotations.read().len();
+
+    let pick = move |picked: AnnotationTool| {
+        let next = if current == Some(picked) {
+            None
+        } else {
+            Some(picked)
+        };
+        tool.set(next);
+        start.set(None);
+    };
+
+    #[rustfmt::skip]
+    let class = sir::css!("
+        padding: 10px;
+        display: flex;
+        flex-wrap: wrap;
+        gap: 10px;
+
+        a {
+            cursor: pointer;
+            color: var(--text-color);
+            &.active {
+                text-decoration: underline;
+            }
+        }
+    ");
+
+    cx.render(rsx! {
+        div {
+            class: "{class}",
+            span { "Draw:" }
+            for picked in AnnotationTool::ALL {
+                a {
+                    class: if current == Some(picked) { "active" } else { "" },
+                    onclick: move |_| pick(picked),
+                    "{picked.name()}"
+                }
+            }
+            if drawn > 0 {
+                rsx!(a {
+                    onclick: move |_| action.annotate(game::AnnotationAction::Clear),
+                    "Clear"
+                })
+            }
+        }
+    })
+}
+
+/// What a click at `pos` draws with `tool`. Lines and arrows take two clicks, the first
+/// one only remembers where they start.
+pub(crate) fn click_annotation(
+    tool: AnnotationTool,
+    start: &mut Option<game::Point>,
+    annotations: &[game::Annotation],
+    pos: game::Point,
+) -> Option<game::Annotation> {
+    let annotation = match tool {
+        AnnotationTool::Circle => game::Annotation::Circle(pos),
+        AnnotationTool::Triangle => game::Annotation::Triangle(pos),
+        AnnotationTool::Letter => {
+            // Clicking a letter again takes it away, see `AnnotationAction::Add`.
+            let existing = annotations.iter().find_map(|a| match a {
+                game::Annotation::Letter(p, c) if *p == pos => Some(*c),
+                _ => None,
+            });
+            let letter = existing.unwrap_or_else(|| {
+                let count = annotations
+                    .iter()
+                    .filter(|a| matches!(a, game::Annotation::Letter(..)))
+                    .count();
+                (b'A' + (count % 26) as u8) as char
+            });
+            game::Annotation::Letter(pos, letter)
+        }
+        AnnotationTool::Line | AnnotationTool::Arrow => {
+            let from = match start.take() {
+                Some(from) if from != pos => from,
+                _ => {
+                    *start = Some(pos);
+                    return None;
+                }
+            };
+            if tool == AnnotationTool::Line {
+                game::Annotation::Line(from, pos)
+            } else {
+                game::Annotation::Arrow(from, pos)
+            }
+        }
+    };
+    Some(annotation)
+}
@@ -0,0 +1,59 @@
+use dioxus::prelude::*;
+use shared::game::{self, GameStateView};
+
+use crate::settings;
+use crate::state::{self, GameView};
+
+/// What the move from `previous` to `view` did, like "White plays D4, captures 2". The
+/// mover is whoever had the turn before.
+pub(crate) fn describe(previous: &GameView, view: &GameView) -> Option<String> {
+    let seat = previous.seats.get(previous.turn as usize)?;
+    let color = game::Color::name(seat.team);
+    match &view.state {
+        GameStateView::Play(state) => match &state.last_stone {
+            Some(points) => {
+                let height = view.size.1 as u32;
+                let style = settings::CoordinateLabels::get().0;
+                let points = points
+                    .iter()
+                    .map(|&p| style.point(p, height))
+                    .collect::<Vec<_>>()
+                    .join(" and ");
+                Some(match state.last_captured.len() {
+                    0 => format!("{} plays {}", color, points),
+                    captured => format!("{} plays {}, captures {}", color, points, captured),
+                })
+            }
+            None => Some(format!("{} passes", color)),
+        },
+        GameStateView::Scoring(_) => Some(format!("{} passes, counting starts", color)),
+        _ => None,
+    }
+}
+
+/// Off-screen live region for screen readers. The text is only replaced when a move
+/// arrives, so re-rendering doesn't read it out again.
+#[component]
+pub fn MoveAnnouncer(cx: Scope) -> Element {
+    let state = state::use_state(cx);
+    let text = state.read().announcement;
+
+    #[rustfmt::skip]
+    let class = sir::css!("
+        position: absolute;
+        width: 1px;
+        height: 1px;
+        overflow: hidden;
+        clip: rect(0 0 0 0);
+        white-space: nowrap;
+    ");
+
+    cx.render(rsx! {
+        div {
+            class: "{class}",
+            role: "status",
+            aria_live: "polite",
+            "{text}"
+        }
+    })
+}
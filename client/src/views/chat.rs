@@ -0,0 +1,262 @@
+use dioxus::{html::input_data::keyboard_types::Key, prelude::*};
+use dioxus_signals::*;
+use gloo_timers::future::TimeoutFuture;
+
+use crate::settings;
+use crate::state::{self, ActionSender};
+use shared::message::{self, ChatChannel};
+
+/// Typing notices are sent at most this often while writing, in milliseconds.
+const TYPING_SEND_INTERVAL: f64 = 2000.0;
+/// Someone counts as typing until this long after their last notice, in milliseconds.
+const TYPING_TIMEOUT: f64 = 5000.0;
+/// Being this close to the bottom of the chat, in pixels, counts as reading the latest lines.
+const SCROLL_FOLLOW_MARGIN: i32 = 20;
+
+fn chat_lines() -> Option<web_sys::Element> {
+    gloo_utils::document().get_element_by_id("chat-lines")
+}
+
+fn scroll_to_latest() {
+    if let Some(lines) = chat_lines() {
+        lines.set_scroll_top(lines.scroll_height());
+    }
+}
+
+#[component]
+pub fn ChatPanel(cx: Scope) -> Element {
+    let state = state::use_state(cx);
+    let action = ActionSender::new(cx);
+    let channel = use_signal(cx, || ChatChannel::Main);
+    let text = use_signal(cx, String::new);
+    let typing_sent = use_signal(cx, || 0.0);
+    // Cleared while the user has scrolled up to read older lines.
+    let following = use_signal(cx, || true);
+
+    let room = state.read().active_room();
+    let user_id = state.read().user.read().user_id;
+    let is_seated = room.read().as_ref().map_or(false, |room| {
+        room.view
+            .seats
+            .iter()
+            .any(|seat| seat.player == Some(user_id))
+    });
+
+    // Players never see the spectator channel.
+    if is_seated && *channel.read() == ChatChannel::Spectator {
+        channel.set(ChatChannel::Main);
+    }
+
+    let chat = state.read().chat;
+    let profiles = state.read().profiles;
+    let current = *channel.read();
+
+    let line_count = chat
+        .read()
+        .iter()
+        .filter(|line| line.channel == current)
+        .count();
+    dioxus::prelude::use_effect(cx, (&line_count, &current), move |_| async move {
+        // Runs after the new lines are rendered, so the height already includes them.
+        if *following.read() && settings::ChatAutoScroll::get().0 {
+            scroll_to_latest();
+        }
+    });
+
+    let on_scroll = move |_| {
+        if let Some(lines) = chat_lines() {
+            let from_bottom = lines.scroll_height() - lines.scroll_top() - lines.client_height();
+            following.set(from_bottom <= SCROLL_FOLLOW_MARGIN);
+        }
+    };
+
+    let jump_to_latest = move |_| {
+        scroll_to_latest();
+        following.set(true);
+    };
+
+    let send = move || {
+        let message = text.read().clone();
+        if message.trim().is_empty() {
+            return;
+        }
+        action.send_chat(*channel.read(), message);
+        text.set(String::new());
+        following.set(true);
+    };
+
+    let on_input = move |e: FormEvent| {
+        text.set(e.value.clone());
+        let now = web_sys::js_sys::Date::now();
+        if now - *typing_sent.read() >= TYPING_SEND_INTERVAL {
+            typing_sent.set(now);
+            action.typing(*channel.read());
+        }
+    };
+
+    #[rustfmt::skip]
+    let class = sir::css!("
+        display: flex;
+        flex-direction: column;
+        padding: 10px;
+
+        .tabs {
+            display: flex;
+            a {
+                padding: 5px 10px;
+                cursor: pointer;
+                &.active {
+                    background: var(--bg-h-color);
+                }
+            }
+        }
+
+        .lines {
+            height: 200px;
+            overflow-y: scroll;
+            padding: 5px 0;
+        }
+
+        .block {
+            margin-left: 5px;
+            cursor: pointer;
+            font-size: 0.8em;
+            opacity: 0;
+        }
+        .lines div:hover .block {
+            opacity: 0.7;
+        }
+
+        .jump {
+            cursor: pointer;
+            text-align: center;
+            color: var(--text-color);
+        }
+
+        input {
+            width: 100%;
+        }
+
+        .typing {
+            height: 1.2em;
+            font-style: italic;
+            opacity: 0.7;
+        }
+    ");
+
+    cx.render(rsx! {
+        div {
+            class: "{class}",
+            if !is_seated {
+                rsx!(div {
+                    class: "tabs",
+                    a {
+                        class: if current == ChatChannel::Main { "active" } else { "" },
+                        onclick: move |_| channel.set(ChatChannel::Main),
+                        "Chat"
+                    }
+                    a {
+                        class: if current == ChatChannel::Spectator { "active" } else { "" },
+                        onclick: move |_| channel.set(ChatChannel::Spectator),
+                        "Spectators"
+                    }
+                })
+            }
+            div {
+                id: "chat-lines",
+                class: "lines",
+                onscroll: on_scroll,
+                for line in chat.read().iter().filter(|line| line.channel == current) {
+                    div {
+                        {
+                            let profiles = profiles.read();
+                            let nick = profiles
+                                .get(&line.user_id)
+                                .map(state::username)
+                                .unwrap_or_else(|| "Unknown".to_string());
+                            // Server notices have no author to show.
+                            let text = if line.user_id == message::SYSTEM_USER_ID {
+                                line.text.clone()
+                            } else {
+                                format!("{nick}: {}", line.text)
+                            };
+                            rsx!("{text}")
+                        }
+                        {
+                            let author = line.user_id;
+                            let blockable = author != message::SYSTEM_USER_ID && author != user_id;
+                            blockable.then(|| rsx!(a {
+                                class: "block",
+                                title: "Stop getting chat and invites from them. They aren't told.",
+                                onclick: move |_| action.block(author, true),
+                                "Block"
+                            }))
+                        }
+                    }
+                }
+            }
+            if !*following.read() {
+                rsx!(a {
+                    class: "jump",
+                    onclick: jump_to_latest,
+                    "Jump to latest"
+                })
+            }
+            input {
+                r#type: "text",
+                value: "{text}",
+                oninput: on_input,
+                onkeydown: move |e| {
+                    if e.key() == Key::Enter {
+                        send();
+                    }
+                },
+            }
+            TypingIndicator { channel: current }
+        }
+    })
+}
+
+/// Names of the others writing in `channel`, hidden once their notices stop.
+#[component]
+fn TypingIndicator(cx: Scope, channel: ChatChannel) -> Element {
+    let state = state::use_state(cx);
+    let tick = use_signal(cx, || 0u32);
+    use_future(cx, (), move |_| async move {
+        loop {
+            TimeoutFuture::new(1000).await;
+            *tick.write() += 1;
+        }
+    });
+
+    let _ = tick.read();
+    let now = web_sys::js_sys::Date::now();
+    let names = {
+        let typing = state.read().typing;
+        let typing = typing.read();
+        let profiles = state.read().profiles;
+        let profiles = profiles.read();
+        let mut names = typing
+            .iter()
+            .filter(|(_, (c, at))| *c == *channel && now - *at < TYPING_TIMEOUT)
+            .map(|(user_id, _)| {
+                profiles
+                    .get(user_id)
+                    .map(state::username)
+                    .unwrap_or_else(|| "Someone".to_string())
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    };
+
+    let text = match names.len() {
+        0 => String::new(),
+        1 => format!("{} is typing…", names[0]),
+        _ => format!("{} are typing…", names.join(", ")),
+    };
+
+    cx.render(rsx! {
+        div { class: "typing", "{text}" }
+    })
+}
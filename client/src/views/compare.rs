@@ -0,0 +1,167 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+use dioxus_router::prelude::*;
+use dioxus_signals::*;
+use web_sys::{wasm_bindgen::JsCast, HtmlCanvasElement};
+
+use crate::board::{self, Board};
+use crate::state::{self, ActionSender};
+
+/// How far back the comparison starts when opened.
+const COMPARE_MOVES_BACK: u32 = 10;
+
+/// An earlier position of the active game, drawn next to the live board. Stepping to the
+/// latest move, or "Live", follows new moves until stepping back again.
+#[component]
+pub fn ComparisonBoard(cx: Scope) -> Element {
+    let state = state::use_state(cx);
+    let action = ActionSender::new(cx);
+    let room = state.read().active_room();
+    let compare = state.read().compare;
+    let canvas_element = use_signal(cx, || None::<Rc<MountedData>>);
+    let following = use_signal(cx, || false);
+    let requested_live = use_signal(cx, || None::<u32>);
+
+    dioxus_signals::use_effect(cx, move || {
+        let Some(history) = compare.read().clone() else {
+            return;
+        };
+        let Some(view) = room.read().as_ref().map(|r| r.view.clone()) else {
+            return;
+        };
+        if canvas_element.read().is_none() {
+            return;
+        }
+        let Some(canvas) = gloo_utils::document()
+            .get_element_by_id("compare-canvas")
+            .and_then(|e| e.dyn_into::<HtmlCanvasElement>().ok())
+        else {
+            return;
+        };
+        let view = crate::with_review_size(view, Some(&history));
+        let result = board::canvas_context(&canvas).and_then(|context| {
+            Board::display(20.0).render_gl(
+                &context,
+                (canvas.width() as f64, canvas.height() as f64),
+                &view,
+                Some(&history),
+            )
+        });
+        if let Err(e) = result {
+            log::error!("Failed to render comparison board: {:?}", e);
+        }
+    });
+
+    let Some((room_id, live_move)) = room.read().as_ref().map(|r| (r.id, r.view.move_number))
+    else {
+        return None;
+    };
+    let shown_move = compare.read().as_ref().map(|h| h.move_number);
+
+    if shown_move.is_none() && *following.read() {
+        following.set(false);
+    }
+    let live = *following.read() && shown_move.is_some();
+    if live && shown_move != Some(live_move) && *requested_live.read() != Some(live_move) {
+        requested_live.set(Some(live_move));
+        action.board_at(live_move);
+    }
+
+    #[rustfmt::skip]
+    let class = sir::css!("
+        padding: 10px;
+
+        .actions {
+            display: flex;
+            gap: 10px;
+            a {
+                cursor: pointer;
+                color: var(--text-color);
+            }
+            .live {
+                font-weight: bold;
+                margin-left: auto;
+            }
+        }
+
+        canvas {
+            width: 100%;
+            aspect-ratio: 1;
+        }
+    ");
+
+    let Some(shown_move) = shown_move else {
+        return cx.render(rsx! {
+            div {
+                class: "{class}",
+                div {
+                    class: "actions",
+                    a {
+                        title: "Show an earlier position next to the live board",
+                        onclick: move |_| {
+                            action.board_at(live_move.saturating_sub(COMPARE_MOVES_BACK))
+                        },
+                        "Compare"
+                    }
+                }
+            }
+        });
+    };
+
+    let behind = live_move.saturating_sub(shown_move);
+
+    cx.render(rsx! {
+        div {
+            class: "{class}",
+            div {
+                class: "actions",
+                a {
+                    onclick: move |_| {
+                        following.set(false);
+                        action.board_at(shown_move.saturating_sub(1))
+                    },
+                    "◀"
+                }
+                if live {
+                    rsx!(span { "Live, move {live_move}" })
+                } else {
+                    rsx!(span { "Move {shown_move} of {live_move}" })
+                }
+                a {
+                    onclick: move |_| {
+                        let next = (shown_move + 1).min(live_move);
+                        following.set(next == live_move);
+                        action.board_at(next)
+                    },
+                    "▶"
+                }
+                if !live && behind > 0 {
+                    rsx!(a {
+                        class: "live",
+                        title: "Jump to the latest move and keep following the game",
+                        onclick: move |_| {
+                            following.set(true);
+                            requested_live.set(Some(live_move));
+                            action.board_at(live_move)
+                        },
+                        "Live ({behind} behind) ⏭"
+                    })
+                }
+                Link {
+                    to: crate::Route::GameMoveRoute { id: room_id, move_number: shown_move },
+                    "Link"
+                }
+                a { onclick: move |_| compare.set(None), "Close" }
+            }
+            canvas {
+                id: "compare-canvas",
+                width: "600",
+                height: "600",
+                onmounted: move |e| {
+                    canvas_element.set(Some(e.inner().clone()));
+                },
+            }
+        }
+    })
+}
@@ -0,0 +1,108 @@
+use dioxus::prelude::*;
+use shared::game::{self, ConsentKind, GameStateView};
+
+use crate::state::{self, ActionSender};
+
+/// Every step the game can't take until all seats agree, with the seats it waits for.
+/// Seated players asked for their consent can give it or decline right here.
+#[component]
+pub fn ConsentPrompts(cx: Scope) -> Element {
+    let state = state::use_state(cx);
+    let room = state.read().active_room();
+    let action = ActionSender::new(cx);
+
+    let me = state.read().user.read().user_id;
+    let (prompts, final_round) = {
+        let room = room.read();
+        let Some(room) = room.as_ref() else {
+            return None;
+        };
+        let view = &room.view;
+        let prompts = view
+            .consents
+            .iter()
+            .map(|request| {
+                let mut teams = Vec::new();
+                for &idx in &request.waiting {
+                    match view.seats.get(idx as usize) {
+                        Some(seat) if !teams.contains(&seat.team) => teams.push(seat.team),
+                        _ => {}
+                    }
+                }
+                let names = teams
+                    .into_iter()
+                    .map(game::Color::name)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let asked = request
+                    .waiting
+                    .iter()
+                    .filter_map(|&idx| view.seats.get(idx as usize))
+                    .any(|s| s.player == Some(me));
+                (request.kind, names, asked)
+            })
+            .collect::<Vec<_>>();
+        let final_round = matches!(
+            &view.state,
+            GameStateView::Scoring(scoring) if scoring.final_round
+        );
+        (prompts, final_round)
+    };
+    if prompts.is_empty() {
+        return None;
+    }
+
+    #[rustfmt::skip]
+    let class = sir::css!("
+        padding: 10px;
+
+        .consent {
+            display: flex;
+            flex-wrap: wrap;
+            gap: 10px;
+            a {
+                cursor: pointer;
+                color: var(--text-color);
+            }
+        }
+    ");
+
+    cx.render(rsx! {
+        div {
+            class: "{class}",
+            for (kind, names, asked) in prompts {
+                div {
+                    class: "consent",
+                    span { "{consent_label(kind)}: waiting for {names}" }
+                    if asked {
+                        rsx!(a {
+                            onclick: move |_| action.pass(),
+                            "{accept_label(kind)}"
+                        })
+                    }
+                    if asked && kind == ConsentKind::AcceptScore && !final_round {
+                        rsx!(a {
+                            title: "Go back to the board to settle dead stones or fill dame",
+                            onclick: move |_| action.undo(),
+                            "Resume game"
+                        })
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn consent_label(kind: ConsentKind) -> &'static str {
+    match kind {
+        ConsentKind::AcceptScore => "Accepting the score",
+        ConsentKind::FinishPlacement => "Placing hidden stones",
+    }
+}
+
+fn accept_label(kind: ConsentKind) -> &'static str {
+    match kind {
+        ConsentKind::AcceptScore => "Accept result",
+        ConsentKind::FinishPlacement => "Done placing",
+    }
+}
@@ -0,0 +1,37 @@
+use dioxus::prelude::*;
+use dioxus_signals::*;
+use gloo_timers::future::TimeoutFuture;
+
+use crate::state;
+use shared::game::clock::Millisecond;
+
+/// Time left until `deadline`, a server time. Ticks locally between server updates.
+#[component]
+pub fn Countdown(cx: Scope, deadline: Millisecond) -> Element {
+    let state = state::use_state(cx);
+    let tick = use_signal(cx, || 0u32);
+    use_future(cx, (), move |_| async move {
+        loop {
+            TimeoutFuture::new(1000).await;
+            *tick.write() += 1;
+        }
+    });
+
+    let _ = tick.read();
+    let remaining = deadline.0 - state.read().server_time().0;
+    let text = format_time(Millisecond(remaining));
+
+    cx.render(rsx! {
+        span { "{text}" }
+    })
+}
+
+/// Formats a duration as `m:ss`, or just seconds under a minute. Rounds up.
+pub fn format_time(time: Millisecond) -> String {
+    let seconds = (time.0.max(0) + 999) / 1000;
+    if seconds >= 60 {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    } else {
+        format!("{}s", seconds)
+    }
+}
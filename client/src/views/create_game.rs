@@ -71,6 +71,7 @@ pub fn CreateGamePanel(cx: Scope) -> Element {
     });
     let chosen_preset = use_signal(cx, || Preset::Standard);
     let modifiers = use_signal(cx, GameModifier::default);
+    let untimed = use_signal(cx, || false);
 
     let start = dioxus_signals::use_selector(cx, move || {
         let preset = chosen_preset.read().clone();
@@ -83,8 +84,9 @@ pub fn CreateGamePanel(cx: Scope) -> Element {
         };
 
         let komis = match preset {
-            Preset::Standard => vec![0, 15],
-            Preset::Rengo => vec![0, 15],
+            // The server picks the usual komi for the rules.
+            Preset::Standard => vec![],
+            Preset::Rengo => vec![],
             Preset::ThreeColor => vec![0, 0, 0],
             Preset::FourColor => vec![0, 0, 0, 0],
             Preset::ThreeColorRengo => vec![0, 0, 0],
@@ -96,6 +98,8 @@ pub fn CreateGamePanel(cx: Scope) -> Element {
             komis,
             size: (19, 19),
             mods: modifiers.read().clone(),
+            seed: None,
+            untimed: *untimed.read(),
         }
     });
 
@@ -126,7 +130,9 @@ pub fn CreateGamePanel(cx: Scope) -> Element {
                     NameInput { name: game_name }
                     PresetSelectors { chosen_preset: chosen_preset }
                     ModifierSelectors { modifiers: modifiers }
+                    UntimedSelector { untimed: untimed }
                     CreateGameButton { start: start }
+                    PresetSharing { start: start }
 
                     // Hack to get mobile usable for now
                     div {
@@ -138,6 +144,36 @@ pub fn CreateGamePanel(cx: Scope) -> Element {
     })
 }
 
+#[component]
+fn UntimedSelector(cx: Scope, untimed: Signal<bool>) -> Element {
+    let untimed = *untimed;
+    let flip = move || {
+        let value = !*untimed.read();
+        untimed.set(value);
+    };
+
+    cx.render(rsx! {
+        div {
+            style: "padding: 10px;",
+            input {
+                r#type: "checkbox",
+                checked: *untimed.read(),
+                onclick: move |_| flip(),
+            }
+            label {
+                class: "tooltip",
+                style: "cursor: pointer; margin-left: 5px;",
+                onclick: move |_| flip(),
+                "Untimed"
+                span {
+                    class: "tooltip-text",
+                    "Variant games get a clock that suits the variant, capture go a short one. Check this to play without a clock."
+                }
+            }
+        }
+    })
+}
+
 #[component]
 fn CreateGameButton(cx: Scope, start: ReadOnlySignal<message::StartGame>) -> Element {
     let start = *start;
@@ -154,6 +190,56 @@ fn CreateGameButton(cx: Scope, start: ReadOnlySignal<message::StartGame>) -> Ele
     })
 }
 
+/// Copies the settings above as a preset string, or starts a game straight from a
+/// pasted one. The server checks the rules like for any other new game.
+#[component]
+fn PresetSharing(cx: Scope, start: ReadOnlySignal<message::StartGame>) -> Element {
+    let start = *start;
+    let pasted = use_signal(cx, String::new);
+
+    let action = ActionSender::new(cx);
+
+    let copy = move |_| {
+        let preset = start.read().to_preset();
+        if let Some(clipboard) = gloo_utils::window().navigator().clipboard() {
+            // The promise only reports whether the browser allowed the write.
+            let _ = clipboard.write_text(&preset);
+        }
+    };
+
+    #[rustfmt::skip]
+    let class = sir::css!("
+        margin-top: 10px;
+
+        textarea {
+            display: block;
+            width: 100%;
+            height: 60px;
+        }
+    ");
+
+    cx.render(rsx! {
+        div {
+            class: class,
+            button {
+                title: "Copies these settings as text to share or reuse later",
+                onclick: copy,
+                "Copy preset"
+            }
+            textarea {
+                placeholder: "Paste a preset",
+                value: "{pasted}",
+                oninput: move |e| pasted.set(e.value.clone()),
+            }
+            button {
+                disabled: pasted.read().trim().is_empty(),
+                onclick: move |_| action.start_game_preset(pasted.read().clone()),
+                "Start from preset"
+            }
+        }
+    })
+}
+
 #[component]
 fn ModifierSelectors(cx: Scope, modifiers: Signal<GameModifier>) -> Element {
     let modifiers = *modifiers;
@@ -191,9 +277,32 @@ fn ModifierSelectors(cx: Scope, modifiers: Signal<GameModifier>) -> Element {
             PhantomGo { modifiers: modifiers }
             TraitorGo { modifiers: modifiers }
             CapturesGivePoints { modifiers: modifiers }
+            PassGivesPrisoner { modifiers: modifiers }
+            NeutralSeki { modifiers: modifiers }
             PonnukiIsPoints { modifiers: modifiers }
+            CaptureBonus { modifiers: modifiers }
             Observable { modifiers: modifiers }
+            NoSpectators { modifiers: modifiers }
+            AnnounceIllegal { modifiers: modifiers }
             NoUndo { modifiers: modifiers }
+            ResumeToOpponent { modifiers: modifiers }
+            PieRule { modifiers: modifiers }
+            PassHint { modifiers: modifiers }
+            TripleKoNoResult { modifiers: modifiers }
+            TeachingGame { modifiers: modifiers }
+            SetupPhase { modifiers: modifiers }
+            ExpandingBoard { modifiers: modifiers }
+            CaptureGoal { modifiers: modifiers }
+            MinPlayers { modifiers: modifiers }
+            NoFirstLine { modifiers: modifiers }
+            MultiStoneSuicide { modifiers: modifiers }
+            SingleStoneSuicide { modifiers: modifiers }
+            MoveLimit { modifiers: modifiers }
+            Handicap { modifiers: modifiers }
+            SkipAbsent { modifiers: modifiers }
+            MostCapturesTiebreak { modifiers: modifiers }
+            FewestCapturesTiebreak { modifiers: modifiers }
+            SeatOrderTiebreak { modifiers: modifiers }
         }
     })
 }
@@ -284,6 +393,28 @@ simple_modifier!(
     "Only the one to remove stones from the board gets the points. Promotes aggressive play. You only get points for removed stones, not dead stones in your territory."
 );
 
+simple_modifier!(
+    PassGivesPrisoner,
+    modifiers => modifiers.pass_gives_prisoner.is_some(),
+    modifiers.pass_gives_prisoner = match modifiers.pass_gives_prisoner {
+        Some(_) => None,
+        None => Some(shared::game::PassGivesPrisoner {}),
+    },
+    "Passing gives a prisoner",
+    "Every pass hands a prisoner to the opponents, like pass stones under Japanese rules."
+);
+
+simple_modifier!(
+    NeutralSeki,
+    modifiers => modifiers.seki == shared::game::SekiScoring::Neutral,
+    modifiers.seki = match modifiers.seki {
+        shared::game::SekiScoring::CountEyes => shared::game::SekiScoring::Neutral,
+        shared::game::SekiScoring::Neutral => shared::game::SekiScoring::CountEyes,
+    },
+    "Seki eyes are neutral",
+    "Eyes of groups in seki don't count as territory, like under Japanese rules. Seki is recognized by its shape when scoring, so check the count."
+);
+
 simple_modifier!(
     Observable,
     modifiers => modifiers.observable,
@@ -292,6 +423,22 @@ simple_modifier!(
     "All users who are not holding a seat can see all hidden stones and the true color of stones if one color go is enabled."
 );
 
+simple_modifier!(
+    NoSpectators,
+    modifiers => modifiers.no_spectators,
+    modifiers.no_spectators = !modifiers.no_spectators,
+    "No spectators",
+    "Only players and users invited into the room can join. For lessons and serious matches."
+);
+
+simple_modifier!(
+    AnnounceIllegal,
+    modifiers => modifiers.announce_illegal,
+    modifiers.announce_illegal = !modifiers.announce_illegal,
+    "Announce illegal moves",
+    "Tells spectators in their chat when a player tries a move the rules don't allow."
+);
+
 simple_modifier!(
     NoUndo,
     modifiers => modifiers.no_undo,
@@ -300,6 +447,127 @@ simple_modifier!(
     "Disables undo for all players."
 );
 
+simple_modifier!(
+    ResumeToOpponent,
+    modifiers => modifiers.resume_to_opponent,
+    modifiers.resume_to_opponent = !modifiers.resume_to_opponent,
+    "Resuming gives the opponent the move",
+    "When play resumes from scoring, the player after the one who resumed moves first."
+);
+
+simple_modifier!(
+    MultiStoneSuicide,
+    modifiers => modifiers.suicide.multi_stone,
+    modifiers.suicide.multi_stone = !modifiers.suicide.multi_stone,
+    "Multi-stone suicide",
+    "Filling the last liberty of your own group removes the group, as under New Zealand rules. Only for groups of two or more stones."
+);
+
+simple_modifier!(
+    SingleStoneSuicide,
+    modifiers => modifiers.suicide.single_stone,
+    modifiers.suicide.single_stone = !modifiers.suicide.single_stone,
+    "Single stone suicide",
+    "A lone stone without liberties is removed again. This repeats the previous position, so it only matters with triple ko rules."
+);
+
+simple_modifier!(
+    PieRule,
+    modifiers => modifiers.pie_rule,
+    modifiers.pie_rule = !modifiers.pie_rule,
+    "Pie rule",
+    "After the first move, the second player may swap sides instead of answering it. Two player games only."
+);
+
+simple_modifier!(
+    PassHint,
+    modifiers => modifiers.pass_hint,
+    modifiers.pass_hint = !modifiers.pass_hint,
+    "Pass hint",
+    "Suggests passing once no move on a neutral point improves your score estimate. Only a heuristic, it doesn't read invasions or ko."
+);
+
+simple_modifier!(
+    TripleKoNoResult,
+    modifiers => modifiers.repetition.is_some(),
+    modifiers.repetition = match modifiers.repetition {
+        Some(_) => None,
+        None => Some(shared::game::Repetition {
+            no_result_after: Some(3),
+        }),
+    },
+    "Triple ko is no result",
+    "Replaces superko with a plain ko rule. Positions may repeat, but the game is void once a position occurs three times."
+);
+
+simple_modifier!(
+    MostCapturesTiebreak,
+    modifiers => modifiers.capture_tiebreak == Some(shared::game::CaptureTiebreak::MostCaptures),
+    modifiers.capture_tiebreak = match modifiers.capture_tiebreak {
+        Some(shared::game::CaptureTiebreak::MostCaptures) => None,
+        _ => Some(shared::game::CaptureTiebreak::MostCaptures),
+    },
+    "Tiebreak: most captures",
+    "If the top scores are tied, the team that captured the most stones during play wins."
+);
+
+simple_modifier!(
+    FewestCapturesTiebreak,
+    modifiers => modifiers.capture_tiebreak == Some(shared::game::CaptureTiebreak::FewestCaptures),
+    modifiers.capture_tiebreak = match modifiers.capture_tiebreak {
+        Some(shared::game::CaptureTiebreak::FewestCaptures) => None,
+        _ => Some(shared::game::CaptureTiebreak::FewestCaptures),
+    },
+    "Tiebreak: fewest captures",
+    "If the top scores are tied, the team that captured the fewest stones during play wins."
+);
+
+simple_modifier!(
+    SeatOrderTiebreak,
+    modifiers => modifiers.seat_order_tiebreak,
+    modifiers.seat_order_tiebreak = !modifiers.seat_order_tiebreak,
+    "Tiebreak: seat order",
+    "Teams still tied after other tiebreaks are ranked by seat order, the team that moves last wins."
+);
+
+simple_modifier!(
+    TeachingGame,
+    modifiers => modifiers.teaching.is_some(),
+    modifiers.teaching = match modifiers.teaching {
+        Some(_) => None,
+        // The server fills in the creator as the teacher.
+        None => Some(shared::game::Teaching::default()),
+    },
+    "Teaching game",
+    "You can place stones for every seat to demonstrate sequences. Students play their own color as usual."
+);
+
+simple_modifier!(
+    SetupPhase,
+    modifiers => modifiers.setup_phase.is_some(),
+    modifiers.setup_phase = match modifiers.setup_phase {
+        Some(_) => None,
+        None => Some(shared::game::SetupPhase {}),
+    },
+    "Set up position",
+    "Players arrange stones freely before the game. Clicking a point cycles its color. Pass to start playing."
+);
+
+simple_modifier!(
+    ExpandingBoard,
+    modifiers => modifiers.expanding_board.is_some(),
+    modifiers.expanding_board = match modifiers.expanding_board {
+        Some(_) => None,
+        None => Some(shared::game::ExpandingBoard {
+            interval: 20,
+            growth: 2,
+            max_size: 19,
+        }),
+    },
+    "Expanding board",
+    "The board grows by a line on every side every 20 moves, up to 19x19. Start small!"
+);
+
 #[component]
 fn HiddenMoveGo(cx: Scope, modifiers: Signal<GameModifier>) -> Element {
     let modifiers = *modifiers;
@@ -507,6 +775,382 @@ fn PonnukiIsPoints(cx: Scope, modifiers: Signal<GameModifier>) -> Element {
     })
 }
 
+#[component]
+fn CaptureBonus(cx: Scope, modifiers: Signal<GameModifier>) -> Element {
+    let modifiers = *modifiers;
+    let min_stones = use_signal(cx, || 5);
+    let points = use_signal(cx, || 5);
+
+    let rule = move || shared::game::CaptureBonus {
+        min_stones: *min_stones.read(),
+        points: *points.read(),
+    };
+
+    dioxus_signals::use_effect(cx, move || {
+        let new_rule = rule();
+        if let Some(rule) = &mut modifiers.write().capture_bonus {
+            *rule = new_rule;
+        }
+    });
+
+    let flip = move || {
+        let mut modifiers = modifiers.write();
+        modifiers.capture_bonus = match modifiers.capture_bonus {
+            Some(_) => None,
+            None => Some(rule()),
+        };
+    };
+
+    cx.render(rsx! {
+        li {
+            input {
+                r#type: "checkbox",
+                checked: modifiers.read().capture_bonus.is_some(),
+                onclick: move |_| flip(),
+            }
+            label {
+                class: "tooltip",
+                onclick: move |_| flip(),
+                "Capture bonus: "
+                span {
+                    class: "tooltip-text",
+                    "Capturing this many stones with one move scores extra points"
+                }
+            }
+            span {
+                class: "adjust",
+                input {
+                    r#type: "number",
+                    min: "1",
+                    value: "{min_stones}",
+                    onchange: move |e| min_stones.set(e.inner().value.parse().unwrap_or(1).max(1))
+                }
+                " stones give "
+                input {
+                    r#type: "number",
+                    value: "{points}",
+                    onchange: move |e| points.set(e.inner().value.parse().unwrap_or(0))
+                }
+                " points"
+            }
+        }
+    })
+}
+
+#[component]
+fn CaptureGoal(cx: Scope, modifiers: Signal<GameModifier>) -> Element {
+    let modifiers = *modifiers;
+    let stone_count = use_signal(cx, || 5);
+
+    dioxus_signals::use_effect(cx, move || {
+        let count = *stone_count.read();
+        if let Some(goal) = &mut modifiers.write().capture_goal {
+            *goal = count;
+        }
+    });
+
+    let flip = move || {
+        let mut modifiers = modifiers.write();
+        modifiers.capture_goal = match modifiers.capture_goal {
+            Some(_) => None,
+            None => Some(*stone_count.read()),
+        };
+    };
+
+    cx.render(rsx! {
+        li {
+            input {
+                r#type: "checkbox",
+                checked: modifiers.read().capture_goal.is_some(),
+                onclick: move |_| flip(),
+            }
+            label {
+                class: "tooltip",
+                onclick: move |_| flip(),
+                "Capture go: "
+                span {
+                    class: "tooltip-text",
+                    "The first to capture this many stones wins. With 1 this is atari go."
+                }
+            }
+            span {
+                class: "adjust",
+                input {
+                    r#type: "number",
+                    min: "1",
+                    value: "{stone_count}",
+                    onchange: move |e| stone_count.set(e.inner().value.parse().unwrap_or(1).max(1))
+                }
+                " stones"
+            }
+        }
+    })
+}
+
+#[component]
+fn MinPlayers(cx: Scope, modifiers: Signal<GameModifier>) -> Element {
+    let modifiers = *modifiers;
+    let player_count = use_signal(cx, || 4);
+
+    dioxus_signals::use_effect(cx, move || {
+        let count = *player_count.read();
+        if let Some(min) = &mut modifiers.write().min_players {
+            *min = count;
+        }
+    });
+
+    let flip = move || {
+        let mut modifiers = modifiers.write();
+        modifiers.min_players = match modifiers.min_players {
+            Some(_) => None,
+            None => Some(*player_count.read()),
+        };
+    };
+
+    cx.render(rsx! {
+        li {
+            input {
+                r#type: "checkbox",
+                checked: modifiers.read().min_players.is_some(),
+                onclick: move |_| flip(),
+            }
+            label {
+                class: "tooltip",
+                onclick: move |_| flip(),
+                "Minimum players: "
+                span {
+                    class: "tooltip-text",
+                    "The game can't start until this many seats are taken. Can't be more than the number of seats."
+                }
+            }
+            span {
+                class: "adjust",
+                input {
+                    r#type: "number",
+                    min: "1",
+                    value: "{player_count}",
+                    onchange: move |e| player_count.set(e.inner().value.parse().unwrap_or(1).max(1))
+                }
+                " players"
+            }
+        }
+    })
+}
+
+#[component]
+fn NoFirstLine(cx: Scope, modifiers: Signal<GameModifier>) -> Element {
+    let modifiers = *modifiers;
+    let move_count = use_signal(cx, || 10);
+
+    let rules = move |moves: u32| shared::game::OpeningRestrictions {
+        rules: vec![shared::game::OpeningRestriction {
+            area: shared::game::RestrictedArea::Edge { lines: 1 },
+            moves,
+        }],
+    };
+
+    dioxus_signals::use_effect(cx, move || {
+        let moves = *move_count.read();
+        if let Some(restrictions) = &mut modifiers.write().opening_restrictions {
+            *restrictions = rules(moves);
+        }
+    });
+
+    let flip = move || {
+        let mut modifiers = modifiers.write();
+        modifiers.opening_restrictions = match modifiers.opening_restrictions {
+            Some(_) => None,
+            None => Some(rules(*move_count.read())),
+        };
+    };
+
+    cx.render(rsx! {
+        li {
+            input {
+                r#type: "checkbox",
+                checked: modifiers.read().opening_restrictions.is_some(),
+                onclick: move |_| flip(),
+            }
+            label {
+                class: "tooltip",
+                onclick: move |_| flip(),
+                "No first line: "
+                span {
+                    class: "tooltip-text",
+                    "Stones can't be played on the first line during the opening moves."
+                }
+            }
+            span {
+                class: "adjust",
+                input {
+                    r#type: "number",
+                    min: "1",
+                    value: "{move_count}",
+                    onchange: move |e| move_count.set(e.inner().value.parse().unwrap_or(1).max(1))
+                }
+                " moves"
+            }
+        }
+    })
+}
+
+#[component]
+fn MoveLimit(cx: Scope, modifiers: Signal<GameModifier>) -> Element {
+    let modifiers = *modifiers;
+    let move_count = use_signal(cx, || 500);
+    let default_limit = shared::game::DEFAULT_MAX_MOVES;
+
+    dioxus_signals::use_effect(cx, move || {
+        let count = *move_count.read();
+        if let Some(max_moves) = &mut modifiers.write().max_moves {
+            *max_moves = count;
+        }
+    });
+
+    let flip = move || {
+        let mut modifiers = modifiers.write();
+        modifiers.max_moves = match modifiers.max_moves {
+            Some(_) => None,
+            None => Some(*move_count.read()),
+        };
+    };
+
+    cx.render(rsx! {
+        li {
+            input {
+                r#type: "checkbox",
+                checked: modifiers.read().max_moves.is_some(),
+                onclick: move |_| flip(),
+            }
+            label {
+                class: "tooltip",
+                onclick: move |_| flip(),
+                "Move limit: "
+                span {
+                    class: "tooltip-text",
+                    "The game goes to scoring after this many moves and play can't be resumed. Without this the limit is {default_limit}."
+                }
+            }
+            span {
+                class: "adjust",
+                input {
+                    r#type: "number",
+                    min: "1",
+                    value: "{move_count}",
+                    onchange: move |e| move_count.set(e.inner().value.parse().unwrap_or(1).max(1))
+                }
+                " moves"
+            }
+        }
+    })
+}
+
+#[component]
+fn Handicap(cx: Scope, modifiers: Signal<GameModifier>) -> Element {
+    let modifiers = *modifiers;
+    let stone_count = use_signal(cx, || 2);
+
+    dioxus_signals::use_effect(cx, move || {
+        let stones = *stone_count.read();
+        if let Some(handicap) = &mut modifiers.write().handicap {
+            handicap.stones = stones;
+        }
+    });
+
+    let flip = move || {
+        let mut modifiers = modifiers.write();
+        modifiers.handicap = match modifiers.handicap {
+            Some(_) => None,
+            None => Some(shared::game::Handicap {
+                stones: *stone_count.read(),
+                keep_komi: false,
+            }),
+        };
+    };
+
+    cx.render(rsx! {
+        li {
+            input {
+                r#type: "checkbox",
+                checked: modifiers.read().handicap.is_some(),
+                onclick: move |_| flip(),
+            }
+            label {
+                class: "tooltip",
+                onclick: move |_| flip(),
+                "Handicap: "
+                span {
+                    class: "tooltip-text",
+                    "Black starts with stones on the star points and white plays first. Komi becomes 0.5. Only for black and white games."
+                }
+            }
+            span {
+                class: "adjust",
+                input {
+                    r#type: "number",
+                    min: "1",
+                    max: "9",
+                    value: "{stone_count}",
+                    onchange: move |e| stone_count.set(e.inner().value.parse().unwrap_or(2).clamp(1, 9))
+                }
+                " stones"
+            }
+        }
+    })
+}
+
+#[component]
+fn SkipAbsent(cx: Scope, modifiers: Signal<GameModifier>) -> Element {
+    let modifiers = *modifiers;
+    let minutes = use_signal(cx, || 3);
+
+    dioxus_signals::use_effect(cx, move || {
+        let turn_seconds = *minutes.read() * 60;
+        if let Some(rule) = &mut modifiers.write().skip_absent {
+            rule.turn_seconds = turn_seconds;
+        }
+    });
+
+    let flip = move || {
+        let mut modifiers = modifiers.write();
+        modifiers.skip_absent = match modifiers.skip_absent {
+            Some(_) => None,
+            None => Some(shared::game::SkipAbsent {
+                turn_seconds: *minutes.read() * 60,
+            }),
+        };
+    };
+
+    cx.render(rsx! {
+        li {
+            input {
+                r#type: "checkbox",
+                checked: modifiers.read().skip_absent.is_some(),
+                onclick: move |_| flip(),
+            }
+            label {
+                class: "tooltip",
+                onclick: move |_| flip(),
+                "Skip absent players: "
+                span {
+                    class: "tooltip-text",
+                    "A seat that doesn't move in time passes automatically, so the others can keep playing. Only for games of three or more seats."
+                }
+            }
+            span {
+                class: "adjust",
+                input {
+                    r#type: "number",
+                    min: "1",
+                    value: "{minutes}",
+                    onchange: move |e| minutes.set(e.inner().value.parse().unwrap_or(1).max(1))
+                }
+                " minutes"
+            }
+        }
+    })
+}
+
 #[component]
 fn PresetSelectors(cx: Scope, chosen_preset: Signal<Preset>) -> Element {
     let presets = [
@@ -0,0 +1,82 @@
+use dioxus::prelude::*;
+
+use crate::state;
+
+/// Errors from `state::handle_error`. Toasts go away when clicked, fatal errors stay
+/// up until the page is reloaded.
+#[component]
+pub fn ErrorToasts(cx: Scope) -> Element {
+    let state = state::use_state(cx);
+    let errors = state.read().errors;
+    let fatal_error = state.read().fatal_error;
+
+    let reload = move |_| {
+        if let Err(e) = gloo_utils::window().location().reload() {
+            log::error!("Reload failed: {:?}", e);
+        }
+    };
+
+    #[rustfmt::skip]
+    let class = sir::css!("
+        position: fixed;
+        right: 10px;
+        bottom: 10px;
+        display: flex;
+        flex-direction: column;
+        gap: 5px;
+        z-index: 10;
+
+        .toast {
+            padding: 10px;
+            max-width: 300px;
+            cursor: pointer;
+            background-color: var(--bg-h-color);
+            color: var(--text-color);
+            border-radius: 3px;
+        }
+
+        .fatal {
+            font-weight: bold;
+            cursor: default;
+            a {
+                cursor: pointer;
+                margin-left: 10px;
+            }
+        }
+    ");
+
+    let shown = errors
+        .read()
+        .iter()
+        .enumerate()
+        .map(|(idx, error)| (idx, error.to_string()))
+        .collect::<Vec<_>>();
+    let fatal = fatal_error.read().as_ref().map(|e| e.to_string());
+
+    cx.render(rsx! {
+        div {
+            class: "{class}",
+            if let Some(text) = fatal {
+                rsx!(div {
+                    class: "toast fatal",
+                    "{text}"
+                    a { onclick: reload, "Reload" }
+                })
+            }
+            for (idx, text) in shown {
+                div {
+                    key: "{idx}",
+                    class: "toast",
+                    title: "Click to dismiss",
+                    onclick: move |_| {
+                        let mut errors = errors.write();
+                        if idx < errors.len() {
+                            errors.remove(idx);
+                        }
+                    },
+                    "{text}"
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,135 @@
+use dioxus::prelude::*;
+use dioxus_signals::*;
+
+use crate::state::{self, ActionSender};
+use crate::{palette, settings};
+use shared::game::{self, GameStateView};
+
+/// Share of the estimated score held by each team, counting every group as alive.
+/// Asked from the server after every move. The answer only comes to us, so spectators
+/// can follow it without the players noticing.
+#[component]
+pub fn ScoreEstimateBar(cx: Scope) -> Element {
+    let state = state::use_state(cx);
+    let room = state.read().active_room();
+    let estimate = state.read().estimate;
+    let action = ActionSender::new(cx);
+    let enabled = use_signal(cx, || settings::ScoreEstimate::get().0);
+    let requested = use_signal(cx, || None::<(u32, u32)>);
+
+    let toggle = move |_| {
+        let value = !*enabled.read();
+        settings::ScoreEstimate(value).save();
+        enabled.set(value);
+        action.sync_settings();
+    };
+
+    let position = {
+        let room = room.read();
+        let Some(room) = room.as_ref() else {
+            return None;
+        };
+        if !matches!(room.view.state, GameStateView::Play(_)) {
+            return None;
+        }
+        (room.id, room.view.move_number)
+    };
+
+    if *enabled.read() && *requested.read() != Some(position) {
+        requested.set(Some(position));
+        action.estimate_score();
+    }
+
+    // Keep showing the previous estimate until the new one arrives.
+    let scores = match &*estimate.read() {
+        Some((_, scores)) => scores.to_vec(),
+        None => Vec::new(),
+    };
+
+    #[rustfmt::skip]
+    let class = sir::css!("
+        padding: 10px;
+
+        .actions {
+            display: flex;
+            gap: 10px;
+            a {
+                cursor: pointer;
+                color: var(--text-color);
+            }
+        }
+
+        .bar {
+            display: flex;
+            height: 20px;
+            margin-top: 5px;
+            overflow: hidden;
+            border-radius: 3px;
+            div {
+                overflow: hidden;
+                white-space: nowrap;
+                font-size: 12px;
+                line-height: 20px;
+                padding: 0 4px;
+            }
+        }
+    ");
+
+    if !*enabled.read() {
+        return cx.render(rsx! {
+            div {
+                class: "{class}",
+                div {
+                    class: "actions",
+                    a {
+                        title: "Show who is ahead according to a rough score estimate",
+                        onclick: toggle,
+                        "Estimate"
+                    }
+                }
+            }
+        });
+    }
+
+    let palette = palette::PaletteOption::get().to_palette();
+    let total = scores.iter().map(|&s| s.max(0)).sum::<i32>().max(1);
+    let segments = scores
+        .iter()
+        .enumerate()
+        .map(|(idx, &score)| {
+            let share = score.max(0) as f64 * 100.0 / total as f64;
+            let label = format!(
+                "{} {}",
+                game::Color::name(idx as u8 + 1),
+                score as f64 / 2.0
+            );
+            (
+                share,
+                label,
+                palette.stone_colors[idx],
+                palette.dead_mark_color[idx],
+            )
+        })
+        .collect::<Vec<_>>();
+
+    cx.render(rsx! {
+        div {
+            class: "{class}",
+            div {
+                class: "actions",
+                span { "Score estimate" }
+                a { onclick: toggle, "Hide" }
+            }
+            div {
+                class: "bar",
+                title: "Estimate only. Every group counts as alive and invasions aren't read.",
+                for (share, label, bg, fg) in segments {
+                    div {
+                        style: "width: {share}%; background: {bg}; color: {fg};",
+                        "{label}"
+                    }
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,109 @@
+use dioxus::prelude::*;
+use shared::game::{self, GameStateView};
+use shared::message;
+
+use crate::state::{self, ActionSender};
+
+/// The count while scoring and a new game with the same rules once it's over. Only for
+/// seated players, accepting the count is up to `ConsentPrompts`.
+#[component]
+pub fn GameOverPanel(cx: Scope) -> Element {
+    let state = state::use_state(cx);
+    let room = state.read().active_room();
+    let action = ActionSender::new(cx);
+
+    let me = state.read().user.read().user_id;
+    let (scoring, done, rematch, seki, places) = {
+        let room = room.read();
+        let Some(room) = room.as_ref() else {
+            return None;
+        };
+        let view = &room.view;
+        let (scoring, done) = match &view.state {
+            GameStateView::Scoring(scoring) => (scoring.clone(), false),
+            GameStateView::Done(scoring) => (scoring.clone(), true),
+            _ => return None,
+        };
+        if !view.seats.iter().any(|s| s.player == Some(me)) {
+            return None;
+        }
+        let rematch = message::StartGame {
+            name: format!("{}'s game", state::username(&state.read().user.read())),
+            seats: view.seats.iter().map(|s| s.team.0).collect(),
+            komis: view.komis.to_vec(),
+            size: view.size,
+            mods: view.mods.clone(),
+            seed: None,
+        };
+        (scoring, done, rematch, view.mods.seki, view.places.clone())
+    };
+
+    let scores = scoring
+        .scores
+        .iter()
+        .enumerate()
+        .map(|(idx, &score)| {
+            format!(
+                "{} {}",
+                game::Color::name(idx as u8 + 1),
+                score as f32 / 2.0
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    // Teams listed by place, the ones sharing a place in seat order.
+    let mut ranked = places.iter().enumerate().collect::<Vec<_>>();
+    ranked.sort_by_key(|&(_, &place)| place);
+    let ranking = ranked
+        .into_iter()
+        .map(|(idx, place)| format!("{}. {}", place, game::Color::name(idx as u8 + 1)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let seki_eyes = scoring.seki_eyes.len();
+    let seki_note = match seki {
+        game::SekiScoring::CountEyes => "counted",
+        game::SekiScoring::Neutral => "left neutral",
+    };
+
+    #[rustfmt::skip]
+    let class = sir::css!("
+        padding: 10px;
+
+        .actions {
+            display: flex;
+            gap: 10px;
+            a {
+                cursor: pointer;
+                color: var(--text-color);
+            }
+        }
+    ");
+
+    cx.render(rsx! {
+        div {
+            class: "{class}",
+            div {
+                if done { "Final score: " } else { "Counted: " }
+                "{scores}"
+            }
+            if !ranking.is_empty() {
+                rsx!(div { "Ranking: {ranking}" })
+            }
+            if seki_eyes > 0 {
+                rsx!(div { "Seki: {seki_eyes} eye points {seki_note}" })
+            }
+            div {
+                class: "actions",
+                if done {
+                    rsx!(a {
+                        title: "Opens a new game with the same rules. The others join by taking a seat in it.",
+                        onclick: move |_| action.start_game(rematch.clone()),
+                        "Rematch"
+                    })
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,73 @@
+use dioxus::prelude::*;
+use shared::game::{self, GameStateView};
+
+use crate::{board, state};
+
+/// A count of the live position for spectators and commentators, with a guess at the
+/// dead stones. It runs the real counting on our copy of the board, so the players and
+/// the game never see it.
+#[component]
+pub fn HypotheticalCount(cx: Scope) -> Element {
+    let state = state::use_state(cx);
+    let room = state.read().active_room();
+    let shown = state.read().hypothetical;
+
+    let me = state.read().user.read().user_id;
+    let scores = {
+        let room = room.read();
+        let Some(room) = room.as_ref() else {
+            return None;
+        };
+        let view = &room.view;
+        let seated = view.seats.iter().any(|s| s.player == Some(me));
+        if seated || !matches!(view.state, GameStateView::Play(_)) {
+            return None;
+        }
+        match *shown.read() {
+            true => board::hypothetical_count(view).map(|count| count.scores),
+            false => None,
+        }
+    };
+
+    let text = scores.map(|scores| {
+        scores
+            .iter()
+            .enumerate()
+            .map(|(idx, &score)| {
+                format!(
+                    "{} {}",
+                    game::Color::name(idx as u8 + 1),
+                    score as f32 / 2.0
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    });
+
+    #[rustfmt::skip]
+    let class = sir::css!("
+        padding: 10px;
+
+        a {
+            cursor: pointer;
+            color: var(--text-color);
+        }
+    ");
+
+    cx.render(rsx! {
+        div {
+            class: "{class}",
+            a {
+                title: "Count the position as if the game ended now, guessing which stones are dead. Only shown to you.",
+                onclick: move |_| {
+                    let value = !*shown.read();
+                    shown.set(value);
+                },
+                if text.is_some() { "Hide count" } else { "Count position" }
+            }
+            if let Some(text) = text {
+                rsx!(div { "Hypothetical count, not the result: {text}" })
+            }
+        }
+    })
+}
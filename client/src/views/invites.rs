@@ -0,0 +1,81 @@
+use dioxus::prelude::*;
+
+use crate::state;
+
+/// Invitations from other users, oldest first. Accepting joins the room like following
+/// does, declining only forgets the invitation.
+#[component]
+pub fn InvitePrompts(cx: Scope) -> Element {
+    let state = state::use_state(cx);
+    let invites = state.read().invites;
+    let random_game = state.read().random_game;
+
+    #[rustfmt::skip]
+    let class = sir::css!("
+        position: fixed;
+        left: 10px;
+        bottom: 10px;
+        display: flex;
+        flex-direction: column;
+        gap: 5px;
+        z-index: 10;
+
+        .invite {
+            padding: 10px;
+            max-width: 300px;
+            background-color: var(--bg-h-color);
+            color: var(--text-color);
+            border-radius: 3px;
+
+            a {
+                cursor: pointer;
+                margin-left: 10px;
+            }
+        }
+    ");
+
+    let shown = {
+        let profiles = state.read().profiles;
+        let profiles = profiles.read();
+        invites
+            .read()
+            .iter()
+            .enumerate()
+            .map(|(idx, invite)| {
+                let nick = profiles
+                    .get(&invite.from)
+                    .map(state::username)
+                    .unwrap_or_else(|| "Someone".to_string());
+                (idx, invite.room_id, nick, invite.room_name.clone())
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let dismiss = move |idx: usize| {
+        let mut invites = invites.write();
+        if idx < invites.len() {
+            invites.remove(idx);
+        }
+    };
+
+    cx.render(rsx! {
+        div {
+            class: "{class}",
+            for (idx, room_id, nick, room_name) in shown {
+                div {
+                    key: "{idx}",
+                    class: "invite",
+                    "{nick} invites you to {room_name}"
+                    a {
+                        onclick: move |_| {
+                            dismiss(idx);
+                            random_game.set(Some(room_id));
+                        },
+                        "Join"
+                    }
+                    a { onclick: move |_| dismiss(idx), "Decline" }
+                }
+            }
+        }
+    })
+}
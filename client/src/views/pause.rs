@@ -0,0 +1,92 @@
+use dioxus::prelude::*;
+use dioxus_signals::*;
+use gloo_timers::future::TimeoutFuture;
+
+use crate::state::{self, ActionSender};
+use crate::views::Countdown;
+
+/// Shown while play is held for a disconnected player. Once they're back or the grace
+/// window is over play can be resumed, and after the window their opponents may also
+/// claim the game.
+#[component]
+pub fn PauseNotice(cx: Scope) -> Element {
+    let state = state::use_state(cx);
+    let room = state.read().active_room();
+    let action = ActionSender::new(cx);
+    let tick = use_signal(cx, || 0u32);
+    use_future(cx, (), move |_| async move {
+        loop {
+            TimeoutFuture::new(1000).await;
+            *tick.write() += 1;
+        }
+    });
+
+    let (pause, seated) = {
+        let room = room.read();
+        let Some(room) = room.as_ref() else {
+            return None;
+        };
+        let Some(pause) = room.view.pause.clone() else {
+            return None;
+        };
+        let me = state.read().user.read().user_id;
+        let seated = room.view.seats.iter().any(|s| s.player == Some(me));
+        (pause, seated.then_some(me))
+    };
+
+    let _ = tick.read();
+    let now = state.read().server_time();
+    let grace_over = now >= pause.grace_ends;
+    let name = state
+        .read()
+        .profiles
+        .read()
+        .get(&pause.player)
+        .map_or_else(|| "Unknown".to_string(), state::username);
+
+    let can_resume = seated.is_some() && (pause.returned || grace_over);
+    let can_claim = seated.map_or(false, |me| me != pause.player) && pause.claimable(now);
+
+    #[rustfmt::skip]
+    let class = sir::css!("
+        padding: 10px;
+
+        .actions {
+            display: flex;
+            gap: 10px;
+            a {
+                cursor: pointer;
+                color: var(--text-color);
+            }
+        }
+    ");
+
+    cx.render(rsx! {
+        div {
+            class: "{class}",
+            if pause.returned {
+                rsx!(div { "{name} is back. Resume when you're ready." })
+            } else if grace_over {
+                rsx!(div { "{name} didn't return in time." })
+            } else {
+                rsx!(div {
+                    "Paused, {name} disconnected. Waiting "
+                    Countdown { deadline: pause.grace_ends }
+                })
+            }
+            div {
+                class: "actions",
+                if can_resume {
+                    rsx!(a { onclick: move |_| action.resume(), "Resume" })
+                }
+                if can_claim {
+                    rsx!(a {
+                        title: "Ends the game. {name} resigns.",
+                        onclick: move |_| action.claim(),
+                        "Claim the game"
+                    })
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,94 @@
+use dioxus::prelude::*;
+use shared::message::{BoardPalette, BoardPresentation};
+
+use crate::state::{self, ActionSender};
+
+/// Lets the room owner pick how everyone's board looks, for lessons and streams. "Own"
+/// leaves each member's setting alone.
+#[component]
+pub fn PresentationPanel(cx: Scope) -> Element {
+    let state = state::use_state(cx);
+    let room = state.read().active_room();
+    let action = ActionSender::new(cx);
+
+    let me = state.read().user.read().user_id;
+    let is_owner = room.read().as_ref().map_or(false, |room| room.owner == me);
+    if !is_owner {
+        return None;
+    }
+
+    let presentation = state.read().presentation;
+    let current = presentation.read().clone();
+    let palette = match current.palette {
+        None => "own",
+        Some(BoardPalette::Normal) => "normal",
+        Some(BoardPalette::Colorblind) => "colorblind",
+    };
+    let coordinates = match current.coordinates {
+        None => "own",
+        Some(true) => "show",
+        Some(false) => "hide",
+    };
+    let rotation = current
+        .rotation
+        .map_or("own".to_string(), |r| r.to_string());
+
+    let change = move |update: &dyn Fn(&mut BoardPresentation)| {
+        let mut presentation = presentation.read().clone();
+        update(&mut presentation);
+        action.present(presentation);
+    };
+
+    #[rustfmt::skip]
+    let class = sir::css!("
+        padding: 10px;
+        display: flex;
+        flex-wrap: wrap;
+        gap: 4px;
+    ");
+
+    cx.render(rsx! {
+        div {
+            class: "{class}",
+            title: "Everyone in the room sees the board like this, unless they turned it off",
+            span { "Room board:" }
+            select {
+                value: "{palette}",
+                onchange: move |e: FormEvent| change(&|p| {
+                    p.palette = match e.inner().value.as_str() {
+                        "normal" => Some(BoardPalette::Normal),
+                        "colorblind" => Some(BoardPalette::Colorblind),
+                        _ => None,
+                    }
+                }),
+                option { value: "own", "Own colors" }
+                option { value: "normal", "Normal colors" }
+                option { value: "colorblind", "Colorblind colors" }
+            }
+            select {
+                value: "{coordinates}",
+                onchange: move |e: FormEvent| change(&|p| {
+                    p.coordinates = match e.inner().value.as_str() {
+                        "show" => Some(true),
+                        "hide" => Some(false),
+                        _ => None,
+                    }
+                }),
+                option { value: "own", "Own coordinates" }
+                option { value: "show", "Show coordinates" }
+                option { value: "hide", "Hide coordinates" }
+            }
+            select {
+                value: "{rotation}",
+                onchange: move |e: FormEvent| change(&|p| {
+                    p.rotation = e.inner().value.parse().ok();
+                }),
+                option { value: "own", "Own rotation" }
+                option { value: "0", "Upright" }
+                option { value: "1", "Turned right" }
+                option { value: "2", "Upside down" }
+                option { value: "3", "Turned left" }
+            }
+        }
+    })
+}
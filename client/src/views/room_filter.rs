@@ -0,0 +1,171 @@
+use dioxus::prelude::*;
+use shared::game::VARIANT_NAMES;
+use shared::message::{RoomFilter, RoomSort};
+
+use crate::state::{self, ActionSender};
+
+/// Rooms on a filtered page of the lobby.
+const PAGE_SIZE: u32 = 20;
+
+const SIZES: [(u8, u8); 3] = [(9, 9), (13, 13), (19, 19)];
+
+/// Narrows the lobby down by variant, board size and whether the game is being played.
+/// The server does the filtering and sends one page at a time. Every game is casual,
+/// so there is nothing to filter by rating.
+#[component]
+pub fn RoomFilterBar(cx: Scope) -> Element {
+    let state = state::use_state(cx);
+    let action = ActionSender::new(cx);
+
+    let current_filter = move || state.read().room_filter.read().clone().unwrap_or_default();
+    let current = current_filter();
+    let found_rooms = state.read().found_rooms;
+    let total = found_rooms.read().as_ref().map(|(_, total)| *total);
+
+    // Every change but paging starts over from the first page.
+    let apply = move |mut filter: RoomFilter| {
+        filter.offset = 0;
+        filter.limit = None;
+        if filter == RoomFilter::default() {
+            action.find_games(None);
+        } else {
+            filter.limit = Some(PAGE_SIZE);
+            action.find_games(Some(filter));
+        }
+    };
+    let page = move |offset: u32| {
+        let mut filter = current_filter();
+        filter.offset = offset;
+        action.find_games(Some(filter));
+    };
+
+    let variant = match current.variant.as_deref() {
+        None => "any".to_string(),
+        Some("") => "standard".to_string(),
+        Some(name) => name.to_string(),
+    };
+    let size = current
+        .size
+        .map_or("any".to_string(), |(w, h)| format!("{w}x{h}"));
+    let status = match current.in_progress {
+        None => "any",
+        Some(true) => "playing",
+        Some(false) => "other",
+    };
+    let sort = match current.sort {
+        RoomSort::Newest => "newest",
+        RoomSort::MostSpectators => "spectators",
+        RoomSort::BoardSize => "size",
+    };
+
+    let paging = total.map(|total| {
+        let first = (current.offset + 1).min(total);
+        let last = (current.offset + PAGE_SIZE).min(total);
+        let previous = (current.offset > 0).then(|| current.offset.saturating_sub(PAGE_SIZE));
+        let next = (last < total).then_some(current.offset + PAGE_SIZE);
+        (first, last, total, previous, next)
+    });
+
+    #[rustfmt::skip]
+    let class = sir::css!("
+        display: flex;
+        flex-wrap: wrap;
+        gap: 4px;
+        padding: 4px;
+        border-bottom: 1px solid var(--text-color);
+
+        .paging {
+            display: flex;
+            gap: 10px;
+            width: 100%;
+            a {
+                cursor: pointer;
+                color: var(--text-color);
+            }
+        }
+    ");
+
+    cx.render(rsx! {
+        div {
+            class: "{class}",
+            select {
+                title: "Variant",
+                value: "{variant}",
+                onchange: move |e: FormEvent| {
+                    let value = e.inner().value.clone();
+                    let mut filter = current_filter();
+                    filter.variant = match value.as_str() {
+                        "any" => None,
+                        "standard" => Some(String::new()),
+                        _ => Some(value),
+                    };
+                    apply(filter);
+                },
+                option { value: "any", "Any variant" }
+                option { value: "standard", "Standard go" }
+                for name in VARIANT_NAMES.iter() {
+                    option { value: "{name}", "{name}" }
+                }
+            }
+            select {
+                title: "Board size",
+                value: "{size}",
+                onchange: move |e: FormEvent| {
+                    let value = &e.inner().value;
+                    let mut filter = current_filter();
+                    filter.size = SIZES.iter().copied().find(|(w, h)| format!("{w}x{h}") == *value);
+                    apply(filter);
+                },
+                option { value: "any", "Any size" }
+                for (w, h) in SIZES.iter() {
+                    option { value: "{w}x{h}", "{w}x{h}" }
+                }
+            }
+            select {
+                title: "Status",
+                value: "{status}",
+                onchange: move |e: FormEvent| {
+                    let mut filter = current_filter();
+                    filter.in_progress = match e.inner().value.as_str() {
+                        "playing" => Some(true),
+                        "other" => Some(false),
+                        _ => None,
+                    };
+                    apply(filter);
+                },
+                option { value: "any", "Any status" }
+                option { value: "playing", "In progress" }
+                option { value: "other", "Not started or over" }
+            }
+            select {
+                title: "Sort",
+                value: "{sort}",
+                onchange: move |e: FormEvent| {
+                    let mut filter = current_filter();
+                    filter.sort = match e.inner().value.as_str() {
+                        "spectators" => RoomSort::MostSpectators,
+                        "size" => RoomSort::BoardSize,
+                        _ => RoomSort::Newest,
+                    };
+                    apply(filter);
+                },
+                option { value: "newest", "Newest first" }
+                option { value: "spectators", "Most watched" }
+                option { value: "size", "Largest board" }
+            }
+            if let Some((first, last, total, previous, next)) = paging {
+                rsx!(div {
+                    class: "paging",
+                    span { "{first}-{last} of {total}" }
+                    if let Some(offset) = previous {
+                        rsx!(a { onclick: move |_| page(offset), "Previous" })
+                    }
+                    if let Some(offset) = next {
+                        rsx!(a { onclick: move |_| page(offset), "Next" })
+                    }
+                    a { onclick: move |_| action.find_games(None), "Show all" }
+                })
+            }
+        }
+    })
+}
@@ -0,0 +1,56 @@
+use dioxus::prelude::*;
+use dioxus_signals::*;
+
+use crate::autosave;
+
+/// Offers the game record kept by `autosave` from an earlier visit. Only read once on
+/// load, dismissing it deletes the record unless a new game is being saved already.
+#[component]
+pub fn SavedGamePrompt(cx: Scope) -> Element {
+    let saved = use_signal(cx, autosave::saved);
+    let Some(sgf) = saved.read().clone() else {
+        return None;
+    };
+
+    let href = format!(
+        "data:application/x-go-sgf;charset=utf-8,{}",
+        String::from(web_sys::js_sys::encode_uri_component(&sgf))
+    );
+
+    #[rustfmt::skip]
+    let class = sir::css!("
+        position: fixed;
+        right: 10px;
+        bottom: 10px;
+        padding: 10px;
+        max-width: 300px;
+        background-color: var(--bg-h-color);
+        color: var(--text-color);
+        border-radius: 3px;
+        z-index: 10;
+
+        a {
+            cursor: pointer;
+            margin-left: 10px;
+        }
+    ");
+
+    cx.render(rsx! {
+        div {
+            class: "{class}",
+            "The record of your last game was kept."
+            a {
+                href: "{href}",
+                download: "last-game.sgf",
+                "Download SGF"
+            }
+            a {
+                onclick: move |_| {
+                    autosave::forget(&sgf);
+                    saved.set(None);
+                },
+                "Dismiss"
+            }
+        }
+    })
+}
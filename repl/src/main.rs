@@ -53,18 +53,17 @@ async fn main() {
                 match msg {
                     ServerMessage::GameStatus {
                         room_id,
-                        move_number,
                         members,
-                        seats,
+                        view,
                         ..
                     } => {
                         let mut state = state.lock().unwrap();
                         let room = state.rooms.entry(room_id).or_insert_with(RoomInfo::default);
                         room.room_id = room_id;
-                        room.move_count = move_number as usize;
+                        room.move_count = view.move_number as usize;
                         room.member_count = members.len() - 1; // Subtract self
 
-                        room.players = seats.into_iter().filter_map(|x| x.0).collect();
+                        room.players = view.seats.iter().filter_map(|x| x.player).collect();
                         room.players.sort_unstable();
                         room.players.dedup();
 
@@ -73,7 +72,7 @@ async fn main() {
                             room_id, room.member_count, room.move_count
                         );
                     }
-                    ServerMessage::AnnounceGame { room_id, name } => {
+                    ServerMessage::AnnounceGame { room_id, name, .. } => {
                         let mut state = state.lock().unwrap();
                         let room = state.rooms.entry(room_id).or_insert_with(RoomInfo::default);
                         room.room_id = room_id;
@@ -90,6 +89,9 @@ async fn main() {
                         let mut state = state.lock().unwrap();
                         state.profiles.insert(profile.user_id, profile.nick);
                     }
+                    ServerMessage::StateDump { json, .. } => {
+                        println!("{}", json);
+                    }
                     ServerMessage::Error(e) => {
                         println!("{:?}", e);
                     }
@@ -168,6 +170,14 @@ async fn read_stdin(state: Arc<Mutex<State>>, tx: futures_channel::mpsc::Unbound
                     .collect(),
                 _ => vec![],
             },
+            "reset" => words
+                .filter_map(|x| x.parse::<u32>().ok())
+                .map(|id| ClientMessage::Admin(AdminAction::ResetRoom(id)))
+                .collect(),
+            "dump" | "d" => words
+                .filter_map(|x| x.parse::<u32>().ok())
+                .map(|id| ClientMessage::Admin(AdminAction::DumpRoom(id)))
+                .collect(),
             "list" | "li" => vec![ClientMessage::GetGameList],
             "visit" | "v" => state
                 .rooms
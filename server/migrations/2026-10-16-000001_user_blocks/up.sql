@@ -0,0 +1,2 @@
+ALTER TABLE users
+ADD COLUMN blocked BIGINT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE users
+DROP COLUMN solved_problems;
+
+DROP TABLE problems;
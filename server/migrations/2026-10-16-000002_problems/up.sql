@@ -0,0 +1,10 @@
+CREATE TABLE problems (
+  id BIGSERIAL PRIMARY KEY,
+  name TEXT NOT NULL,
+  width SMALLINT NOT NULL,
+  height SMALLINT NOT NULL,
+  problem TEXT NOT NULL
+);
+
+ALTER TABLE users
+ADD COLUMN solved_problems BIGINT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,5 @@
+CREATE TABLE retired_tokens (
+  token TEXT PRIMARY KEY,
+  user_id BIGINT NOT NULL REFERENCES users,
+  expires BIGINT NOT NULL
+);
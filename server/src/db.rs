@@ -7,6 +7,8 @@ use dotenv::dotenv;
 use std::env;
 
 use crate::schema::games;
+use crate::schema::problems;
+use crate::schema::retired_tokens;
 use crate::schema::users;
 
 fn establish_connection() -> PgConnection {
@@ -29,6 +31,9 @@ pub struct User {
     pub auth_token: String,
     pub nick: Option<String>,
     pub has_integration_access: bool,
+    pub settings: Option<String>,
+    pub blocked: Vec<i64>,
+    pub solved_problems: Vec<i64>,
 }
 
 #[derive(Insertable, AsChangeset)]
@@ -38,6 +43,16 @@ pub struct NewUser<'a> {
     pub nick: Option<&'a str>,
 }
 
+/// A rotated token that still logs in as the user until `expires`, in seconds since the
+/// Unix epoch.
+#[derive(Queryable, Insertable, Debug)]
+#[table_name = "retired_tokens"]
+pub struct RetiredToken {
+    pub token: String,
+    pub user_id: i64,
+    pub expires: i64,
+}
+
 // Game ///////////////////////////////////////////////////////////////////////
 
 #[derive(Queryable, Debug)]
@@ -46,6 +61,8 @@ pub struct Game {
     pub name: String,
     pub replay: Option<Vec<u8>>,
     pub owner: Option<i64>,
+    /// Chat lines of both channels as JSON.
+    pub chat: Option<String>,
 }
 
 #[derive(Insertable, AsChangeset)]
@@ -57,6 +74,27 @@ pub struct NewGame<'a> {
     pub owner: Option<i64>,
 }
 
+// Problem ////////////////////////////////////////////////////////////////////
+
+#[derive(Queryable, Debug)]
+pub struct Problem {
+    pub id: i64,
+    pub name: String,
+    pub width: i16,
+    pub height: i16,
+    /// `game::Problem` as JSON.
+    pub problem: String,
+}
+
+#[derive(Insertable)]
+#[table_name = "problems"]
+pub struct NewProblem<'a> {
+    pub name: &'a str,
+    pub width: i16,
+    pub height: i16,
+    pub problem: &'a str,
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 //                               Actor messages                              //
 ///////////////////////////////////////////////////////////////////////////////
@@ -66,6 +104,10 @@ pub struct NewGame<'a> {
 pub struct IdentifyUser {
     pub auth_token: String,
     pub nick: Option<String>,
+    /// Replaces the token of an existing user. New users keep `auth_token`.
+    pub new_auth_token: Option<String>,
+    /// The user `auth_token` was retired from. Looked up by id when no user has the token.
+    pub retired_user: Option<u64>,
 }
 
 impl Message for IdentifyUser {
@@ -82,6 +124,48 @@ impl Message for GetUserByToken {
     type Result = Result<User, ()>;
 }
 
+pub struct StoreSettings {
+    pub user_id: u64,
+    pub settings: String,
+}
+
+impl Message for StoreSettings {
+    type Result = Result<(), ()>;
+}
+
+pub struct StoreBlocked {
+    pub user_id: u64,
+    pub blocked: Vec<i64>,
+}
+
+impl Message for StoreBlocked {
+    type Result = Result<(), ()>;
+}
+
+pub struct StoreSolvedProblems {
+    pub user_id: u64,
+    pub solved: Vec<i64>,
+}
+
+impl Message for StoreSolvedProblems {
+    type Result = Result<(), ()>;
+}
+
+pub struct StoreRetiredToken(pub RetiredToken);
+
+impl Message for StoreRetiredToken {
+    type Result = Result<(), ()>;
+}
+
+/// Tokens still in their grace period. Expired ones are removed.
+pub struct GetRetiredTokens {
+    pub now: i64,
+}
+
+impl Message for GetRetiredTokens {
+    type Result = Result<Vec<RetiredToken>, ()>;
+}
+
 // Game ///////////////////////////////////////////////////////////////////////
 
 pub struct StoreGame {
@@ -101,6 +185,34 @@ impl Message for GetGame {
     type Result = Result<Game, ()>;
 }
 
+pub struct StoreChat {
+    pub id: u64,
+    pub chat: String,
+}
+
+impl Message for StoreChat {
+    type Result = Result<(), ()>;
+}
+
+// Problem ////////////////////////////////////////////////////////////////////
+
+/// All problems in the order they are served.
+pub struct GetProblems;
+
+impl Message for GetProblems {
+    type Result = Result<Vec<Problem>, ()>;
+}
+
+pub struct StoreProblem {
+    pub name: String,
+    pub size: (u8, u8),
+    pub problem: String,
+}
+
+impl Message for StoreProblem {
+    type Result = Result<Problem, ()>;
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 //                                   Actor                                   //
 ///////////////////////////////////////////////////////////////////////////////
@@ -141,10 +253,19 @@ impl Handler<IdentifyUser> for DbActor {
         let existing = users
             .filter(auth_token.eq(&msg.auth_token))
             .first::<User>(&self.connection);
+        let existing = match (existing, msg.retired_user) {
+            (Err(DError::NotFound), Some(user)) => {
+                users.find(user as i64).first::<User>(&self.connection)
+            }
+            (existing, _) => existing,
+        };
 
         let result = match existing {
             Ok(u) => diesel::update(users.filter(id.eq(u.id)))
-                .set(new_user)
+                .set(NewUser {
+                    auth_token: msg.new_auth_token.as_deref().unwrap_or(&u.auth_token),
+                    ..new_user
+                })
                 .get_result(&self.connection),
             Err(DError::NotFound) => diesel::insert_into(users)
                 .values(new_user)
@@ -197,6 +318,89 @@ impl Handler<GetUserByToken> for DbActor {
     }
 }
 
+impl Handler<StoreSettings> for DbActor {
+    type Result = Result<(), ()>;
+
+    fn handle(&mut self, msg: StoreSettings, _ctx: &mut Self::Context) -> Self::Result {
+        use crate::schema::users::dsl::*;
+
+        diesel::update(users.find(msg.user_id as i64))
+            .set(settings.eq(Some(msg.settings)))
+            .execute(&self.connection)
+            .map(|_| ())
+            .map_err(|e| {
+                println!("{:?}", e);
+            })
+    }
+}
+
+impl Handler<StoreBlocked> for DbActor {
+    type Result = Result<(), ()>;
+
+    fn handle(&mut self, msg: StoreBlocked, _ctx: &mut Self::Context) -> Self::Result {
+        use crate::schema::users::dsl::*;
+
+        diesel::update(users.find(msg.user_id as i64))
+            .set(blocked.eq(msg.blocked))
+            .execute(&self.connection)
+            .map(|_| ())
+            .map_err(|e| {
+                println!("{:?}", e);
+            })
+    }
+}
+
+impl Handler<StoreSolvedProblems> for DbActor {
+    type Result = Result<(), ()>;
+
+    fn handle(&mut self, msg: StoreSolvedProblems, _ctx: &mut Self::Context) -> Self::Result {
+        use crate::schema::users::dsl::*;
+
+        diesel::update(users.find(msg.user_id as i64))
+            .set(solved_problems.eq(msg.solved))
+            .execute(&self.connection)
+            .map(|_| ())
+            .map_err(|e| {
+                println!("{:?}", e);
+            })
+    }
+}
+
+impl Handler<StoreRetiredToken> for DbActor {
+    type Result = Result<(), ()>;
+
+    fn handle(&mut self, msg: StoreRetiredToken, _ctx: &mut Self::Context) -> Self::Result {
+        use crate::schema::retired_tokens::dsl::*;
+
+        diesel::insert_into(retired_tokens)
+            .values(&msg.0)
+            .execute(&self.connection)
+            .map(|_| ())
+            .map_err(|e| {
+                println!("{:?}", e);
+            })
+    }
+}
+
+impl Handler<GetRetiredTokens> for DbActor {
+    type Result = Result<Vec<RetiredToken>, ()>;
+
+    fn handle(&mut self, msg: GetRetiredTokens, _ctx: &mut Self::Context) -> Self::Result {
+        use crate::schema::retired_tokens::dsl::*;
+
+        let expired = retired_tokens.filter(expires.le(msg.now));
+        if let Err(e) = diesel::delete(expired).execute(&self.connection) {
+            println!("{:?}", e);
+        }
+
+        retired_tokens
+            .load::<RetiredToken>(&self.connection)
+            .map_err(|e| {
+                println!("{:?}", e);
+            })
+    }
+}
+
 impl Handler<StoreGame> for DbActor {
     type Result = Result<Game, ()>;
 
@@ -238,3 +442,56 @@ impl Handler<GetGame> for DbActor {
         })
     }
 }
+
+impl Handler<StoreChat> for DbActor {
+    type Result = Result<(), ()>;
+
+    fn handle(&mut self, msg: StoreChat, _ctx: &mut Self::Context) -> Self::Result {
+        use crate::schema::games::dsl::*;
+
+        diesel::update(games.find(msg.id as i64))
+            .set(chat.eq(Some(msg.chat)))
+            .execute(&self.connection)
+            .map(|_| ())
+            .map_err(|e| {
+                println!("{:?}", e);
+            })
+    }
+}
+
+impl Handler<GetProblems> for DbActor {
+    type Result = Result<Vec<Problem>, ()>;
+
+    fn handle(&mut self, _: GetProblems, _ctx: &mut Self::Context) -> Self::Result {
+        use crate::schema::problems::dsl::*;
+
+        problems
+            .order(id.asc())
+            .load::<Problem>(&self.connection)
+            .map_err(|e| {
+                println!("{:?}", e);
+            })
+    }
+}
+
+impl Handler<StoreProblem> for DbActor {
+    type Result = Result<Problem, ()>;
+
+    fn handle(&mut self, msg: StoreProblem, _ctx: &mut Self::Context) -> Self::Result {
+        use crate::schema::problems::dsl::*;
+
+        let new_problem = NewProblem {
+            name: &msg.name,
+            width: msg.size.0 as _,
+            height: msg.size.1 as _,
+            problem: &msg.problem,
+        };
+
+        diesel::insert_into(problems)
+            .values(new_problem)
+            .get_result(&self.connection)
+            .map_err(|e| {
+                println!("{:?}", e);
+            })
+    }
+}
@@ -1,14 +1,25 @@
 use actix::prelude::*;
 use std::collections::{HashMap, HashSet};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-use crate::{db, server};
+use crate::{db, gtp, server};
 use shared::game;
 use shared::game::clock::Millisecond;
 use shared::message;
 
 // TODO: add room timeout
 
+/// Maximum length of a single chat message in bytes.
+const MAX_CHAT_LENGTH: usize = 500;
+/// How many lines of each chat channel are kept for late joiners.
+const CHAT_HISTORY_LENGTH: usize = 100;
+/// Typing notices of a user are relayed at most this often.
+const TYPING_INTERVAL: Duration = Duration::from_secs(2);
+/// How long players get to agree on the dead stones before play resumes.
+const SCORING_TIMEOUT: Duration = Duration::from_secs(180);
+/// How often rooms check for scoring and turn timeouts.
+const SCORING_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
 ///////////////////////////////////////////////////////////////////////////////
 //                               Actor messages                              //
 ///////////////////////////////////////////////////////////////////////////////
@@ -25,14 +36,61 @@ pub enum Message {
         members: Vec<u64>,
         view: game::GameView,
     },
+    GameStarted {
+        room_id: u32,
+        config: message::StartGame,
+    },
     BoardAt {
         room_id: u32,
         view: game::GameHistory,
     },
+    Review {
+        room_id: u32,
+        view: game::ReviewView,
+    },
+    Annotations {
+        room_id: u32,
+        annotations: Vec<game::Annotation>,
+    },
+    Presentation {
+        room_id: u32,
+        presentation: message::BoardPresentation,
+    },
     SGF {
         room_id: u32,
         sgf: String,
     },
+    Stats {
+        room_id: u32,
+        csv: String,
+    },
+    LegalMoves {
+        room_id: u32,
+        seat: u32,
+        moves: Vec<(u32, u32)>,
+    },
+    ScoreEstimate {
+        room_id: u32,
+        move_number: u32,
+        scores: game::GroupVec<i32>,
+    },
+    StateDump {
+        room_id: u32,
+        json: String,
+    },
+    Chat {
+        room_id: u32,
+        line: message::ChatLine,
+    },
+    ChatHistory {
+        room_id: u32,
+        lines: Vec<message::ChatLine>,
+    },
+    UserTyping {
+        room_id: u32,
+        user_id: u64,
+        channel: message::ChatChannel,
+    },
 }
 
 // Actions ////////////////////////////////////////////////////////////////////
@@ -63,20 +121,76 @@ pub struct Leave {
     pub session_id: usize,
 }
 
-#[derive(Message)]
-#[rtype(result = "()")]
+/// Rejected if the game doesn't admit the user, see `game::Game::admits`.
 pub struct Join {
     pub session_id: usize,
     pub user_id: u64,
     pub addr: Recipient<Message>,
 }
 
+impl actix::Message for Join {
+    type Result = Result<(), message::Error>;
+}
+
+/// Another user asked the user into the room, see `server::Invite`.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Invited {
+    pub from: u64,
+    pub user_id: u64,
+}
+
 // Control ////////////////////////////////////////////////////////////////////
 
+/// Answer of the engine, sent back by its thread. Stale if the move number changed.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct EngineMove {
+    pub engine: gtp::Engine,
+    pub move_number: usize,
+    pub result: Result<game::ActionKind, gtp::EngineError>,
+}
+
 #[derive(Message)]
 #[rtype(result = "()")]
 pub struct Unload;
 
+/// Admin access checks are done by the server before this is sent.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Reset {
+    pub seed: u64,
+}
+
+/// Admin access checks are done by the server before this is sent.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct DumpState {
+    pub addr: Recipient<Message>,
+}
+
+/// Whether the user holds a seat in an unfinished game.
+pub struct HoldsSeat {
+    pub user_id: u64,
+}
+
+/// `Some` if the user holds a seat in an unfinished game, with whether it's waiting on them.
+impl actix::Message for HoldsSeat {
+    type Result = Option<bool>;
+}
+
+/// Activity of an unfinished game, `None` once it's done.
+pub struct GetActivity;
+
+impl actix::Message for GetActivity {
+    type Result = Option<Activity>;
+}
+
+pub struct Activity {
+    pub idle: Duration,
+    pub member_count: usize,
+}
+
 pub struct GetAdminView;
 
 impl actix::Message for GetAdminView {
@@ -95,12 +209,43 @@ pub struct GameRoom {
     pub name: String,
     pub last_action: Instant,
     pub game: game::Game,
+    /// Post-game review shared by the room. Not persisted.
+    pub review: Option<game::Review>,
+    /// Drawn by the teacher of a teaching game, see `game::Game::annotate`. Not persisted.
+    pub annotations: Vec<game::Annotation>,
+    /// Board presentation the owner shares with the room. Not persisted either.
+    pub presentation: message::BoardPresentation,
+    /// When the current scoring phase started. Disputes end after `SCORING_TIMEOUT`.
+    pub scoring_since: Option<Instant>,
+    /// Move number on turn and when it started, for skipping absent seats.
+    pub turn_since: Option<(u32, Instant)>,
+    /// When the game last changed. Moves are timed from here.
+    pub changed_at: Instant,
+    /// Milliseconds each user took for their moves, checked by `game::timing` once the
+    /// game ends. Not persisted.
+    pub think_times: HashMap<u64, Vec<u32>>,
+    /// Whether `Message::GameStarted` went out for the current game.
+    pub started: bool,
+    /// External engine holding a seat as `gtp::ENGINE_USER_ID`. Taken by the engine
+    /// thread while `engine_thinking`.
+    pub engine: Option<gtp::Engine>,
+    pub engine_thinking: bool,
     pub db: Addr<db::DbActor>,
     pub server: Addr<server::GameServer>,
 
     /// Kicked players are not visible to other users in the game and can not
     /// hold seats. They can still follow the game.
     pub kicked_players: HashSet<u64>,
+    /// Users invited by someone the game admits. Only they can join as spectators
+    /// when the game has `no_spectators`. Not persisted.
+    pub invited: HashSet<u64>,
+
+    /// Chat channels are stored separately so spectator chatter never reaches players.
+    /// Both are stored with the game, see `store_chat`.
+    pub main_chat: Vec<message::ChatLine>,
+    pub spectator_chat: Vec<message::ChatLine>,
+    /// When the typing notice of each user was last relayed.
+    pub typing_relayed: HashMap<u64, Instant>,
 }
 
 impl GameRoom {
@@ -120,7 +265,432 @@ impl GameRoom {
                 .copied()
                 .filter(|id| !self.kicked_players.contains(id))
                 .collect(),
-            view: self.game.get_view(user_id),
+            view: game::GameView {
+                scoring_timeout: self.scoring_deadline(),
+                ..self.game.get_view(user_id)
+            },
+        }
+    }
+
+    /// Settings the game was created with, as a `StartGame` request would carry them.
+    fn start_config(&self) -> message::StartGame {
+        let setup = &self.game.setup;
+        message::StartGame {
+            name: self.name.clone(),
+            seats: setup.seats.to_vec(),
+            komis: setup.komis.to_vec(),
+            size: setup.size,
+            mods: setup.mods.clone(),
+            seed: None,
+            untimed: setup.mods.clock.is_none(),
+        }
+    }
+
+    /// Stores the game and sends the new state to everyone in the room.
+    fn game_changed(&mut self) {
+        self.db.do_send(db::StoreGame {
+            id: Some(self.room_id as _),
+            name: self.name.clone(),
+            replay: Some(self.game.dump()),
+            owner: self.owner,
+        });
+
+        self.send_room_messages(|user_id| self.view_for_user(user_id));
+        if !self.started && self.game.has_started() {
+            self.started = true;
+            let config = self.start_config();
+            self.send_room_messages(|_| Message::GameStarted {
+                room_id: self.room_id,
+                config: config.clone(),
+            });
+        }
+        self.send_thumbnail();
+        self.send_spectators();
+        if self.game.result().is_some() {
+            let timing_flags = self.timing_flags();
+            self.server.do_send(server::GameFinished {
+                room_id: self.room_id,
+                winner: self.winner(),
+                timing_flags,
+            });
+        }
+        if self.game.problem_solved() {
+            self.server.do_send(server::ProblemSolved {
+                room_id: self.room_id,
+            });
+        }
+        self.changed_at = Instant::now();
+    }
+
+    /// Players whose move timing looks machine-like. The times are dropped, so every game
+    /// is only checked once.
+    fn timing_flags(&mut self) -> Vec<(u64, game::timing::TimingFlag)> {
+        std::mem::take(&mut self.think_times)
+            .into_iter()
+            .filter_map(|(user_id, times)| {
+                let flag = game::timing::suspicious_timing(&times)?;
+                Some((user_id, flag))
+            })
+            .collect()
+    }
+
+    /// The player seated for the only team in first place. `None` for ties and voided games.
+    fn winner(&self) -> Option<u64> {
+        let places = self.game.places();
+        let mut first = (0..places.len()).filter(|&idx| places[idx] == 1);
+        let team = match (first.next(), first.next()) {
+            (Some(idx), None) => game::Color(idx as u8 + 1),
+            _ => return None,
+        };
+        let seat = self.game.shared.seats.iter().find(|s| s.team == team)?;
+        seat.player
+    }
+
+    /// Starts or stops the scoring timer to match the game state.
+    fn update_scoring_timer(&mut self) {
+        let scoring = matches!(self.game.state, game::GameState::Scoring(_));
+        match (scoring, self.scoring_since) {
+            (true, None) => self.scoring_since = Some(Instant::now()),
+            (false, Some(_)) => self.scoring_since = None,
+            _ => {}
+        }
+    }
+
+    fn scoring_deadline(&self) -> Option<Millisecond> {
+        let remaining = SCORING_TIMEOUT.saturating_sub(self.scoring_since?.elapsed());
+        Some(Millisecond(
+            current_time().0 + remaining.as_millis() as i128,
+        ))
+    }
+
+    /// Resumes play when the players can't agree on the dead stones in time.
+    /// Toggling stones doesn't extend the timeout, so a dispute can't stall the game.
+    /// In the final round of scoring the game is counted with the estimated dead stones
+    /// instead, see `game::Game::settle_scoring`.
+    fn check_scoring_timeout(&mut self) {
+        self.update_scoring_timer();
+        let expired = self
+            .scoring_since
+            .map_or(false, |since| since.elapsed() >= SCORING_TIMEOUT);
+        if !expired {
+            return;
+        }
+
+        let final_round = match &self.game.state {
+            game::GameState::Scoring(state) => state.final_round,
+            _ => false,
+        };
+        if final_round {
+            // Nobody accepts on behalf of the players, the estimated dead stones count.
+            if self.game.settle_scoring().is_err() {
+                return;
+            }
+        } else {
+            // Any seat holder may cancel scoring. Acting as one keeps the replay valid.
+            // Whoever passed last sits before the turn, resuming as them keeps the turn
+            // order under `GameModifier::resume_to_opponent` as well.
+            let seats = &self.game.shared.seats;
+            let turn = self.game.shared.turn;
+            let player_id = (1..=seats.len())
+                .map(|offset| &seats[(turn + seats.len() - offset) % seats.len()])
+                .filter(|s| !s.resigned)
+                .find_map(|s| s.player);
+            let player_id = match player_id {
+                Some(x) => x,
+                None => return,
+            };
+            let res = self
+                .game
+                .make_action(player_id, game::ActionKind::Cancel, current_time());
+            if res.is_err() {
+                return;
+            }
+        }
+
+        self.scoring_since = None;
+        self.last_action = Instant::now();
+        self.game_changed();
+    }
+
+    /// Passes for the seat on turn once it has been on turn for longer than
+    /// `SkipAbsent::turn_seconds`, so the other players can go on.
+    fn check_turn_timeout(&mut self) {
+        let turn_seconds = match &self.game.shared.mods.skip_absent {
+            Some(rule) => rule.turn_seconds,
+            None => return,
+        };
+        if !matches!(self.game.state, game::GameState::Play(_)) {
+            self.turn_since = None;
+            return;
+        }
+
+        let move_number = self.game.shared.board_history.len() as u32;
+        let since = match self.turn_since {
+            Some((turn, since)) if turn == move_number => since,
+            _ => {
+                self.turn_since = Some((move_number, Instant::now()));
+                return;
+            }
+        };
+        if since.elapsed() < Duration::from_secs(turn_seconds as u64) {
+            return;
+        }
+
+        if self.game.skip_turn(current_time()).is_err() {
+            return;
+        }
+        self.turn_since = None;
+        self.update_scoring_timer();
+        self.game_changed();
+    }
+
+    /// Lets the engine act when the game waits on it. The score is accepted right away,
+    /// moves are asked for on a thread of their own and come back as `EngineMove`.
+    fn poll_engine(&mut self, ctx: &mut Context<Self>) {
+        if !self.game.holds_seat(gtp::ENGINE_USER_ID) {
+            // Kicked from its seat or the room was reset.
+            self.engine = None;
+            return;
+        }
+        if self.engine_thinking
+            || self.game.pause.is_some()
+            || !self.game.awaits_player(gtp::ENGINE_USER_ID)
+        {
+            return;
+        }
+
+        match self.game.state {
+            game::GameState::Scoring(_) => {
+                let res = self.game.make_action(
+                    gtp::ENGINE_USER_ID,
+                    game::ActionKind::Pass,
+                    current_time(),
+                );
+                if res.is_ok() {
+                    self.update_scoring_timer();
+                    self.game_changed();
+                }
+            }
+            game::GameState::Play(_) => {
+                // Not running after a server restart or a crash, start it again.
+                let engine = match self.engine.take() {
+                    Some(engine) => Ok(engine),
+                    None => gtp::Engine::spawn(),
+                };
+                let mut engine = match engine {
+                    Ok(engine) => engine,
+                    Err(err) => return self.engine_failed(err),
+                };
+                self.engine_thinking = true;
+                let game = self.game.clone();
+                let seat = self.game.shared.turn;
+                let move_number = self.game.shared.board_history.len();
+                let addr = ctx.address();
+                std::thread::spawn(move || {
+                    let result = engine.genmove(&game, seat);
+                    addr.do_send(EngineMove {
+                        engine,
+                        move_number,
+                        result,
+                    });
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// Holds the game like for a disconnected player, so the opponents can resume with a
+    /// restarted engine or claim the game.
+    fn engine_failed(&mut self, err: gtp::EngineError) {
+        println!("Engine failed in room {}: {}", self.room_id, err);
+        self.engine = None;
+        if self
+            .game
+            .pause_for(gtp::ENGINE_USER_ID, current_time())
+            .is_ok()
+        {
+            self.game_changed();
+        }
+    }
+
+    /// Play going back and forth between the board and scoring can be used to stall
+    /// the game, so repeated resumes are logged for moderation.
+    fn log_scoring_resume(&self, user_id: u64) {
+        let rounds = match &self.game.state {
+            game::GameState::Play(state) => state.scoring_rounds,
+            _ => return,
+        };
+        if rounds > 1 {
+            println!(
+                "Room {}: user {} resumed play from scoring {} times, possible stalling",
+                self.room_id, user_id, rounds
+            );
+        }
+    }
+
+    fn state_dump(&self) -> Message {
+        let mut members: Vec<u64> = self.users.iter().copied().collect();
+        members.sort_unstable();
+        let mut kicked_players: Vec<u64> = self.kicked_players.iter().copied().collect();
+        kicked_players.sort_unstable();
+
+        let dump = message::RoomDump {
+            room_id: self.room_id,
+            name: self.name.clone(),
+            owner: self.owner,
+            members,
+            kicked_players,
+            game: self.game.dump_state(),
+        };
+
+        Message::StateDump {
+            room_id: self.room_id,
+            json: serde_json::to_string_pretty(&dump).expect("State dump failed"),
+        }
+    }
+
+    fn review_message(&self) -> Option<Message> {
+        let review = self.review.as_ref()?;
+        Some(Message::Review {
+            room_id: self.room_id,
+            view: self.game.get_review_view(review)?,
+        })
+    }
+
+    fn annotations_message(&self) -> Message {
+        Message::Annotations {
+            room_id: self.room_id,
+            annotations: self.annotations.clone(),
+        }
+    }
+
+    fn presentation_message(&self) -> Message {
+        Message::Presentation {
+            room_id: self.room_id,
+            presentation: self.presentation.clone(),
+        }
+    }
+
+    /// Tells the server how many users follow the game without a seat. Kicked players count.
+    fn send_spectators(&self) {
+        let spectators = self
+            .users
+            .iter()
+            .filter(|&&user_id| !self.game.holds_seat(user_id))
+            .count();
+        self.server.do_send(server::UpdateSpectators {
+            room_id: self.room_id,
+            spectators: spectators as u32,
+        });
+    }
+
+    fn send_thumbnail(&self) {
+        let board = &self.game.shared.board;
+        self.server.do_send(server::UpdateThumbnail {
+            room_id: self.room_id,
+            thumbnail: self.game.thumbnail(),
+            size: (board.width as _, board.height as _),
+            in_progress: self.game.in_progress(),
+        });
+    }
+
+    /// The owner counts as invited to their own room.
+    fn is_invited(&self, user_id: u64) -> bool {
+        self.owner == Some(user_id) || self.invited.contains(&user_id)
+    }
+
+    fn can_see_channel(&self, user_id: u64, channel: message::ChatChannel) -> bool {
+        match channel {
+            message::ChatChannel::Main => true,
+            message::ChatChannel::Spectator => !self.game.holds_seat(user_id),
+        }
+    }
+
+    fn send_chat(
+        &mut self,
+        user_id: u64,
+        channel: message::ChatChannel,
+        text: String,
+    ) -> Result<(), message::Error> {
+        use message::Error;
+
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(());
+        }
+        if text.len() > MAX_CHAT_LENGTH {
+            return Err(Error::other("Message too long"));
+        }
+        if self.kicked_players.contains(&user_id) {
+            return Err(Error::other("Kicked from game"));
+        }
+        if !self.can_see_channel(user_id, channel) {
+            return Err(Error::Game {
+                room_id: self.room_id,
+                error: message::GameError::NoSeat,
+            });
+        }
+
+        self.push_chat(message::ChatLine {
+            user_id,
+            channel,
+            text: text.to_owned(),
+        });
+
+        Ok(())
+    }
+
+    fn push_chat(&mut self, line: message::ChatLine) {
+        let channel = line.channel;
+        let history = match channel {
+            message::ChatChannel::Main => &mut self.main_chat,
+            message::ChatChannel::Spectator => &mut self.spectator_chat,
+        };
+        history.push(line.clone());
+        if history.len() > CHAT_HISTORY_LENGTH {
+            history.remove(0);
+        }
+        self.store_chat();
+
+        for (member_id, addr) in self.sessions.values() {
+            if self.can_see_channel(*member_id, channel) {
+                let _ = addr.do_send(Message::Chat {
+                    room_id: self.room_id,
+                    line: line.clone(),
+                });
+            }
+        }
+    }
+
+    fn store_chat(&self) {
+        let lines: Vec<_> = self.main_chat.iter().chain(&self.spectator_chat).collect();
+        let chat = serde_json::to_string(&lines).expect("chat serialization failed");
+        self.db.do_send(db::StoreChat {
+            id: self.room_id as _,
+            chat,
+        });
+    }
+
+    fn relay_typing(&mut self, user_id: u64, channel: message::ChatChannel) {
+        if self.kicked_players.contains(&user_id) || !self.can_see_channel(user_id, channel) {
+            return;
+        }
+        let now = Instant::now();
+        if let Some(&last) = self.typing_relayed.get(&user_id) {
+            if now - last < TYPING_INTERVAL {
+                return;
+            }
+        }
+        self.typing_relayed.insert(user_id, now);
+
+        for (member_id, addr) in self.sessions.values() {
+            if *member_id != user_id && self.can_see_channel(*member_id, channel) {
+                let _ = addr.do_send(Message::UserTyping {
+                    room_id: self.room_id,
+                    user_id,
+                    channel,
+                });
+            }
         }
     }
 
@@ -132,31 +702,120 @@ impl GameRoom {
     ) -> Result<(), message::Error> {
         use message::Error;
 
-        let current_time = Millisecond(
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as i128,
+        let current_time = current_time();
+
+        if let message::GameAction::Chat { channel, text } = action {
+            return self.send_chat(user_id, channel, text);
+        }
+        if let message::GameAction::Typing(channel) = action {
+            self.relay_typing(user_id, channel);
+            return Ok(());
+        }
+        // Only the asking session hears back. Doesn't count as room activity either.
+        if let message::GameAction::EstimateScore = action {
+            let addr = addr.expect("Address needed to get score estimate");
+            let _ = addr.do_send(Message::ScoreEstimate {
+                room_id: self.room_id,
+                move_number: self.game.shared.board_history.len() as u32 - 1,
+                scores: self.game.score_estimate(user_id),
+            });
+            return Ok(());
+        }
+
+        let seat_bound = matches!(
+            action,
+            message::GameAction::Place(..)
+                | message::GameAction::Pass
+                | message::GameAction::Cancel
+                | message::GameAction::Resign
+                | message::GameAction::Swap
+                | message::GameAction::Review(_)
+                | message::GameAction::SetupStones(_)
+                | message::GameAction::Resume
+                | message::GameAction::Claim
         );
+        if seat_bound && !self.game.can_play(user_id) {
+            return Err(Error::Game {
+                room_id: self.room_id,
+                error: message::GameError::NoSeat,
+            });
+        }
 
         self.last_action = Instant::now();
         let res = match action {
-            message::GameAction::Place(x, y) => self
-                .game
-                .make_action(user_id, game::ActionKind::Place(x, y), current_time)
-                .map_err(Into::into),
+            message::GameAction::Place(x, y) => {
+                let kind = game::ActionKind::Place(x, y);
+                let res = self.game.make_action(user_id, kind, current_time);
+                if res.is_ok() {
+                    let millis = self.changed_at.elapsed().as_millis() as u32;
+                    self.think_times.entry(user_id).or_default().push(millis);
+                }
+                if let Err(err) = &res {
+                    if let Some(text) = self.game.illegal_move_notice(user_id, err) {
+                        self.push_chat(message::ChatLine {
+                            user_id: message::SYSTEM_USER_ID,
+                            channel: message::ChatChannel::Spectator,
+                            text,
+                        });
+                    }
+                }
+                res.map_err(Into::into)
+            }
             message::GameAction::Pass => self
                 .game
                 .make_action(user_id, game::ActionKind::Pass, current_time)
                 .map_err(Into::into),
-            message::GameAction::Cancel => self
-                .game
-                .make_action(user_id, game::ActionKind::Cancel, current_time)
-                .map_err(Into::into),
+            message::GameAction::Cancel => {
+                let was_scoring = matches!(self.game.state, game::GameState::Scoring(_));
+                let res = self
+                    .game
+                    .make_action(user_id, game::ActionKind::Cancel, current_time)
+                    .map_err(Into::into);
+                if was_scoring && res.is_ok() {
+                    self.log_scoring_resume(user_id);
+                }
+                res
+            }
             message::GameAction::Resign => self
                 .game
                 .make_action(user_id, game::ActionKind::Resign, current_time)
                 .map_err(Into::into),
+            message::GameAction::Swap => self
+                .game
+                .make_action(user_id, game::ActionKind::Swap, current_time)
+                .map_err(Into::into),
+            message::GameAction::SetupStones(stones) => {
+                self.game.setup_stones(user_id, stones).map_err(Into::into)
+            }
+            message::GameAction::Resume => {
+                self.game.resume(user_id, current_time).map_err(Into::into)
+            }
+            message::GameAction::Claim => {
+                self.game.claim(user_id, current_time).map_err(Into::into)
+            }
+            message::GameAction::SeatEngine(seat_id) => {
+                if self.owner != Some(user_id) {
+                    return Err(Error::other("Not room owner"));
+                }
+                if !game::export::gtp_supported(&self.game) {
+                    return Err(Error::other("Engines can't play this game"));
+                }
+                let engine = match gtp::Engine::spawn() {
+                    Ok(engine) => engine,
+                    Err(err) => {
+                        println!("Starting an engine failed: {}", err);
+                        return Err(Error::other("No engine available"));
+                    }
+                };
+                let res = self
+                    .game
+                    .take_seat(gtp::ENGINE_USER_ID, seat_id as _)
+                    .map_err(Into::into);
+                if res.is_ok() {
+                    self.engine = Some(engine);
+                }
+                res
+            }
             message::GameAction::TakeSeat(seat_id) => {
                 if self.kicked_players.contains(&user_id) {
                     return Err(Error::other("Kicked from game"));
@@ -174,6 +833,9 @@ impl GameRoom {
                 if start > end {
                     return Ok(());
                 }
+                if start as usize >= self.game.shared.board_history.len() {
+                    return Err(Error::other("No such move"));
+                }
                 // Prevent asking for a ridiculous amount.
                 if end as usize > self.game.shared.board_history.len() + 20 {
                     return Ok(());
@@ -189,19 +851,106 @@ impl GameRoom {
                 }
                 return Ok(());
             }
-            message::GameAction::RequestSGF => {
+            message::GameAction::RequestSGF(options) => {
                 let addr = addr.expect("Address needed to get SGF");
                 let game_done = matches!(self.game.state, game::GameState::Done(_));
                 if !game_done {
                     return Err(Error::other("Game not finished"));
                 }
-                let sgf = game::export::sgf_export(&self.game);
+                let sgf = game::export::sgf_export_with(&self.game, &options);
                 let _ = addr.do_send(Message::SGF {
                     room_id: self.room_id,
                     sgf,
                 });
                 return Ok(());
             }
+            message::GameAction::RequestStats => {
+                let addr = addr.expect("Address needed to get stats");
+                let game_done = matches!(self.game.state, game::GameState::Done(_));
+                if !game_done {
+                    return Err(Error::other("Game not finished"));
+                }
+                let _ = addr.do_send(Message::Stats {
+                    room_id: self.room_id,
+                    csv: game::export::stats_csv(&self.game),
+                });
+                return Ok(());
+            }
+            message::GameAction::RequestLegalMoves(seat) => {
+                let addr = addr.expect("Address needed to get legal moves");
+                let mods = &self.game.shared.mods;
+                // Occupied points would give away hidden stones.
+                if mods.hidden_move.is_some() || mods.phantom.is_some() {
+                    return Err(Error::other("Not available with hidden stones"));
+                }
+                let moves = self.game.legal_moves(seat as usize);
+                let _ = addr.do_send(Message::LegalMoves {
+                    room_id: self.room_id,
+                    seat,
+                    moves,
+                });
+                return Ok(());
+            }
+            message::GameAction::RequestStateDump => {
+                let addr = addr.expect("Address needed to get state dump");
+                if self.owner != Some(user_id) {
+                    return Err(Error::other("Not room owner"));
+                }
+                // The dump holds hidden stones and visibility, which a player must not see.
+                let game_done = matches!(self.game.state, game::GameState::Done(_));
+                if !game_done && self.game.holds_seat(user_id) {
+                    return Err(Error::other("Not available while seated"));
+                }
+                let _ = addr.do_send(self.state_dump());
+                return Ok(());
+            }
+            // Handled before game actions.
+            message::GameAction::Chat { .. }
+            | message::GameAction::Typing(_)
+            | message::GameAction::EstimateScore => return Ok(()),
+            message::GameAction::Review(review_action) => {
+                let mut review = self
+                    .review
+                    .clone()
+                    .unwrap_or_else(|| self.game.start_review());
+                if let Err(err) = self.game.make_review_action(&mut review, review_action) {
+                    return Err(Error::Game {
+                        room_id: self.room_id,
+                        error: err.into(),
+                    });
+                }
+                self.review = Some(review);
+                if let Some(msg) = self.review_message() {
+                    self.send_room_messages(|_| msg.clone());
+                }
+                return Ok(());
+            }
+            message::GameAction::Annotate(annotation_action) => {
+                let mut annotations = std::mem::take(&mut self.annotations);
+                let res = self
+                    .game
+                    .annotate(&mut annotations, user_id, annotation_action);
+                self.annotations = annotations;
+                if let Err(err) = res {
+                    return Err(Error::Game {
+                        room_id: self.room_id,
+                        error: err.into(),
+                    });
+                }
+                let msg = self.annotations_message();
+                self.send_room_messages(|_| msg.clone());
+                return Ok(());
+            }
+            message::GameAction::Present(presentation) => {
+                if self.owner != Some(user_id) {
+                    return Err(Error::other("Not room owner"));
+                }
+                presentation.check()?;
+                self.presentation = presentation;
+                let msg = self.presentation_message();
+                self.send_room_messages(|_| msg.clone());
+                return Ok(());
+            }
             message::GameAction::KickPlayer(kick_player_id) => {
                 if self.owner != Some(user_id) {
                     return Err(Error::other("Not room owner"));
@@ -226,14 +975,8 @@ impl GameRoom {
             });
         }
 
-        self.db.do_send(db::StoreGame {
-            id: Some(self.room_id as _),
-            name: self.name.clone(),
-            replay: Some(self.game.dump()),
-            owner: self.owner,
-        });
-
-        self.send_room_messages(|user_id| self.view_for_user(user_id));
+        self.update_scoring_timer();
+        self.game_changed();
 
         Ok(())
     }
@@ -242,6 +985,14 @@ impl GameRoom {
 impl Actor for GameRoom {
     type Context = Context<Self>;
 
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(SCORING_CHECK_INTERVAL, |act, ctx| {
+            act.check_scoring_timeout();
+            act.check_turn_timeout();
+            act.poll_engine(ctx);
+        });
+    }
+
     fn stopping(&mut self, _ctx: &mut Self::Context) -> Running {
         println!("Room {} stopping!", self.room_id);
 
@@ -259,14 +1010,22 @@ impl Handler<Leave> for GameRoom {
             let sessions = &self.sessions;
             if !sessions.values().any(|(uid, _addr)| *uid == user_id) {
                 self.users.remove(&user_id);
-                self.send_room_messages(|user_id| self.view_for_user(user_id));
+                // Absent seats are skipped in those games instead.
+                let paused = self.game.shared.mods.skip_absent.is_none()
+                    && self.game.pause_for(user_id, current_time()).is_ok();
+                if paused {
+                    self.game_changed();
+                } else {
+                    self.send_room_messages(|user_id| self.view_for_user(user_id));
+                    self.send_spectators();
+                }
             }
         }
     }
 }
 
 impl Handler<Join> for GameRoom {
-    type Result = ();
+    type Result = Result<(), message::Error>;
 
     fn handle(&mut self, msg: Join, _ctx: &mut Self::Context) -> Self::Result {
         let Join {
@@ -275,9 +1034,36 @@ impl Handler<Join> for GameRoom {
             addr,
         } = msg;
 
-        self.sessions.insert(session_id, (user_id, addr));
+        if !self.game.admits(user_id, self.is_invited(user_id)) {
+            return Err(message::Error::other("This game doesn't allow spectators"));
+        }
+
+        self.sessions.insert(session_id, (user_id, addr.clone()));
         self.users.insert(user_id);
+        self.game.player_returned(user_id);
         self.send_room_messages(|user_id| self.view_for_user(user_id));
+        self.send_spectators();
+
+        let lines = self
+            .main_chat
+            .iter()
+            .chain(&self.spectator_chat)
+            .filter(|line| self.can_see_channel(user_id, line.channel))
+            .cloned()
+            .collect();
+        let _ = addr.do_send(Message::ChatHistory {
+            room_id: self.room_id,
+            lines,
+        });
+        if let Some(msg) = self.review_message() {
+            let _ = addr.do_send(msg);
+        }
+        if !self.annotations.is_empty() {
+            let _ = addr.do_send(self.annotations_message());
+        }
+        if self.presentation != message::BoardPresentation::default() {
+            let _ = addr.do_send(self.presentation_message());
+        }
 
         // TODO: Announce profile to room members
 
@@ -288,13 +1074,26 @@ impl Handler<Join> for GameRoom {
                 self.server.do_send(server::QueryProfile { user_id });
             }
         }
+
+        Ok(())
+    }
+}
+
+impl Handler<Invited> for GameRoom {
+    type Result = ();
+
+    fn handle(&mut self, msg: Invited, _ctx: &mut Self::Context) -> Self::Result {
+        // Spectators the game doesn't admit can't bring others along.
+        if self.game.admits(msg.from, self.is_invited(msg.from)) {
+            self.invited.insert(msg.user_id);
+        }
     }
 }
 
 impl Handler<GameAction> for GameRoom {
     type Result = MessageResult<GameAction>;
 
-    fn handle(&mut self, msg: GameAction, _: &mut Context<Self>) -> MessageResult<GameAction> {
+    fn handle(&mut self, msg: GameAction, ctx: &mut Context<Self>) -> MessageResult<GameAction> {
         use message::Error;
 
         let GameAction { id, action } = msg;
@@ -307,7 +1106,9 @@ impl Handler<GameAction> for GameRoom {
         };
         let addr = addr.clone();
 
-        MessageResult(self.make_action(user_id, action, Some(addr)))
+        let res = self.make_action(user_id, action, Some(addr));
+        self.poll_engine(ctx);
+        MessageResult(res)
     }
 }
 
@@ -317,11 +1118,49 @@ impl Handler<GameActionAsUser> for GameRoom {
     fn handle(
         &mut self,
         msg: GameActionAsUser,
-        _: &mut Context<Self>,
+        ctx: &mut Context<Self>,
     ) -> MessageResult<GameActionAsUser> {
         let GameActionAsUser { user_id, action } = msg;
 
-        MessageResult(self.make_action(user_id, action, None))
+        let res = self.make_action(user_id, action, None);
+        self.poll_engine(ctx);
+        MessageResult(res)
+    }
+}
+
+impl Handler<EngineMove> for GameRoom {
+    type Result = ();
+
+    fn handle(&mut self, msg: EngineMove, ctx: &mut Self::Context) -> Self::Result {
+        let EngineMove {
+            engine,
+            move_number,
+            result,
+        } = msg;
+        self.engine_thinking = false;
+
+        // The position changed while the engine was thinking, eg. a move was undone.
+        if move_number != self.game.shared.board_history.len() {
+            self.engine = Some(engine);
+            self.poll_engine(ctx);
+            return;
+        }
+
+        let action = match result {
+            Ok(action) => action,
+            Err(err) => return self.engine_failed(err),
+        };
+        self.engine = Some(engine);
+        if let Err(err) = self
+            .game
+            .make_action(gtp::ENGINE_USER_ID, action, current_time())
+        {
+            return self.engine_failed(gtp::EngineError::Failed(format!("{:?}", err)));
+        }
+        self.last_action = Instant::now();
+        self.update_scoring_timer();
+        self.game_changed();
+        self.poll_engine(ctx);
     }
 }
 
@@ -333,6 +1172,74 @@ impl Handler<Unload> for GameRoom {
     }
 }
 
+impl Handler<Reset> for GameRoom {
+    type Result = ();
+
+    fn handle(&mut self, msg: Reset, _ctx: &mut Self::Context) -> Self::Result {
+        // Handicaps and a growing board changed the shared state, start over from the setup.
+        let setup = &self.game.setup;
+        let game = game::Game::standard(
+            &setup.seats,
+            setup.komis.clone(),
+            setup.size,
+            setup.mods.clone(),
+            msg.seed,
+        );
+        let game = match game {
+            Some(game) => game,
+            None => return,
+        };
+
+        self.game = game;
+        self.review = None;
+        self.annotations.clear();
+        self.think_times.clear();
+        self.scoring_since = None;
+        self.started = false;
+        self.last_action = Instant::now();
+
+        self.game_changed();
+    }
+}
+
+impl Handler<DumpState> for GameRoom {
+    type Result = ();
+
+    fn handle(&mut self, msg: DumpState, _ctx: &mut Self::Context) -> Self::Result {
+        let _ = msg.addr.do_send(self.state_dump());
+    }
+}
+
+impl Handler<HoldsSeat> for GameRoom {
+    type Result = Option<bool>;
+
+    fn handle(&mut self, msg: HoldsSeat, _ctx: &mut Self::Context) -> Self::Result {
+        let done = matches!(self.game.state, game::GameState::Done(_));
+        if done || !self.game.holds_seat(msg.user_id) {
+            return None;
+        }
+        Some(self.game.awaits_player(msg.user_id))
+    }
+}
+
+impl Handler<GetActivity> for GameRoom {
+    type Result = MessageResult<GetActivity>;
+
+    fn handle(&mut self, _: GetActivity, _: &mut Self::Context) -> Self::Result {
+        if matches!(self.game.state, game::GameState::Done(_)) {
+            return MessageResult(None);
+        }
+        MessageResult(Some(Activity {
+            idle: self.last_action.elapsed(),
+            member_count: self
+                .users
+                .iter()
+                .filter(|id| !self.kicked_players.contains(id))
+                .count(),
+        }))
+    }
+}
+
 impl Handler<GetAdminView> for GameRoom {
     type Result = <GetAdminView as actix::Message>::Result;
 
@@ -340,3 +1247,239 @@ impl Handler<GetAdminView> for GameRoom {
         Ok(self.game.get_view(0))
     }
 }
+
+fn current_time() -> Millisecond {
+    Millisecond(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i128,
+    )
+}
+
+/// Splits the chat stored by `GameRoom::store_chat` back into the main and spectator channels.
+pub fn load_chat(chat: Option<&str>) -> (Vec<message::ChatLine>, Vec<message::ChatLine>) {
+    let lines: Vec<message::ChatLine> = chat
+        .and_then(|chat| serde_json::from_str(chat).ok())
+        .unwrap_or_default();
+    lines
+        .into_iter()
+        .partition(|line| line.channel == message::ChatChannel::Main)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix::dev::channel::{channel, AddressReceiver};
+    use futures_util::{FutureExt, StreamExt};
+
+    /// Stands in for a client session, keeping what the room sent it.
+    #[derive(Default)]
+    struct Session {
+        received: Vec<Message>,
+    }
+
+    impl Actor for Session {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Message> for Session {
+        type Result = ();
+
+        fn handle(&mut self, msg: Message, _: &mut Context<Self>) {
+            self.received.push(msg);
+        }
+    }
+
+    #[derive(actix::Message)]
+    #[rtype(result = "Vec<Message>")]
+    struct TakeReceived;
+
+    impl Handler<TakeReceived> for Session {
+        type Result = MessageResult<TakeReceived>;
+
+        fn handle(&mut self, _: TakeReceived, _: &mut Context<Self>) -> Self::Result {
+            MessageResult(std::mem::take(&mut self.received))
+        }
+    }
+
+    /// A room with two seated players. Whatever it sends the database or the server stays
+    /// queued in the returned receivers.
+    fn test_room() -> (
+        GameRoom,
+        AddressReceiver<db::DbActor>,
+        AddressReceiver<server::GameServer>,
+    ) {
+        let mut game = game::Game::standard(
+            &[1, 2],
+            game::GroupVec::from(&[0, 15][..]),
+            (9, 9),
+            game::GameModifier::default(),
+            0,
+        )
+        .unwrap();
+        game.take_seat(100, 0).unwrap();
+        game.take_seat(200, 1).unwrap();
+
+        let (db_tx, db_rx) = channel(16);
+        let (server_tx, server_rx) = channel(16);
+        let room = GameRoom {
+            room_id: 1,
+            owner: Some(100),
+            sessions: HashMap::new(),
+            users: HashSet::new(),
+            name: "Test".to_owned(),
+            last_action: Instant::now(),
+            game,
+            review: None,
+            annotations: Vec::new(),
+            presentation: message::BoardPresentation::default(),
+            scoring_since: None,
+            turn_since: None,
+            changed_at: Instant::now(),
+            think_times: HashMap::new(),
+            started: true,
+            engine: None,
+            engine_thinking: false,
+            db: Addr::new(db_tx),
+            server: Addr::new(server_tx),
+            kicked_players: HashSet::new(),
+            invited: HashSet::new(),
+            main_chat: Vec::new(),
+            spectator_chat: Vec::new(),
+            typing_relayed: HashMap::new(),
+        };
+        (room, db_rx, server_rx)
+    }
+
+    #[actix_rt::test]
+    async fn spectator_estimate_is_private() {
+        let (mut room, mut db, mut server) = test_room();
+        let player = Session::default().start();
+        let spectator = Session::default().start();
+        room.sessions.insert(1, (100, player.clone().recipient()));
+        room.sessions
+            .insert(2, (300, spectator.clone().recipient()));
+        room.users.extend(&[100, 300]);
+        let last_action = room.last_action;
+
+        room.make_action(
+            300,
+            message::GameAction::EstimateScore,
+            Some(spectator.clone().recipient()),
+        )
+        .unwrap();
+
+        let received = spectator.send(TakeReceived).await.unwrap();
+        assert!(matches!(received[..], [Message::ScoreEstimate { .. }]));
+        assert!(player.send(TakeReceived).await.unwrap().is_empty());
+        assert!(db.next().now_or_never().is_none());
+        assert!(server.next().now_or_never().is_none());
+        assert_eq!(room.last_action, last_action);
+    }
+
+    /// The room with both players connected, each with a session of their own.
+    fn connected_room() -> (
+        GameRoom,
+        Addr<Session>,
+        Addr<Session>,
+        AddressReceiver<db::DbActor>,
+    ) {
+        let (mut room, db, _) = test_room();
+        let black = Session::default().start();
+        let white = Session::default().start();
+        room.sessions.insert(1, (100, black.clone().recipient()));
+        room.sessions.insert(2, (200, white.clone().recipient()));
+        room.users.extend(&[100, 200]);
+        (room, black, white, db)
+    }
+
+    #[actix_rt::test]
+    async fn disconnect_pauses_until_resumed() {
+        let (mut room, black, white, mut db) = connected_room();
+        let mut ctx = Context::new();
+
+        room.handle(Leave { session_id: 1 }, &mut ctx);
+        let pause = room.game.pause.clone().unwrap();
+        assert_eq!(pause.player, 100);
+        assert!(!pause.returned);
+        // The pause is stored and shown to the players still there.
+        assert!(db.next().now_or_never().is_some());
+        let received = white.send(TakeReceived).await.unwrap();
+        assert!(received
+            .iter()
+            .any(|m| matches!(m, Message::GameStatus { .. })));
+        let res = room.make_action(200, message::GameAction::Place(4, 4), None);
+        assert!(res.is_err());
+
+        let join = Join {
+            session_id: 3,
+            user_id: 100,
+            addr: black.recipient(),
+        };
+        room.handle(join, &mut ctx).unwrap();
+        assert!(room.game.pause.as_ref().unwrap().returned);
+
+        room.make_action(200, message::GameAction::Resume, None)
+            .unwrap();
+        assert!(room.game.pause.is_none());
+        room.make_action(100, message::GameAction::Place(4, 4), None)
+            .unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn disconnect_past_grace_can_be_claimed() {
+        let (mut room, _black, _white, _db) = connected_room();
+        let mut ctx = Context::new();
+
+        room.handle(Leave { session_id: 1 }, &mut ctx);
+        let res = room.make_action(200, message::GameAction::Claim, None);
+        assert!(matches!(
+            res,
+            Err(message::Error::Game {
+                error: message::GameError::Action(game::MakeActionError::Paused),
+                ..
+            })
+        ));
+
+        // Black doesn't come back before the grace window is over.
+        room.game.pause.as_mut().unwrap().grace_ends = Millisecond(0);
+        room.make_action(200, message::GameAction::Claim, None)
+            .unwrap();
+        assert!(room.game.pause.is_none());
+        assert!(room.game.result().is_some());
+        assert_eq!(room.winner(), Some(200));
+    }
+
+    #[actix_rt::test]
+    async fn spectator_has_no_seat() {
+        let (mut room, mut db, mut server) = test_room();
+        let last_action = room.last_action;
+        let actions = [
+            message::GameAction::Place(4, 4),
+            message::GameAction::Pass,
+            message::GameAction::Cancel,
+            message::GameAction::Resign,
+            message::GameAction::Swap,
+            message::GameAction::Review(game::ReviewAction::Navigate(0)),
+            message::GameAction::SetupStones(vec![(4, 4, 1)]),
+            message::GameAction::Resume,
+            message::GameAction::Claim,
+        ];
+
+        for action in actions {
+            let res = room.make_action(300, action, None);
+            assert!(matches!(
+                res,
+                Err(message::Error::Game {
+                    error: message::GameError::NoSeat,
+                    ..
+                })
+            ));
+        }
+
+        assert!(db.next().now_or_never().is_none());
+        assert!(server.next().now_or_never().is_none());
+        assert_eq!(room.last_action, last_action);
+    }
+}
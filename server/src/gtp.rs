@@ -0,0 +1,121 @@
+//! External Go engines, like GnuGo or KataGo, playing a seat over GTP.
+//! The engine command line is read from `GTP_ENGINE`, eg. `gnugo --mode gtp`.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use shared::game::{self, export};
+
+/// Seat holder id of engines. Real user ids never get this high, and a room has at most
+/// one engine so the id is never needed for two seats.
+pub const ENGINE_USER_ID: u64 = u64::MAX;
+
+#[derive(Debug)]
+pub enum EngineError {
+    /// `GTP_ENGINE` isn't set.
+    NotConfigured,
+    Io(io::Error),
+    /// The engine answered a command with `?` and this message.
+    Failed(String),
+    /// The engine exited.
+    Closed,
+}
+
+impl std::fmt::Display for EngineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EngineError::NotConfigured => write!(f, "GTP_ENGINE isn't set"),
+            EngineError::Io(error) => write!(f, "{}", error),
+            EngineError::Failed(message) => write!(f, "engine answered: {}", message),
+            EngineError::Closed => write!(f, "engine exited"),
+        }
+    }
+}
+
+impl From<io::Error> for EngineError {
+    fn from(error: io::Error) -> Self {
+        EngineError::Io(error)
+    }
+}
+
+pub struct Engine {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Engine {
+    /// Starts the engine configured with `GTP_ENGINE`.
+    pub fn spawn() -> Result<Engine, EngineError> {
+        let command = std::env::var("GTP_ENGINE").map_err(|_| EngineError::NotConfigured)?;
+        let mut args = command.split_whitespace();
+        let program = args.next().ok_or(EngineError::NotConfigured)?;
+
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().ok_or(EngineError::Closed)?;
+        let stdout = child.stdout.take().ok_or(EngineError::Closed)?;
+
+        Ok(Engine {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+        })
+    }
+
+    /// Sends one command and waits for the answer, without the leading `=`.
+    fn command(&mut self, command: &str) -> Result<String, EngineError> {
+        writeln!(self.stdin, "{}", command)?;
+        self.stdin.flush()?;
+
+        // Answers end with an empty line.
+        let mut response = String::new();
+        loop {
+            let mut line = String::new();
+            if self.stdout.read_line(&mut line)? == 0 {
+                return Err(EngineError::Closed);
+            }
+            if line.trim().is_empty() {
+                if response.is_empty() {
+                    continue;
+                }
+                break;
+            }
+            response.push_str(&line);
+        }
+
+        let response = response.trim();
+        if let Some(error) = response.strip_prefix('?') {
+            return Err(EngineError::Failed(error.trim().to_string()));
+        }
+        Ok(response.trim_start_matches('=').trim().to_string())
+    }
+
+    /// Sets up the position of `game` and asks for the move of `seat`. Blocks until the
+    /// engine answers.
+    pub fn genmove(
+        &mut self,
+        game: &game::Game,
+        seat: usize,
+    ) -> Result<game::ActionKind, EngineError> {
+        let board = &game.shared.board;
+        let mut response = String::new();
+        for command in export::gtp_commands(game, seat) {
+            response = self.command(&command)?;
+        }
+        export::parse_gtp_move(&response, (board.width, board.height))
+            .ok_or(EngineError::Failed(response))
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        let _ = writeln!(self.stdin, "quit");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -3,6 +3,7 @@ extern crate diesel;
 
 mod db;
 mod game_room;
+mod gtp;
 mod schema;
 mod server;
 
@@ -15,6 +16,8 @@ use actix_web::{middleware, web, App, Error, HttpRequest, HttpResponse, HttpServ
 use actix_web_actors::ws;
 
 use crate::server::GameServer;
+use shared::game::tournament::{Standing, Tournament, TournamentFormat};
+use shared::game::Problem;
 use shared::message::{self, ClientMessage, ClientMode, ServerMessage};
 
 use serde::{Deserialize, Serialize};
@@ -33,6 +36,18 @@ const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
 /// How often time syncs are sent
 const TIMESYNC_INTERVAL: Duration = Duration::from_secs(2);
 
+/// Rejected game actions are counted over this window
+const REJECTED_WINDOW: Duration = Duration::from_secs(60);
+
+/// Sessions with more rejected actions in a window get blocked like rate limited ones
+const REJECTED_LIMIT: u32 = 30;
+
+/// How long a session sending too many rejected actions is blocked for
+const REJECTED_BLOCK: Duration = Duration::from_secs(10);
+
+/// Rejected actions are logged at most this often per session
+const REJECTED_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
 /// do websocket handshake and start `MyWebSocket` actor
 async fn ws_index(
     r: HttpRequest,
@@ -46,10 +61,15 @@ async fn ws_index(
         game_addr: HashMap::new(),
         room_id: None,
         mode: ClientMode::Client,
+        encoding: message::Encoding::default(),
         ratelimit_hb: Instant::now(),
         ratelimit_counter: 0,
         ratelimit_block_target: None,
+        rejected_since: Instant::now(),
+        rejected_count: 0,
+        rejected_logged: None,
         is_admin: false,
+        blocked: message::BlockList::default(),
     };
     ws::start(actor, &r, stream)
 }
@@ -68,12 +88,21 @@ struct ClientWebSocket {
     game_addr: HashMap<u32, Addr<game_room::GameRoom>>,
     room_id: Option<u32>,
     mode: ClientMode,
+    encoding: message::Encoding,
 
     ratelimit_hb: Instant,
     ratelimit_counter: u64,
     ratelimit_block_target: Option<Instant>,
 
+    /// Rejected game actions since `rejected_since`, for spotting clients spamming invalid input.
+    rejected_since: Instant,
+    rejected_count: u32,
+    rejected_logged: Option<Instant>,
+
     is_admin: bool,
+    /// Users whose chat this session drops, see `message::BlockList`. Chat goes from the
+    /// rooms straight to the sessions, so this is where it's filtered.
+    blocked: message::BlockList,
 }
 
 type Context = ws::WebsocketContext<ClientWebSocket>;
@@ -122,35 +151,110 @@ impl Handler<game_room::Message> for ClientWebSocket {
                 members,
                 view,
             } => {
-                ctx.binary(
+                self.send(
+                    ctx,
                     ServerMessage::GameStatus {
                         room_id,
                         owner,
                         members,
-                        seats: view
-                            .seats
-                            .into_iter()
-                            .map(|x| (x.player, x.team.0, x.resigned))
-                            .collect(),
-                        turn: view.turn,
-                        board: view.board.into_iter().map(|x| x.0).collect(),
-                        board_visibility: view.board_visibility,
-                        hidden_stones_left: view.hidden_stones_left,
-                        size: view.size,
-                        state: view.state,
-                        mods: view.mods,
-                        points: view.points.to_vec(),
-                        move_number: view.move_number,
-                        clock: view.clock,
-                    }
-                    .pack(),
+                        view,
+                    },
                 );
             }
+            game_room::Message::GameStarted { room_id, config } => {
+                self.send(ctx, ServerMessage::GameStarted { room_id, config });
+            }
             game_room::Message::BoardAt { view, room_id } => {
-                ctx.binary(ServerMessage::BoardAt { view, room_id }.pack());
+                self.send(ctx, ServerMessage::BoardAt { view, room_id });
+            }
+            game_room::Message::Review { view, room_id } => {
+                self.send(ctx, ServerMessage::Review { view, room_id });
+            }
+            game_room::Message::Annotations {
+                room_id,
+                annotations,
+            } => {
+                self.send(
+                    ctx,
+                    ServerMessage::Annotations {
+                        room_id,
+                        annotations,
+                    },
+                );
+            }
+            game_room::Message::Presentation {
+                room_id,
+                presentation,
+            } => {
+                self.send(
+                    ctx,
+                    ServerMessage::Presentation {
+                        room_id,
+                        presentation,
+                    },
+                );
             }
             game_room::Message::SGF { sgf, room_id } => {
-                ctx.binary(ServerMessage::SGF { sgf, room_id }.pack());
+                self.send(ctx, ServerMessage::SGF { sgf, room_id });
+            }
+            game_room::Message::Stats { csv, room_id } => {
+                self.send(ctx, ServerMessage::Stats { csv, room_id });
+            }
+            game_room::Message::LegalMoves {
+                room_id,
+                seat,
+                moves,
+            } => {
+                self.send(
+                    ctx,
+                    ServerMessage::LegalMoves {
+                        room_id,
+                        seat,
+                        moves,
+                    },
+                );
+            }
+            game_room::Message::ScoreEstimate {
+                room_id,
+                move_number,
+                scores,
+            } => {
+                self.send(
+                    ctx,
+                    ServerMessage::ScoreEstimate {
+                        room_id,
+                        move_number,
+                        scores,
+                    },
+                );
+            }
+            game_room::Message::StateDump { room_id, json } => {
+                self.send(ctx, ServerMessage::StateDump { room_id, json });
+            }
+            game_room::Message::Chat { room_id, line } => {
+                if self.blocked.delivers(&line) {
+                    self.send(ctx, ServerMessage::Chat { room_id, line });
+                }
+            }
+            game_room::Message::ChatHistory { room_id, mut lines } => {
+                lines.retain(|line| self.blocked.delivers(line));
+                self.send(ctx, ServerMessage::ChatHistory { room_id, lines });
+            }
+            game_room::Message::UserTyping {
+                room_id,
+                user_id,
+                channel,
+            } => {
+                if !self.blocked.blocks(user_id) {
+                    self.send(
+                        ctx,
+                        ServerMessage::UserTyping {
+                            room_id,
+                            user_id,
+                            channel,
+                        },
+                    );
+                }
             }
         }
     }
@@ -161,29 +265,82 @@ impl Handler<server::Message> for ClientWebSocket {
 
     fn handle(&mut self, msg: server::Message, ctx: &mut Self::Context) {
         match msg {
-            server::Message::AnnounceRoom(room_id, name) => {
-                ctx.binary(ServerMessage::AnnounceGame { room_id, name }.pack());
+            server::Message::AnnounceRoom {
+                room_id,
+                name,
+                no_spectators,
+            } => {
+                self.send(
+                    ctx,
+                    ServerMessage::AnnounceGame {
+                        room_id,
+                        name,
+                        thumbnail: None,
+                        spectators: 0,
+                        no_spectators,
+                    },
+                );
+            }
+            server::Message::RoomUpdated {
+                room_id,
+                spectators,
+            } => {
+                self.send(
+                    ctx,
+                    ServerMessage::RoomUpdated {
+                        room_id,
+                        spectators,
+                    },
+                );
             }
             server::Message::CloseRoom(room_id) => {
-                ctx.binary(ServerMessage::CloseGame { room_id }.pack());
+                self.send(ctx, ServerMessage::CloseGame { room_id });
             }
             server::Message::Identify(res) => {
-                ctx.binary(
+                self.blocked = res.blocked.clone();
+                self.send(
+                    ctx,
                     ServerMessage::Identify {
                         user_id: res.user_id,
                         token: res.token.to_string(),
                         nick: res.nick,
-                    }
-                    .pack(),
+                        settings: res.settings,
+                        blocked: res.blocked,
+                    },
+                );
+            }
+            server::Message::ActiveRooms(rooms) => {
+                self.send(ctx, ServerMessage::ActiveRooms(rooms));
+            }
+            server::Message::FollowInto { user_id, room_id } => {
+                self.handle_join_game(room_id, ctx);
+                self.send(ctx, ServerMessage::Followed { user_id, room_id });
+            }
+            server::Message::Invited {
+                from,
+                room_id,
+                room_name,
+            } => {
+                self.send(
+                    ctx,
+                    ServerMessage::Invited {
+                        from,
+                        room_id,
+                        room_name,
+                    },
                 );
             }
+            server::Message::ProblemProgress { solved, total } => {
+                self.send(ctx, ServerMessage::ProblemProgress { solved, total });
+            }
             server::Message::UpdateProfile(res) => {
-                ctx.binary(
+                self.send(
+                    ctx,
                     ServerMessage::Profile(message::Profile {
                         user_id: res.user_id,
                         nick: res.nick,
-                    })
-                    .pack(),
+                        rating: None,
+                    }),
                 );
             }
         };
@@ -206,7 +363,7 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for ClientWebSocket {
 
             if let Some(target) = self.ratelimit_block_target {
                 if now < target {
-                    ctx.binary(ServerMessage::Error(message::Error::RateLimit).pack());
+                    self.send(ctx, ServerMessage::Error(message::Error::RateLimit));
                     return;
                 }
                 self.ratelimit_block_target = None;
@@ -234,7 +391,7 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for ClientWebSocket {
                 let data = serde_cbor::from_slice::<ClientMessage>(&bin);
                 match data {
                     Ok(data) => self.handle_message(data, ctx),
-                    Err(e) => ctx.binary(ServerMessage::MsgError(format!("{}", e)).pack()),
+                    Err(e) => self.send(ctx, ServerMessage::MsgError(format!("{}", e))),
                 }
             }
             Ok(ws::Message::Close(reason)) => {
@@ -267,26 +424,94 @@ impl ClientWebSocket {
             ctx.ping(b"");
         });
 
-        ctx.run_interval(TIMESYNC_INTERVAL, |_act, ctx| {
-            ctx.binary(ServerMessage::ServerTime(shared::game::clock::Millisecond::now()).pack());
+        ctx.run_interval(TIMESYNC_INTERVAL, |act, ctx| {
+            act.send(
+                ctx,
+                ServerMessage::ServerTime(shared::game::clock::Millisecond::now()),
+            );
         });
     }
 
+    fn send(&self, ctx: &mut Context, msg: ServerMessage) {
+        ctx.binary(msg.pack_with(self.encoding));
+    }
+
+    /// Counts a rejected game action and blocks the session if it keeps sending them.
+    fn record_rejected_action(&mut self, err: &message::Error) {
+        let now = Instant::now();
+        if now - self.rejected_since > REJECTED_WINDOW {
+            self.rejected_since = now;
+            self.rejected_count = 0;
+        }
+        self.rejected_count += 1;
+        self.server_addr
+            .do_send(server::RejectedAction { id: self.id });
+
+        let log_due = self
+            .rejected_logged
+            .map_or(true, |at| now - at >= REJECTED_LOG_INTERVAL);
+        if log_due {
+            println!(
+                "Session {} had {} rejected actions this minute, latest: {:?}",
+                self.id, self.rejected_count, err
+            );
+            self.rejected_logged = Some(now);
+        }
+
+        if self.rejected_count > REJECTED_LIMIT && !self.is_admin {
+            if log_due {
+                println!("Blocking session {} for rejected actions", self.id);
+            }
+            self.ratelimit_block_target = Some(now + REJECTED_BLOCK);
+        }
+    }
+
     fn handle_get_game_list(&mut self, ctx: &mut Context) {
-        fn send_rooms(mut rooms: Vec<(u32, String)>, ctx: &mut Context) {
-            // Sort newest first
-            rooms.sort_unstable_by_key(|x| -(x.0 as i32));
-            for (room_id, name) in rooms {
-                ctx.binary(ServerMessage::AnnounceGame { room_id, name }.pack());
+        fn send_rooms(act: &ClientWebSocket, rooms: Vec<message::RoomInfo>, ctx: &mut Context) {
+            for message::RoomInfo {
+                room_id,
+                name,
+                thumbnail,
+                spectators,
+                no_spectators,
+                ..
+            } in rooms
+            {
+                act.send(
+                    ctx,
+                    ServerMessage::AnnounceGame {
+                        room_id,
+                        name,
+                        thumbnail,
+                        spectators,
+                        no_spectators,
+                    },
+                );
             }
         }
 
+        // The default filter lists every room, newest first.
+        let filter = message::RoomFilter::default();
         self.server_addr
-            .send(server::ListRooms)
+            .send(server::ListRooms { filter })
             .into_actor(self)
-            .then(|res, _act, ctx| {
+            .then(|res, act, ctx| {
                 match res {
-                    Ok(res) => send_rooms(res, ctx),
+                    Ok((rooms, _)) => send_rooms(act, rooms, ctx),
+                    _ => ctx.stop(),
+                }
+                fut::ready(())
+            })
+            .wait(ctx);
+    }
+
+    fn handle_find_games(&mut self, filter: message::RoomFilter, ctx: &mut Context) {
+        self.server_addr
+            .send(server::ListRooms { filter })
+            .into_actor(self)
+            .then(|res, act, ctx| {
+                match res {
+                    Ok((rooms, total)) => act.send(ctx, ServerMessage::GameList { rooms, total }),
                     _ => ctx.stop(),
                 }
                 fut::ready(())
@@ -312,7 +537,7 @@ impl ClientWebSocket {
                         act.game_addr.insert(id, addr.unwrap());
                     }
                     Ok(Err(err)) => {
-                        ctx.binary(ServerMessage::Error(err).pack());
+                        act.send(ctx, ServerMessage::Error(err));
                     }
                     _ => {}
                 }
@@ -321,6 +546,30 @@ impl ClientWebSocket {
             .wait(ctx);
     }
 
+    fn handle_next_problem(&mut self, ctx: &mut Context) {
+        self.server_addr
+            .send(server::StartProblem {
+                id: self.id,
+                leave_previous: match self.mode {
+                    ClientMode::Client => true,
+                    ClientMode::Integration => false,
+                },
+            })
+            .into_actor(self)
+            .then(|res, act, ctx| {
+                match res {
+                    Ok(Ok((id, addr))) => {
+                        act.room_id = Some(id);
+                        act.game_addr.insert(id, addr);
+                    }
+                    Ok(Err(err)) => act.send(ctx, ServerMessage::Error(err)),
+                    Err(_) => {}
+                }
+                fut::ready(())
+            })
+            .wait(ctx);
+    }
+
     fn handle_join_game(&mut self, room_id: u32, ctx: &mut Context) {
         self.server_addr
             .send(server::Join {
@@ -332,10 +581,41 @@ impl ClientWebSocket {
                 },
             })
             .into_actor(self)
-            .then(move |res, act, _| {
-                if let Ok(Ok(addr)) = res {
-                    act.room_id = Some(room_id);
-                    act.game_addr.insert(room_id, addr);
+            .then(move |res, act, ctx| {
+                match res {
+                    Ok(Ok(addr)) => {
+                        act.room_id = Some(room_id);
+                        act.game_addr.insert(room_id, addr);
+                    }
+                    Ok(Err(err)) => act.send(ctx, ServerMessage::Error(err)),
+                    Err(_) => {}
+                }
+                fut::ready(())
+            })
+            .wait(ctx);
+    }
+
+    fn handle_spectate_random(&mut self, ctx: &mut Context) {
+        self.server_addr
+            .send(server::SpectateRandom {
+                id: self.id,
+                leave_previous: match self.mode {
+                    ClientMode::Client => true,
+                    ClientMode::Integration => false,
+                },
+            })
+            .into_actor(self)
+            .then(move |res, act, ctx| {
+                match res {
+                    Ok(Ok((room_id, addr))) => {
+                        act.room_id = Some(room_id);
+                        act.game_addr.insert(room_id, addr);
+                        act.send(ctx, ServerMessage::RandomGame { room_id });
+                    }
+                    Ok(Err(err)) => {
+                        act.send(ctx, ServerMessage::Error(err));
+                    }
+                    _ => {}
                 }
                 fut::ready(())
             })
@@ -365,17 +645,20 @@ impl ClientWebSocket {
                 match res {
                     Ok(Ok(res)) => {
                         act.is_admin = res.is_admin;
-                        ctx.binary(
+                        act.blocked = res.blocked.clone();
+                        act.send(
+                            ctx,
                             ServerMessage::Identify {
                                 user_id: res.user_id,
                                 token: res.token.to_string(),
                                 nick: res.nick,
-                            }
-                            .pack(),
+                                settings: res.settings,
+                                blocked: res.blocked,
+                            },
                         )
                     }
                     Ok(Err(err)) => {
-                        ctx.binary(ServerMessage::Error(err).pack());
+                        act.send(ctx, ServerMessage::Error(err));
                     }
                     _ => ctx.stop(),
                 }
@@ -384,21 +667,105 @@ impl ClientWebSocket {
             .wait(ctx);
     }
 
+    fn handle_get_info(&mut self, ctx: &mut Context) {
+        self.server_addr
+            .send(server::GetInfo)
+            .into_actor(self)
+            .then(|res, act, ctx| {
+                if let Ok(info) = res {
+                    act.send(ctx, ServerMessage::Info(info));
+                }
+                fut::ready(())
+            })
+            .wait(ctx);
+    }
+
+    fn handle_invite(&mut self, user_id: u64, room_id: u32, ctx: &mut Context) {
+        self.server_addr
+            .send(server::Invite {
+                id: self.id,
+                user_id,
+                room_id,
+            })
+            .into_actor(self)
+            .then(|res, act, ctx| {
+                if let Ok(Err(err)) = res {
+                    act.send(ctx, ServerMessage::Error(err));
+                }
+                fut::ready(())
+            })
+            .wait(ctx);
+    }
+
+    fn handle_update_settings(&mut self, settings: serde_json::Value, ctx: &mut Context) {
+        self.server_addr
+            .send(server::UpdateSettings {
+                id: self.id,
+                settings,
+            })
+            .into_actor(self)
+            .then(|res, act, ctx| {
+                if let Ok(Err(err)) = res {
+                    act.send(ctx, ServerMessage::Error(err));
+                }
+                fut::ready(())
+            })
+            .wait(ctx);
+    }
+
+    fn handle_block(&mut self, user_id: u64, blocked: bool, ctx: &mut Context) {
+        self.server_addr
+            .send(server::Block {
+                id: self.id,
+                user_id,
+                blocked,
+            })
+            .into_actor(self)
+            .then(|res, act, ctx| {
+                if let Ok(Err(err)) = res {
+                    act.send(ctx, ServerMessage::Error(err));
+                }
+                fut::ready(())
+            })
+            .wait(ctx);
+    }
+
     fn handle_message(&mut self, msg: ClientMessage, ctx: &mut Context) {
         println!("WS: {:?}", msg);
         match msg {
             ClientMessage::GetGameList => {
                 self.handle_get_game_list(ctx);
             }
+            ClientMessage::FindGames(filter) => {
+                self.handle_find_games(filter, ctx);
+            }
             ClientMessage::StartGame(start) => {
-                self.handle_start_game(start, ctx);
+                self.handle_start_game(*start, ctx);
+            }
+            ClientMessage::StartGamePreset(preset) => {
+                match message::StartGame::from_preset(&preset) {
+                    Ok(start) => self.handle_start_game(start, ctx),
+                    Err(err) => self.send(ctx, ServerMessage::Error(err)),
+                }
             }
             ClientMessage::JoinGame(room_id) => {
                 self.handle_join_game(room_id, ctx);
             }
+            ClientMessage::SpectateRandomGame => {
+                self.handle_spectate_random(ctx);
+            }
             ClientMessage::LeaveGame(room_id) => {
                 self.handle_leave_game(room_id, ctx);
             }
+            ClientMessage::Follow(user_id) => {
+                self.server_addr.do_send(server::Follow {
+                    id: self.id,
+                    user_id,
+                });
+            }
+            ClientMessage::Invite { user_id, room_id } => {
+                self.handle_invite(user_id, room_id, ctx);
+            }
             ClientMessage::GameAction { room_id, action } => {
                 if let Some(addr) = &self.game_addr.get(&room_id.or(self.room_id).unwrap_or(0)) {
                     addr.send(game_room::GameAction {
@@ -406,11 +773,14 @@ impl ClientWebSocket {
                         action,
                     })
                     .into_actor(self)
-                    .then(|res, _act, ctx| {
+                    .then(|res, act, ctx| {
                         match res {
                             Ok(Ok(())) => {}
                             Ok(Err(err)) => {
-                                ctx.binary(ServerMessage::Error(err).pack());
+                                if let message::Error::Game { .. } = err {
+                                    act.record_rejected_action(&err);
+                                }
+                                act.send(ctx, ServerMessage::Error(err));
                             }
                             _ => {}
                         }
@@ -431,6 +801,21 @@ impl ClientWebSocket {
             ClientMessage::Mode(mode) => {
                 self.mode = mode;
             }
+            ClientMessage::Encoding(encoding) => {
+                self.encoding = encoding;
+            }
+            ClientMessage::GetInfo => {
+                self.handle_get_info(ctx);
+            }
+            ClientMessage::UpdateSettings(settings) => {
+                self.handle_update_settings(settings, ctx);
+            }
+            ClientMessage::Block { user_id, blocked } => {
+                self.handle_block(user_id, blocked, ctx);
+            }
+            ClientMessage::NextProblem => {
+                self.handle_next_problem(ctx);
+            }
         };
     }
 }
@@ -447,29 +832,40 @@ struct CreateGameResponse {
     id: u32,
 }
 
-async fn create_game(
-    req: actix_web::HttpRequest,
-    body: web::Json<CreateGameBody>,
-    server_addr: web::Data<Addr<GameServer>>,
-    db_addr: web::Data<Addr<db::DbActor>>,
-) -> actix_web::Result<HttpResponse> {
-    println!("POST /game/create: {:?}", body);
-
+/// Checks the request carries the token of a user with integration access.
+async fn check_integration(
+    req: &actix_web::HttpRequest,
+    db_addr: &Addr<db::DbActor>,
+) -> Result<(), HttpResponse> {
     let token = match catch! {
         let header = req.headers().get("Authentication")?;
         header.to_str().ok()?.to_owned()
     } {
         Some(x) => x,
-        None => return Ok(HttpResponse::BadRequest().body("Bearer token required")),
+        None => return Err(HttpResponse::BadRequest().body("Bearer token required")),
     };
 
     let user = match db_addr.send(db::GetUserByToken(token)).await.unwrap() {
         Ok(x) => x,
-        Err(_) => return Ok(HttpResponse::BadRequest().body("Invalid token")),
+        Err(_) => return Err(HttpResponse::BadRequest().body("Invalid token")),
     };
 
     if !user.has_integration_access {
-        return Ok(HttpResponse::BadRequest().body("Invalid token"));
+        return Err(HttpResponse::BadRequest().body("Invalid token"));
+    }
+    Ok(())
+}
+
+async fn create_game(
+    req: actix_web::HttpRequest,
+    body: web::Json<CreateGameBody>,
+    server_addr: web::Data<Addr<GameServer>>,
+    db_addr: web::Data<Addr<db::DbActor>>,
+) -> actix_web::Result<HttpResponse> {
+    println!("POST /game/create: {:?}", body);
+
+    if let Err(resp) = check_integration(&req, &db_addr).await {
+        return Ok(resp);
     }
 
     let CreateGameBody { game, players } = body.into_inner();
@@ -592,6 +988,155 @@ async fn get_game_result(
     Ok(HttpResponse::Ok().json(response))
 }
 
+#[derive(Debug, Deserialize)]
+struct CreateTournamentBody {
+    name: String,
+    format: TournamentFormat,
+    /// User ids in seeding order.
+    participants: Vec<u64>,
+    game: message::StartGame,
+}
+
+async fn create_tournament(
+    req: actix_web::HttpRequest,
+    body: web::Json<CreateTournamentBody>,
+    server_addr: web::Data<Addr<GameServer>>,
+    db_addr: web::Data<Addr<db::DbActor>>,
+) -> actix_web::Result<HttpResponse> {
+    println!("POST /tournament/create: {:?}", body);
+
+    if let Err(resp) = check_integration(&req, &db_addr).await {
+        return Ok(resp);
+    }
+
+    let CreateTournamentBody {
+        name,
+        format,
+        participants,
+        game,
+    } = body.into_inner();
+
+    let resp = server_addr
+        .send(server::CreateTournament {
+            name,
+            format,
+            participants,
+            room: game,
+        })
+        .await
+        .unwrap();
+
+    match resp {
+        Ok(id) => Ok(HttpResponse::Ok().json(CreateGameResponse { id })),
+        Err(_) => Ok(HttpResponse::BadRequest().body("Tournament creation error")),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct GetTournamentResponse {
+    tournament: Tournament,
+    standings: Vec<Standing>,
+    finished: bool,
+    winner: Option<u64>,
+}
+
+async fn get_tournament(
+    req: actix_web::HttpRequest,
+    server_addr: web::Data<Addr<GameServer>>,
+) -> actix_web::Result<HttpResponse> {
+    let tournament_id = req.match_info().get("id").unwrap().parse().unwrap();
+
+    let resp = server_addr
+        .send(server::GetTournament { tournament_id })
+        .await
+        .unwrap();
+
+    let tournament = match resp {
+        Some(x) => x,
+        None => return Ok(HttpResponse::BadRequest().body("No such tournament")),
+    };
+
+    Ok(HttpResponse::Ok().json(GetTournamentResponse {
+        standings: tournament.standings(),
+        finished: tournament.is_finished(),
+        winner: tournament.winner(),
+        tournament,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateProblemBody {
+    name: String,
+    size: (u8, u8),
+    problem: Problem,
+}
+
+#[derive(Debug, Serialize)]
+struct CreateProblemResponse {
+    id: u64,
+}
+
+async fn create_problem(
+    req: actix_web::HttpRequest,
+    body: web::Json<CreateProblemBody>,
+    server_addr: web::Data<Addr<GameServer>>,
+    db_addr: web::Data<Addr<db::DbActor>>,
+) -> actix_web::Result<HttpResponse> {
+    println!("POST /problem/create: {:?}", body);
+
+    if let Err(resp) = check_integration(&req, &db_addr).await {
+        return Ok(resp);
+    }
+
+    let CreateProblemBody {
+        name,
+        size,
+        problem,
+    } = body.into_inner();
+
+    let resp = server_addr
+        .send(server::AddProblem {
+            name,
+            size,
+            problem,
+        })
+        .await
+        .unwrap();
+
+    match resp {
+        Ok(id) => Ok(HttpResponse::Ok().json(CreateProblemResponse { id })),
+        Err(_) => Ok(HttpResponse::BadRequest().body("Problem creation error")),
+    }
+}
+
+async fn get_metrics(server_addr: web::Data<Addr<GameServer>>) -> actix_web::Result<HttpResponse> {
+    let metrics = server_addr.send(server::GetMetrics).await.unwrap();
+    Ok(HttpResponse::Ok().json(metrics))
+}
+
+/// Flagged move timing, see `server::GetTimingReports`. Needs the admin token.
+async fn get_timing_reports(
+    req: actix_web::HttpRequest,
+    server_addr: web::Data<Addr<GameServer>>,
+) -> actix_web::Result<HttpResponse> {
+    let token = match catch! {
+        let header = req.headers().get("Authentication")?;
+        header.to_str().ok()?.to_owned()
+    } {
+        Some(x) => x,
+        None => return Ok(HttpResponse::BadRequest().body("Bearer token required")),
+    };
+
+    match server_addr
+        .send(server::GetTimingReports { token })
+        .await
+        .unwrap()
+    {
+        Ok(reports) => Ok(HttpResponse::Ok().json(reports)),
+        Err(()) => Ok(HttpResponse::BadRequest().body("Invalid token")),
+    }
+}
+
 #[actix_rt::main]
 async fn main() -> std::io::Result<()> {
     std::env::set_var("RUST_LOG", "actix_server=info,actix_web=info");
@@ -611,6 +1156,13 @@ async fn main() -> std::io::Result<()> {
             .service(web::resource("/api/game/create").route(web::post().to(create_game)))
             .service(web::resource("/api/game/{id}").route(web::get().to(get_game_view)))
             .service(web::resource("/api/game/{id}/result").route(web::get().to(get_game_result)))
+            .service(
+                web::resource("/api/tournament/create").route(web::post().to(create_tournament)),
+            )
+            .service(web::resource("/api/tournament/{id}").route(web::get().to(get_tournament)))
+            .service(web::resource("/api/problem/create").route(web::post().to(create_problem)))
+            .service(web::resource("/api/metrics").route(web::get().to(get_metrics)))
+            .service(web::resource("/api/admin/timing").route(web::get().to(get_timing_reports)))
     })
     .bind("0.0.0.0:8088")?
     .run()
@@ -6,6 +6,25 @@ diesel::table! {
         name -> Text,
         replay -> Nullable<Bytea>,
         owner -> Nullable<Int8>,
+        chat -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    problems (id) {
+        id -> Int8,
+        name -> Text,
+        width -> Int2,
+        height -> Int2,
+        problem -> Text,
+    }
+}
+
+diesel::table! {
+    retired_tokens (token) {
+        token -> Text,
+        user_id -> Int8,
+        expires -> Int8,
     }
 }
 
@@ -15,12 +34,18 @@ diesel::table! {
         auth_token -> Text,
         nick -> Nullable<Text>,
         has_integration_access -> Bool,
+        settings -> Nullable<Text>,
+        blocked -> Array<Int8>,
+        solved_problems -> Array<Int8>,
     }
 }
 
 diesel::joinable!(games -> users (owner));
+diesel::joinable!(retired_tokens -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     games,
+    problems,
+    retired_tokens,
     users,
 );
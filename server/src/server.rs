@@ -1,15 +1,29 @@
 use actix::prelude::*;
 use rand::prelude::*;
-use std::collections::{HashMap, HashSet};
-use std::time::{Duration, Instant};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant, SystemTime};
 use uuid::Uuid;
 
 use crate::db;
 use crate::game_room::{self, GameRoom};
 use shared::game;
+use shared::game::problem_set::{ProblemProgress, ProblemSet, SetProblem};
 use shared::message::{self, AdminAction};
 
 use futures_util::future::Either;
+use futures_util::FutureExt;
+
+/// How long a rotated token keeps working, so other devices can pick up the new one.
+const TOKEN_GRACE_PERIOD: Duration = Duration::from_secs(60 * 60 * 24);
+/// Games idle for longer are only picked for spectating when nothing else is going on.
+const SPECTATE_MAX_IDLE: Duration = Duration::from_secs(60 * 10);
+
+/// Timing reports kept for moderators, older ones are dropped.
+const MAX_TIMING_REPORTS: usize = 200;
+
+/// Spectator counts are collected and sent to the lobby at this rate, instead of on every join.
+const SPECTATOR_BROADCAST_INTERVAL: Duration = Duration::from_secs(10);
 
 macro_rules! catch {
     ($($code:tt)+) => {
@@ -27,10 +41,35 @@ macro_rules! catch {
 #[rtype(result = "()")]
 pub enum Message {
     // TODO: Use a proper struct, not magic tuples
-    AnnounceRoom(u32, String),
+    AnnounceRoom {
+        room_id: u32,
+        name: String,
+        no_spectators: bool,
+    },
+    RoomUpdated {
+        room_id: u32,
+        spectators: u32,
+    },
     CloseRoom(u32),
     Identify(Profile),
     UpdateProfile(Profile),
+    ActiveRooms(Vec<message::SeatedRoom>),
+    /// The followed user joined a room, the session should join it too. See `Follow`.
+    FollowInto {
+        user_id: u64,
+        room_id: u32,
+    },
+    /// Another user asks the user into a room, see `Invite`.
+    Invited {
+        from: u64,
+        room_id: u32,
+        room_name: String,
+    },
+    /// Problems of the set the user solved, see `StartProblem`.
+    ProblemProgress {
+        solved: u32,
+        total: u32,
+    },
 }
 
 // Client lifetime ////////////////////////////////////////////////////////////
@@ -52,12 +91,32 @@ pub struct Disconnect {
 
 // Rooms //////////////////////////////////////////////////////////////////////
 
-/// List of available rooms
-pub struct ListRooms;
+/// Page of available rooms matching the filter, with the number of matching rooms
+pub struct ListRooms {
+    pub filter: message::RoomFilter,
+}
 
 impl actix::Message for ListRooms {
-    // TODO: Use a proper struct, not magic tuples
-    type Result = Vec<(u32, String)>;
+    type Result = (Vec<message::RoomInfo>, u32);
+}
+
+/// Sent by rooms after the board changes.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct UpdateThumbnail {
+    pub room_id: u32,
+    pub thumbnail: game::BoardThumbnail,
+    /// Expanding boards grow during the game.
+    pub size: (u8, u8),
+    pub in_progress: bool,
+}
+
+/// Sent by rooms when users without a seat join or leave.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct UpdateSpectators {
+    pub room_id: u32,
+    pub spectators: u32,
 }
 
 /// Join room
@@ -69,7 +128,7 @@ pub struct Join {
 }
 
 impl actix::Message for Join {
-    type Result = Result<Addr<GameRoom>, ()>;
+    type Result = Result<Addr<GameRoom>, message::Error>;
 }
 
 #[derive(Message)]
@@ -81,6 +140,28 @@ pub struct LeaveRoom {
     pub room_id: Option<u32>,
 }
 
+/// Follows a user into every room they join as a spectator, replacing any earlier
+/// follow of the session. `None` stops following.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Follow {
+    /// Client id
+    pub id: usize,
+    pub user_id: Option<u64>,
+}
+
+/// Relays an invitation into a room to every session of another user.
+pub struct Invite {
+    /// Client id
+    pub id: usize,
+    pub user_id: u64,
+    pub room_id: u32,
+}
+
+impl actix::Message for Invite {
+    type Result = Result<(), message::Error>;
+}
+
 /// Create room, announce to clients
 pub struct CreateRoom {
     /// Client id
@@ -93,6 +174,72 @@ impl actix::Message for CreateRoom {
     type Result = Result<(u32, Option<Addr<GameRoom>>), message::Error>;
 }
 
+// Tournaments ////////////////////////////////////////////////////////////////
+
+/// Starts a tournament and creates rooms for its first round, see `game::tournament`.
+pub struct CreateTournament {
+    pub name: String,
+    pub format: game::tournament::TournamentFormat,
+    pub participants: Vec<u64>,
+    /// Settings for every game. Black takes the first seat and white the second.
+    pub room: message::StartGame,
+}
+
+impl actix::Message for CreateTournament {
+    type Result = Result<u32, message::Error>;
+}
+
+pub struct GetTournament {
+    pub tournament_id: u32,
+}
+
+impl actix::Message for GetTournament {
+    type Result = Option<game::tournament::Tournament>;
+}
+
+/// Sent by rooms when their game ends.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct GameFinished {
+    pub room_id: u32,
+    /// `None` for draws and voided games.
+    pub winner: Option<u64>,
+    /// Players with machine-like move timing, see `game::timing::suspicious_timing`.
+    pub timing_flags: Vec<(u64, game::timing::TimingFlag)>,
+}
+
+// Problems ///////////////////////////////////////////////////////////////////
+
+/// Opens a room with the first problem the user hasn't solved and seats them on both
+/// sides, so they play the replies of the solution too. See `game::problem_set`.
+pub struct StartProblem {
+    /// Client id
+    pub id: usize,
+    pub leave_previous: bool,
+}
+
+impl actix::Message for StartProblem {
+    type Result = Result<(u32, Addr<GameRoom>), message::Error>;
+}
+
+/// Sent by rooms after the solution of their problem has been played.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct ProblemSolved {
+    pub room_id: u32,
+}
+
+/// Adds a problem to the end of the problem set.
+pub struct AddProblem {
+    pub name: String,
+    pub size: (u8, u8),
+    pub problem: game::Problem,
+}
+
+impl actix::Message for AddProblem {
+    type Result = Result<u64, message::Error>;
+}
+
 // User management ////////////////////////////////////////////////////////////
 
 pub struct IdentifyAs {
@@ -121,6 +268,74 @@ impl actix::Message for GetAdminView {
     type Result = Result<game::GameView, ()>;
 }
 
+/// Sent by sessions when the room rejects one of their game actions.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct RejectedAction {
+    pub id: usize,
+}
+
+pub struct GetMetrics;
+
+impl actix::Message for GetMetrics {
+    type Result = Metrics;
+}
+
+/// Served without authentication, so only aggregate counts belong here.
+#[derive(Debug, Clone, Serialize)]
+pub struct Metrics {
+    pub sessions: u32,
+    /// Rejected actions since the server started, including closed sessions.
+    pub rejected_actions: u64,
+    /// Connected sessions with rejected actions.
+    pub rejecting_sessions: u32,
+}
+
+/// Games where a player's move timing looked machine-like, newest last. Only answered
+/// for the admin token.
+pub struct GetTimingReports {
+    pub token: String,
+}
+
+impl actix::Message for GetTimingReports {
+    type Result = Result<Vec<TimingReport>, ()>;
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimingReport {
+    pub room_id: u32,
+    pub user_id: u64,
+    pub flag: game::timing::TimingFlag,
+}
+
+pub struct GetInfo;
+
+impl actix::Message for GetInfo {
+    type Result = message::ServerInfo;
+}
+
+pub struct UpdateSettings {
+    /// Client id
+    pub id: usize,
+    pub settings: serde_json::Value,
+}
+
+impl actix::Message for UpdateSettings {
+    type Result = Result<(), message::Error>;
+}
+
+/// Adds or removes a user from the block list of the client's user.
+pub struct Block {
+    /// Client id
+    pub id: usize,
+    pub user_id: u64,
+    pub blocked: bool,
+}
+
+impl actix::Message for Block {
+    type Result = Result<(), message::Error>;
+}
+
 // Admin //////////////////////////////////////////////////////////////////////
 
 #[derive(Message)]
@@ -141,6 +356,11 @@ pub struct Profile {
     pub nick: Option<String>,
     pub last_game_time: Option<Instant>,
     pub is_admin: bool,
+    /// Opaque client settings, only ever sent to the user themselves.
+    pub settings: serde_json::Value,
+    /// Also private to the user. Chat from these users is dropped by their sessions.
+    pub blocked: message::BlockList,
+    pub solved_problems: ProblemProgress,
 }
 
 pub struct Session {
@@ -148,18 +368,52 @@ pub struct Session {
     pub client: Recipient<Message>,
     pub game_client: Recipient<game_room::Message>,
     pub room_ids: Vec<u32>,
+    /// Game actions of this session that were rejected as illegal.
+    pub rejected_actions: u64,
 }
 
 #[derive(Clone)]
 pub struct Room {
     pub addr: Addr<GameRoom>,
     pub name: String,
+    pub thumbnail: Option<game::BoardThumbnail>,
+    pub spectators: u32,
+    pub size: (u8, u8),
+    pub variants: Vec<String>,
+    pub in_progress: bool,
+    pub no_spectators: bool,
+}
+
+/// What the lobby filters rooms by, see `message::RoomFilter`.
+struct RoomListing {
+    size: (u8, u8),
+    variants: Vec<String>,
+    in_progress: bool,
+    no_spectators: bool,
+}
+
+impl RoomListing {
+    fn of(game: &game::Game) -> RoomListing {
+        let board = &game.shared.board;
+        let variants = game.shared.mods.variants();
+        RoomListing {
+            size: (board.width as u8, board.height as u8),
+            variants: variants.into_iter().map(String::from).collect(),
+            in_progress: game.in_progress(),
+            no_spectators: game.shared.mods.no_spectators,
+        }
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////
 //                                   Actor                                   //
 ///////////////////////////////////////////////////////////////////////////////
 
+pub struct TournamentRun {
+    pub tournament: game::tournament::Tournament,
+    pub room: message::StartGame,
+}
+
 /// `GameServer` manages chat rooms and responsible for coordinating chat
 /// session. implementation is super primitive
 pub struct GameServer {
@@ -169,7 +423,30 @@ pub struct GameServer {
     rooms: HashMap<u32, Room>,
     rng: ThreadRng,
     admin_token: Uuid,
+    /// Issue a new token on every identify. Enabled with `ROTATE_TOKENS=1`.
+    rotate_tokens: bool,
+    /// Rotated tokens that still log in during the grace period, with the user and when
+    /// they expire as seconds since the Unix epoch. Stored so restarts keep them.
+    retired_tokens: HashMap<Uuid, (u64, i64)>,
+    /// Rotations sent to the database but not answered yet, old token to new token.
+    pending_rotations: HashMap<Uuid, Uuid>,
+    /// Rooms with a spectator count the lobby hasn't been told about yet.
+    spectators_changed: HashSet<u32>,
+    /// Sessions following each user, see `Follow`.
+    follows: HashMap<u64, HashSet<usize>>,
+    /// Tournaments are only kept in memory and end with a restart.
+    tournaments: HashMap<u32, TournamentRun>,
+    /// The tournament and pairing of each room with a tournament game going on.
+    tournament_rooms: HashMap<u32, (u32, usize)>,
+    /// Flagged move timing for moderators, at most `MAX_TIMING_REPORTS`.
+    timing_reports: VecDeque<TimingReport>,
+    /// Loaded from the database on start.
+    problems: ProblemSet,
+    /// The user and problem of each room opened by `StartProblem`, until it's solved.
+    problem_rooms: HashMap<u32, (u64, u64)>,
+    rejected_actions: u64,
     db: Addr<db::DbActor>,
+    started: Instant,
 }
 
 impl Default for GameServer {
@@ -185,6 +462,11 @@ impl Default for GameServer {
 
         println!("Admin token: {:?}", admin_token);
 
+        let rotate_tokens = matches!(
+            std::env::var("ROTATE_TOKENS").as_deref(),
+            Ok("1") | Ok("true")
+        );
+
         GameServer {
             sessions: HashMap::new(),
             sessions_by_user: HashMap::new(),
@@ -192,12 +474,37 @@ impl Default for GameServer {
             rooms,
             rng: rand::thread_rng(),
             admin_token,
+            rotate_tokens,
+            retired_tokens: HashMap::new(),
+            pending_rotations: HashMap::new(),
+            spectators_changed: HashSet::new(),
+            follows: HashMap::new(),
+            tournaments: HashMap::new(),
+            tournament_rooms: HashMap::new(),
+            timing_reports: VecDeque::new(),
+            problems: ProblemSet::default(),
+            problem_rooms: HashMap::new(),
+            rejected_actions: 0,
             db,
+            started: Instant::now(),
         }
     }
 }
 
 impl GameServer {
+    /// A rotated token within its grace period logs in as the current token of the user.
+    /// After a restart the current token isn't known yet, so the user is returned for the
+    /// database to find them by.
+    fn resolve_token(&self, token: Uuid) -> (Uuid, Option<u64>) {
+        match self.retired_tokens.get(&token) {
+            Some((user_id, _)) => match self.profiles.get(user_id) {
+                Some(profile) => (profile.token, None),
+                None => (token, Some(*user_id)),
+            },
+            None => (token, None),
+        }
+    }
+
     /// Send message to all users
     fn send_global_message(&self, message: Message) {
         for session in self.sessions.values() {
@@ -224,6 +531,116 @@ impl GameServer {
         }
     }
 
+    /// Creates a room for each pairing and seats its players.
+    fn start_pairings(
+        &mut self,
+        tournament_id: u32,
+        pairings: Vec<usize>,
+        ctx: &mut Context<Self>,
+    ) {
+        let run = match self.tournaments.get(&tournament_id) {
+            Some(x) => x,
+            None => return,
+        };
+        for pairing_idx in pairings {
+            let pairing = &run.tournament.pairings[pairing_idx];
+            let players: Vec<u64> = pairing.players().collect();
+            let room = message::StartGame {
+                name: format!("{} round {}", run.tournament.name, pairing.round + 1),
+                ..run.room.clone()
+            };
+            ctx.address()
+                .send(CreateRoom {
+                    id: 0,
+                    room,
+                    leave_previous: false,
+                })
+                .into_actor(self)
+                .map(move |res, act, _| {
+                    let (room_id, addr) = match res {
+                        Ok(Ok((room_id, Some(addr)))) => (room_id, addr),
+                        _ => return,
+                    };
+                    act.tournament_rooms
+                        .insert(room_id, (tournament_id, pairing_idx));
+                    for (seat_id, user_id) in players.into_iter().enumerate() {
+                        addr.do_send(game_room::GameActionAsUser {
+                            user_id,
+                            action: message::GameAction::TakeSeat(seat_id as _),
+                        });
+                    }
+                })
+                .spawn(ctx);
+        }
+    }
+
+    fn send_problem_progress(&self, user_id: u64) {
+        let progress = match self.profiles.get(&user_id) {
+            Some(profile) => &profile.solved_problems,
+            None => return,
+        };
+        let message = Message::ProblemProgress {
+            solved: self.problems.solved_count(progress),
+            total: self.problems.problems.len() as u32,
+        };
+        self.send_user_message(user_id, message);
+    }
+
+    /// Sends the followers of the user after them into the room. Followers already in the
+    /// room are skipped, so users following each other don't keep rejoining.
+    fn notify_followers(&self, user_id: u64, room_id: u32) {
+        let followers = match self.follows.get(&user_id) {
+            Some(x) => x,
+            None => return,
+        };
+        for session_id in followers {
+            let joined = self
+                .sessions
+                .get(session_id)
+                .map_or(true, |s| s.room_ids.contains(&room_id));
+            if !joined {
+                self.send_message(*session_id, Message::FollowInto { user_id, room_id });
+            }
+        }
+    }
+
+    fn unfollow(&mut self, session_id: usize) {
+        for followers in self.follows.values_mut() {
+            followers.remove(&session_id);
+        }
+        self.follows.retain(|_, followers| !followers.is_empty());
+    }
+
+    /// Tells the session which rooms the user holds a seat in, so they can rejoin.
+    fn send_active_rooms(&self, session_id: usize, user_id: u64, ctx: &mut Context<Self>) {
+        let queries = self.rooms.iter().map(|(&room_id, room)| {
+            let name = room.name.clone();
+            let thumbnail = room.thumbnail.clone();
+            room.addr
+                .send(game_room::HoldsSeat { user_id })
+                .map(move |res| match res {
+                    Ok(Some(your_turn)) => Some(message::SeatedRoom {
+                        room_id,
+                        name,
+                        thumbnail,
+                        your_turn,
+                    }),
+                    _ => None,
+                })
+        });
+
+        let fut = futures_util::future::join_all(queries)
+            .into_actor(self)
+            .map(move |rooms, act, _| {
+                let mut rooms: Vec<_> = rooms.into_iter().flatten().collect();
+                rooms.sort_by_key(|r| (!r.your_turn, r.room_id));
+                if let Some(session) = act.sessions.get(&session_id) {
+                    let _ = session.client.do_send(Message::ActiveRooms(rooms));
+                }
+            });
+        ctx.spawn(fut);
+    }
+
     fn leave_room(
         &mut self,
         session_id: usize,
@@ -259,7 +676,7 @@ impl GameServer {
         &mut self,
         session_id: usize,
         room_id: u32,
-    ) -> impl ActorFuture<Self, Output = ()> {
+    ) -> impl ActorFuture<Self, Output = Result<(), message::Error>> {
         let session = self
             .sessions
             .get_mut(&session_id)
@@ -287,11 +704,28 @@ impl GameServer {
                             _ => return fut::err(()),
                         };
 
-                        let game = match game::Game::load(&replay) {
-                            Some(r) => r,
-                            _ => return fut::err(()),
+                        let game = match game::Game::load_checked(&replay) {
+                            Ok(game) => game,
+                            Err(err) => {
+                                println!("Game {} can't be loaded: {:?}", room_id, err);
+                                return fut::err(());
+                            }
                         };
 
+                        if matches!(game.state, game::GameState::Done(_)) {
+                            if let Err(mismatch) = game::Game::verify_replay(&replay) {
+                                println!(
+                                    "Game {} doesn't replay to its stored result: {:?}",
+                                    room_id, mismatch
+                                );
+                            }
+                        }
+
+                        let thumbnail = game.thumbnail();
+                        let started = game.has_started();
+                        let listing = RoomListing::of(&game);
+                        let (main_chat, spectator_chat) =
+                            game_room::load_chat(db_game.chat.as_deref());
                         let room = GameRoom {
                             room_id,
                             owner: db_game.owner.map(|x| x as _),
@@ -300,9 +734,23 @@ impl GameServer {
                             name: db_game.name.to_owned(),
                             last_action: Instant::now(),
                             game,
+                            review: None,
+                            annotations: Vec::new(),
+                            presentation: message::BoardPresentation::default(),
+                            scoring_since: None,
+                            turn_since: None,
+                            changed_at: Instant::now(),
+                            think_times: HashMap::new(),
+                            started,
+                            engine: None,
+                            engine_thinking: false,
                             db: act.db.clone(),
                             server: ctx.address(),
                             kicked_players: HashSet::new(),
+                            invited: HashSet::new(),
+                            main_chat,
+                            spectator_chat,
+                            typing_relayed: HashMap::new(),
                         };
 
                         let addr = room.start();
@@ -312,6 +760,12 @@ impl GameServer {
                             Room {
                                 addr: addr.clone(),
                                 name: db_game.name.to_owned(),
+                                thumbnail: Some(thumbnail),
+                                spectators: 0,
+                                size: listing.size,
+                                variants: listing.variants,
+                                in_progress: listing.in_progress,
+                                no_spectators: listing.no_spectators,
                             },
                         );
 
@@ -327,14 +781,31 @@ impl GameServer {
         };
 
         prefetch.then(move |res, act, _| {
-            if let Ok(room_addr) = res {
-                room_addr.do_send(game_room::Join {
-                    session_id,
-                    user_id,
-                    addr,
+            let room_addr = match res {
+                Ok(x) => x,
+                Err(()) => return Either::Left(fut::err(message::Error::other("No such game"))),
+            };
+            let join = game_room::Join {
+                session_id,
+                user_id,
+                addr,
+            };
+            let joined = room_addr
+                .send(join)
+                .into_actor(act)
+                .map(move |res, act, _| {
+                    let res = res.unwrap_or_else(|_| Err(message::Error::other("Game closed")));
+                    match &res {
+                        Ok(()) => act.notify_followers(user_id, room_id),
+                        Err(_) => {
+                            if let Some(session) = act.sessions.get_mut(&session_id) {
+                                session.room_ids.retain(|&id| id != room_id);
+                            }
+                        }
+                    }
+                    res
                 });
-            }
-            async {}.into_actor(act)
+            Either::Right(joined)
         })
     }
 }
@@ -342,6 +813,37 @@ impl GameServer {
 impl Actor for GameServer {
     type Context = Context<Self>;
 
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(SPECTATOR_BROADCAST_INTERVAL, |act, _ctx| {
+            for room_id in std::mem::take(&mut act.spectators_changed) {
+                if let Some(room) = act.rooms.get(&room_id) {
+                    act.send_global_message(Message::RoomUpdated {
+                        room_id,
+                        spectators: room.spectators,
+                    });
+                }
+            }
+        });
+
+        self.db
+            .send(db::GetProblems)
+            .into_actor(self)
+            .map(|res, act, _| match res {
+                Ok(Ok(problems)) => act.problems = parse_problems(problems),
+                _ => println!("Problems can't be loaded"),
+            })
+            .wait(ctx);
+
+        self.db
+            .send(db::GetRetiredTokens { now: unix_time() })
+            .into_actor(self)
+            .map(|res, act, _| match res {
+                Ok(Ok(tokens)) => act.retired_tokens = parse_retired_tokens(tokens),
+                _ => println!("Retired tokens can't be loaded"),
+            })
+            .wait(ctx);
+    }
+
     fn stopping(&mut self, _ctx: &mut Self::Context) -> Running {
         println!("Server stopping!");
         Running::Stop
@@ -366,6 +868,7 @@ impl Handler<Connect> for GameServer {
                 client: msg.addr,
                 game_client: msg.game_addr,
                 room_ids: Vec::new(),
+                rejected_actions: 0,
             },
         );
 
@@ -387,6 +890,8 @@ impl Handler<Disconnect> for GameServer {
     fn handle(&mut self, msg: Disconnect, ctx: &mut Context<Self>) {
         println!("Someone disconnected");
 
+        self.unfollow(msg.id);
+
         self.leave_room(msg.id, None)
             .then(move |(), act, _| {
                 // remove address
@@ -416,22 +921,58 @@ impl Handler<Disconnect> for GameServer {
 impl Handler<ListRooms> for GameServer {
     type Result = MessageResult<ListRooms>;
 
-    fn handle(&mut self, _: ListRooms, _: &mut Context<Self>) -> Self::Result {
+    fn handle(&mut self, msg: ListRooms, _: &mut Context<Self>) -> Self::Result {
         let mut rooms = Vec::new();
 
         for (&key, room) in &self.rooms {
-            rooms.push((key, room.name.clone()));
+            rooms.push(message::RoomInfo {
+                room_id: key,
+                name: room.name.clone(),
+                thumbnail: room.thumbnail.clone(),
+                spectators: room.spectators,
+                size: room.size,
+                variants: room.variants.clone(),
+                in_progress: room.in_progress,
+                no_spectators: room.no_spectators,
+            });
+        }
+
+        MessageResult(msg.filter.apply(rooms))
+    }
+}
+
+impl Handler<UpdateSpectators> for GameServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: UpdateSpectators, _: &mut Context<Self>) -> Self::Result {
+        if let Some(room) = self.rooms.get_mut(&msg.room_id) {
+            if room.spectators != msg.spectators {
+                room.spectators = msg.spectators;
+                self.spectators_changed.insert(msg.room_id);
+            }
         }
+    }
+}
+
+impl Handler<UpdateThumbnail> for GameServer {
+    type Result = ();
 
-        MessageResult(rooms)
+    fn handle(&mut self, msg: UpdateThumbnail, _: &mut Context<Self>) -> Self::Result {
+        if let Some(room) = self.rooms.get_mut(&msg.room_id) {
+            room.thumbnail = Some(msg.thumbnail);
+            room.size = msg.size;
+            room.in_progress = msg.in_progress;
+        }
     }
 }
 
 /// Join room, send disconnect message to old room
 impl Handler<Join> for GameServer {
-    type Result = ActorResponse<Self, Result<Addr<GameRoom>, ()>>;
+    type Result = ActorResponse<Self, Result<Addr<GameRoom>, message::Error>>;
 
     fn handle(&mut self, msg: Join, _ctx: &mut Context<Self>) -> Self::Result {
+        use message::Error;
+
         let Join {
             id,
             room_id,
@@ -440,12 +981,12 @@ impl Handler<Join> for GameServer {
 
         let session = match self.sessions.get(&id) {
             Some(x) => x,
-            None => return ActorResponse::reply(Err(())),
+            None => return ActorResponse::reply(Err(Error::other("No session"))),
         };
 
         match session.user_id {
             Some(x) => x,
-            None => return ActorResponse::reply(Err(())),
+            None => return ActorResponse::reply(Err(Error::other("Not identified"))),
         };
 
         let after_leave = if leave_previous {
@@ -456,17 +997,117 @@ impl Handler<Join> for GameServer {
 
         let result = after_leave
             .then(move |(), act, _ctx| act.join_room(id, room_id))
-            .then(move |(), act, _ctx| {
-                fut::ready(match act.rooms.get(&room_id) {
+            .then(move |res, act, _ctx| {
+                fut::ready(res.and_then(|()| match act.rooms.get(&room_id) {
                     Some(room) => Ok(room.addr.clone()),
-                    None => Err(()),
-                })
+                    None => Err(Error::other("Game closed")),
+                }))
+            });
+
+        ActorResponse::r#async(result)
+    }
+}
+
+/// Joins a random game in progress as a spectator. Games with recent moves are preferred
+/// and empty rooms are skipped.
+pub struct SpectateRandom {
+    /// Client id
+    pub id: usize,
+    pub leave_previous: bool,
+}
+
+impl actix::Message for SpectateRandom {
+    type Result = Result<(u32, Addr<GameRoom>), message::Error>;
+}
+
+impl Handler<SpectateRandom> for GameServer {
+    type Result = ActorResponse<Self, Result<(u32, Addr<GameRoom>), message::Error>>;
+
+    fn handle(&mut self, msg: SpectateRandom, _ctx: &mut Context<Self>) -> Self::Result {
+        use message::Error;
+
+        let SpectateRandom { id, leave_previous } = msg;
+
+        if self.sessions.get(&id).and_then(|s| s.user_id).is_none() {
+            return ActorResponse::reply(Err(Error::other("Not identified")));
+        }
+
+        let queries = self.rooms.iter().map(|(&room_id, room)| {
+            room.addr
+                .send(game_room::GetActivity)
+                .map(move |res| Some((room_id, res.ok()??)))
+        });
+
+        let result = futures_util::future::join_all(queries)
+            .into_actor(self)
+            .map(|rooms, act, _ctx| {
+                let rooms: Vec<_> = rooms
+                    .into_iter()
+                    .flatten()
+                    .filter(|(_, activity)| activity.member_count > 0)
+                    .filter(|(room_id, _)| {
+                        act.rooms.get(room_id).map_or(false, |r| !r.no_spectators)
+                    })
+                    .collect();
+                let recent: Vec<u32> = rooms
+                    .iter()
+                    .filter(|(_, activity)| activity.idle < SPECTATE_MAX_IDLE)
+                    .map(|(room_id, _)| *room_id)
+                    .collect();
+                let candidates = if recent.is_empty() {
+                    rooms.iter().map(|(room_id, _)| *room_id).collect()
+                } else {
+                    recent
+                };
+                candidates.choose(&mut act.rng).copied()
+            })
+            .then(move |room_id, act, _ctx| {
+                let room_id = match room_id {
+                    Some(x) => x,
+                    None => return Either::Left(fut::err(Error::other("No live games to watch"))),
+                };
+                if !act.sessions.contains_key(&id) {
+                    return Either::Left(fut::err(Error::other("No session")));
+                }
+
+                let after_leave = if leave_previous {
+                    Either::Left(act.leave_room(id, None))
+                } else {
+                    Either::Right(async {}.into_actor(act))
+                };
+
+                Either::Right(
+                    after_leave
+                        .then(move |(), act, _ctx| act.join_room(id, room_id))
+                        .then(move |res, act, _ctx| {
+                            fut::ready(res.and_then(|()| match act.rooms.get(&room_id) {
+                                Some(room) => Ok((room_id, room.addr.clone())),
+                                None => Err(Error::other("Game closed")),
+                            }))
+                        }),
+                )
             });
 
         ActorResponse::r#async(result)
     }
 }
 
+impl Handler<Follow> for GameServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: Follow, _: &mut Context<Self>) -> Self::Result {
+        let own_id = match self.sessions.get(&msg.id).and_then(|s| s.user_id) {
+            Some(x) => x,
+            None => return,
+        };
+
+        self.unfollow(msg.id);
+        if let Some(user_id) = msg.user_id.filter(|&u| u != own_id) {
+            self.follows.entry(user_id).or_default().insert(msg.id);
+        }
+    }
+}
+
 impl Handler<LeaveRoom> for GameServer {
     type Result = ();
 
@@ -493,6 +1134,7 @@ impl Handler<CreateRoom> for GameServer {
 
     fn handle(&mut self, msg: CreateRoom, _: &mut Context<Self>) -> Self::Result {
         use message::Error;
+        let mods = msg.room.modifiers();
         let CreateRoom {
             id,
             room:
@@ -501,7 +1143,8 @@ impl Handler<CreateRoom> for GameServer {
                     seats,
                     komis,
                     size,
-                    mods,
+                    seed,
+                    ..
                 },
             leave_previous,
         } = msg;
@@ -528,8 +1171,13 @@ impl Handler<CreateRoom> for GameServer {
             0
         };
 
+        let mut mods = mods;
+        if let Some(teaching) = &mut mods.teaching {
+            teaching.teacher = user_id;
+        }
+
         let komis = komis.as_slice().into();
-        let seed = self.rng.next_u64();
+        let seed = seed.unwrap_or_else(|| self.rng.next_u64());
         let game = match game::Game::standard(&seats, komis, size, mods, seed) {
             Some(g) => g,
             None => return ActorResponse::reply(Err(Error::other("Rules not accepted"))),
@@ -585,6 +1233,9 @@ impl Handler<CreateRoom> for GameServer {
                     }
                 };
 
+                let thumbnail = game.thumbnail();
+                let started = game.has_started();
+                let listing = RoomListing::of(&game);
                 let room = GameRoom {
                     room_id,
                     owner,
@@ -593,9 +1244,23 @@ impl Handler<CreateRoom> for GameServer {
                     name: name.clone(),
                     last_action: Instant::now(),
                     game,
+                    review: None,
+                    annotations: Vec::new(),
+                    presentation: message::BoardPresentation::default(),
+                    scoring_since: None,
+                    turn_since: None,
+                    changed_at: Instant::now(),
+                    think_times: HashMap::new(),
+                    started,
+                    engine: None,
+                    engine_thinking: false,
                     db: act.db.clone(),
                     server: ctx.address(),
                     kicked_players: HashSet::new(),
+                    invited: HashSet::new(),
+                    main_chat: Vec::new(),
+                    spectator_chat: Vec::new(),
+                    typing_relayed: HashMap::new(),
                 };
 
                 let addr = room.start();
@@ -605,17 +1270,27 @@ impl Handler<CreateRoom> for GameServer {
                     Room {
                         addr: addr.clone(),
                         name: name.clone(),
+                        thumbnail: Some(thumbnail),
+                        spectators: 0,
+                        size: listing.size,
+                        variants: listing.variants,
+                        in_progress: listing.in_progress,
+                        no_spectators: listing.no_spectators,
                     },
                 );
 
-                act.send_global_message(Message::AnnounceRoom(room_id, name));
+                act.send_global_message(Message::AnnounceRoom {
+                    room_id,
+                    name,
+                    no_spectators: listing.no_spectators,
+                });
 
                 Either::Right(if user_id == 0 {
                     Either::Left(fut::ready(Ok((room_id, Some(addr)))))
                 } else {
                     Either::Right(
                         act.join_room(id, room_id)
-                            .then(move |(), _, _| fut::ready(Ok((room_id, Some(addr))))),
+                            .map(move |res, _, _| res.map(|()| (room_id, Some(addr)))),
                     )
                 })
             });
@@ -624,6 +1299,259 @@ impl Handler<CreateRoom> for GameServer {
     }
 }
 
+impl Handler<CreateTournament> for GameServer {
+    type Result = Result<u32, message::Error>;
+
+    fn handle(&mut self, msg: CreateTournament, ctx: &mut Context<Self>) -> Self::Result {
+        use game::tournament::{Tournament, TournamentError};
+        use message::Error;
+
+        if msg.name.len() > 30 {
+            return Err(Error::other("Name too long"));
+        }
+        if msg.room.seats.len() != 2 {
+            return Err(Error::other("Tournament games need two seats"));
+        }
+        // The teacher could play for either side.
+        if msg.room.mods.teaching.is_some() {
+            return Err(Error::other("Tournament games can't be teaching games"));
+        }
+
+        let tournament = Tournament::new(msg.name, msg.format, msg.participants);
+        let tournament = match tournament {
+            Ok(x) => x,
+            Err(TournamentError::TooFewPlayers) => {
+                return Err(Error::other("Not enough participants"))
+            }
+            Err(_) => return Err(Error::other("Participant listed twice")),
+        };
+
+        let tournament_id = (1..).find(|id| !self.tournaments.contains_key(id)).unwrap();
+        let pairings = tournament.open();
+        self.tournaments.insert(
+            tournament_id,
+            TournamentRun {
+                tournament,
+                room: msg.room,
+            },
+        );
+        self.start_pairings(tournament_id, pairings, ctx);
+
+        Ok(tournament_id)
+    }
+}
+
+impl Handler<GetTournament> for GameServer {
+    type Result = Option<game::tournament::Tournament>;
+
+    fn handle(&mut self, msg: GetTournament, _: &mut Context<Self>) -> Self::Result {
+        let run = self.tournaments.get(&msg.tournament_id)?;
+        Some(run.tournament.clone())
+    }
+}
+
+impl Handler<GameFinished> for GameServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: GameFinished, ctx: &mut Context<Self>) -> Self::Result {
+        for (user_id, flag) in msg.timing_flags {
+            println!(
+                "Room {}: move timing of user {} flagged, {:?}",
+                msg.room_id, user_id, flag
+            );
+            if self.timing_reports.len() >= MAX_TIMING_REPORTS {
+                self.timing_reports.pop_front();
+            }
+            self.timing_reports.push_back(TimingReport {
+                room_id: msg.room_id,
+                user_id,
+                flag,
+            });
+        }
+
+        let (tournament_id, pairing) = match self.tournament_rooms.remove(&msg.room_id) {
+            Some(x) => x,
+            None => return,
+        };
+        let run = match self.tournaments.get_mut(&tournament_id) {
+            Some(x) => x,
+            None => return,
+        };
+        // Rooms for the next round, or for the rematch of a drawn elimination game.
+        let next = run
+            .tournament
+            .record(pairing, msg.winner)
+            .unwrap_or_default();
+        self.start_pairings(tournament_id, next, ctx);
+    }
+}
+
+impl Handler<StartProblem> for GameServer {
+    type Result = ActorResponse<Self, Result<(u32, Addr<GameRoom>), message::Error>>;
+
+    fn handle(&mut self, msg: StartProblem, _: &mut Context<Self>) -> Self::Result {
+        use message::Error;
+
+        let StartProblem { id, leave_previous } = msg;
+
+        let user_id = match self.sessions.get(&id).and_then(|s| s.user_id) {
+            Some(x) => x,
+            None => return ActorResponse::reply(Err(Error::other("Not identified"))),
+        };
+        let problem = match self.profiles.get(&user_id) {
+            Some(profile) => self.problems.next(&profile.solved_problems),
+            None => return ActorResponse::reply(Err(Error::other("No profile"))),
+        };
+        let problem = match problem {
+            Some(x) => x.clone(),
+            None => return ActorResponse::reply(Err(Error::other("No problems left"))),
+        };
+        self.send_problem_progress(user_id);
+
+        let after_leave = if leave_previous {
+            Either::Left(self.leave_room(id, None))
+        } else {
+            Either::Right(async {}.into_actor(self))
+        };
+
+        // Go back to the room of an unsolved problem instead of opening another one.
+        let open_room = self
+            .problem_rooms
+            .iter()
+            .find(|(room_id, &entry)| {
+                entry == (user_id, problem.id) && self.rooms.contains_key(room_id)
+            })
+            .map(|(&room_id, _)| room_id);
+        if let Some(room_id) = open_room {
+            let result = after_leave
+                .then(move |(), act, _ctx| act.join_room(id, room_id))
+                .then(move |res, act, _ctx| {
+                    fut::ready(res.and_then(|()| match act.rooms.get(&room_id) {
+                        Some(room) => Ok((room_id, room.addr.clone())),
+                        None => Err(Error::other("Game closed")),
+                    }))
+                });
+            return ActorResponse::r#async(result.boxed_local());
+        }
+
+        let problem_id = problem.id;
+        let room = message::StartGame {
+            name: problem.name,
+            seats: vec![1, 2],
+            komis: vec![0, 0],
+            size: problem.size,
+            mods: game::GameModifier {
+                problem: Some(problem.problem),
+                ..game::GameModifier::default()
+            },
+            seed: None,
+            untimed: true,
+        };
+
+        let result = after_leave
+            .then(move |(), act, ctx| {
+                ctx.address()
+                    .send(CreateRoom {
+                        id: 0,
+                        room,
+                        leave_previous: false,
+                    })
+                    .into_actor(act)
+            })
+            .then(move |res, act, _ctx| {
+                let (room_id, addr) = match res {
+                    Ok(Ok((room_id, Some(addr)))) => (room_id, addr),
+                    Ok(Err(err)) => return Either::Left(fut::err(err)),
+                    _ => return Either::Left(fut::err(Error::other("Room not created"))),
+                };
+                act.problem_rooms.insert(room_id, (user_id, problem_id));
+                for seat_id in 0..2 {
+                    addr.do_send(game_room::GameActionAsUser {
+                        user_id,
+                        action: message::GameAction::TakeSeat(seat_id),
+                    });
+                }
+                let joined = act.join_room(id, room_id);
+                Either::Right(joined.map(move |res, _, _| res.map(|()| (room_id, addr))))
+            });
+
+        ActorResponse::r#async(result.boxed_local())
+    }
+}
+
+impl Handler<ProblemSolved> for GameServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: ProblemSolved, _: &mut Context<Self>) -> Self::Result {
+        let (user_id, problem_id) = match self.problem_rooms.remove(&msg.room_id) {
+            Some(x) => x,
+            None => return,
+        };
+        let profile = match self.profiles.get_mut(&user_id) {
+            Some(x) => x,
+            None => return,
+        };
+        if !profile.solved_problems.solve(problem_id) {
+            return;
+        }
+
+        let solved = &profile.solved_problems.0;
+        self.db.do_send(db::StoreSolvedProblems {
+            user_id,
+            solved: solved.iter().map(|&id| id as i64).collect(),
+        });
+        self.send_problem_progress(user_id);
+    }
+}
+
+impl Handler<AddProblem> for GameServer {
+    type Result = ActorResponse<Self, Result<u64, message::Error>>;
+
+    fn handle(&mut self, msg: AddProblem, _: &mut Context<Self>) -> Self::Result {
+        use message::Error;
+
+        let AddProblem {
+            name,
+            size,
+            problem,
+        } = msg;
+
+        if name.len() > 50 {
+            return ActorResponse::reply(Err(Error::other("Name too long")));
+        }
+        // Rooms for the problem are created with these rules, so check them up front.
+        let mods = game::GameModifier {
+            problem: Some(problem.clone()),
+            ..game::GameModifier::default()
+        };
+        if game::Game::standard(&[1, 2], (&[0, 0][..]).into(), size, mods, 0).is_none() {
+            return ActorResponse::reply(Err(Error::other("Rules not accepted")));
+        }
+
+        let fut = self.db.send(db::StoreProblem {
+            name: name.clone(),
+            size,
+            problem: serde_json::to_string(&problem).unwrap(),
+        });
+        let fut = fut.into_actor(self).then(move |res, act, _| {
+            let stored = match res {
+                Ok(Ok(x)) => x,
+                _ => return fut::err(Error::other("Problem not stored")),
+            };
+            let id = stored.id as u64;
+            act.problems.problems.push(SetProblem {
+                id,
+                name,
+                size,
+                problem,
+            });
+            fut::ok(id)
+        });
+
+        ActorResponse::r#async(fut)
+    }
+}
+
 impl Handler<IdentifyAs> for GameServer {
     type Result = ActorResponse<Self, Result<Profile, message::Error>>;
 
@@ -642,19 +1570,54 @@ impl Handler<IdentifyAs> for GameServer {
             }
         }
 
-        let rng = &mut self.rng;
+        let now = unix_time();
+        self.retired_tokens.retain(|_, (_, expires)| *expires > now);
+
+        let supplied_token = token.and_then(|t| Uuid::parse_str(&t).ok());
+        let (supplied_token, retired_user) = match supplied_token {
+            Some(t) => {
+                let (t, retired_user) = self.resolve_token(t);
+                (Some(t), retired_user)
+            }
+            None => (None, None),
+        };
 
-        let token = token
-            .and_then(|t| Uuid::parse_str(&t).ok())
-            .unwrap_or_else(|| Uuid::from_bytes(rng.gen()));
+        // Another session of the user may be rotating the same token right now. The
+        // database handles requests in order, so by the time it gets to this one the
+        // token has already changed. Rotating the old token again would create a new user.
+        let pending = supplied_token.and_then(|t| self.pending_rotations.get(&t).copied());
+        let supplied_token = pending.or(supplied_token);
+
+        let rng = &mut self.rng;
+        let token = supplied_token.unwrap_or_else(|| Uuid::from_bytes(rng.gen()));
+
+        let rotate = self.rotate_tokens
+            && supplied_token.is_some()
+            && pending.is_none()
+            && retired_user.is_none()
+            && token != self.admin_token;
+        let new_token = if rotate {
+            Some(Uuid::from_bytes(self.rng.gen()))
+        } else {
+            None
+        };
+        if let Some(new_token) = new_token {
+            self.pending_rotations.insert(token, new_token);
+        }
 
         let db = self.db.clone();
         let fut = db.send(db::IdentifyUser {
             auth_token: token.to_string(),
             nick: nick.clone(),
+            new_auth_token: new_token.map(|t| t.to_string()),
+            retired_user,
         });
 
-        let fut = fut.into_actor(self).then(move |res, act, _| {
+        let fut = fut.into_actor(self).then(move |res, act, ctx| {
+            if new_token.is_some() {
+                act.pending_rotations.remove(&token);
+            }
+
             let user = match res {
                 Ok(Ok(u)) => u,
                 _ => return fut::err(Error::other("No profile")),
@@ -662,12 +1625,31 @@ impl Handler<IdentifyAs> for GameServer {
 
             let user_id = user.id as u64;
 
+            // The database tells whether the token was actually rotated.
+            let current_token = Uuid::parse_str(&user.auth_token).unwrap_or(token);
+            if current_token != token && retired_user.is_none() {
+                let expires = unix_time() + TOKEN_GRACE_PERIOD.as_secs() as i64;
+                act.retired_tokens.insert(token, (user_id, expires));
+                act.db.do_send(db::StoreRetiredToken(db::RetiredToken {
+                    token: token.to_string(),
+                    user_id: user_id as _,
+                    expires,
+                }));
+            }
+            let token = current_token;
+
+            let settings = parse_settings(user.settings.as_deref());
+            let blocked = parse_blocked(&user.blocked);
+            let solved_problems = parse_solved(&user.solved_problems);
             let profile = act.profiles.entry(user_id).or_insert_with(move || Profile {
                 user_id,
                 token,
                 nick: user.nick,
                 last_game_time: None,
                 is_admin: false,
+                settings,
+                blocked,
+                solved_problems,
             });
 
             if let Some(nick) = nick {
@@ -680,11 +1662,13 @@ impl Handler<IdentifyAs> for GameServer {
                 }
             }
 
+            profile.token = token;
             profile.is_admin = profile.token == act.admin_token;
 
             let profile = profile.clone();
 
             act.send_user_message(user_id, Message::Identify(profile.clone()));
+            act.send_active_rooms(id, user_id, ctx);
 
             let sessions = act
                 .sessions_by_user
@@ -707,6 +1691,139 @@ impl Handler<IdentifyAs> for GameServer {
     }
 }
 
+impl Handler<Invite> for GameServer {
+    type Result = MessageResult<Invite>;
+
+    fn handle(&mut self, msg: Invite, _: &mut Context<Self>) -> Self::Result {
+        use message::Error;
+
+        let Invite {
+            id,
+            user_id,
+            room_id,
+        } = msg;
+
+        let from = match self.sessions.get(&id).and_then(|s| s.user_id) {
+            Some(x) => x,
+            None => return MessageResult(Err(Error::other("Not identified"))),
+        };
+        if from == user_id {
+            return MessageResult(Err(Error::other("Can't invite yourself")));
+        }
+        let room = match self.rooms.get(&room_id) {
+            Some(room) => room,
+            None => return MessageResult(Err(Error::other("No such room"))),
+        };
+        let room_name = room.name.clone();
+        let online = self
+            .sessions_by_user
+            .get(&user_id)
+            .map_or(false, |sessions| !sessions.is_empty());
+        if !online {
+            return MessageResult(Err(Error::other("That user is not online")));
+        }
+        // Declined without a word, the block stays private.
+        let blocked = self
+            .profiles
+            .get(&user_id)
+            .map_or(false, |p| p.blocked.blocks(from));
+        if blocked {
+            return MessageResult(Ok(()));
+        }
+
+        room.addr.do_send(game_room::Invited { from, user_id });
+        self.send_user_message(
+            user_id,
+            Message::Invited {
+                from,
+                room_id,
+                room_name,
+            },
+        );
+
+        MessageResult(Ok(()))
+    }
+}
+
+impl Handler<UpdateSettings> for GameServer {
+    type Result = MessageResult<UpdateSettings>;
+
+    fn handle(&mut self, msg: UpdateSettings, _: &mut Context<Self>) -> Self::Result {
+        use message::Error;
+
+        let UpdateSettings { id, settings } = msg;
+
+        if !settings.is_object() {
+            return MessageResult(Err(Error::other("Settings must be an object")));
+        }
+        let serialized = settings.to_string();
+        if serialized.len() > message::MAX_SETTINGS_SIZE {
+            return MessageResult(Err(Error::other("Settings too large")));
+        }
+
+        let user_id = match self.sessions.get(&id).and_then(|s| s.user_id) {
+            Some(x) => x,
+            None => return MessageResult(Err(Error::other("Not identified"))),
+        };
+        let profile = match self.profiles.get_mut(&user_id) {
+            Some(x) => x,
+            None => return MessageResult(Err(Error::other("No profile"))),
+        };
+        profile.settings = settings;
+        let profile = profile.clone();
+
+        self.db.do_send(db::StoreSettings {
+            user_id,
+            settings: serialized,
+        });
+
+        // Keep the user's other sessions in sync.
+        self.send_user_message(user_id, Message::Identify(profile));
+
+        MessageResult(Ok(()))
+    }
+}
+
+impl Handler<Block> for GameServer {
+    type Result = MessageResult<Block>;
+
+    fn handle(&mut self, msg: Block, _: &mut Context<Self>) -> Self::Result {
+        use message::Error;
+
+        let Block {
+            id,
+            user_id,
+            blocked,
+        } = msg;
+
+        let me = match self.sessions.get(&id).and_then(|s| s.user_id) {
+            Some(x) => x,
+            None => return MessageResult(Err(Error::other("Not identified"))),
+        };
+        if me == user_id {
+            return MessageResult(Err(Error::other("Can't block yourself")));
+        }
+        let profile = match self.profiles.get_mut(&me) {
+            Some(x) => x,
+            None => return MessageResult(Err(Error::other("No profile"))),
+        };
+        if let Err(err) = profile.blocked.set(user_id, blocked) {
+            return MessageResult(Err(err));
+        }
+        let profile = profile.clone();
+
+        self.db.do_send(db::StoreBlocked {
+            user_id: me,
+            blocked: profile.blocked.0.iter().map(|&id| id as i64).collect(),
+        });
+
+        // Every session of the user filters chat with the new list.
+        self.send_user_message(me, Message::Identify(profile));
+
+        MessageResult(Ok(()))
+    }
+}
+
 impl Handler<QueryProfile> for GameServer {
     type Result = ActorResponse<Self, Result<Profile, ()>>;
 
@@ -732,6 +1849,9 @@ impl Handler<QueryProfile> for GameServer {
                 nick: user.nick,
                 last_game_time: None,
                 is_admin,
+                settings: parse_settings(user.settings.as_deref()),
+                blocked: parse_blocked(&user.blocked),
+                solved_problems: parse_solved(&user.solved_problems),
             };
 
             // TODO: only send the profile to users in relevant rooms
@@ -765,21 +1885,90 @@ impl Handler<AdminMessage> for GameServer {
         let profile = r!(self.profiles.get(&user_id));
 
         if !profile.is_admin {
+            println!("User {} denied admin action {:?}", user_id, action);
             return MessageResult(());
         }
 
+        println!("Admin {} ran {:?}", user_id, action);
+
         match action {
             AdminAction::UnloadRoom(room_id) => {
                 let room = r!(self.rooms.remove(&room_id));
                 room.addr.do_send(game_room::Unload);
                 self.send_global_message(Message::CloseRoom(room_id));
             }
+            AdminAction::ResetRoom(room_id) => {
+                let seed = self.rng.next_u64();
+                let room = r!(self.rooms.get(&room_id));
+                room.addr.do_send(game_room::Reset { seed });
+            }
+            AdminAction::DumpRoom(room_id) => {
+                let room = r!(self.rooms.get(&room_id));
+                room.addr.do_send(game_room::DumpState {
+                    addr: session.game_client.clone(),
+                });
+            }
         }
 
         MessageResult(())
     }
 }
 
+impl Handler<GetInfo> for GameServer {
+    type Result = MessageResult<GetInfo>;
+
+    fn handle(&mut self, _: GetInfo, _: &mut Context<Self>) -> Self::Result {
+        MessageResult(message::ServerInfo {
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            protocol_version: message::PROTOCOL_VERSION,
+            uptime_secs: self.started.elapsed().as_secs(),
+            room_count: self.rooms.len() as u32,
+            user_count: self.sessions_by_user.len() as u32,
+        })
+    }
+}
+
+impl Handler<RejectedAction> for GameServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: RejectedAction, _: &mut Context<Self>) -> Self::Result {
+        self.rejected_actions += 1;
+        if let Some(session) = self.sessions.get_mut(&msg.id) {
+            session.rejected_actions += 1;
+        }
+    }
+}
+
+impl Handler<GetMetrics> for GameServer {
+    type Result = MessageResult<GetMetrics>;
+
+    fn handle(&mut self, _: GetMetrics, _: &mut Context<Self>) -> Self::Result {
+        let rejecting_sessions = self
+            .sessions
+            .values()
+            .filter(|session| session.rejected_actions > 0)
+            .count() as u32;
+
+        MessageResult(Metrics {
+            sessions: self.sessions.len() as u32,
+            rejected_actions: self.rejected_actions,
+            rejecting_sessions,
+        })
+    }
+}
+
+impl Handler<GetTimingReports> for GameServer {
+    type Result = Result<Vec<TimingReport>, ()>;
+
+    fn handle(&mut self, msg: GetTimingReports, _: &mut Context<Self>) -> Self::Result {
+        match Uuid::parse_str(&msg.token) {
+            Ok(token) if token == self.admin_token => {}
+            _ => return Err(()),
+        }
+        Ok(self.timing_reports.iter().cloned().collect())
+    }
+}
+
 impl Handler<GetAdminView> for GameServer {
     type Result = ActorResponse<Self, Result<game::GameView, ()>>;
 
@@ -804,3 +1993,147 @@ impl Handler<GetAdminView> for GameServer {
         ActorResponse::r#async(fut)
     }
 }
+
+fn parse_blocked(blocked: &[i64]) -> message::BlockList {
+    message::BlockList(blocked.iter().map(|&id| id as u64).collect())
+}
+
+fn parse_solved(solved: &[i64]) -> ProblemProgress {
+    ProblemProgress(solved.iter().map(|&id| id as u64).collect())
+}
+
+/// Problems that don't parse are left out of the set.
+fn parse_problems(problems: Vec<db::Problem>) -> ProblemSet {
+    let problems = problems
+        .into_iter()
+        .filter_map(|p| {
+            let problem = match serde_json::from_str(&p.problem) {
+                Ok(x) => x,
+                Err(err) => {
+                    println!("Problem {} can't be loaded: {:?}", p.id, err);
+                    return None;
+                }
+            };
+            Some(SetProblem {
+                id: p.id as u64,
+                name: p.name,
+                size: (p.width as u8, p.height as u8),
+                problem,
+            })
+        })
+        .collect();
+    ProblemSet { problems }
+}
+
+fn parse_retired_tokens(tokens: Vec<db::RetiredToken>) -> HashMap<Uuid, (u64, i64)> {
+    tokens
+        .into_iter()
+        .filter_map(|t| {
+            let token = Uuid::parse_str(&t.token).ok()?;
+            Some((token, (t.user_id as u64, t.expires)))
+        })
+        .collect()
+}
+
+/// Seconds since the Unix epoch.
+fn unix_time() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+fn parse_settings(settings: Option<&str>) -> serde_json::Value {
+    settings
+        .and_then(|s| serde_json::from_str(s).ok())
+        .unwrap_or(serde_json::Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix::dev::channel::channel;
+
+    /// A server that keeps whatever it sends the database queued and unanswered.
+    fn test_server() -> GameServer {
+        let (db_tx, _) = channel(16);
+        GameServer {
+            sessions: HashMap::new(),
+            sessions_by_user: HashMap::new(),
+            profiles: HashMap::new(),
+            rooms: HashMap::new(),
+            rng: rand::thread_rng(),
+            admin_token: Uuid::from_bytes([0; 16]),
+            rotate_tokens: true,
+            retired_tokens: HashMap::new(),
+            pending_rotations: HashMap::new(),
+            spectators_changed: HashSet::new(),
+            follows: HashMap::new(),
+            tournaments: HashMap::new(),
+            tournament_rooms: HashMap::new(),
+            timing_reports: VecDeque::new(),
+            problems: ProblemSet::default(),
+            problem_rooms: HashMap::new(),
+            rejected_actions: 0,
+            db: Addr::new(db_tx),
+            started: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn retired_token_after_restart() {
+        let old_token = Uuid::from_bytes([1; 16]);
+        let new_token = Uuid::from_bytes([2; 16]);
+        let mut server = test_server();
+        server.retired_tokens = parse_retired_tokens(vec![db::RetiredToken {
+            token: old_token.to_string(),
+            user_id: 7,
+            expires: unix_time() + 60,
+        }]);
+
+        // Nobody has identified since the restart, so the database finds the user by id.
+        assert_eq!(server.resolve_token(old_token), (old_token, Some(7)));
+        assert_eq!(server.resolve_token(new_token), (new_token, None));
+
+        server.profiles.insert(
+            7,
+            Profile {
+                user_id: 7,
+                token: new_token,
+                nick: None,
+                last_game_time: None,
+                is_admin: false,
+                settings: serde_json::Value::Null,
+                blocked: Default::default(),
+                solved_problems: Default::default(),
+            },
+        );
+        assert_eq!(server.resolve_token(old_token), (new_token, None));
+    }
+
+    #[actix_rt::test]
+    async fn tournaments_reject_teaching() {
+        let mut server = test_server();
+        let mods = game::GameModifier {
+            teaching: Some(game::Teaching::default()),
+            ..game::GameModifier::default()
+        };
+        let msg = CreateTournament {
+            name: "Cup".to_owned(),
+            format: game::tournament::TournamentFormat::RoundRobin,
+            participants: vec![100, 200],
+            room: message::StartGame {
+                name: String::new(),
+                seats: vec![1, 2],
+                komis: Vec::new(),
+                size: (9, 9),
+                mods,
+                seed: None,
+                untimed: false,
+            },
+        };
+
+        let res = server.handle(msg, &mut Context::new());
+        assert!(res.is_err());
+        assert!(server.tournaments.is_empty());
+    }
+}
@@ -1,21 +1,31 @@
 mod board;
+pub mod bot;
 pub mod clock;
 pub mod export;
+pub mod problem_set;
 #[cfg(test)]
 mod tests;
+pub mod timing;
+pub mod tournament;
+pub mod variations;
 
-use clock::{ClockRule, GameClock, Millisecond};
+use clock::{ClockRule, FischerClock, GameClock, Millisecond};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashSet, VecDeque};
 
 use bitmaps::Bitmap;
-use tinyvec::TinyVec;
+use tinyvec::{tiny_vec, TinyVec};
 
 use crate::states::play::traitor::TraitorState;
+use crate::states::scoring::{estimate_score, estimate_territory};
 pub use crate::states::GameState;
 use crate::states::PlayState;
 use crate::states::ScoringState;
-pub use board::{Board, Point};
+use crate::states::SetupState;
+pub use board::{
+    board_thumbnail, column_label, handicap_points, parse_coordinate, point_label, removed_stones,
+    rotate_point, unrotate_point, Board, BoardThumbnail, LabelStyle, Point,
+};
 
 ///////////////////////////////////////////////////////////////////////////////
 //                                    Data                                   //
@@ -23,6 +33,16 @@ pub use board::{Board, Point};
 
 pub type GroupVec<T> = TinyVec<[T; 8]>;
 
+/// Cells per side in board thumbnails. Keeps room list payloads small.
+pub const THUMBNAIL_SIZE: u32 = 9;
+
+/// Moves after which a game is scored when `GameModifier::max_moves` isn't set.
+pub const DEFAULT_MAX_MOVES: u32 = 2000;
+
+/// How long a disconnected player has to come back before their opponents may claim
+/// the game, see `Game::pause_for`.
+pub const RECONNECT_GRACE: Millisecond = Millisecond(120_000);
+
 pub type Visibility = Bitmap<typenum::U16>;
 pub type VisibilityBoard = Board<Bitmap<typenum::U16>>;
 
@@ -77,7 +97,7 @@ impl From<u8> for Color {
 
 // Seat ///////////////////////////////////////////////////////////////////////
 
-#[derive(Debug, Copy, Clone, PartialEq, Default, Serialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct Seat {
     pub player: Option<u64>,
     pub team: Color,
@@ -114,6 +134,8 @@ pub enum ActionKind {
     Pass,
     Cancel,
     Resign,
+    /// Pie rule: the second player takes over the first move, see `GameModifier::pie_rule`.
+    Swap,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -121,6 +143,13 @@ pub enum ReplayActionKind {
     Play(ActionKind),
     TakeSeat(u32),
     LeaveSeat(u32),
+    SetupStones(Vec<(u32, u32, u8)>),
+    /// Pass made for the seat on turn after it ran out of time, see `SkipAbsent`.
+    Skip,
+    /// Every seat of the user was resigned after they didn't return from a pause.
+    Forfeit,
+    /// A final round of scoring ran out of time and was counted, see `Game::settle_scoring`.
+    Settle,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -139,6 +168,69 @@ impl GameAction {
     }
 }
 
+// Review /////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ReviewAction {
+    /// Jump to a move of the game. Drops the current variation.
+    Navigate(u32),
+    /// Add a stone to the variation. Colors follow the seat order of the game.
+    Place(u32, u32),
+    /// Take back the last variation stone.
+    Undo,
+}
+
+/// Shared review of a finished game. Kept apart from the game state, so nothing
+/// done during the review can change the result.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Review {
+    pub move_number: u32,
+    /// Stones played on top of `move_number`, in order.
+    pub variation: Vec<Point>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewView {
+    pub move_number: u32,
+    pub variation: Vec<Point>,
+    pub board: Vec<u8>,
+    /// Board size at `move_number`, which differs from the final one on expanding boards.
+    pub size: (u8, u8),
+}
+
+/// The most annotations a room holds at once.
+pub const MAX_ANNOTATIONS: usize = 100;
+
+/// A drawing on top of the board by the teacher of a teaching game. Shown to the whole
+/// room, but never part of the game itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Annotation {
+    Circle(Point),
+    Triangle(Point),
+    /// A single letter or digit.
+    Letter(Point, char),
+    Line(Point, Point),
+    Arrow(Point, Point),
+}
+
+impl Annotation {
+    pub fn points(&self) -> GroupVec<Point> {
+        match *self {
+            Annotation::Circle(p) | Annotation::Triangle(p) | Annotation::Letter(p, _) => {
+                tiny_vec![[Point; 8] => p]
+            }
+            Annotation::Line(a, b) | Annotation::Arrow(a, b) => tiny_vec![[Point; 8] => a, b],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AnnotationAction {
+    /// Adding an annotation that is already there removes it again.
+    Add(Annotation),
+    Clear,
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 //                               Game modifiers                              //
 ///////////////////////////////////////////////////////////////////////////////
@@ -172,6 +264,48 @@ pub struct NPlusOne {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CapturesGivePoints {}
 
+/// Party rule: a move capturing at least `min_stones` stones at once earns its team
+/// `points` on top of the score.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaptureBonus {
+    pub min_stones: u32,
+    pub points: i32,
+}
+
+/// Which self captures are legal. Servers disagree here, so both sizes can be allowed
+/// separately. A suicided group is removed without counting as prisoners.
+/// Single stone suicide recreates the previous position, so superko still rejects it;
+/// only with `repetition` it goes through, much like a pass.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SuicideRule {
+    pub single_stone: bool,
+    pub multi_stone: bool,
+}
+
+impl SuicideRule {
+    pub fn allows(&self, group_size: usize) -> bool {
+        if group_size == 1 {
+            self.single_stone
+        } else {
+            self.multi_stone
+        }
+    }
+}
+
+/// How eyes of groups in seki are scored, see `ScoringState::seki_eyes`.
+#[derive(Debug, Copy, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub enum SekiScoring {
+    /// Counted like any territory, as under area scoring.
+    #[default]
+    CountEyes,
+    /// Left neutral, as under Japanese rules.
+    Neutral,
+}
+
+/// Japanese style pass stones. Passing hands a prisoner to every opposing team.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PassGivesPrisoner {}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TetrisGo {}
 
@@ -193,6 +327,208 @@ pub struct TraitorGo {
     pub traitor_count: u32,
 }
 
+/// Starts the game with an edit phase for authoring positions. Seat holders place and
+/// remove stones freely with `SetupStones` until one of them passes.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SetupPhase {}
+
+/// The board grows at set move intervals. Existing stones stay centered.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ExpandingBoard {
+    /// Moves between growth events, passes included.
+    pub interval: u32,
+    /// Lines added to each dimension per growth event, split between opposite edges.
+    pub growth: u32,
+    /// Neither dimension grows past this.
+    pub max_size: u32,
+}
+
+/// Limits on the stones on the board, for puzzle variants and as a guard
+/// against crafted games. Checked after captures.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct StoneCap {
+    /// Maximum number of stones on the board, counting every team.
+    pub max_stones: Option<u32>,
+    /// Maximum number of stones in a single group.
+    pub max_group_size: Option<u32>,
+}
+
+/// Parts of the board closed to stones early in the game, eg. no first line stones
+/// for teaching openings. Every rule applies on its own.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct OpeningRestrictions {
+    pub rules: Vec<OpeningRestriction>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpeningRestriction {
+    pub area: RestrictedArea,
+    /// The area opens up once this many moves have been played, passes included.
+    pub moves: u32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RestrictedArea {
+    /// Points on the outermost `lines` lines, 1 being only the first line.
+    Edge { lines: u32 },
+    /// The middle `size` by `size` square, shifted up and left on even boards.
+    Center { size: u32 },
+}
+
+impl RestrictedArea {
+    pub fn contains(&self, board: &Board, (x, y): Point) -> bool {
+        match *self {
+            RestrictedArea::Edge { lines } => {
+                x < lines || y < lines || x + lines >= board.width || y + lines >= board.height
+            }
+            RestrictedArea::Center { size } => {
+                let start_x = board.width.saturating_sub(size) / 2;
+                let start_y = board.height.saturating_sub(size) / 2;
+                (start_x..start_x + size).contains(&x) && (start_y..start_y + size).contains(&y)
+            }
+        }
+    }
+}
+
+impl OpeningRestrictions {
+    /// The area closing `point` after `move_number` moves, if any.
+    pub fn forbidding(
+        &self,
+        board: &Board,
+        point: Point,
+        move_number: u32,
+    ) -> Option<RestrictedArea> {
+        self.rules
+            .iter()
+            .find(|rule| move_number < rule.moves && rule.area.contains(board, point))
+            .map(|rule| rule.area)
+    }
+
+    /// Points closed after `move_number` moves, occupied or not so hidden stones stay hidden.
+    pub fn forbidden_points(&self, board: &Board, move_number: u32) -> Vec<Point> {
+        (0..board.points.len())
+            .filter_map(|idx| board.idx_to_coord(idx))
+            .filter(|&point| self.forbidding(board, point, move_number).is_some())
+            .collect()
+    }
+}
+
+/// Lets the teacher play for every seat to demonstrate sequences for both sides.
+/// Only for casual rooms, tournament games can't be teaching games.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Teaching {
+    /// Set by the server to the room creator.
+    pub teacher: u64,
+}
+
+/// Black starts with stones on the star points and white moves first. Only for games
+/// between black and white.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Handicap {
+    /// 1 places no stones and only lowers komi.
+    pub stones: u32,
+    /// Keeps the komi the game was created with. Otherwise white gets the usual half point.
+    #[serde(default)]
+    pub keep_komi: bool,
+}
+
+impl Handicap {
+    /// The usual komi in half points, indexed by team.
+    pub fn komis(&self, komis: &GroupVec<i32>) -> GroupVec<i32> {
+        if self.keep_komi {
+            return komis.clone();
+        }
+        komis
+            .iter()
+            .enumerate()
+            .map(|(idx, _)| if idx == 1 { 1 } else { 0 })
+            .collect()
+    }
+}
+
+/// The usual komi for white in half points: 7.5 from 13x13 up and 6.5 on smaller boards.
+/// Pass stones make the count match territory scoring, which is a point lower.
+pub fn default_komi(size: (u8, u8), mods: &GameModifier) -> i32 {
+    let komi = if size.0.min(size.1) >= 13 { 15 } else { 13 };
+    if mods.pass_gives_prisoner.is_some() {
+        komi - 2
+    } else {
+        komi
+    }
+}
+
+/// The clock of a game created with a variant but without a clock, see
+/// `message::StartGame::modifiers`. Fischer time, short for quick variants like capture
+/// go and longer on bigger boards. Standard go and games for studying stay untimed.
+pub fn default_clock(size: (u8, u8), mods: &GameModifier) -> Option<Clock> {
+    let studying = mods.teaching.is_some() || mods.problem.is_some() || mods.setup_phase.is_some();
+    if mods.variants().is_empty() || studying {
+        return None;
+    }
+    let minutes = |m: i128| Millisecond(m * 60_000);
+    let seconds = |s: i128| Millisecond(s * 1000);
+    let (main_time, increment) = if mods.capture_goal.is_some() {
+        (minutes(3), seconds(5))
+    } else if size.0.max(size.1) <= 9 {
+        (minutes(5), seconds(10))
+    } else if size.0.max(size.1) <= 13 {
+        (minutes(10), seconds(15))
+    } else {
+        (minutes(20), seconds(20))
+    };
+    Some(Clock {
+        rule: ClockRule::Fischer(FischerClock {
+            main_time,
+            increment,
+        }),
+    })
+}
+
+/// Passes for the seat on turn once it hasn't moved in `turn_seconds`, so one absent
+/// player doesn't hold up the rest. Only for games of three or more seats.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SkipAbsent {
+    pub turn_seconds: u32,
+}
+
+/// Decides a game whose top scores are tied by the stones each team captured during play.
+/// Pass stones and dead stones removed in scoring don't count.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CaptureTiebreak {
+    MostCaptures,
+    FewestCaptures,
+}
+
+/// Replaces positional superko with a plain ko rule. Whole board repetition is
+/// allowed, except for immediately retaking a ko.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Repetition {
+    /// The game ends with no result once a position has occurred this many times,
+    /// eg. 3 for triple ko. `None` only flags repeated positions.
+    pub no_result_after: Option<u32>,
+}
+
+/// A tsumego. The first seat has to follow `solution` exactly, the replies of
+/// the second seat are played automatically. Only single-line solutions for now.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Problem {
+    /// Stones on the board before the first move as `(x, y, team)`.
+    pub setup: Vec<(u32, u32, u8)>,
+    /// Alternating moves, starting with the solver.
+    pub solution: Vec<(u32, u32)>,
+}
+
+impl Problem {
+    /// The move the solution expects after `move_number` moves.
+    pub fn expected_move(&self, move_number: usize) -> Option<(u32, u32)> {
+        self.solution.get(move_number).copied()
+    }
+
+    pub fn is_solved(&self, move_number: usize) -> bool {
+        move_number >= self.solution.len()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct GameModifier {
     /// Pixel go is a game mode where you place 2x2 blobs instead of a single stone.
@@ -226,6 +562,9 @@ pub struct GameModifier {
     #[serde(default)]
     pub captures_give_points: Option<CapturesGivePoints>,
 
+    #[serde(default)]
+    pub pass_gives_prisoner: Option<PassGivesPrisoner>,
+
     #[serde(default)]
     pub tetris: Option<TetrisGo>,
 
@@ -241,12 +580,147 @@ pub struct GameModifier {
     #[serde(default)]
     pub traitor: Option<TraitorGo>,
 
+    #[serde(default)]
+    pub problem: Option<Problem>,
+
+    /// `None` means positional superko.
+    #[serde(default)]
+    pub repetition: Option<Repetition>,
+
+    #[serde(default)]
+    pub teaching: Option<Teaching>,
+
+    #[serde(default)]
+    pub stone_cap: Option<StoneCap>,
+
+    #[serde(default)]
+    pub setup_phase: Option<SetupPhase>,
+
+    #[serde(default)]
+    pub expanding_board: Option<ExpandingBoard>,
+
+    /// The first team to capture this many stones wins, like atari go with 1.
+    #[serde(default)]
+    pub capture_goal: Option<u32>,
+
+    #[serde(default)]
+    pub opening_restrictions: Option<OpeningRestrictions>,
+
+    /// Pie rule for two seat games. Instead of answering the first move, the second player
+    /// may swap seats with the first player and keep the first move for themselves.
+    #[serde(default)]
+    pub pie_rule: bool,
+
+    /// Scores the game once this many moves have been played, so a looping game can't
+    /// run forever. Play can't be resumed from that scoring, and it's settled like any
+    /// final round if the players don't agree in time. `None` uses `DEFAULT_MAX_MOVES`.
+    #[serde(default)]
+    pub max_moves: Option<u32>,
+
+    #[serde(default)]
+    pub handicap: Option<Handicap>,
+
+    #[serde(default)]
+    pub skip_absent: Option<SkipAbsent>,
+
+    #[serde(default)]
+    pub capture_tiebreak: Option<CaptureTiebreak>,
+
     /// If true, spectators can see all hidden stones and one color stones.
     #[serde(default)]
     pub observable: bool,
 
+    /// Rejects taking back moves. Going back from scoring to play is still possible.
     #[serde(default)]
     pub no_undo: bool,
+
+    /// Hint the player on turn to pass once no single move improves their score estimate.
+    /// This is only a heuristic and never passes on anyone's behalf.
+    #[serde(default)]
+    pub pass_hint: bool,
+
+    #[serde(default)]
+    pub capture_bonus: Option<CaptureBonus>,
+
+    /// Play can't begin until this many seats are taken, so a four player game doesn't
+    /// start with two. Seats emptied later don't stop the game.
+    #[serde(default)]
+    pub min_players: Option<u32>,
+
+    #[serde(default)]
+    pub suicide: SuicideRule,
+
+    /// Tells spectators which color tried an illegal move, for commentary. The player
+    /// always gets the full error.
+    #[serde(default)]
+    pub announce_illegal: bool,
+
+    #[serde(default)]
+    pub seki: SekiScoring,
+
+    /// Teams still tied after `capture_tiebreak` are ranked by seat order, the team that
+    /// sits down last first. Like komi, it makes up for moving later. Komi is given per
+    /// team, so half points only break ties where they're given.
+    #[serde(default)]
+    pub seat_order_tiebreak: bool,
+
+    /// Keeps the room to its players, for lessons and serious matches. Only seat holders,
+    /// the owner and users invited in may enter, see `Game::admits`.
+    #[serde(default)]
+    pub no_spectators: bool,
+
+    /// When play resumes from scoring, the seat after the one that resumed moves first, so
+    /// the player asking to resume doesn't also get the next move. Otherwise the turn order
+    /// goes on from the pass that started scoring, see `Game::resume_turn`.
+    #[serde(default)]
+    pub resume_to_opponent: bool,
+}
+
+/// Names of the variants in `GameModifier::variants`, in the same order.
+pub const VARIANT_NAMES: [&str; 14] = [
+    "Pixel go",
+    "Zen go",
+    "Hidden move go",
+    "One color go",
+    "N+1",
+    "Captures give points",
+    "Tetris go",
+    "Toroidal go",
+    "Phantom go",
+    "Traitor go",
+    "Problem",
+    "Capture go",
+    "Expanding board",
+    "Teaching game",
+];
+
+impl GameModifier {
+    /// Names of the variants the game is played with, for listing and filtering rooms.
+    /// Rule tweaks like komi or the tiebreaks aren't variants. Empty for standard go.
+    pub fn variants(&self) -> Vec<&'static str> {
+        let enabled = [
+            self.pixel,
+            self.zen_go.is_some(),
+            self.hidden_move.is_some(),
+            self.visibility_mode.is_some(),
+            self.n_plus_one.is_some(),
+            self.captures_give_points.is_some(),
+            self.tetris.is_some(),
+            self.toroidal.is_some(),
+            self.phantom.is_some(),
+            self.traitor.is_some(),
+            self.problem.is_some(),
+            self.capture_goal.is_some(),
+            self.expanding_board.is_some(),
+            self.teaching.is_some(),
+        ];
+        VARIANT_NAMES
+            .iter()
+            .zip(enabled.iter())
+            .filter(|(_, &enabled)| enabled)
+            .map(|(&name, _)| name)
+            .collect()
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -279,21 +753,90 @@ pub struct SharedState {
     pub traitor: Option<TraitorState>,
 }
 
+/// Play is held while a seated player is disconnected, see `Game::pause_for`.
+/// Not part of the replay, a reloaded game is never paused.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pause {
+    /// The player who lost connection.
+    pub player: u64,
+    /// After this the others may claim the game if the player hasn't returned.
+    pub grace_ends: Millisecond,
+    /// The player is connected again and play can be resumed.
+    pub returned: bool,
+    /// The clock was running and starts again on resume.
+    pub resume_clock: bool,
+}
+
+impl Pause {
+    /// The other players may claim the game at `time`, see `Game::claim`.
+    pub fn claimable(&self, time: Millisecond) -> bool {
+        !self.returned && time >= self.grace_ends
+    }
+}
+
+/// A step of the game that only goes ahead once every seat agrees to it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ConsentKind {
+    /// Accepting the count, see `ScoringState::players_accepted`. Declining resumes play.
+    AcceptScore,
+    /// Ending the hidden stone placement, see `FreePlacement::players_ready`.
+    FinishPlacement,
+}
+
+/// A consent the game is waiting for, see `Game::consent_requests`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConsentRequest {
+    pub kind: ConsentKind,
+    /// Seats that haven't agreed yet, in seat order.
+    pub waiting: Vec<u32>,
+}
+
+/// The seats, komis, size and rules a game was created with. Handicaps and a growing
+/// board change the ones in `SharedState`, so replays and resets start from these.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameSetup {
+    pub seats: GroupVec<u8>,
+    pub komis: GroupVec<i32>,
+    pub size: (u8, u8),
+    pub mods: GameModifier,
+}
+
 #[derive(Clone)]
 pub struct Game {
     pub state: GameState,
     pub state_stack: Vec<GameState>,
     pub shared: SharedState,
+    pub setup: GameSetup,
     pub actions: Vec<GameAction>,
     pub seed: u64,
+    pub pause: Option<Pause>,
 }
 
 impl SharedState {
     pub fn get_active_seat(&self) -> Seat {
         self.seats
             .get(self.turn)
+            .copied()
             .expect("Game turn number invalid")
-            .clone()
+    }
+
+    pub fn is_teacher(&self, player_id: u64) -> bool {
+        self.mods
+            .teaching
+            .as_ref()
+            .is_some_and(|t| t.teacher == player_id)
+    }
+
+    /// How many times the current position has occurred, including now.
+    pub fn position_count(&self) -> usize {
+        let current = match self.board_history.last() {
+            Some(x) => x,
+            None => return 0,
+        };
+        self.board_history
+            .iter()
+            .filter(|h| h.hash == current.hash && h.board == current.board)
+            .count()
     }
 }
 
@@ -318,6 +861,24 @@ pub enum MakeActionError {
     Ko,
     Illegal,
     GameDone,
+    /// The move is not part of the problem's solution.
+    WrongMove,
+    /// The board would hold more stones than `StoneCap::max_stones` allows.
+    TooManyStones,
+    /// A group would grow past `StoneCap::max_group_size`.
+    GroupTooLarge,
+    /// Reviews are only possible once the game is over.
+    NotDone,
+    /// Moves can't be taken back with `GameModifier::no_undo`.
+    UndoDisabled,
+    /// The point is in an area closed by `OpeningRestrictions` this early in the game.
+    Restricted(RestrictedArea),
+    /// Play was already resumed from scoring `MAX_SCORING_RESUMES` times.
+    ResumeLimit,
+    /// Play is held until the disconnected player returns, see `Game::pause_for`.
+    Paused,
+    /// Fewer seats are taken than `GameModifier::min_players` asks for.
+    NotEnoughPlayers,
 }
 
 pub enum ActionChange {
@@ -344,6 +905,7 @@ pub enum GameStateView {
     Play(PlayState),
     Scoring(ScoringState),
     Done(ScoringState),
+    Setup(SetupState),
 }
 
 impl From<GameState> for GameStateView {
@@ -355,11 +917,27 @@ impl From<GameState> for GameStateView {
             GameState::Play(state) => GameStateView::Play(state),
             GameState::Scoring(state) => GameStateView::Scoring(state),
             GameState::Done(state) => GameStateView::Done(state),
+            GameState::Setup(state) => GameStateView::Setup(state),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GameResult {
+    /// Decided by scoring or resignation.
+    Finished,
+    /// Voided by a repetition cycle.
+    NoResult,
+    /// The team captured the stones needed by `GameModifier::capture_goal`.
+    CaptureGoal(Color),
+    /// The top scores were tied and `GameModifier::capture_tiebreak` or
+    /// `GameModifier::seat_order_tiebreak` picked this team.
+    Tiebreak(Color),
+    /// The team claimed the game after its last opponent didn't return, see `Game::claim`.
+    Abandoned(Color),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GameView {
     // TODO: we need a separate state view since we have hidden information
     // currently players can cheat :F
@@ -374,13 +952,60 @@ pub struct GameView {
     pub points: GroupVec<i32>,
     pub move_number: u32,
     pub clock: Option<GameClock>,
+    /// The seat on turn is empty. The game is paused until someone takes it.
+    pub waiting_for_player: bool,
+    /// Set for the player on turn when `GameModifier::pass_hint` thinks passing is reasonable.
+    pub pass_suggested: bool,
+    /// Every move of the problem's solution has been played.
+    pub problem_solved: bool,
+    /// The current position has occurred before. Only possible with the repetition rule.
+    pub repetition: bool,
+    /// None while the game is in progress.
+    pub result: Option<GameResult>,
+    /// Server time when play resumes because scoring was not agreed on. Set by the server.
+    pub scoring_timeout: Option<Millisecond>,
+    /// Time left on each seat's clock as of its last start or stop. Empty without a clock.
+    pub time_remaining: Vec<Millisecond>,
+    /// The seat whose clock is running down, if any.
+    pub clock_running_for_seat: Option<u32>,
+    /// Points the player on turn can't play due to ko. Only filled in teaching games.
+    pub ko_points: Vec<(u32, u32)>,
+    /// Stones captured by each team, indexed by team. Counted under every ruleset, pass stones excluded.
+    pub prisoners: GroupVec<u32>,
+    /// Neutral points next to living stones while scoring. Under area scoring they're worth a point to whoever fills them.
+    pub dame: Vec<(u32, u32)>,
+    /// Points closed by opening restrictions at this move.
+    pub restricted_points: Vec<(u32, u32)>,
+    /// The second seat may swap seats under the pie rule instead of answering the first move.
+    pub swap_available: bool,
+    /// Moves after which the game is scored, see `GameModifier::max_moves`.
+    pub max_moves: u32,
+    /// Komi in half points, indexed by team. Lowered by `Handicap` unless it keeps komi.
+    pub komis: GroupVec<i32>,
+    /// Passes made for absent seats as `(move number, seat)`, see `SkipAbsent`.
+    pub skips: Vec<(u32, u32)>,
+    /// Play is held for a disconnected player, see `Game::pause_for`.
+    pub pause: Option<Pause>,
+    /// Points earned by `CaptureBonus` so far in half points, indexed by team.
+    pub capture_bonuses: GroupVec<i32>,
+    /// Seats to fill before play can begin, see `GameModifier::min_players`.
+    pub players_missing: u32,
+    /// Final place of each team, see `Game::places`. Empty until the game is done.
+    pub places: GroupVec<u32>,
+    /// Consents the game waits for, see `Game::consent_requests`.
+    pub consents: Vec<ConsentRequest>,
+    /// Passes in a row since the last stone, 0 outside of play.
+    pub pass_streak: u32,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GameHistory {
+    pub size: (u8, u8),
     pub board: Vec<u8>,
     pub board_visibility: Option<Vec<u16>>,
     pub last_stone: Option<GroupVec<(u32, u32)>>,
+    /// Stones the move removed from the board.
+    pub captured: Vec<(u32, u32)>,
     pub move_number: u32,
 }
 
@@ -393,6 +1018,94 @@ struct GameReplay {
     size: (u8, u8),
     #[serde(default)]
     seed: u64,
+    /// Older replays were stored without one.
+    #[serde(default)]
+    check: Option<ReplayCheck>,
+}
+
+/// Where the game stood when the replay was stored, to notice replays that no longer
+/// play out the same after rule or format changes.
+#[derive(Serialize, Deserialize)]
+struct ReplayCheck {
+    move_number: u32,
+    board: Vec<Color>,
+    result: Option<GameResult>,
+    scores: Option<GroupVec<i32>>,
+}
+
+impl ReplayCheck {
+    fn new(game: &Game) -> Self {
+        ReplayCheck {
+            move_number: game.shared.board_history.len() as u32 - 1,
+            board: game.shared.board.points.clone(),
+            result: game.result(),
+            scores: match &game.state {
+                GameState::Done(state) => Some(state.scores.clone()),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// How a replayed game differs from the position stored with it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayMismatch {
+    /// The replay couldn't be decoded or one of its actions was rejected.
+    Unplayable,
+    MoveNumber {
+        stored: u32,
+        replayed: u32,
+    },
+    Board,
+    Result {
+        stored: Option<GameResult>,
+        replayed: Option<GameResult>,
+    },
+    Scores {
+        stored: Option<GroupVec<i32>>,
+        replayed: Option<GroupVec<i32>>,
+    },
+}
+
+/// Why a replay dump was rejected, see `Game::load_checked`. `index` counts the recorded
+/// actions from 0.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadError {
+    /// The dump isn't a replay.
+    Malformed,
+    /// The seats, komis, size and rules don't make a game.
+    Setup,
+    /// A stone is placed outside the board as it was at that point of the game.
+    OutOfBounds { index: usize, point: Point },
+    /// A seat couldn't be taken or left.
+    Seat { index: usize, error: TakeSeatError },
+    /// The action was refused, like a stone on an occupied point.
+    Action {
+        index: usize,
+        error: MakeActionError,
+    },
+}
+
+/// Complete, unfiltered state of a game for tooling and bug reports.
+/// Unlike `GameView` this includes hidden stones and everyone's visibility.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameDump {
+    pub state: GameState,
+    pub state_stack: Vec<GameState>,
+    pub seats: GroupVec<Seat>,
+    pub points: GroupVec<i32>,
+    pub turn: u32,
+    pub pass_count: u32,
+    pub size: (u8, u8),
+    pub board: Vec<Color>,
+    pub board_visibility: Option<Vec<u16>>,
+    pub komis: GroupVec<i32>,
+    pub mods: GameModifier,
+    pub clock: Option<GameClock>,
+    pub move_number: u32,
+    /// Replaying these with `seed` reconstructs the game exactly.
+    pub actions: Vec<GameAction>,
+    pub seed: u64,
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -411,6 +1124,24 @@ impl Game {
             return None;
         }
 
+        // No komis given, so one per team, and only white gets the usual one.
+        let komis = if komis.is_empty() {
+            let teams = seats.iter().copied().max().unwrap_or(0) as usize;
+            let mut komis: GroupVec<i32> = (0..teams).map(|_| 0).collect();
+            if teams == 2 {
+                komis[1] = default_komi(size, &mods);
+            }
+            komis
+        } else {
+            komis
+        };
+        let setup = GameSetup {
+            seats: seats.iter().copied().collect(),
+            komis: komis.clone(),
+            size,
+            mods: mods.clone(),
+        };
+
         // 7 = 3 colors, rengo
         // 4 = 4 colors
         if !(1..=7).contains(&seats.len()) || !(1..=4).contains(&komis.len()) {
@@ -422,39 +1153,121 @@ impl Game {
             return None;
         }
 
-        let board = Board::empty(size.0 as _, size.1 as _, mods.toroidal.is_some());
-        let state = if let Some(rules) = &mods.hidden_move {
-            GameState::free_placement(
-                seats.len(),
-                komis.len(),
-                board.clone(),
-                rules.teams_share_stones,
-            )
-        } else {
-            GameState::play(seats.len())
-        };
+        let mut board = Board::empty(size.0 as _, size.1 as _, mods.toroidal.is_some());
 
-        let mut clock = mods
-            .clock
-            .as_ref()
-            .map(|r| GameClock::new(r.rule.clone(), seats.len()));
+        // Hidden stones can't be taught when the teacher sees everything.
+        if mods.teaching.is_some() && (mods.hidden_move.is_some() || mods.phantom.is_some()) {
+            return None;
+        }
 
-        // TODO: PUZZLE use the original game creation time
-        if let Some(clock) = &mut clock {
-            use std::time;
-            clock.initialize_clocks(clock::Millisecond(
-                time::SystemTime::now()
-                    .duration_since(time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as i128,
-            ));
+        // The setup phase replaces the other ways of preparing a board.
+        let other_setup = mods.hidden_move.is_some() || mods.problem.is_some();
+        if mods.setup_phase.is_some() && (other_setup || mods.phantom.is_some()) {
+            return None;
         }
 
-        let board_visibility = if mods.phantom.is_some() {
-            Some(VisibilityBoard::empty(
-                size.0 as _,
-                size.1 as _,
-                mods.toroidal.is_some(),
+        if mods.capture_goal == Some(0) {
+            return None;
+        }
+
+        if mods.pie_rule && seats.len() != 2 {
+            return None;
+        }
+
+        if let Some(min) = mods.min_players {
+            if min == 0 || min as usize > seats.len() {
+                return None;
+            }
+        }
+
+        if mods.max_moves == Some(0) {
+            return None;
+        }
+
+        // Restricted areas are checked per point, which doesn't fit pixel blobs.
+        if mods.opening_restrictions.is_some() && mods.pixel {
+            return None;
+        }
+
+        // Wrapping edges and problem coordinates don't survive a resize.
+        if let Some(rule) = &mods.expanding_board {
+            let fixed_layout = mods.toroidal.is_some() || mods.problem.is_some();
+            if fixed_layout || rule.interval == 0 || rule.max_size > 25 {
+                return None;
+            }
+        }
+
+        if let Some(problem) = &mods.problem {
+            if seats.len() != 2 || mods.hidden_move.is_some() || problem.solution.is_empty() {
+                return None;
+            }
+            for &(x, y, team) in &problem.setup {
+                if !board.point_within((x, y)) || !(1..=4).contains(&team) {
+                    return None;
+                }
+                *board.point_mut((x, y)) = Color(team);
+            }
+        }
+
+        if let Some(rule) = &mods.skip_absent {
+            if seats.len() < 3 || rule.turn_seconds == 0 {
+                return None;
+            }
+        }
+
+        let mut komis = komis;
+        let mut turn = 0;
+        if let Some(handicap) = &mods.handicap {
+            let black_and_white = seats.iter().all(|&t| t == 1 || t == 2) && komis.len() == 2;
+            let other_setup = mods.problem.is_some() || mods.hidden_move.is_some();
+            let other_rules = mods.pixel || mods.pie_rule;
+            if !black_and_white || other_setup || other_rules || handicap.stones == 0 {
+                return None;
+            }
+            komis = handicap.komis(&komis);
+            // A single stone only lowers komi, black still starts.
+            if handicap.stones > 1 {
+                for point in handicap_points(board.width, board.height, handicap.stones)? {
+                    *board.point_mut(point) = Color(1);
+                }
+                turn = seats.iter().position(|&t| t == 2)?;
+            }
+        }
+
+        let state = if let Some(rules) = &mods.hidden_move {
+            GameState::free_placement(
+                seats.len(),
+                komis.len(),
+                board.clone(),
+                rules.teams_share_stones,
+            )
+        } else if mods.setup_phase.is_some() {
+            GameState::Setup(SetupState::default())
+        } else {
+            GameState::play(seats.len())
+        };
+
+        let mut clock = mods
+            .clock
+            .as_ref()
+            .map(|r| GameClock::new(r.rule.clone(), seats.len()));
+
+        // TODO: PUZZLE use the original game creation time
+        if let Some(clock) = &mut clock {
+            use std::time;
+            clock.initialize_clocks(clock::Millisecond(
+                time::SystemTime::now()
+                    .duration_since(time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_millis() as i128,
+            ));
+        }
+
+        let board_visibility = if mods.phantom.is_some() {
+            Some(VisibilityBoard::empty(
+                size.0 as _,
+                size.1 as _,
+                mods.toroidal.is_some(),
             ))
         } else {
             None
@@ -475,7 +1288,7 @@ impl Game {
             shared: SharedState {
                 seats: seats.iter().map(|&t| Seat::new(Color(t))).collect(),
                 points: komis.clone(),
-                turn: 0,
+                turn,
                 pass_count: 0,
                 board: board.clone(),
                 board_visibility: board_visibility.clone(),
@@ -485,7 +1298,7 @@ impl Game {
                     board_visibility,
                     state: GameState::play(seats.len()),
                     points: komis.clone(),
-                    turn: 0,
+                    turn,
                     traitor: traitor.clone(),
                 }],
                 komis,
@@ -493,54 +1306,144 @@ impl Game {
                 clock,
                 traitor,
             },
+            setup,
             actions: vec![],
             seed,
+            pause: None,
         })
     }
 
     /// Loads a game from a replay dump. Can fail at any point due to changed rules...
     /// Such is life.
     pub fn load(dump: &[u8]) -> Option<Game> {
-        let mut replay: GameReplay = serde_cbor::from_slice(dump).ok()?;
+        Game::load_checked(dump).ok()
+    }
+
+    /// Like `load`, but tells which recorded action broke the replay.
+    pub fn load_checked(dump: &[u8]) -> Result<Game, LoadError> {
+        let replay: GameReplay = serde_cbor::from_slice(dump).map_err(|_| LoadError::Malformed)?;
+        Game::from_replay(replay)
+    }
+
+    fn from_replay(mut replay: GameReplay) -> Result<Game, LoadError> {
         // TODO: PUZZLE make replays conserve clocks
-        replay.mods.clock = None;
+        let clock = replay.mods.clock.take();
         let mut game = Game::standard(
             &replay.seats,
             replay.komis,
             replay.size,
             replay.mods,
             replay.seed,
-        )?;
+        )
+        .ok_or(LoadError::Setup)?;
+        // Kept for the next dump and resets.
+        game.setup.mods.clock = clock;
 
-        for action in replay.actions {
+        for (index, action) in replay.actions.into_iter().enumerate() {
             use ReplayActionKind::*;
+            let seat_error = |error| LoadError::Seat { index, error };
+            let action_error = |error| LoadError::Action { index, error };
             match action.action {
                 TakeSeat(seat_id) => {
-                    game.take_seat(action.user_id, seat_id as _).ok()?;
+                    game.take_seat(action.user_id, seat_id as _)
+                        .map_err(seat_error)?;
                 }
                 LeaveSeat(seat_id) => {
-                    game.leave_seat(action.user_id, seat_id as _).ok()?;
+                    game.leave_seat(action.user_id, seat_id as _)
+                        .map_err(seat_error)?;
                 }
                 Play(play) => {
+                    if let ActionKind::Place(x, y) = play {
+                        game.check_replay_point(index, (x, y))?;
+                    }
                     game.make_action(action.user_id, play, Millisecond(0))
-                        .ok()?;
+                        .map_err(action_error)?;
+                }
+                SetupStones(stones) => {
+                    for &(x, y, _) in &stones {
+                        game.check_replay_point(index, (x, y))?;
+                    }
+                    game.setup_stones(action.user_id, stones)
+                        .map_err(action_error)?;
+                }
+                Skip => {
+                    game.skip_turn(Millisecond(0)).map_err(action_error)?;
+                }
+                Forfeit => {
+                    game.forfeit(action.user_id).map_err(action_error)?;
+                }
+                Settle => {
+                    game.settle_scoring().map_err(action_error)?;
                 }
             }
         }
 
-        Some(game)
+        Ok(game)
+    }
+
+    /// Rejects points of recorded actions outside the current board. Pixel go counts
+    /// from one past the edge, see `PlayState::place_stone`.
+    fn check_replay_point(&self, index: usize, point: Point) -> Result<(), LoadError> {
+        let board = &self.shared.board;
+        let (width, height) = if self.shared.mods.pixel {
+            (board.width + 1, board.height + 1)
+        } else {
+            (board.width, board.height)
+        };
+        if point.0 < width && point.1 < height {
+            Ok(())
+        } else {
+            Err(LoadError::OutOfBounds { index, point })
+        }
+    }
+
+    /// Replays a dump from scratch and compares the outcome with the position stored in it.
+    /// Dumps without a stored position always pass.
+    pub fn verify_replay(dump: &[u8]) -> Result<(), ReplayMismatch> {
+        let mut replay: GameReplay =
+            serde_cbor::from_slice(dump).map_err(|_| ReplayMismatch::Unplayable)?;
+        let stored = match replay.check.take() {
+            Some(check) => check,
+            None => return Ok(()),
+        };
+        let game = Game::from_replay(replay).map_err(|_| ReplayMismatch::Unplayable)?;
+        let replayed = ReplayCheck::new(&game);
+
+        if stored.move_number != replayed.move_number {
+            return Err(ReplayMismatch::MoveNumber {
+                stored: stored.move_number,
+                replayed: replayed.move_number,
+            });
+        }
+        if stored.board != replayed.board {
+            return Err(ReplayMismatch::Board);
+        }
+        if stored.result != replayed.result {
+            return Err(ReplayMismatch::Result {
+                stored: stored.result,
+                replayed: replayed.result,
+            });
+        }
+        if stored.scores != replayed.scores {
+            return Err(ReplayMismatch::Scores {
+                stored: stored.scores,
+                replayed: replayed.scores,
+            });
+        }
+        Ok(())
     }
 
     /// Dumps the game to a (hopefully somewhat) stable replay format.
     pub fn dump(&self) -> Vec<u8> {
-        let shared = &self.shared;
+        let setup = &self.setup;
         let replay = GameReplay {
             actions: self.actions.clone(),
-            komis: shared.komis.clone(),
-            size: (shared.board.width as _, shared.board.height as _),
-            seats: shared.seats.iter().map(|x| x.team.0).collect(),
-            mods: shared.mods.clone(),
+            komis: setup.komis.clone(),
+            size: setup.size,
+            seats: setup.seats.clone(),
+            mods: setup.mods.clone(),
             seed: self.seed,
+            check: Some(ReplayCheck::new(self)),
         };
 
         let mut vec = Vec::new();
@@ -550,6 +1453,30 @@ impl Game {
         vec
     }
 
+    pub fn dump_state(&self) -> GameDump {
+        let shared = &self.shared;
+        GameDump {
+            state: self.state.clone(),
+            state_stack: self.state_stack.clone(),
+            seats: shared.seats.clone(),
+            points: shared.points.clone(),
+            turn: shared.turn as _,
+            pass_count: shared.pass_count as _,
+            size: (shared.board.width as _, shared.board.height as _),
+            board: shared.board.points.clone(),
+            board_visibility: shared
+                .board_visibility
+                .as_ref()
+                .map(|b| b.points.iter().map(|x| x.into_value()).collect()),
+            komis: shared.komis.clone(),
+            mods: shared.mods.clone(),
+            clock: shared.clock.clone(),
+            move_number: shared.board_history.len() as u32 - 1,
+            actions: self.actions.clone(),
+            seed: self.seed,
+        }
+    }
+
     pub fn take_seat(&mut self, player_id: u64, seat_id: usize) -> Result<(), TakeSeatError> {
         let shared = &mut self.shared;
 
@@ -575,6 +1502,8 @@ impl Game {
         Ok(())
     }
 
+    /// Leaving the seat on turn doesn't move the turn forward. The game waits until
+    /// the seat is taken again, rejecting moves from the other seats in the meantime.
     pub fn leave_seat(&mut self, player_id: u64, seat_id: usize) -> Result<(), TakeSeatError> {
         let shared = &mut self.shared;
         let seat = shared
@@ -592,20 +1521,161 @@ impl Game {
         Ok(())
     }
 
+    /// True if the user may enter the room of the game. `invited` is true for the room
+    /// owner and users they or the players invited, the only spectators `no_spectators`
+    /// allows. The teacher of a teaching game holds no seat but always gets in.
+    pub fn admits(&self, user_id: u64, invited: bool) -> bool {
+        !self.shared.mods.no_spectators
+            || invited
+            || self.holds_seat(user_id)
+            || self.shared.is_teacher(user_id)
+    }
+
+    /// True if the player holds at least one seat in the game.
+    pub fn holds_seat(&self, player_id: u64) -> bool {
+        self.shared
+            .seats
+            .iter()
+            .any(|s| s.player == Some(player_id))
+    }
+
+    /// Consents the game is waiting for. Resolved requests are left out, so a request
+    /// disappears once every seat agreed or the state it belongs to is left.
+    pub fn consent_requests(&self) -> Vec<ConsentRequest> {
+        let request = |kind, done: &[bool]| {
+            let waiting = (0..done.len() as u32)
+                .filter(|&idx| !done[idx as usize])
+                .collect::<Vec<_>>();
+            if waiting.is_empty() {
+                None
+            } else {
+                Some(ConsentRequest { kind, waiting })
+            }
+        };
+        let pending = match &self.state {
+            GameState::Scoring(state) => request(ConsentKind::AcceptScore, &state.players_accepted),
+            GameState::FreePlacement(state) => {
+                request(ConsentKind::FinishPlacement, &state.players_ready)
+            }
+            _ => None,
+        };
+        pending.into_iter().collect()
+    }
+
+    /// True if the game is waiting on the player, eg. for their move or to accept the score.
+    pub fn awaits_player(&self, player_id: u64) -> bool {
+        let seats = &self.shared.seats;
+        let pending = |done: &[bool]| {
+            seats
+                .iter()
+                .zip(done)
+                .any(|(s, &done)| s.player == Some(player_id) && !done)
+        };
+        if self.players_missing() > 0 {
+            return false;
+        }
+        match &self.state {
+            GameState::Play(_) => self.shared.get_active_seat().player == Some(player_id),
+            GameState::FreePlacement(state) => pending(&state.players_ready),
+            GameState::Scoring(state) => pending(&state.players_accepted),
+            GameState::Setup(_) => self.holds_seat(player_id),
+            GameState::Done(_) => false,
+        }
+    }
+
+    /// True once play is underway: the first move was made, or the setup or free
+    /// placement phase ended. Turns true once and stays so for the rest of the game.
+    pub fn has_started(&self) -> bool {
+        match self.state {
+            GameState::Setup(_) | GameState::FreePlacement(_) => false,
+            _ => self
+                .actions
+                .iter()
+                .any(|a| matches!(a.action, ReplayActionKind::Play(_) | ReplayActionKind::Skip)),
+        }
+    }
+
+    /// Play has started and the game isn't over yet.
+    pub fn in_progress(&self) -> bool {
+        self.has_started() && !matches!(self.state, GameState::Done(_))
+    }
+
+    /// Seats that still have to be taken before play can begin, see
+    /// `GameModifier::min_players`. Always 0 once the game has started.
+    pub fn players_missing(&self) -> u32 {
+        let min = match self.shared.mods.min_players {
+            Some(min) if !self.has_started() => min,
+            _ => return 0,
+        };
+        let taken = self
+            .shared
+            .seats
+            .iter()
+            .filter(|s| s.player.is_some())
+            .count();
+        min.saturating_sub(taken as u32)
+    }
+
+    /// Notice for spectators when the player on turn tries a move the rules forbid,
+    /// see `GameModifier::announce_illegal`. Mistakes like clicking out of turn aren't
+    /// announced.
+    pub fn illegal_move_notice(&self, player_id: u64, error: &MakeActionError) -> Option<String> {
+        use MakeActionError::*;
+
+        if !self.shared.mods.announce_illegal {
+            return None;
+        }
+        match error {
+            PointOccupied | Suicide | Ko | Illegal | TooManyStones | GroupTooLarge
+            | Restricted(_) => {}
+            _ => return None,
+        }
+        let seat = self.shared.seats.get(self.shared.turn)?;
+        if seat.player != Some(player_id) {
+            return None;
+        }
+        Some(format!(
+            "{} attempted an illegal move",
+            Color::name(seat.team)
+        ))
+    }
+
+    /// Built-in bot for `seat`, seeded from the game so a replayed or identically seeded
+    /// game gets the same moves.
+    pub fn bot(&self, level: bot::BotLevel, seat: usize) -> Box<dyn bot::GoBot> {
+        level.bot(self.seed.wrapping_add(seat as u64))
+    }
+
+    /// Preview of the board as a spectator sees it.
+    pub fn thumbnail(&self) -> BoardThumbnail {
+        let view = self.get_view(0);
+        board_thumbnail(
+            &view.board,
+            (view.size.0 as u32, view.size.1 as u32),
+            THUMBNAIL_SIZE,
+        )
+    }
+
+    /// Seat holders and the teacher of a teaching game can play.
+    pub fn can_play(&self, player_id: u64) -> bool {
+        self.holds_seat(player_id) || self.shared.is_teacher(player_id)
+    }
+
     pub fn make_action(
         &mut self,
         player_id: u64,
         mut action: ActionKind,
         time: Millisecond,
     ) -> Result<(), MakeActionError> {
-        if !self
-            .shared
-            .seats
-            .iter()
-            .any(|s| s.player == Some(player_id))
-        {
+        if !self.can_play(player_id) {
             return Err(MakeActionError::NotPlayer);
         }
+        if self.pause.is_some() {
+            return Err(MakeActionError::Paused);
+        }
+        if self.players_missing() > 0 {
+            return Err(MakeActionError::NotEnoughPlayers);
+        }
 
         let res = match &mut self.state {
             GameState::FreePlacement(state) => {
@@ -614,17 +1684,19 @@ impl Game {
             GameState::Play(state) => {
                 let seat_idx = self.shared.turn;
                 // We want to keep the clock reset until all players have made a move.
-                let start_clock = self.shared.board_history.len() == self.shared.seats.len();
+                // A swap isn't a move, so it doesn't start or end turns either.
+                let timed = action != ActionKind::Swap;
+                let start_clock =
+                    timed && self.shared.board_history.len() == self.shared.seats.len();
                 if let Some(clock) = &mut self.shared.clock {
                     if start_clock {
                         clock.initialize_clocks(time);
                     }
                 }
 
-                let time_left = if let Some(clock) = &mut self.shared.clock {
-                    clock.advance_clock(seat_idx, time)
-                } else {
-                    Millisecond(0)
+                let time_left = match &mut self.shared.clock {
+                    Some(clock) if timed => clock.advance_clock(seat_idx, time),
+                    _ => Millisecond(0),
                 };
 
                 if time_left.0 < -2000 {
@@ -633,7 +1705,7 @@ impl Game {
 
                 let res = state.make_action(&mut self.shared, player_id, action.clone());
 
-                if res.is_ok() {
+                if res.is_ok() && timed {
                     if let Some(clock) = &mut self.shared.clock {
                         clock.end_turn(seat_idx, time);
                         if start_clock {
@@ -647,6 +1719,7 @@ impl Game {
                 state.make_action(&mut self.shared, player_id, action.clone())
             }
             GameState::Done(_) => Err(MakeActionError::GameDone),
+            GameState::Setup(state) => state.make_action(&mut self.shared, action.clone()),
         };
 
         match res {
@@ -660,7 +1733,11 @@ impl Game {
                         self.state_stack.push(old_state);
                     }
                     ActionChange::PopState => {
+                        let resumed = matches!(self.state, GameState::Scoring(_));
                         self.state = self.state_stack.pop().expect("Empty state stack popped");
+                        if resumed {
+                            self.resume_turn(player_id);
+                        }
 
                         if let Some(clock) = &mut self.shared.clock {
                             clock.initialize_clocks(time);
@@ -669,6 +1746,8 @@ impl Game {
                     ActionChange::None => {}
                 }
 
+                self.check_max_moves();
+
                 self.actions.push(GameAction::play(player_id, action));
 
                 Ok(())
@@ -677,6 +1756,241 @@ impl Game {
         }
     }
 
+    /// Picks the seat to move after `player_id` resumed play from scoring.
+    ///
+    /// Without `GameModifier::resume_to_opponent` the turn stays where the last pass left
+    /// it: with the seat after the one that passed last, as if scoring never started. With
+    /// it, the turn goes to the first seat after the resumer's that hasn't resigned. When
+    /// the player who passed last resumes, both give the same seat.
+    fn resume_turn(&mut self, player_id: u64) {
+        if !self.shared.mods.resume_to_opponent {
+            return;
+        }
+        let seats = &self.shared.seats;
+        let held = match seats.iter().position(|s| s.player == Some(player_id)) {
+            Some(x) => x,
+            None => return,
+        };
+        let next = (1..=seats.len())
+            .map(|offset| (held + offset) % seats.len())
+            .find(|&idx| !seats[idx].resigned);
+        if let Some(idx) = next {
+            self.shared.turn = idx;
+        }
+    }
+
+    /// Passes for the seat on turn whoever holds it. The server calls this once the seat
+    /// has been on turn for longer than `SkipAbsent::turn_seconds`.
+    pub fn skip_turn(&mut self, time: Millisecond) -> Result<(), MakeActionError> {
+        if self.shared.mods.skip_absent.is_none() {
+            return Err(MakeActionError::Illegal);
+        }
+        if self.pause.is_some() {
+            return Err(MakeActionError::Paused);
+        }
+        let state = match &mut self.state {
+            GameState::Play(state) => state,
+            _ => return Err(MakeActionError::Illegal),
+        };
+
+        let seat_idx = self.shared.turn;
+        if let Some(clock) = &mut self.shared.clock {
+            clock.advance_clock(seat_idx, time);
+            clock.end_turn(seat_idx, time);
+        }
+
+        if let ActionChange::PushState(new_state) = state.skip_turn(&mut self.shared)? {
+            let old_state = std::mem::replace(&mut self.state, new_state);
+            self.state_stack.push(old_state);
+        }
+        self.check_max_moves();
+
+        self.actions
+            .push(GameAction::new(0, ReplayActionKind::Skip));
+
+        Ok(())
+    }
+
+    /// Holds play for a seated player who lost connection. The clock stops and moves are
+    /// rejected until someone resumes, or the game is claimed once `RECONNECT_GRACE`
+    /// has passed. Only during play, and only one player at a time. Leaving again before
+    /// play resumed doesn't extend the grace window.
+    pub fn pause_for(&mut self, player_id: u64, time: Millisecond) -> Result<(), MakeActionError> {
+        if !self.holds_seat(player_id) {
+            return Err(MakeActionError::NotPlayer);
+        }
+        if let Some(pause) = &mut self.pause {
+            if pause.player != player_id {
+                return Err(MakeActionError::Illegal);
+            }
+            pause.returned = false;
+            return Ok(());
+        }
+        if !matches!(self.state, GameState::Play(_)) {
+            return Err(MakeActionError::Illegal);
+        }
+
+        let turn = self.shared.turn;
+        let resume_clock = match &mut self.shared.clock {
+            Some(clock) if !clock.paused => {
+                clock.advance_clock(turn, time);
+                clock.pause(true);
+                true
+            }
+            _ => false,
+        };
+        self.pause = Some(Pause {
+            player: player_id,
+            grace_ends: Millisecond(time.0 + RECONNECT_GRACE.0),
+            returned: false,
+            resume_clock,
+        });
+        Ok(())
+    }
+
+    /// Marks the paused for player as connected again. Play still waits for `resume`.
+    pub fn player_returned(&mut self, player_id: u64) {
+        if let Some(pause) = &mut self.pause {
+            if pause.player == player_id {
+                pause.returned = true;
+            }
+        }
+    }
+
+    /// Ends the pause. Anyone playing may resume once the player is back or the grace
+    /// window is over, the returning player right away.
+    pub fn resume(&mut self, player_id: u64, time: Millisecond) -> Result<(), MakeActionError> {
+        if !self.can_play(player_id) {
+            return Err(MakeActionError::NotPlayer);
+        }
+        let pause = match &self.pause {
+            Some(pause) => pause,
+            None => return Err(MakeActionError::Illegal),
+        };
+        let allowed = pause.returned || pause.player == player_id || time >= pause.grace_ends;
+        if !allowed {
+            return Err(MakeActionError::Paused);
+        }
+
+        let resume_clock = pause.resume_clock;
+        self.pause = None;
+        if let Some(clock) = &mut self.shared.clock {
+            if resume_clock {
+                clock.initialize_clocks(time);
+                clock.pause(false);
+            }
+        }
+        Ok(())
+    }
+
+    /// Resigns every seat of the paused for player once they've been gone for longer
+    /// than `RECONNECT_GRACE`. Claimed by one of the other seat holders.
+    pub fn claim(&mut self, player_id: u64, time: Millisecond) -> Result<(), MakeActionError> {
+        let pause = match &self.pause {
+            Some(pause) => pause,
+            None => return Err(MakeActionError::Illegal),
+        };
+        if pause.player == player_id || !self.holds_seat(player_id) {
+            return Err(MakeActionError::NotPlayer);
+        }
+        if !pause.claimable(time) {
+            return Err(MakeActionError::Paused);
+        }
+
+        let absent = pause.player;
+        let resume_clock = pause.resume_clock;
+        self.pause = None;
+        self.forfeit(absent)?;
+        if let Some(clock) = &mut self.shared.clock {
+            if resume_clock && matches!(self.state, GameState::Play(_)) {
+                clock.initialize_clocks(time);
+                clock.pause(false);
+            }
+        }
+        Ok(())
+    }
+
+    fn forfeit(&mut self, player_id: u64) -> Result<(), MakeActionError> {
+        let state = match &mut self.state {
+            GameState::Play(state) => state,
+            _ => return Err(MakeActionError::Illegal),
+        };
+        if let ActionChange::PushState(new_state) = state.forfeit(&mut self.shared, player_id)? {
+            let old_state = std::mem::replace(&mut self.state, new_state);
+            self.state_stack.push(old_state);
+        }
+        self.actions
+            .push(GameAction::new(player_id, ReplayActionKind::Forfeit));
+        Ok(())
+    }
+
+    /// Ends a final round of scoring the players didn't agree on in time, counted with
+    /// the estimated dead stones, see `ScoringState::settle`. The server calls this once
+    /// the scoring timeout has passed.
+    pub fn settle_scoring(&mut self) -> Result<(), MakeActionError> {
+        let state = match &mut self.state {
+            GameState::Scoring(state) if state.final_round => state,
+            _ => return Err(MakeActionError::Illegal),
+        };
+        state.settle(&self.shared);
+        self.state = GameState::Done(state.clone());
+        self.actions
+            .push(GameAction::new(0, ReplayActionKind::Settle));
+        Ok(())
+    }
+
+    pub fn max_moves(&self) -> u32 {
+        self.shared.mods.max_moves.unwrap_or(DEFAULT_MAX_MOVES)
+    }
+
+    /// Moves on to a final round of scoring once the move cap is reached. Like the round
+    /// after the last resume, it ends by agreement or through `settle_scoring`.
+    fn check_max_moves(&mut self) {
+        let state = match &self.state {
+            GameState::Play(state) => state,
+            _ => return,
+        };
+        if self.shared.board_history.len() as u32 - 1 < self.max_moves() {
+            return;
+        }
+
+        let shared = &self.shared;
+        let mut scoring = ScoringState::new(
+            &shared.board,
+            &shared.seats,
+            &shared.points,
+            shared.mods.seki,
+        );
+        scoring.pass_stones = state.pass_stones.clone();
+        scoring.final_round = true;
+        let old_state = std::mem::replace(&mut self.state, GameState::Scoring(scoring));
+        self.state_stack.push(old_state);
+    }
+
+    /// Places and removes stones during the setup phase, see `SetupState::setup_stones`.
+    pub fn setup_stones(
+        &mut self,
+        player_id: u64,
+        stones: Vec<(u32, u32, u8)>,
+    ) -> Result<(), MakeActionError> {
+        if !self.can_play(player_id) {
+            return Err(MakeActionError::NotPlayer);
+        }
+
+        let state = match &mut self.state {
+            GameState::Setup(state) => state,
+            _ => return Err(MakeActionError::Illegal),
+        };
+        state.setup_stones(&mut self.shared, &stones)?;
+
+        self.actions.push(GameAction::new(
+            player_id,
+            ReplayActionKind::SetupStones(stones),
+        ));
+
+        Ok(())
+    }
+
     fn get_board_view(
         &self,
         player_id: u64,
@@ -775,16 +2089,85 @@ impl Game {
                     (board, None, 0)
                 }
             }
-            GameState::Scoring(_) | GameState::Done(_) => (board.points.clone(), None, 0),
+            GameState::Scoring(_) | GameState::Done(_) | GameState::Setup(_) => {
+                (board.points.clone(), None, 0)
+            }
         };
 
         (board, board_visibility, hidden_stones_left)
     }
 
+    /// Heuristic endgame detection. True if no stone the player on turn can place on a
+    /// neutral point improves their area score estimate against the best opponent.
+    /// Invasions, ko and hidden stones are not considered.
+    pub fn pass_suggested(&self) -> bool {
+        let shared = &self.shared;
+        if !matches!(self.state, GameState::Play(_))
+            || shared.mods.pixel
+            || shared.board_visibility.is_some()
+        {
+            return false;
+        }
+
+        let team = shared.get_active_seat().team;
+        let team_count = shared.komis.len();
+        let margin = |scores: &[i32]| {
+            let best_opponent = scores
+                .iter()
+                .enumerate()
+                .filter(|(idx, _)| idx + 1 != team.as_usize())
+                .map(|(_, &s)| s)
+                .max()
+                .unwrap_or(0);
+            scores[team.as_usize() - 1] - best_opponent
+        };
+        let current = margin(&estimate_score(&shared.board, team_count));
+        let territory = estimate_territory(&shared.board);
+
+        for (idx, (color, owner)) in shared
+            .board
+            .points
+            .iter()
+            .zip(&territory.points)
+            .enumerate()
+        {
+            if !color.is_empty() || !owner.is_empty() {
+                continue;
+            }
+
+            let mut board = shared.board.clone();
+            *board.point_mut(board.idx_to_coord(idx).unwrap()) = team;
+
+            for group in find_groups(&board) {
+                if group.liberties == 0 && group.team != team {
+                    for &point in &group.points {
+                        *board.point_mut(point) = Color::empty();
+                    }
+                }
+            }
+
+            let suicide = find_groups(&board)
+                .iter()
+                .any(|g| g.liberties == 0 && g.team == team);
+            if suicide {
+                continue;
+            }
+
+            if margin(&estimate_score(&board, team_count)) > current {
+                return false;
+            }
+        }
+
+        true
+    }
+
     pub fn get_view(&self, player_id: u64) -> GameView {
         let shared = &self.shared;
+        // Views are made right as rooms are created, don't assume anything about the seats.
+        let active_player = shared.seats.get(shared.turn).and_then(|s| s.player);
         let game_done = matches!(self.state, GameState::Done(_));
         let game_active = matches!(self.state, GameState::Play(_));
+        let problem_solved = self.problem_solved();
         let (board, board_visibility, hidden_stones_left) = self.get_board_view(
             player_id,
             &self.state,
@@ -816,7 +2199,386 @@ impl Game {
             } else {
                 None
             },
+            waiting_for_player: game_active && !problem_solved && active_player.is_none(),
+            players_missing: self.players_missing(),
+            pass_suggested: shared.mods.pass_hint
+                && active_player == Some(player_id)
+                && self.pass_suggested(),
+            problem_solved,
+            repetition: game_active && shared.position_count() > 1,
+            result: self.result(),
+            scoring_timeout: None,
+            time_remaining: shared.clock.as_ref().map_or_else(Vec::new, |clock| {
+                clock.clocks.iter().map(|c| c.time_left()).collect()
+            }),
+            clock_running_for_seat: match &shared.clock {
+                Some(clock) if game_active && !clock.paused => Some(shared.turn as u32),
+                _ => None,
+            },
+            ko_points: self.ko_points(),
+            prisoners: self.prisoners(),
+            capture_bonuses: self
+                .latest_play_state()
+                .map_or_else(GroupVec::new, |s| s.capture_bonuses.clone()),
+            dame: match &self.state {
+                GameState::Scoring(state) => state.dame(),
+                _ => Vec::new(),
+            },
+            restricted_points: match (&self.state, &shared.mods.opening_restrictions) {
+                (GameState::Play(_), Some(rules)) => {
+                    let move_number = shared.board_history.len() as u32 - 1;
+                    rules.forbidden_points(&shared.board, move_number)
+                }
+                _ => Vec::new(),
+            },
+            swap_available: match &self.state {
+                GameState::Play(state) => state.swap_available(shared),
+                _ => false,
+            },
+            max_moves: self.max_moves(),
+            komis: shared.komis.clone(),
+            skips: self
+                .latest_play_state()
+                .map_or_else(Vec::new, |s| s.skips.clone()),
+            pass_streak: match &self.state {
+                GameState::Play(state) => state.pass_streak,
+                _ => 0,
+            },
+            pause: self.pause.clone(),
+            places: self.places(),
+            consents: self.consent_requests(),
+        }
+    }
+
+    /// Applies the teacher's `action` to the annotations of the room. Only the teacher
+    /// of a teaching game can draw, in any state of the game.
+    pub fn annotate(
+        &self,
+        annotations: &mut Vec<Annotation>,
+        player_id: u64,
+        action: AnnotationAction,
+    ) -> Result<(), MakeActionError> {
+        match &self.shared.mods.teaching {
+            Some(teaching) if teaching.teacher == player_id => {}
+            _ => return Err(MakeActionError::NotPlayer),
+        }
+
+        match action {
+            AnnotationAction::Add(annotation) => {
+                if let Some(idx) = annotations.iter().position(|a| a == &annotation) {
+                    annotations.remove(idx);
+                    return Ok(());
+                }
+                if annotation
+                    .points()
+                    .iter()
+                    .any(|&p| !self.shared.board.point_within(p))
+                {
+                    return Err(MakeActionError::OutOfBounds);
+                }
+                match annotation {
+                    Annotation::Letter(_, c) if !c.is_ascii_alphanumeric() => {
+                        return Err(MakeActionError::Illegal);
+                    }
+                    Annotation::Line(a, b) | Annotation::Arrow(a, b) if a == b => {
+                        return Err(MakeActionError::Illegal);
+                    }
+                    _ => {}
+                }
+                if annotations.len() >= MAX_ANNOTATIONS {
+                    return Err(MakeActionError::Illegal);
+                }
+                annotations.push(annotation);
+            }
+            AnnotationAction::Clear => annotations.clear(),
+        }
+
+        Ok(())
+    }
+
+    /// A review starting from the final position.
+    pub fn start_review(&self) -> Review {
+        Review {
+            move_number: self.shared.board_history.len() as u32 - 1,
+            variation: Vec::new(),
+        }
+    }
+
+    /// Applies `action` to `review`. The review is left untouched on errors.
+    pub fn make_review_action(
+        &self,
+        review: &mut Review,
+        action: ReviewAction,
+    ) -> Result<(), MakeActionError> {
+        if !matches!(self.state, GameState::Done(_)) {
+            return Err(MakeActionError::NotDone);
+        }
+
+        match action {
+            ReviewAction::Navigate(move_number) => {
+                if move_number as usize >= self.shared.board_history.len() {
+                    return Err(MakeActionError::OutOfBounds);
+                }
+                review.move_number = move_number;
+                review.variation.clear();
+            }
+            ReviewAction::Place(x, y) => {
+                let mut board = self.review_board(review)?;
+                let team = self.review_team(review, review.variation.len());
+                place_review_stone(&mut board, (x, y), team)?;
+                review.variation.push((x, y));
+            }
+            ReviewAction::Undo => {
+                review.variation.pop();
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get_review_view(&self, review: &Review) -> Option<ReviewView> {
+        let board = self.review_board(review).ok()?;
+        Some(ReviewView {
+            move_number: review.move_number,
+            variation: review.variation.clone(),
+            board: board.points.iter().map(|x| x.0).collect(),
+            size: (board.width as u8, board.height as u8),
+        })
+    }
+
+    /// The game position at `review.move_number` with the variation played on top.
+    /// Everything is visible, hidden stones included, since the game is over.
+    fn review_board(&self, review: &Review) -> Result<Board, MakeActionError> {
+        let history = self
+            .shared
+            .board_history
+            .get(review.move_number as usize)
+            .ok_or(MakeActionError::OutOfBounds)?;
+        let mut board = history.board.clone();
+        for (idx, &point) in review.variation.iter().enumerate() {
+            place_review_stone(&mut board, point, self.review_team(review, idx))?;
+        }
+        Ok(board)
+    }
+
+    /// Team of the `idx`th variation stone. Continues from the seat on turn at that move.
+    fn review_team(&self, review: &Review, idx: usize) -> Color {
+        let seats = &self.shared.seats;
+        let turn = self.shared.board_history[review.move_number as usize].turn;
+        seats[(turn + idx) % seats.len()].team
+    }
+
+    /// Points where the seat could place a stone if it was on turn, following occupancy,
+    /// suicide, ko and the variant rules. Problem solutions aren't considered.
+    /// Empty outside of play.
+    pub fn legal_moves(&self, seat: usize) -> Vec<Point> {
+        let state = match &self.state {
+            GameState::Play(state) => state,
+            _ => return Vec::new(),
+        };
+        if seat >= self.shared.seats.len() {
+            return Vec::new();
+        }
+
+        let mut shared = self.shared.clone();
+        shared.turn = seat;
+
+        // Pixel go coordinates are offset by one, see `PlayState::place_stone`.
+        let (width, height) = (shared.board.width, shared.board.height);
+        let (width, height) = if shared.mods.pixel {
+            (width + 1, height + 1)
+        } else {
+            (width, height)
+        };
+
+        let mut moves = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                if state.is_legal(&mut shared, (x, y)) {
+                    moves.push((x, y));
+                }
+            }
+        }
+        moves
+    }
+
+    /// Rough score of each team on the board the player sees, counting every group as alive.
+    /// Komi and captures are included. Anyone in the room can ask, the game isn't changed.
+    /// Empty outside of play.
+    pub fn score_estimate(&self, player_id: u64) -> GroupVec<i32> {
+        let shared = &self.shared;
+        if !matches!(self.state, GameState::Play(_)) {
+            return GroupVec::new();
+        }
+
+        let (points, _, _) = self.get_board_view(
+            player_id,
+            &self.state,
+            &shared.board,
+            &shared.board_visibility,
+            false,
+        );
+        let board = Board {
+            width: shared.board.width,
+            height: shared.board.height,
+            toroidal: shared.board.toroidal,
+            points,
+        };
+        estimate_score(&board, shared.points.len())
+            .iter()
+            .zip(shared.points.iter())
+            .map(|(area, points)| area + points)
+            .collect()
+    }
+
+    /// Every move of the problem's solution has been played. False without a problem.
+    pub fn problem_solved(&self) -> bool {
+        match &self.shared.mods.problem {
+            Some(problem) => problem.is_solved(self.shared.board_history.len() - 1),
+            None => false,
+        }
+    }
+
+    /// How the game ended, `None` while it's still going.
+    pub fn result(&self) -> Option<GameResult> {
+        match &self.state {
+            GameState::Done(state) if state.no_result => Some(GameResult::NoResult),
+            GameState::Done(ScoringState {
+                capture_winner: Some(team),
+                ..
+            }) => Some(GameResult::CaptureGoal(*team)),
+            GameState::Done(_) if self.ended_by_claim() => {
+                let winner = self.shared.seats.iter().find(|s| !s.resigned);
+                Some(winner.map_or(GameResult::Finished, |s| GameResult::Abandoned(s.team)))
+            }
+            GameState::Done(state) => match self.tiebreak_winner(state) {
+                Some(team) => Some(GameResult::Tiebreak(team)),
+                None => Some(GameResult::Finished),
+            },
+            _ => None,
+        }
+    }
+
+    /// True if a claim ended the game. Seats taken or left after the game don't count.
+    fn ended_by_claim(&self) -> bool {
+        let last = self.actions.iter().rev().find(|a| {
+            !matches!(
+                a.action,
+                ReplayActionKind::TakeSeat(_) | ReplayActionKind::LeaveSeat(_)
+            )
+        });
+        matches!(last.map(|a| &a.action), Some(ReplayActionKind::Forfeit))
+    }
+
+    /// The team the tiebreak rules pick out of those tied for the top score. `None`
+    /// without a tie, after a resignation or if the tiebreaks tie as well.
+    fn tiebreak_winner(&self, state: &ScoringState) -> Option<Color> {
+        let mods = &self.shared.mods;
+        if mods.capture_tiebreak.is_none() && !mods.seat_order_tiebreak {
+            return None;
+        }
+        if self.shared.seats.iter().any(|s| s.resigned) {
+            return None;
+        }
+        let best = *state.scores.iter().max()?;
+        let tied = state.scores.iter().filter(|&&score| score == best).count();
+        if tied < 2 {
+            return None;
+        }
+
+        let places = self.places_in(state);
+        let mut winners = (0..places.len()).filter(|&idx| places[idx] == 1);
+        match (winners.next(), winners.next()) {
+            (Some(idx), None) => Some(Color(idx as u8 + 1)),
+            _ => None,
+        }
+    }
+
+    /// Final place of each team, indexed by team. 1 is the winner and tied teams share a
+    /// place. Empty until the game is done, and for voided games.
+    pub fn places(&self) -> GroupVec<u32> {
+        match &self.state {
+            GameState::Done(state) if !state.no_result => self.places_in(state),
+            _ => GroupVec::new(),
+        }
+    }
+
+    /// Ranks the teams of a finished game: the capture goal winner first, then teams with
+    /// a seat left that didn't resign, then by score, `GameModifier::capture_tiebreak` and
+    /// `GameModifier::seat_order_tiebreak`.
+    fn places_in(&self, state: &ScoringState) -> GroupVec<u32> {
+        let mods = &self.shared.mods;
+        let seats = &self.shared.seats;
+        let prisoners = self.prisoners();
+        let key = |idx: usize| {
+            let team = Color(idx as u8 + 1);
+            let goal = state.capture_winner == Some(team);
+            let standing = seats.iter().any(|s| s.team == team && !s.resigned);
+            let captures = match mods.capture_tiebreak {
+                Some(CaptureTiebreak::MostCaptures) => prisoners[idx] as i64,
+                Some(CaptureTiebreak::FewestCaptures) => -(prisoners[idx] as i64),
+                None => 0,
+            };
+            let order = match mods.seat_order_tiebreak {
+                true => seats
+                    .iter()
+                    .position(|s| s.team == team)
+                    .map_or(-1, |p| p as i64),
+                false => 0,
+            };
+            (goal, standing, state.scores[idx], captures, order)
+        };
+
+        let keys = (0..state.scores.len()).map(key).collect::<Vec<_>>();
+        keys.iter()
+            .map(|own| 1 + keys.iter().filter(|other| *other > own).count() as u32)
+            .collect()
+    }
+
+    /// The current play state or the one a scoring or finished game was played out in.
+    fn latest_play_state(&self) -> Option<&PlayState> {
+        std::iter::once(&self.state)
+            .chain(self.state_stack.iter().rev())
+            .find_map(|state| match state {
+                GameState::Play(state) => Some(state),
+                _ => None,
+            })
+    }
+
+    /// Stones captured by each team.
+    fn prisoners(&self) -> GroupVec<u32> {
+        let play = self.latest_play_state();
+        let mut prisoners = play.map_or_else(GroupVec::new, |s| s.prisoners.clone());
+        prisoners.resize(self.shared.komis.len(), 0);
+        prisoners
+    }
+
+    /// Empty points the seat on turn can't play into because of ko. Only computed for
+    /// teaching games, where the teacher can try the move on the seat's behalf.
+    fn ko_points(&self) -> Vec<Point> {
+        let play = match (&self.state, &self.shared.mods.teaching) {
+            (GameState::Play(play), Some(_)) if !self.shared.mods.pixel => play,
+            _ => return Vec::new(),
+        };
+
+        // Only a capturing move can repeat a position, so the last liberties of groups
+        // in atari are the only candidates.
+        let board = &self.shared.board;
+        let mut candidates = Vec::new();
+        for group in find_groups(board).iter().filter(|g| g.liberties == 1) {
+            for &point in &group.points {
+                for p in board.surrounding_points(point) {
+                    if board.get_point(p).is_empty() && !candidates.contains(&p) {
+                        candidates.push(p);
+                    }
+                }
+            }
         }
+
+        candidates
+            .into_iter()
+            .filter(|&point| play.repeats_position(&self.shared, point))
+            .collect()
     }
 
     pub fn get_view_at(&self, player_id: u64, turn: u32) -> Option<GameHistory> {
@@ -834,18 +2596,52 @@ impl Game {
             return None;
         }
 
+        let size = (board.width as u8, board.height as u8);
         let (board, board_visibility, _hidden_stones_left) =
             self.get_board_view(player_id, state, board, board_visibility, game_done);
 
         Some(GameHistory {
+            size,
             board: board.iter().map(|x| x.0).collect(),
             board_visibility: board_visibility.map(|b| b.iter().map(|x| x.into_value()).collect()),
             last_stone: state.assume::<PlayState>().last_stone.clone(),
+            captured: state.assume::<PlayState>().last_captured.clone(),
             move_number: turn,
         })
     }
 }
 
+/// Plays a stone with captures but without any ko or variant rules.
+fn place_review_stone(board: &mut Board, point: Point, team: Color) -> Result<(), MakeActionError> {
+    if !board.point_within(point) {
+        return Err(MakeActionError::OutOfBounds);
+    }
+    if !board.get_point(point).is_empty() {
+        return Err(MakeActionError::PointOccupied);
+    }
+
+    *board.point_mut(point) = team;
+
+    let groups = find_groups(board);
+    let mut captured = false;
+    for group in groups.iter().filter(|g| g.team != team && g.liberties == 0) {
+        for &p in &group.points {
+            *board.point_mut(p) = Color::empty();
+        }
+        captured = true;
+    }
+
+    let suicide = groups
+        .iter()
+        .any(|g| g.liberties == 0 && g.points.contains(&point));
+    if !captured && suicide {
+        *board.point_mut(point) = Color::empty();
+        return Err(MakeActionError::Suicide);
+    }
+
+    Ok(())
+}
+
 pub fn find_groups(board: &Board) -> Vec<Group> {
     let mut legal_points = board
         .points
@@ -865,9 +2661,11 @@ pub fn find_groups(board: &Board) -> Vec<Group> {
     let mut groups = Vec::new();
 
     while let Some(point) = legal_points.pop() {
-        let mut group = Group::default();
-        group.alive = true;
-        group.team = board.get_point(point);
+        let mut group = Group {
+            alive: true,
+            team: board.get_point(point),
+            ..Group::default()
+        };
         if group.team.is_empty() {
             unreachable!("scanned an empty point");
         }
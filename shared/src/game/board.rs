@@ -25,6 +25,18 @@ impl<T: Copy + Default> Board<T> {
         }
     }
 
+    /// A `width` x `height` copy with the current points shifted by `offset`.
+    /// The new size must fit the current board at `offset`.
+    pub fn expanded(&self, (width, height): (u32, u32), (dx, dy): Point) -> Self {
+        let mut board = Board::empty(width, height, self.toroidal);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                *board.point_mut((x + dx, y + dy)) = self.get_point((x, y));
+            }
+        }
+        board
+    }
+
     pub fn point_within(&self, (x, y): Point) -> bool {
         (0..self.width).contains(&x) && (0..self.height).contains(&y)
     }
@@ -80,6 +92,117 @@ impl<T: Hash> Board<T> {
     }
 }
 
+/// Column letters used for board coordinates. `I` is skipped as is traditional.
+pub fn column_label(x: u32) -> Option<char> {
+    ('A'..'I').chain('J'..='Z').nth(x as usize)
+}
+
+/// Coordinate of a point as shown next to the board, like `C3`.
+pub fn point_label((x, y): Point, height: u32) -> String {
+    format!("{}{}", column_label(x).unwrap_or('?'), height - y)
+}
+
+/// How points are labeled around the board and read from typed coordinates. SGF and GTP
+/// always use letters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LabelStyle {
+    /// Column letters and row numbers, like `D4`. See `point_label`.
+    #[default]
+    Letters,
+    /// Numbers on both axes, column first, like `4-4`. Rows still count from the bottom.
+    Numbers,
+}
+
+impl LabelStyle {
+    pub fn column(self, x: u32) -> String {
+        match self {
+            LabelStyle::Letters => column_label(x).unwrap_or('?').to_string(),
+            LabelStyle::Numbers => (x + 1).to_string(),
+        }
+    }
+
+    pub fn point(self, (x, y): Point, height: u32) -> String {
+        match self {
+            LabelStyle::Letters => point_label((x, y), height),
+            LabelStyle::Numbers => format!("{}-{}", x + 1, height - y),
+        }
+    }
+
+    /// Reads a coordinate written in this style. Numbers may also be separated by a comma
+    /// or a space, like "4,4".
+    pub fn parse(self, text: &str, (width, height): (u32, u32)) -> Option<Point> {
+        if self == LabelStyle::Letters {
+            return parse_coordinate(text, (width, height));
+        }
+        let separator = |c: char| c == '-' || c == ',' || c.is_whitespace();
+        let (column, row) = text.trim().split_once(separator)?;
+        let column: u32 = column.trim().parse().ok()?;
+        let row: u32 = row.trim().parse().ok()?;
+        if column == 0 || column > width || row == 0 || row > height {
+            return None;
+        }
+        Some((column - 1, height - row))
+    }
+}
+
+/// Turns a point of a square board of `size` a quarter turn clockwise, `quarters` times.
+pub fn rotate_point((x, y): Point, size: u32, quarters: u8) -> Point {
+    let mut point = (x, y);
+    for _ in 0..quarters % 4 {
+        point = (size - 1 - point.1, point.0);
+    }
+    point
+}
+
+/// Undoes `rotate_point`, eg. to find the point under a click on a rotated board.
+pub fn unrotate_point(point: Point, size: u32, quarters: u8) -> Point {
+    rotate_point(point, size, (4 - quarters % 4) % 4)
+}
+
+/// Star points for `stones` handicap stones in the traditional order, starting from the
+/// upper right corner. 5 or more stones use the middle lines, so those need odd sizes.
+pub fn handicap_points(width: u32, height: u32, stones: u32) -> Option<Vec<Point>> {
+    if width < 7 || height < 7 || stones > 9 {
+        return None;
+    }
+    if stones >= 5 && (width.is_multiple_of(2) || height.is_multiple_of(2)) {
+        return None;
+    }
+
+    // The 4-4 points, or the 3-3 points on small boards.
+    let line = |size: u32| if size >= 13 { 3 } else { 2 };
+    let (left, right) = (line(width), width - 1 - line(width));
+    let (top, bottom) = (line(height), height - 1 - line(height));
+    let (mid_x, mid_y) = (width / 2, height / 2);
+
+    let corners = [(right, top), (left, bottom), (right, bottom), (left, top)];
+    let mut points = corners[..stones.min(4) as usize].to_vec();
+    if stones >= 6 {
+        points.extend_from_slice(&[(left, mid_y), (right, mid_y)]);
+    }
+    if stones >= 8 {
+        points.extend_from_slice(&[(mid_x, top), (mid_x, bottom)]);
+    }
+    if stones % 2 == 1 && stones >= 5 {
+        points.push((mid_x, mid_y));
+    }
+    Some(points)
+}
+
+/// Parses a coordinate like "D4" or "q16" into a point on a board of the given size.
+/// Rows are counted from the bottom edge like the labels drawn around the board.
+pub fn parse_coordinate(text: &str, (width, height): (u32, u32)) -> Option<Point> {
+    let text = text.trim();
+    let mut chars = text.chars();
+    let column = chars.next()?.to_ascii_uppercase();
+    let x = (0..width).find(|&x| column_label(x) == Some(column))?;
+    let row: u32 = chars.as_str().parse().ok()?;
+    if row == 0 || row > height {
+        return None;
+    }
+    Some((x, height - row))
+}
+
 fn wrap_point(x: i32, y: i32, width: i32, height: i32, toroidal: bool) -> Option<Point> {
     if x >= 0 && x < width && y >= 0 && y < height {
         Some((x as u32, y as u32))
@@ -103,3 +226,57 @@ fn wrap_point(x: i32, y: i32, width: i32, height: i32, toroidal: bool) -> Option
         None
     }
 }
+
+/// Indices of every stone in `before` that is gone in `after`, like all groups
+/// captured by a single move. The removed stones don't need to be connected.
+pub fn removed_stones(before: &[Color], after: &[Color]) -> Vec<usize> {
+    before
+        .iter()
+        .zip(after)
+        .enumerate()
+        .filter(|(_, (before, after))| !before.is_empty() && after.is_empty())
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Downsampled board for previews in the room list.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoardThumbnail {
+    pub width: u8,
+    pub height: u8,
+    /// Team with the most stones in the area each cell covers, 0 for empty.
+    pub points: Vec<u8>,
+}
+
+/// Shrinks a board to at most `max_size` cells per side.
+pub fn board_thumbnail(
+    points: &[Color],
+    (width, height): (u32, u32),
+    max_size: u32,
+) -> BoardThumbnail {
+    let thumb_width = width.min(max_size);
+    let thumb_height = height.min(max_size);
+    let mut counts = vec![[0u32; 5]; (thumb_width * thumb_height) as usize];
+
+    for (idx, color) in points.iter().enumerate() {
+        let (x, y) = (idx as u32 % width, idx as u32 / width);
+        let cell = (y * thumb_height / height) * thumb_width + x * thumb_width / width;
+        counts[cell as usize][color.0 as usize] += 1;
+    }
+
+    let points = counts
+        .iter()
+        .map(|count| {
+            (1..count.len())
+                .filter(|&team| count[team] > 0)
+                .max_by_key(|&team| (count[team], std::cmp::Reverse(team)))
+                .unwrap_or(0) as u8
+        })
+        .collect();
+
+    BoardThumbnail {
+        width: thumb_width as u8,
+        height: thumb_height as u8,
+        points,
+    }
+}
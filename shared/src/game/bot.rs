@@ -0,0 +1,225 @@
+//! Built-in opponents for practice. None of them read further than one move ahead, they
+//! are meant as graduated sparring partners, not as strong players.
+
+use rand::prelude::*;
+use rand_pcg::Lcg64Xsh32;
+use serde::{Deserialize, Serialize};
+
+use super::{find_groups, ActionKind, Game, GameState, Point, SharedState};
+use crate::states::scoring::influence_map;
+use crate::states::PlayState;
+
+pub trait GoBot {
+    /// Move for `seat` in the current position. Passes when the seat has no legal move
+    /// or the game isn't in play.
+    fn choose_move(&mut self, game: &Game, seat: usize) -> ActionKind;
+}
+
+/// The built-in bots, weakest first.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BotLevel {
+    /// Any legal move, see `RandomBot`.
+    Random,
+    /// Captures and ataris, see `GreedyBot`.
+    Greedy,
+    /// Claims area, see `InfluenceBot`.
+    Influence,
+}
+
+impl BotLevel {
+    pub fn bot(self, seed: u64) -> Box<dyn GoBot> {
+        match self {
+            BotLevel::Random => Box::new(RandomBot::new(seed)),
+            BotLevel::Greedy => Box::new(GreedyBot::new(seed)),
+            BotLevel::Influence => Box::new(InfluenceBot::new(seed)),
+        }
+    }
+}
+
+/// Plays a legal move picked at random.
+pub struct RandomBot {
+    rng: Lcg64Xsh32,
+}
+
+impl RandomBot {
+    pub fn new(seed: u64) -> Self {
+        RandomBot {
+            rng: Lcg64Xsh32::seed_from_u64(seed),
+        }
+    }
+}
+
+impl GoBot for RandomBot {
+    fn choose_move(&mut self, game: &Game, seat: usize) -> ActionKind {
+        match game.legal_moves(seat).choose(&mut self.rng) {
+            Some(&(x, y)) => ActionKind::Place(x, y),
+            None => ActionKind::Pass,
+        }
+    }
+}
+
+/// Takes the biggest capture it sees, otherwise puts opponent groups in atari and keeps its
+/// own groups out of it. Random among equally good moves.
+pub struct GreedyBot {
+    rng: Lcg64Xsh32,
+}
+
+impl GreedyBot {
+    pub fn new(seed: u64) -> Self {
+        GreedyBot {
+            rng: Lcg64Xsh32::seed_from_u64(seed),
+        }
+    }
+}
+
+impl GoBot for GreedyBot {
+    fn choose_move(&mut self, game: &Game, seat: usize) -> ActionKind {
+        let team = game.shared.seats[seat].team;
+        best_move(game, seat, &mut self.rng, |point, state, shared| {
+            let mut score = 100 * state.last_captured.len() as i32;
+            for group in find_groups(&shared.board) {
+                if group.liberties != 1 {
+                    continue;
+                }
+                if group.team == team {
+                    if group.points.contains(&point) {
+                        score -= 50;
+                    }
+                } else if group
+                    .points
+                    .iter()
+                    .any(|&p| shared.board.surrounding_points(p).any(|n| n == point))
+                {
+                    score += 10 * group.points.len() as i32;
+                }
+            }
+            score
+        })
+    }
+}
+
+/// Plays where its lead on the influence map grows the most, see `influence_map`.
+/// Captured stones count too, a capture frees the area around them.
+pub struct InfluenceBot {
+    rng: Lcg64Xsh32,
+}
+
+impl InfluenceBot {
+    pub fn new(seed: u64) -> Self {
+        InfluenceBot {
+            rng: Lcg64Xsh32::seed_from_u64(seed),
+        }
+    }
+}
+
+impl GoBot for InfluenceBot {
+    fn choose_move(&mut self, game: &Game, seat: usize) -> ActionKind {
+        let team = game.shared.seats[seat].team;
+        best_move(game, seat, &mut self.rng, |_, state, shared| {
+            let balance: i32 = influence_map(&shared.board)
+                .iter()
+                .map(|&(owner, strength)| {
+                    if owner == team {
+                        strength as i32
+                    } else {
+                        -(strength as i32)
+                    }
+                })
+                .sum();
+            balance + 10 * state.last_captured.len() as i32
+        })
+    }
+}
+
+/// Legal move of the seat with the highest `score`, ties broken at random. The score
+/// sees the position after the move.
+fn best_move(
+    game: &Game,
+    seat: usize,
+    rng: &mut Lcg64Xsh32,
+    score: impl Fn(Point, &PlayState, &SharedState) -> i32,
+) -> ActionKind {
+    let state = match &game.state {
+        GameState::Play(state) => state,
+        _ => return ActionKind::Pass,
+    };
+    let mut shared = game.shared.clone();
+    shared.turn = seat;
+
+    let mut best = Vec::new();
+    let mut best_score = i32::MIN;
+    for point in game.legal_moves(seat) {
+        let (state, shared) = match state.after_place(&shared, point) {
+            Some(x) => x,
+            None => continue,
+        };
+        let score = score(point, &state, &shared);
+        if score > best_score {
+            best_score = score;
+            best.clear();
+        }
+        if score == best_score {
+            best.push(point);
+        }
+    }
+
+    match best.choose(rng) {
+        Some(&(x, y)) => ActionKind::Place(x, y),
+        None => ActionKind::Pass,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::tests::seated_game;
+    use crate::game::{clock, GameModifier};
+
+    #[test]
+    fn bots_play_legal_moves() {
+        for &level in &[BotLevel::Random, BotLevel::Greedy, BotLevel::Influence] {
+            let mut game = seated_game((9, 9), GameModifier::default());
+            let mut bot = level.bot(7);
+
+            // Bots play both sides from the empty board into the middle game.
+            for _ in 0..40 {
+                let seat = game.shared.turn;
+                let player = game.shared.seats[seat].player.unwrap();
+                let action = bot.choose_move(&game, seat);
+                match action {
+                    ActionKind::Place(x, y) => assert!(
+                        game.legal_moves(seat).contains(&(x, y)),
+                        "{:?} played an illegal move",
+                        level
+                    ),
+                    ActionKind::Pass => {}
+                    _ => panic!("{:?} chose {:?}", level, action),
+                }
+                game.make_action(player, action, clock::Millisecond(0))
+                    .unwrap();
+                if !matches!(game.state, GameState::Play(_)) {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn greedy_bot_captures() {
+        let mut game = seated_game((9, 9), GameModifier::default());
+        let time = clock::Millisecond(0);
+
+        // The white stone on B9 has a single liberty left, on C9.
+        game.make_action(100, ActionKind::Place(0, 0), time)
+            .unwrap();
+        game.make_action(200, ActionKind::Place(1, 0), time)
+            .unwrap();
+        game.make_action(100, ActionKind::Place(1, 1), time)
+            .unwrap();
+        game.make_action(200, ActionKind::Place(5, 5), time)
+            .unwrap();
+
+        let action = GreedyBot::new(0).choose_move(&game, 0);
+        assert_eq!(action, ActionKind::Place(2, 0));
+    }
+}
@@ -95,6 +95,21 @@ pub enum PlayerClock {
     },
 }
 
+impl PlayerClock {
+    pub fn time_left(&self) -> Millisecond {
+        match self {
+            PlayerClock::Plain { time_left, .. } => *time_left,
+        }
+    }
+
+    /// When the clock was last started or stopped.
+    pub fn last_time(&self) -> Millisecond {
+        match self {
+            PlayerClock::Plain { last_time, .. } => *last_time,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct GameClock {
     /// One clock per team or player. This is decided by the game controller, this module doesn't care which is used.
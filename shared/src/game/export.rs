@@ -1,7 +1,18 @@
-use super::Board;
-use super::Game;
+use super::variations::VariationTree;
+use super::{column_label, parse_coordinate, point_label, GameState, Point};
+use super::{place_review_stone, Board, MakeActionError};
+use super::{ActionKind, Color, Game, GameResult, GroupVec};
+use crate::states::scoring::estimate_score;
+use serde::{Deserialize, Serialize};
 use std::fmt::Write;
 
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SgfOptions {
+    /// Comment every move with its number and the stones it captured.
+    #[serde(default)]
+    pub move_comments: bool,
+}
+
 struct SGFWriter {
     buffer: String,
 }
@@ -21,6 +32,10 @@ impl SGFWriter {
         }
     }
 
+    fn result(&mut self, text: &str) {
+        let _ = write!(&mut self.buffer, "RE[{}]", escape(text));
+    }
+
     fn set_point(&mut self, point: (u32, u32), color: u8) {
         let name = match color {
             0 => "AE",
@@ -41,32 +56,71 @@ impl SGFWriter {
         (x, y)
     }
 
+    fn play(&mut self, point: (u32, u32), color: u8) {
+        let name = if color == 1 { "B" } else { "W" };
+        let (x, y) = self.point(point);
+
+        let _ = write!(&mut self.buffer, "{}[{}{}]", name, x, y);
+    }
+
     fn label(&mut self, point: (u32, u32), text: &str) {
         let (x, y) = self.point(point);
 
-        let _ = write!(&mut self.buffer, "LB[{}{}:{}]", x, y, text);
+        let _ = write!(&mut self.buffer, "LB[{}{}:{}]", x, y, escape(text));
+    }
+
+    fn comment(&mut self, text: &str) {
+        let _ = write!(&mut self.buffer, "C[{}]", escape(text));
     }
 
     fn end_turn(&mut self) {
         let _ = write!(&mut self.buffer, ";");
     }
 
+    fn start_variation(&mut self) {
+        let _ = write!(&mut self.buffer, "(;");
+    }
+
+    fn end_variation(&mut self) {
+        let _ = write!(&mut self.buffer, ")");
+    }
+
     fn finish(mut self) -> String {
         let _ = write!(&mut self.buffer, ")");
         self.buffer
     }
 }
 
+/// Escapes the characters that would end or break an SGF text value.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, ']' | '\\' | ':') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
 /// Write a simple single-variation representation of the game.
 /// Limited to two colors so has to use markers for the other colors and hidden stones.
 pub fn sgf_export(game: &Game) -> String {
+    sgf_export_with(game, &SgfOptions::default())
+}
+
+pub fn sgf_export_with(game: &Game, options: &SgfOptions) -> String {
     let mut writer = SGFWriter::new();
     let (width, height) = (game.shared.board.width, game.shared.board.height);
     writer.size((width, height));
+    // Other results need a winner, which isn't well defined past two colors.
+    if game.result() == Some(GameResult::NoResult) {
+        writer.result("Void");
+    }
 
     let mut last = Board::empty(width, height, game.shared.board.toroidal);
 
-    for history in &game.shared.board_history {
+    for (move_number, history) in game.shared.board_history.iter().enumerate() {
         let board = &history.board;
 
         for (idx, (old, new)) in last.points.iter_mut().zip(&board.points).enumerate() {
@@ -87,6 +141,16 @@ pub fn sgf_export(game: &Game) -> String {
             }
         }
 
+        if options.move_comments && move_number > 0 {
+            let mut text = format!("Move {}", move_number);
+            if let GameState::Play(state) = &history.state {
+                if let Some(note) = capture_note(&state.last_captured, board.height) {
+                    let _ = write!(&mut text, ", {}", note);
+                }
+            }
+            writer.comment(&text);
+        }
+
         writer.end_turn();
 
         // TODO: PUZZLE markers for hidden stones
@@ -94,3 +158,688 @@ pub fn sgf_export(game: &Game) -> String {
 
     writer.finish()
 }
+
+/// Write a game only known by its positions, one per move, like a client that watched
+/// it sees it. Every position is set up as the stones that changed since the previous
+/// one, colors past white are mapped to black and white like in `sgf_export`.
+pub fn positions_sgf(size: (u32, u32), positions: &[Vec<Color>]) -> String {
+    let mut writer = SGFWriter::new();
+    writer.size(size);
+
+    let mut last = vec![Color(0); (size.0 * size.1) as usize];
+    for position in positions {
+        for (idx, (old, new)) in last.iter_mut().zip(position).enumerate() {
+            if *old != *new {
+                let color = if new.0 == 0 { 0 } else { (new.0 - 1) % 2 + 1 };
+                let point = (idx as u32 % size.0, idx as u32 / size.0);
+                writer.set_point(point, color);
+                *old = *new;
+            }
+        }
+        writer.end_turn();
+    }
+
+    writer.finish()
+}
+
+/// Write a variation tree with every branch. The root position is set up with stones,
+/// colors past white are mapped to black and white like in `sgf_export`.
+pub fn variation_sgf(tree: &VariationTree) -> String {
+    let mut writer = SGFWriter::new();
+    let board = &tree.board;
+    writer.size((board.width, board.height));
+
+    for (idx, color) in board.points.iter().enumerate() {
+        if !color.is_empty() {
+            let color = (color.0 - 1) % 2 + 1;
+            writer.set_point(board.idx_to_coord(idx).unwrap(), color);
+        }
+    }
+
+    write_variation(&mut writer, tree, VariationTree::ROOT);
+
+    writer.finish()
+}
+
+/// Writes `node` and the main line after it, with the other children as variations.
+fn write_variation(writer: &mut SGFWriter, tree: &VariationTree, mut node: usize) {
+    loop {
+        let current = &tree.nodes[node];
+        if let Some((point, team)) = current.stone {
+            writer.play(point, (team.0 - 1) % 2 + 1);
+        }
+        if !current.comment.is_empty() {
+            writer.comment(&current.comment);
+        }
+
+        match current.children.as_slice() {
+            [] => return,
+            [next] => {
+                writer.end_turn();
+                node = *next;
+            }
+            children => {
+                for &child in children {
+                    writer.start_variation();
+                    write_variation(writer, tree, child);
+                    writer.end_variation();
+                }
+                return;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SgfError {
+    /// Not an SGF game tree, or a bracket or parenthesis isn't closed.
+    Malformed,
+    /// `SZ` isn't a board size SGF points can reach.
+    BadSize,
+    /// A point outside the board.
+    BadPoint,
+    IllegalMove(MakeActionError),
+    /// Setup stones in the middle of a line that don't amount to a single move, like the
+    /// blobs of pixel go. Variation trees only hold moves.
+    Unsupported,
+}
+
+/// Variations nested deeper than this are refused rather than read with deep recursion.
+const MAX_SGF_DEPTH: usize = 200;
+
+/// Properties of an SGF node in the order they appear, each with its values.
+type SgfNode = Vec<(String, Vec<String>)>;
+
+/// A line of nodes and the variations after its last node.
+struct SgfTree {
+    nodes: Vec<SgfNode>,
+    variations: Vec<SgfTree>,
+}
+
+struct SGFReader<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> SGFReader<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    fn tree(&mut self, depth: usize) -> Result<SgfTree, SgfError> {
+        self.skip_whitespace();
+        if depth > MAX_SGF_DEPTH || self.chars.next() != Some('(') {
+            return Err(SgfError::Malformed);
+        }
+
+        let mut tree = SgfTree {
+            nodes: Vec::new(),
+            variations: Vec::new(),
+        };
+        loop {
+            self.skip_whitespace();
+            match self.chars.peek() {
+                Some(';') if tree.variations.is_empty() => {
+                    self.chars.next();
+                    tree.nodes.push(self.node()?);
+                }
+                Some('(') if !tree.nodes.is_empty() => {
+                    tree.variations.push(self.tree(depth + 1)?);
+                }
+                Some(')') if !tree.nodes.is_empty() => {
+                    self.chars.next();
+                    return Ok(tree);
+                }
+                _ => return Err(SgfError::Malformed),
+            }
+        }
+    }
+
+    fn node(&mut self) -> Result<SgfNode, SgfError> {
+        let mut node = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let mut ident = String::new();
+            while let Some(&c) = self.chars.peek() {
+                if !c.is_ascii_alphabetic() {
+                    break;
+                }
+                // Old files write properties like `AddBlack`, only the capitals count.
+                if c.is_ascii_uppercase() {
+                    ident.push(c);
+                }
+                self.chars.next();
+            }
+            if ident.is_empty() {
+                return Ok(node);
+            }
+
+            let mut values = Vec::new();
+            loop {
+                self.skip_whitespace();
+                if self.chars.peek() != Some(&'[') {
+                    break;
+                }
+                self.chars.next();
+                values.push(self.value()?);
+            }
+            if values.is_empty() {
+                return Err(SgfError::Malformed);
+            }
+            node.push((ident, values));
+        }
+    }
+
+    fn value(&mut self) -> Result<String, SgfError> {
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some(']') => return Ok(value),
+                Some('\\') => match self.chars.next() {
+                    // An escaped line break is a soft one and left out.
+                    Some('\n') => {}
+                    Some(c) => value.push(c),
+                    None => return Err(SgfError::Malformed),
+                },
+                Some(c) => value.push(c),
+                None => return Err(SgfError::Malformed),
+            }
+        }
+    }
+}
+
+/// Board size of `SZ`, like `19` or `19:13` for other shapes.
+fn parse_sgf_size(value: &str) -> Result<(u32, u32), SgfError> {
+    let parse = |text: &str| match text.trim().parse() {
+        Ok(side) if (1..=52).contains(&side) => Ok(side),
+        _ => Err(SgfError::BadSize),
+    };
+    match value.split_once(':') {
+        Some((width, height)) => Ok((parse(width)?, parse(height)?)),
+        None => parse(value).map(|side| (side, side)),
+    }
+}
+
+/// A point as two letters, `a` to `z` and then `A` to `Z`. `None` for a pass, which is
+/// empty or `tt` on boards up to 19 lines.
+fn parse_sgf_point(value: &str, board: &Board) -> Result<Option<Point>, SgfError> {
+    if value.is_empty() || (value == "tt" && board.width <= 19 && board.height <= 19) {
+        return Ok(None);
+    }
+    let coordinate = |c: char| match c {
+        'a'..='z' => Some(c as u32 - 'a' as u32),
+        'A'..='Z' => Some(c as u32 - 'A' as u32 + 26),
+        _ => None,
+    };
+    let mut chars = value.chars();
+    let point = match (chars.next(), chars.next(), chars.next()) {
+        (Some(x), Some(y), None) => coordinate(x).zip(coordinate(y)),
+        _ => None,
+    };
+    match point {
+        Some(point) if board.point_within(point) => Ok(Some(point)),
+        _ => Err(SgfError::BadPoint),
+    }
+}
+
+/// Points of a setup value, a single point or a rectangle like `aa:cc`.
+fn parse_sgf_points(value: &str, board: &Board) -> Result<Vec<Point>, SgfError> {
+    let point = |text| parse_sgf_point(text, board)?.ok_or(SgfError::BadPoint);
+    let (from, to) = match value.split_once(':') {
+        Some((from, to)) => (point(from)?, point(to)?),
+        None => (point(value)?, point(value)?),
+    };
+    let mut points = Vec::new();
+    for y in from.1.min(to.1)..=from.1.max(to.1) {
+        for x in from.0.min(to.0)..=from.0.max(to.0) {
+            points.push((x, y));
+        }
+    }
+    Ok(points)
+}
+
+/// The move that turns `before` into `after`: one new stone and its captures.
+fn setup_as_move(before: &Board, after: &Board) -> Result<(Point, Color), SgfError> {
+    let mut added = (0..after.points.len())
+        .filter(|&idx| !after.points[idx].is_empty() && after.points[idx] != before.points[idx]);
+    let idx = match (added.next(), added.next()) {
+        (Some(idx), None) => idx,
+        _ => return Err(SgfError::Unsupported),
+    };
+    let point = after.idx_to_coord(idx).ok_or(SgfError::Unsupported)?;
+    let team = after.points[idx];
+
+    let mut played = before.clone();
+    match place_review_stone(&mut played, point, team) {
+        Ok(()) if played == *after => Ok((point, team)),
+        _ => Err(SgfError::Unsupported),
+    }
+}
+
+/// Adds the moves of `sgf` after `parent`, which shows `board`, and then its variations.
+fn read_variation(
+    tree: &mut VariationTree,
+    sgf: &SgfTree,
+    mut parent: usize,
+    mut board: Board,
+) -> Result<(), SgfError> {
+    for (idx, node) in sgf.nodes.iter().enumerate() {
+        let mut setup = board.clone();
+        let mut stone = None;
+        let mut comment = None;
+        for (ident, values) in node {
+            let color = match ident.as_str() {
+                "B" | "AB" => Color(1),
+                "W" | "AW" => Color(2),
+                _ => Color::empty(),
+            };
+            match ident.as_str() {
+                "B" | "W" => {
+                    // Passes have no node in the tree, the next move follows right on.
+                    if let Some(point) = parse_sgf_point(&values[0], &board)? {
+                        stone = Some((point, color));
+                    }
+                }
+                "AB" | "AW" | "AE" => {
+                    for value in values {
+                        for point in parse_sgf_points(value, &board)? {
+                            *setup.point_mut(point) = color;
+                        }
+                    }
+                }
+                "C" => comment = Some(values[0].clone()),
+                _ => {}
+            }
+        }
+
+        if setup != board {
+            let at_start = parent == VariationTree::ROOT
+                && tree.nodes[VariationTree::ROOT].children.is_empty();
+            let as_move = match stone {
+                Some(_) => return Err(SgfError::Unsupported),
+                // Stones in the first node are never a move, like in `variation_sgf`.
+                None if at_start && idx == 0 => Err(SgfError::Unsupported),
+                None => setup_as_move(&board, &setup),
+            };
+            match as_move {
+                Ok(played) => stone = Some(played),
+                // Setup before the first move is the starting position.
+                Err(_) if at_start => {
+                    tree.board = setup.clone();
+                    board = setup;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        if let Some((point, team)) = stone {
+            parent = tree
+                .play_as(parent, point, team)
+                .map_err(SgfError::IllegalMove)?;
+            // The tree accepted the stone, so it plays the same here.
+            let _ = place_review_stone(&mut board, point, team);
+        }
+        if let Some(comment) = comment {
+            tree.nodes[parent].comment = comment;
+        }
+    }
+
+    for variation in &sgf.variations {
+        read_variation(tree, variation, parent, board.clone())?;
+    }
+    Ok(())
+}
+
+/// Reads the first game of an SGF file into a variation tree with all its variations.
+/// Only the board, moves, setup stones and comments are read. Setup stones after the
+/// first move are read as the move they amount to, which covers the two color games
+/// written by `sgf_export` and `positions_sgf`.
+pub fn parse_sgf(text: &str) -> Result<VariationTree, SgfError> {
+    let mut reader = SGFReader {
+        chars: text.chars().peekable(),
+    };
+    let sgf = reader.tree(0)?;
+
+    let size = sgf.nodes[0]
+        .iter()
+        .find(|(ident, _)| ident == "SZ")
+        .map(|(_, values)| parse_sgf_size(&values[0]))
+        .unwrap_or(Ok((19, 19)))?;
+    let board = Board::empty(size.0, size.1, false);
+    let mut tree = VariationTree::new(board.clone(), Vec::new());
+    read_variation(&mut tree, &sgf, VariationTree::ROOT, board)?;
+
+    // Continue with whoever played first, usually black.
+    let first = tree.nodes[VariationTree::ROOT].children.first();
+    if let Some((_, Color(2))) = first.and_then(|&node| tree.nodes[node].stone) {
+        tree.teams = vec![Color(2), Color(1)];
+    }
+    Ok(tree)
+}
+
+/// Names the stones a move captured, like `2 captured (C3 D3)`. `None` without captures.
+pub fn capture_note(captured: &[Point], height: u32) -> Option<String> {
+    if captured.is_empty() {
+        return None;
+    }
+    let points = captured
+        .iter()
+        .map(|&p| point_label(p, height))
+        .collect::<Vec<_>>();
+    Some(format!(
+        "{} captured ({})",
+        captured.len(),
+        points.join(" ")
+    ))
+}
+
+/// Stone characters for text diagrams, indexed by team. Black and white follow the
+/// usual `X` and `O`, blue and red use their initials.
+const DIAGRAM_STONES: [char; 4] = ['X', 'O', 'B', 'R'];
+
+/// Draws the board as plain text for pasting into forums. Stones in `last_move` are
+/// drawn in lower case. Labels use the same coordinates as the board.
+pub fn text_diagram(board: &Board, last_move: &[Point], labels: bool) -> String {
+    let row_width = board.height.to_string().len();
+    let mut out = String::new();
+
+    let column_labels = || {
+        let mut line = " ".repeat(row_width);
+        for x in 0..board.width {
+            let _ = write!(&mut line, " {}", column_label(x).unwrap_or('?'));
+        }
+        line.push('\n');
+        line
+    };
+
+    if labels {
+        out.push_str(&column_labels());
+    }
+    for y in 0..board.height {
+        if labels {
+            let _ = write!(&mut out, "{:>width$}", board.height - y, width = row_width);
+        }
+        for x in 0..board.width {
+            let color = board.get_point((x, y));
+            let c = if color.is_empty() {
+                '.'
+            } else {
+                let c = DIAGRAM_STONES
+                    .get(color.0 as usize - 1)
+                    .copied()
+                    .unwrap_or('?');
+                if last_move.contains(&(x, y)) {
+                    c.to_ascii_lowercase()
+                } else {
+                    c
+                }
+            };
+            if x > 0 || labels {
+                out.push(' ');
+            }
+            out.push(c);
+        }
+        if labels {
+            let _ = write!(&mut out, " {}", board.height - y);
+        }
+        out.push('\n');
+    }
+    if labels {
+        out.push_str(&column_labels());
+    }
+
+    out
+}
+
+/// What happened on one move, see `move_stats`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MoveStats {
+    pub move_number: u32,
+    pub seat: u32,
+    pub team: Color,
+    /// The stone played, the first one for pixel go. `None` for passes.
+    pub point: Option<Point>,
+    pub captures: u32,
+    /// Area estimate after the move plus the points gathered so far, in half points and
+    /// indexed by team. Every group counts as alive.
+    pub scores: GroupVec<i32>,
+}
+
+/// Statistics of every move in the game, for analysis after the game. Reveals hidden
+/// stones, so only for finished games.
+pub fn move_stats(game: &Game) -> Vec<MoveStats> {
+    let shared = &game.shared;
+    shared
+        .board_history
+        .windows(2)
+        .enumerate()
+        .map(|(idx, pair)| {
+            let (before, after) = (&pair[0], &pair[1]);
+            // Passes keep the last stone of the move before.
+            let placed = after.hash != before.hash;
+            let (point, captures) = match &after.state {
+                GameState::Play(state) => (
+                    state
+                        .last_stone
+                        .as_ref()
+                        .and_then(|s| s.first().copied())
+                        .filter(|_| placed),
+                    state.last_captured.len() as u32,
+                ),
+                _ => (None, 0),
+            };
+            let mut scores = estimate_score(&after.board, after.points.len());
+            for (score, points) in scores.iter_mut().zip(&after.points) {
+                *score += points;
+            }
+            MoveStats {
+                move_number: idx as u32 + 1,
+                seat: before.turn as u32,
+                team: shared.seats[before.turn].team,
+                point,
+                captures,
+                scores,
+            }
+        })
+        .collect()
+}
+
+/// `move_stats` as CSV with a header row. Scores are in points, one column per team.
+pub fn stats_csv(game: &Game) -> String {
+    let height = game.shared.board.height;
+    let mut out = "move,seat,color,point,captures".to_string();
+    for team in 1..=game.shared.points.len() {
+        let _ = write!(&mut out, ",{}", Color::name(team as u8));
+    }
+    out.push('\n');
+
+    for stats in move_stats(game) {
+        let point = stats
+            .point
+            .map_or_else(|| "pass".to_string(), |p| point_label(p, height));
+        let _ = write!(
+            &mut out,
+            "{},{},{},{},{}",
+            stats.move_number,
+            stats.seat,
+            Color::name(stats.team),
+            point,
+            stats.captures
+        );
+        for score in &stats.scores {
+            let _ = write!(&mut out, ",{}", *score as f32 / 2.0);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Largest board external engines are asked to play on.
+pub const GTP_MAX_SIZE: u32 = 19;
+
+/// Whether an external engine can play the game over GTP: black and white on a square
+/// board up to `GTP_MAX_SIZE`, without rules that change where stones may go or what
+/// the players see.
+pub fn gtp_supported(game: &Game) -> bool {
+    let shared = &game.shared;
+    let mods = &shared.mods;
+    let board = &shared.board;
+    let variant = mods.pixel
+        || mods.zen_go.is_some()
+        || mods.hidden_move.is_some()
+        || mods.visibility_mode.is_some()
+        || mods.n_plus_one.is_some()
+        || mods.tetris.is_some()
+        || mods.toroidal.is_some()
+        || mods.phantom.is_some()
+        || mods.traitor.is_some()
+        || mods.problem.is_some()
+        || mods.stone_cap.is_some()
+        || mods.expanding_board.is_some()
+        || mods.capture_goal.is_some()
+        || mods.opening_restrictions.is_some();
+
+    !variant
+        && board.width == board.height
+        && board.width <= GTP_MAX_SIZE
+        && shared.komis.len() == 2
+        && shared.seats.iter().all(|s| s.team.0 == 1 || s.team.0 == 2)
+}
+
+/// GTP commands that set up the current position and ask for the move of `seat`.
+/// Stones are placed one by one, so the engine doesn't see the move order or the ko.
+pub fn gtp_commands(game: &Game, seat: usize) -> Vec<String> {
+    let shared = &game.shared;
+    let board = &shared.board;
+    let color = |team: u8| if team == 1 { "b" } else { "w" };
+    // Komis are in half points, GTP only knows the komi of white.
+    let komi = (shared.komis[1] - shared.komis[0]) as f32 / 2.0;
+
+    let mut commands = vec![
+        format!("boardsize {}", board.width),
+        "clear_board".to_string(),
+        format!("komi {}", komi),
+    ];
+    for (idx, stone) in board.points.iter().enumerate() {
+        if stone.is_empty() {
+            continue;
+        }
+        let point = board.idx_to_coord(idx).unwrap();
+        commands.push(format!(
+            "play {} {}",
+            color(stone.0),
+            point_label(point, board.height)
+        ));
+    }
+    commands.push(format!("genmove {}", color(shared.seats[seat].team.0)));
+    commands
+}
+
+/// The move in a `genmove` response, without the leading `=`. `None` if the engine
+/// answered something else.
+pub fn parse_gtp_move(response: &str, size: (u32, u32)) -> Option<ActionKind> {
+    let response = response.trim();
+    if response.eq_ignore_ascii_case("pass") {
+        Some(ActionKind::Pass)
+    } else if response.eq_ignore_ascii_case("resign") {
+        Some(ActionKind::Resign)
+    } else {
+        parse_coordinate(response, size).map(|(x, y)| ActionKind::Place(x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::tests::seated_game;
+    use crate::game::{clock, GameModifier};
+
+    #[test]
+    fn gtp_translation() {
+        let mut game = seated_game((9, 9), GameModifier::default());
+        let time = clock::Millisecond(0);
+        game.make_action(100, ActionKind::Place(2, 6), time)
+            .unwrap();
+        game.make_action(200, ActionKind::Place(8, 0), time)
+            .unwrap();
+
+        assert!(gtp_supported(&game));
+        assert_eq!(
+            gtp_commands(&game, 0),
+            vec![
+                "boardsize 9",
+                "clear_board",
+                "komi 7.5",
+                "play w J9",
+                "play b C3",
+                "genmove b",
+            ]
+        );
+
+        assert_eq!(
+            parse_gtp_move(" D4\n", (9, 9)),
+            Some(ActionKind::Place(3, 5))
+        );
+        assert_eq!(parse_gtp_move("PASS", (9, 9)), Some(ActionKind::Pass));
+        assert_eq!(parse_gtp_move("resign", (9, 9)), Some(ActionKind::Resign));
+        assert_eq!(parse_gtp_move("Z99", (9, 9)), None);
+
+        let mods = GameModifier {
+            pixel: true,
+            ..GameModifier::default()
+        };
+        let pixel = Game::standard(&[1, 2], GroupVec::from(&[0, 15][..]), (9, 9), mods, 0).unwrap();
+        assert!(!gtp_supported(&pixel));
+    }
+
+    #[test]
+    fn sgf_reads_back() {
+        // Variation files read back the same.
+        let text = "(;FF[4]GM[1]SZ[9]AB[ba](;W[aa];B[ab]C[Ponnuki [sort of\\]])(;W[ee]))";
+        let tree = parse_sgf(text).unwrap();
+        assert_eq!(variation_sgf(&tree), text);
+        assert_eq!(tree.teams, vec![Color(2), Color(1)]);
+        assert!(tree.board_at(2).get_point((0, 0)).is_empty());
+
+        // Games are written as setup stones, they read back as moves with their captures.
+        let time = clock::Millisecond(0);
+        let mods = GameModifier::default();
+        let mut game = seated_game((9, 9), mods);
+        game.make_action(100, ActionKind::Place(1, 0), time)
+            .unwrap();
+        game.make_action(200, ActionKind::Place(0, 0), time)
+            .unwrap();
+        game.make_action(100, ActionKind::Place(0, 1), time)
+            .unwrap();
+        game.make_action(200, ActionKind::Pass, time).unwrap();
+        game.make_action(100, ActionKind::Place(4, 4), time)
+            .unwrap();
+
+        let tree = parse_sgf(&sgf_export(&game)).unwrap();
+        assert_eq!(tree.teams, vec![Color(1), Color(2)]);
+        assert_eq!(tree.nodes[1].stone, Some(((1, 0), Color(1))));
+        let mut last = VariationTree::ROOT;
+        while let Some(&next) = tree.nodes[last].children.first() {
+            last = next;
+        }
+        // The pass has no node.
+        assert_eq!(tree.path(last).len(), 5);
+        assert_eq!(tree.board_at(last).points, game.shared.board.points);
+
+        let error = |text: &str| parse_sgf(text).err();
+        assert_eq!(error(""), Some(SgfError::Malformed));
+        assert_eq!(error("(;SZ[9];B[aa]"), Some(SgfError::Malformed));
+        assert_eq!(error(&"(;".repeat(1000)), Some(SgfError::Malformed));
+        assert_eq!(error("(;SZ[99])"), Some(SgfError::BadSize));
+        assert_eq!(error("(;SZ[9];B[ka])"), Some(SgfError::BadPoint));
+        assert_eq!(
+            error("(;SZ[9];B[aa];W[aa])"),
+            Some(SgfError::IllegalMove(MakeActionError::PointOccupied))
+        );
+        assert_eq!(
+            error("(;SZ[9];B[aa];AB[cc][dd])"),
+            Some(SgfError::Unsupported)
+        );
+    }
+}
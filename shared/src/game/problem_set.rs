@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+use super::Problem;
+
+/// A problem the server hands out as part of its `ProblemSet`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SetProblem {
+    pub id: u64,
+    pub name: String,
+    pub size: (u8, u8),
+    pub problem: Problem,
+}
+
+/// Problems in the order users work through them.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ProblemSet {
+    pub problems: Vec<SetProblem>,
+}
+
+impl ProblemSet {
+    pub fn get(&self, id: u64) -> Option<&SetProblem> {
+        self.problems.iter().find(|p| p.id == id)
+    }
+
+    /// The first problem the user hasn't solved, `None` once they solved them all.
+    pub fn next(&self, progress: &ProblemProgress) -> Option<&SetProblem> {
+        self.problems.iter().find(|p| !progress.has_solved(p.id))
+    }
+
+    /// How many problems of the set the user solved. Removed problems don't count.
+    pub fn solved_count(&self, progress: &ProblemProgress) -> u32 {
+        let solved = self.problems.iter().filter(|p| progress.has_solved(p.id));
+        solved.count() as u32
+    }
+}
+
+/// Ids of the problems a user solved, stored on their profile.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ProblemProgress(pub Vec<u64>);
+
+impl ProblemProgress {
+    pub fn has_solved(&self, id: u64) -> bool {
+        self.0.contains(&id)
+    }
+
+    /// Records a solved problem. False if it was solved before, so there's nothing to store.
+    pub fn solve(&mut self, id: u64) -> bool {
+        if self.has_solved(id) {
+            return false;
+        }
+        self.0.push(id);
+        true
+    }
+}
@@ -1,9 +1,36 @@
 use super::*;
 
+/// A game with black seated as user 100 and white as 200, with 7.5 komi.
+pub(crate) fn seated_game(size: (u8, u8), mods: GameModifier) -> Game {
+    seated_game_with(&[1, 2], &[0, 15], size, mods)
+}
+
+/// A game with every seat taken, seat `n` by user `100 * (n + 1)`.
+fn seated_game_with(seats: &[u8], komis: &[i32], size: (u8, u8), mods: GameModifier) -> Game {
+    let mut game = Game::standard(seats, GroupVec::from(komis), size, mods, 0).unwrap();
+    for seat in 0..seats.len() {
+        game.take_seat(100 * (seat as u64 + 1), seat).unwrap();
+    }
+    game
+}
+
+/// Starts `game` from a position with the black and white stones on the board.
+fn set_up_position(game: &mut Game, black: &[Point], white: &[Point]) {
+    for &point in black {
+        *game.shared.board.point_mut(point) = Color(1);
+    }
+    for &point in white {
+        *game.shared.board.point_mut(point) = Color(2);
+    }
+    let start = &mut game.shared.board_history[0];
+    start.board = game.shared.board.clone();
+    start.hash = start.board.hash();
+}
+
 #[test]
 fn seats() {
     let mut game = Game::standard(
-        &vec![1, 2],
+        &[1, 2],
         GroupVec::from(&[0, 15][..]),
         (9, 9),
         GameModifier::default(),
@@ -67,3 +94,2695 @@ fn replay_snapshots() {
         assert_debug_snapshot!(view);
     });
 }
+
+#[test]
+fn pass_gives_prisoner() {
+    fn play_out(mods: GameModifier) -> GroupVec<i32> {
+        let mut game = seated_game((9, 9), mods);
+
+        let time = clock::Millisecond(0);
+        game.make_action(100, ActionKind::Pass, time).unwrap();
+        game.make_action(200, ActionKind::Place(4, 4), time)
+            .unwrap();
+        game.make_action(100, ActionKind::Pass, time).unwrap();
+        game.make_action(200, ActionKind::Pass, time).unwrap();
+
+        game.state.assume::<ScoringState>().scores.clone()
+    }
+
+    let without = play_out(GameModifier::default());
+    let with = play_out(GameModifier {
+        pass_gives_prisoner: Some(PassGivesPrisoner {}),
+        ..GameModifier::default()
+    });
+
+    // Black passed twice and white once, scores are in half points.
+    assert_eq!(with[0] - without[0], 2);
+    assert_eq!(with[1] - without[1], 4);
+}
+
+#[test]
+fn spectator_actions_rejected() {
+    let mut game = seated_game((9, 9), GameModifier::default());
+
+    assert!(game.holds_seat(100));
+    assert!(!game.holds_seat(300));
+
+    let time = clock::Millisecond(0);
+    for action in [
+        ActionKind::Place(0, 0),
+        ActionKind::Pass,
+        ActionKind::Cancel,
+        ActionKind::Resign,
+    ] {
+        assert_eq!(
+            game.make_action(300, action, time),
+            Err(MakeActionError::NotPlayer)
+        );
+    }
+
+    // Spectators can't accept the score either.
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    assert!(matches!(game.state, GameState::Scoring(_)));
+    assert_eq!(
+        game.make_action(300, ActionKind::Pass, time),
+        Err(MakeActionError::NotPlayer)
+    );
+    assert!(matches!(game.state, GameState::Scoring(_)));
+}
+
+#[test]
+fn coordinates() {
+    assert_eq!(parse_coordinate("A1", (19, 19)), Some((0, 18)));
+    assert_eq!(parse_coordinate("D4", (19, 19)), Some((3, 15)));
+    assert_eq!(parse_coordinate(" q16 ", (19, 19)), Some((15, 3)));
+    assert_eq!(parse_coordinate("J9", (9, 9)), Some((8, 0)));
+    assert_eq!(parse_coordinate("I5", (19, 19)), None);
+    assert_eq!(parse_coordinate("K1", (9, 9)), None);
+    assert_eq!(parse_coordinate("A0", (9, 9)), None);
+    assert_eq!(parse_coordinate("A10", (9, 9)), None);
+    assert_eq!(parse_coordinate("", (9, 9)), None);
+}
+
+#[test]
+fn label_styles_round_trip() {
+    for &style in &[LabelStyle::Letters, LabelStyle::Numbers] {
+        for &size in &[(9, 9), (19, 19), (13, 7)] {
+            for x in 0..size.0 {
+                for y in 0..size.1 {
+                    let label = style.point((x, y), size.1);
+                    assert_eq!(style.parse(&label, size), Some((x, y)), "{}", label);
+                }
+            }
+        }
+    }
+
+    let numbers = LabelStyle::Numbers;
+    assert_eq!(numbers.point((3, 15), 19), "4-4");
+    assert_eq!(numbers.column(18), "19");
+    assert_eq!(numbers.parse(" 16, 4 ", (19, 19)), Some((15, 15)));
+    assert_eq!(numbers.parse("3 9", (9, 9)), Some((2, 0)));
+    assert_eq!(numbers.parse("0-1", (9, 9)), None);
+    assert_eq!(numbers.parse("10-1", (9, 9)), None);
+    assert_eq!(numbers.parse("D4", (9, 9)), None);
+    assert_eq!(LabelStyle::Letters.parse("4-4", (9, 9)), None);
+}
+
+#[test]
+fn vacated_seat_on_turn() {
+    let mut game = seated_game((9, 9), GameModifier::default());
+
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(0, 0), time)
+        .unwrap();
+    assert!(!game.get_view(0).waiting_for_player);
+
+    game.leave_seat(200, 1).unwrap();
+    assert!(game.get_view(0).waiting_for_player);
+    assert_eq!(
+        game.make_action(100, ActionKind::Place(1, 1), time),
+        Err(MakeActionError::NotTurn)
+    );
+
+    // Someone else picks up the seat and the game continues.
+    game.take_seat(300, 1).unwrap();
+    assert!(!game.get_view(0).waiting_for_player);
+    game.make_action(300, ActionKind::Place(1, 1), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Place(2, 2), time)
+        .unwrap();
+}
+
+#[test]
+fn pass_hint() {
+    let mods = GameModifier {
+        pass_hint: true,
+        ..GameModifier::default()
+    };
+    let mut game = seated_game((5, 5), mods);
+
+    // Black wall on the second column and white wall on the fourth, the middle is dame.
+    for y in 0..5 {
+        *game.shared.board.point_mut((1, y)) = Color(1);
+        *game.shared.board.point_mut((3, y)) = Color(2);
+    }
+    assert!(!game.pass_suggested());
+    assert!(!game.get_view(100).pass_suggested);
+
+    // Filling the dame leaves nothing to gain.
+    for y in 0..5 {
+        *game.shared.board.point_mut((2, y)) = Color(1);
+    }
+    assert!(game.pass_suggested());
+    assert!(game.get_view(100).pass_suggested);
+    // Only the player on turn gets the hint.
+    assert!(!game.get_view(200).pass_suggested);
+}
+
+#[test]
+fn problem_solution() {
+    let mods = GameModifier {
+        problem: Some(Problem {
+            setup: vec![(0, 1, 1), (1, 0, 1), (2, 1, 1), (1, 1, 2)],
+            solution: vec![(4, 4), (1, 2), (5, 5)],
+        }),
+        ..GameModifier::default()
+    };
+    let mut game = Game::standard(&[1, 2], GroupVec::from(&[0, 15][..]), (9, 9), mods, 0).unwrap();
+    game.take_seat(100, 0).unwrap();
+    assert_eq!(game.shared.board.get_point((1, 1)), Color(2));
+
+    let time = clock::Millisecond(0);
+    assert_eq!(
+        game.make_action(100, ActionKind::Place(3, 3), time),
+        Err(MakeActionError::WrongMove)
+    );
+    assert_eq!(
+        game.make_action(100, ActionKind::Pass, time),
+        Err(MakeActionError::Illegal)
+    );
+
+    // The reply is played for the empty seat and the turn comes back.
+    game.make_action(100, ActionKind::Place(4, 4), time)
+        .unwrap();
+    assert_eq!(game.shared.board.get_point((1, 2)), Color(2));
+    assert_eq!(game.shared.turn, 0);
+    assert!(!game.get_view(100).problem_solved);
+
+    game.make_action(100, ActionKind::Place(5, 5), time)
+        .unwrap();
+    let view = game.get_view(100);
+    assert!(view.problem_solved);
+    assert!(!view.waiting_for_player);
+}
+
+#[test]
+fn capture_two_groups() {
+    let mut game = seated_game((5, 5), GameModifier::default());
+
+    // Two separate white stones that share their last liberty.
+    for &point in &[(0, 0), (1, 1), (3, 1), (4, 0)] {
+        *game.shared.board.point_mut(point) = Color(1);
+    }
+    for &point in &[(1, 0), (3, 0)] {
+        *game.shared.board.point_mut(point) = Color(2);
+    }
+    let before = game.shared.board.points.clone();
+
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(2, 0), time)
+        .unwrap();
+
+    let board = &game.shared.board;
+    let removed: Vec<_> = removed_stones(&before, &board.points)
+        .into_iter()
+        .filter_map(|idx| board.idx_to_coord(idx))
+        .collect();
+    assert_eq!(removed, vec![(1, 0), (3, 0)]);
+}
+
+#[test]
+fn thumbnail() {
+    let mut board = Board::<Color>::empty(19, 19, false);
+    // Black takes the top left corner, white the bottom right one.
+    for &point in &[(0, 0), (1, 0), (2, 1)] {
+        *board.point_mut(point) = Color(1);
+    }
+    for &point in &[(18, 18), (17, 18)] {
+        *board.point_mut(point) = Color(2);
+    }
+
+    let thumbnail = board_thumbnail(&board.points, (19, 19), 9);
+    assert_eq!((thumbnail.width, thumbnail.height), (9, 9));
+    assert_eq!(thumbnail.points[0], 1);
+    assert_eq!(thumbnail.points[80], 2);
+    assert_eq!(thumbnail.points.iter().filter(|&&p| p != 0).count(), 2);
+
+    // Small boards are kept as they are.
+    let small = board_thumbnail(&board.points[..25], (5, 5), 9);
+    assert_eq!((small.width, small.height), (5, 5));
+}
+
+/// Three kos stacked on a 5x11 board. Black owns the first ko, white the other two.
+fn triple_ko_game(repetition: Option<Repetition>) -> Game {
+    let mods = GameModifier {
+        repetition,
+        ..GameModifier::default()
+    };
+    let mut game = seated_game((5, 11), mods);
+
+    let mut black = vec![(2, 1)];
+    let mut white = vec![(1, 5), (1, 9)];
+    for &r in &[0, 4, 8] {
+        black.extend_from_slice(&[(1, r), (0, r + 1), (1, r + 2)]);
+        white.extend_from_slice(&[(2, r), (3, r + 1), (2, r + 2)]);
+    }
+    set_up_position(&mut game, &black, &white);
+    game
+}
+
+/// Each move retakes a different ko, returning to the start after six moves.
+const TRIPLE_KO_CYCLE: [(u64, (u32, u32)); 6] = [
+    (100, (2, 5)),
+    (200, (1, 1)),
+    (100, (2, 9)),
+    (200, (1, 5)),
+    (100, (2, 1)),
+    (200, (1, 9)),
+];
+
+#[test]
+fn triple_ko_superko() {
+    let mut game = triple_ko_game(None);
+    let time = clock::Millisecond(0);
+    let (last, moves) = TRIPLE_KO_CYCLE.split_last().unwrap();
+    for &(player, (x, y)) in moves {
+        game.make_action(player, ActionKind::Place(x, y), time)
+            .unwrap();
+    }
+    let &(player, (x, y)) = last;
+    assert_eq!(
+        game.make_action(player, ActionKind::Place(x, y), time),
+        Err(MakeActionError::Ko)
+    );
+}
+
+#[test]
+fn triple_ko_no_result() {
+    let mut game = triple_ko_game(Some(Repetition {
+        no_result_after: Some(3),
+    }));
+    let time = clock::Millisecond(0);
+
+    // Retaking a ko immediately is still forbidden.
+    game.make_action(100, ActionKind::Place(2, 5), time)
+        .unwrap();
+    assert_eq!(
+        game.make_action(200, ActionKind::Place(1, 5), time),
+        Err(MakeActionError::Ko)
+    );
+    game.make_action(200, ActionKind::Cancel, time).unwrap();
+
+    for &(player, (x, y)) in &TRIPLE_KO_CYCLE {
+        game.make_action(player, ActionKind::Place(x, y), time)
+            .unwrap();
+    }
+    let view = game.get_view(0);
+    assert!(view.repetition);
+    assert_eq!(view.result, None);
+
+    for &(player, (x, y)) in &TRIPLE_KO_CYCLE {
+        game.make_action(player, ActionKind::Place(x, y), time)
+            .unwrap();
+    }
+    assert_eq!(game.get_view(0).result, Some(GameResult::NoResult));
+
+    assert!(export::sgf_export(&game).starts_with("(;FF[4]GM[1]SZ[5:11]RE[Void]"));
+}
+
+#[test]
+fn teaching_game() {
+    let mods = GameModifier {
+        teaching: Some(Teaching { teacher: 1 }),
+        ..GameModifier::default()
+    };
+    let mut game = Game::standard(&[1, 2], GroupVec::from(&[0, 15][..]), (9, 9), mods, 0).unwrap();
+    game.take_seat(200, 1).unwrap();
+
+    // The teacher plays both colors without holding a seat.
+    let time = clock::Millisecond(0);
+    game.make_action(1, ActionKind::Place(0, 0), time).unwrap();
+    game.make_action(1, ActionKind::Place(1, 1), time).unwrap();
+    assert_eq!(game.shared.board.get_point((1, 1)), Color(2));
+
+    // Students still only play their own seat on their turn.
+    assert_eq!(
+        game.make_action(200, ActionKind::Place(2, 2), time),
+        Err(MakeActionError::NotTurn)
+    );
+    assert_eq!(
+        game.make_action(300, ActionKind::Place(2, 2), time),
+        Err(MakeActionError::NotPlayer)
+    );
+
+    // Resigning stays with the seat holder.
+    assert_eq!(
+        game.make_action(1, ActionKind::Resign, time),
+        Err(MakeActionError::NotTurn)
+    );
+}
+
+#[test]
+fn teaching_ko_points() {
+    let mods = GameModifier {
+        teaching: Some(Teaching { teacher: 1 }),
+        ..GameModifier::default()
+    };
+    let mut game = Game::standard(&[1, 2], GroupVec::from(&[0, 15][..]), (9, 9), mods, 0).unwrap();
+
+    let time = clock::Millisecond(0);
+    let moves = [
+        (1, 0),
+        (2, 0),
+        (0, 1),
+        (3, 1),
+        (1, 2),
+        (2, 2),
+        (8, 8),
+        (1, 1),
+    ];
+    for &(x, y) in &moves {
+        game.make_action(1, ActionKind::Place(x, y), time).unwrap();
+    }
+    assert!(game.get_view(1).ko_points.is_empty());
+
+    // Black takes the ko, white can't retake immediately.
+    game.make_action(1, ActionKind::Place(2, 1), time).unwrap();
+    assert_eq!(game.get_view(1).ko_points, vec![(1, 1)]);
+    assert_eq!(
+        game.make_action(1, ActionKind::Place(1, 1), time),
+        Err(MakeActionError::Ko)
+    );
+
+    // Not computed outside teaching games.
+    let mut game = seated_game((9, 9), GameModifier::default());
+    for (i, &(x, y)) in moves.iter().chain(&[(2, 1)]).enumerate() {
+        let player = if i % 2 == 0 { 100 } else { 200 };
+        game.make_action(player, ActionKind::Place(x, y), time)
+            .unwrap();
+    }
+    assert!(game.get_view(100).ko_points.is_empty());
+}
+
+#[test]
+fn legal_moves() {
+    let mut game = seated_game((9, 9), GameModifier::default());
+    assert_eq!(game.legal_moves(0).len(), 81);
+    assert!(game.legal_moves(2).is_empty());
+
+    let time = clock::Millisecond(0);
+    let moves = [
+        (1, 0),
+        (2, 0),
+        (0, 1),
+        (3, 1),
+        (1, 2),
+        (2, 2),
+        (8, 8),
+        (1, 1),
+        (2, 1),
+    ];
+    for (i, &(x, y)) in moves.iter().enumerate() {
+        let player = if i % 2 == 0 { 100 } else { 200 };
+        game.make_action(player, ActionKind::Place(x, y), time)
+            .unwrap();
+    }
+
+    // White can't retake the ko or play the suicide in the corner.
+    let white = game.legal_moves(1);
+    assert_eq!(white.len(), 71);
+    assert!(!white.contains(&(1, 1)));
+    assert!(!white.contains(&(0, 0)));
+    assert!(!white.contains(&(2, 1)));
+
+    // Black fills either point just fine, even when it's not on turn.
+    let black = game.legal_moves(0);
+    assert_eq!(black.len(), 73);
+    assert!(black.contains(&(1, 1)));
+    assert!(black.contains(&(0, 0)));
+
+    // Asking didn't change the game.
+    assert!(game.shared.board.get_point((1, 1)).is_empty());
+    assert_eq!(game.shared.turn, 1);
+    game.make_action(200, ActionKind::Place(4, 4), time)
+        .unwrap();
+}
+
+#[test]
+fn score_estimate() {
+    let mut game = seated_game((9, 9), GameModifier::default());
+
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(0, 0), time)
+        .unwrap();
+
+    // A spectator gets the estimate, the game stays as it was.
+    let before = game.dump();
+    assert_eq!(&game.score_estimate(300)[..], &[162, 15]);
+    assert_eq!(&game.score_estimate(100)[..], &[162, 15]);
+    assert_eq!(game.dump(), before);
+    game.make_action(200, ActionKind::Place(8, 8), time)
+        .unwrap();
+    assert_eq!(&game.score_estimate(300)[..], &[2, 17]);
+
+    game.make_action(100, ActionKind::Resign, time).unwrap();
+    assert!(game.score_estimate(300).is_empty());
+}
+
+#[test]
+fn opening_restrictions() {
+    let mods = GameModifier {
+        opening_restrictions: Some(OpeningRestrictions {
+            rules: vec![OpeningRestriction {
+                area: RestrictedArea::Edge { lines: 1 },
+                moves: 2,
+            }],
+        }),
+        ..GameModifier::default()
+    };
+    let mut game = seated_game((5, 5), mods);
+
+    let time = clock::Millisecond(0);
+    assert_eq!(game.get_view(100).restricted_points.len(), 16);
+    assert_eq!(game.legal_moves(0).len(), 9);
+    assert_eq!(
+        game.make_action(100, ActionKind::Place(0, 2), time),
+        Err(MakeActionError::Restricted(RestrictedArea::Edge {
+            lines: 1
+        }))
+    );
+    game.make_action(100, ActionKind::Place(2, 2), time)
+        .unwrap();
+    assert_eq!(
+        game.make_action(200, ActionKind::Place(4, 4), time),
+        Err(MakeActionError::Restricted(RestrictedArea::Edge {
+            lines: 1
+        }))
+    );
+
+    // Passes count towards opening the area.
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    assert!(game.get_view(100).restricted_points.is_empty());
+    game.make_action(100, ActionKind::Place(0, 2), time)
+        .unwrap();
+
+    let center = RestrictedArea::Center { size: 2 };
+    let board = Board::empty(4, 4, false);
+    assert!(center.contains(&board, (1, 1)));
+    assert!(center.contains(&board, (2, 2)));
+    assert!(!center.contains(&board, (0, 1)));
+    assert!(!center.contains(&board, (3, 2)));
+}
+
+#[test]
+fn expanding_board() {
+    let mods = GameModifier {
+        expanding_board: Some(ExpandingBoard {
+            interval: 2,
+            growth: 2,
+            max_size: 8,
+        }),
+        ..GameModifier::default()
+    };
+    let mut game = seated_game((5, 5), mods);
+
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(0, 0), time)
+        .unwrap();
+    assert_eq!(game.get_view(100).size, (5, 5));
+
+    // The second move grows the board by a line on every side.
+    game.make_action(200, ActionKind::Place(4, 3), time)
+        .unwrap();
+    let board = &game.shared.board;
+    assert_eq!((board.width, board.height), (7, 7));
+    assert_eq!(board.get_point((1, 1)), Color(1));
+    assert_eq!(board.get_point((5, 4)), Color(2));
+    assert_eq!(board.points.iter().filter(|c| !c.is_empty()).count(), 2);
+    assert_eq!(game.get_view(100).size, (7, 7));
+
+    // Growth stops at the maximum, the odd line goes to the bottom right.
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    game.make_action(200, ActionKind::Place(0, 0), time)
+        .unwrap();
+    let board = &game.shared.board;
+    assert_eq!((board.width, board.height), (8, 8));
+    assert_eq!(board.get_point((1, 1)), Color(1));
+    assert_eq!(board.get_point((0, 0)), Color(2));
+
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    assert_eq!(game.get_view(100).size, (8, 8));
+
+    // Earlier positions keep their own size.
+    assert_eq!(game.get_view_at(100, 1).unwrap().size, (5, 5));
+
+    // Replays and resets start from the size the game was created with.
+    assert_eq!(game.setup.size, (5, 5));
+    let loaded = Game::load(&game.dump()).unwrap();
+    assert_eq!(loaded.shared.board, game.shared.board);
+}
+
+#[test]
+fn scoring_empty_board() {
+    fn pass_out(moves: &[(u32, u32)]) -> Game {
+        let mut game = seated_game((9, 9), GameModifier::default());
+
+        let time = clock::Millisecond(0);
+        for &(x, y) in moves {
+            game.make_action(100, ActionKind::Place(x, y), time)
+                .unwrap();
+            game.make_action(200, ActionKind::Pass, time).unwrap();
+        }
+        game.make_action(100, ActionKind::Pass, time).unwrap();
+        game.make_action(200, ActionKind::Pass, time).unwrap();
+        game
+    }
+
+    // Nothing on the board, komi decides.
+    let game = pass_out(&[]);
+    let scoring = game.state.assume::<ScoringState>();
+    assert!(scoring.points.points.iter().all(|c| c.is_empty()));
+    assert_eq!(&scoring.scores[..], &[0, 15]);
+
+    // A lone stone surrounds the whole board.
+    let mut game = pass_out(&[(4, 4)]);
+    let scoring = game.state.assume::<ScoringState>();
+    assert!(scoring.points.points.iter().all(|&c| c == Color(1)));
+    assert_eq!(&scoring.scores[..], &[162, 15]);
+
+    // Marking it dead leaves the board neutral again.
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(4, 4), time)
+        .unwrap();
+    let scoring = game.state.assume::<ScoringState>();
+    assert!(scoring.points.points.iter().all(|c| c.is_empty()));
+    assert_eq!(&scoring.scores[..], &[0, 15]);
+}
+
+#[test]
+fn awaits_player() {
+    let mut game = seated_game((9, 9), GameModifier::default());
+    assert!(game.awaits_player(100));
+    assert!(!game.awaits_player(200));
+    assert!(!game.awaits_player(300));
+
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    assert!(game.awaits_player(200));
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+
+    // Both have to accept the score, until they do.
+    assert!(game.awaits_player(100) && game.awaits_player(200));
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    assert!(!game.awaits_player(100));
+    assert!(game.awaits_player(200));
+}
+
+#[test]
+fn sgf_move_comments() {
+    let mut game = seated_game((9, 9), GameModifier::default());
+
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(1, 0), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(0, 0), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Place(0, 1), time)
+        .unwrap();
+
+    assert!(!export::sgf_export(&game).contains("C["));
+
+    let options = export::SgfOptions {
+        move_comments: true,
+    };
+    let sgf = export::sgf_export_with(&game, &options);
+    assert_eq!(
+        sgf,
+        "(;FF[4]GM[1]SZ[9];AB[ba]C[Move 1];AW[aa]C[Move 2];AE[aa]AB[ab]C[Move 3, 1 captured (A9)];)"
+    );
+}
+
+#[test]
+fn positions_sgf() {
+    let empty = Color(0);
+    let positions = vec![
+        vec![empty; 4],
+        vec![Color(1), empty, empty, empty],
+        vec![Color(1), Color(2), empty, empty],
+        // A third color shows up as black, captured stones are cleared.
+        vec![empty, Color(2), Color(3), empty],
+    ];
+    assert_eq!(
+        export::positions_sgf((2, 2), &positions),
+        "(;FF[4]GM[1]SZ[2];AB[aa];AW[ba];AE[aa]AB[ab];)"
+    );
+}
+
+#[test]
+fn undo_restores_captures() {
+    let time = clock::Millisecond(0);
+    let mut game = seated_game((9, 9), GameModifier::default());
+    let capture_count = |game: &Game| game.state.assume::<PlayState>().capture_count;
+
+    game.make_action(100, ActionKind::Place(1, 0), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(0, 0), time)
+        .unwrap();
+    let before_capture = (game.shared.board.clone(), game.state.clone());
+    game.make_action(100, ActionKind::Place(0, 1), time)
+        .unwrap();
+    let after_capture = (game.shared.board.clone(), game.state.clone());
+    assert!(game.shared.board.get_point((0, 0)).is_empty());
+    assert_eq!(&game.get_view(100).prisoners[..], &[1, 0]);
+    assert_eq!(capture_count(&game), 1);
+
+    // Taking back the reply goes back to the counts right after the capture.
+    game.make_action(200, ActionKind::Place(5, 5), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Cancel, time).unwrap();
+    assert_eq!(
+        (game.shared.board.clone(), game.state.clone()),
+        after_capture
+    );
+    assert_eq!(capture_count(&game), 1);
+
+    // Taking back the capture puts the stone back and forgets the prisoner.
+    game.make_action(100, ActionKind::Cancel, time).unwrap();
+    assert_eq!(
+        (game.shared.board.clone(), game.state.clone()),
+        before_capture
+    );
+    assert_eq!(game.shared.board.get_point((0, 0)), Color(2));
+    assert_eq!(&game.get_view(100).prisoners[..], &[0, 0]);
+    assert_eq!(capture_count(&game), 0);
+}
+
+#[test]
+fn undo_own_move() {
+    let time = clock::Millisecond(0);
+    let mut game = seated_game((9, 9), GameModifier::default());
+
+    // Nothing to take back yet.
+    assert_eq!(
+        game.make_action(200, ActionKind::Cancel, time),
+        Err(MakeActionError::NotTurn)
+    );
+
+    // Black takes back a move before white replies.
+    game.make_action(100, ActionKind::Place(4, 4), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Cancel, time).unwrap();
+    assert!(game.shared.board.get_point((4, 4)).is_empty());
+    assert_eq!(game.shared.turn, 0);
+
+    // Not with undo disabled.
+    let mut game = seated_game(
+        (9, 9),
+        GameModifier {
+            no_undo: true,
+            ..GameModifier::default()
+        },
+    );
+    game.make_action(100, ActionKind::Place(4, 4), time)
+        .unwrap();
+    assert_eq!(
+        game.make_action(100, ActionKind::Cancel, time),
+        Err(MakeActionError::UndoDisabled)
+    );
+    assert_eq!(
+        game.make_action(200, ActionKind::Cancel, time),
+        Err(MakeActionError::UndoDisabled)
+    );
+
+    // Only the latest move, not one with replies after it.
+    let mut game = Game::standard(
+        &[1, 2, 3],
+        GroupVec::from(&[0, 0, 0][..]),
+        (9, 9),
+        GameModifier::default(),
+        0,
+    )
+    .unwrap();
+    game.take_seat(100, 0).unwrap();
+    game.take_seat(200, 1).unwrap();
+    game.take_seat(300, 2).unwrap();
+    game.make_action(100, ActionKind::Place(4, 4), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(5, 5), time)
+        .unwrap();
+    assert_eq!(
+        game.make_action(100, ActionKind::Cancel, time),
+        Err(MakeActionError::NotTurn)
+    );
+    game.make_action(200, ActionKind::Cancel, time).unwrap();
+    assert!(game.shared.board.get_point((5, 5)).is_empty());
+    assert_eq!(game.shared.turn, 1);
+}
+
+#[test]
+fn prisoners() {
+    let mut game = seated_game((9, 9), GameModifier::default());
+    assert_eq!(&game.get_view(100).prisoners[..], &[0, 0]);
+
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(1, 0), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(0, 0), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Place(0, 1), time)
+        .unwrap();
+    assert_eq!(&game.get_view(100).prisoners[..], &[1, 0]);
+
+    // Undo takes the prisoner back.
+    game.make_action(100, ActionKind::Cancel, time).unwrap();
+    assert_eq!(&game.get_view(100).prisoners[..], &[0, 0]);
+    game.make_action(100, ActionKind::Place(0, 1), time)
+        .unwrap();
+
+    // Still known once the game is scored.
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    assert_eq!(&game.get_view(100).prisoners[..], &[1, 0]);
+}
+
+#[test]
+fn view_before_seats_taken() {
+    let game = Game::standard(
+        &[1, 2],
+        GroupVec::from(&[0, 15][..]),
+        (9, 9),
+        GameModifier {
+            pass_hint: true,
+            ..GameModifier::default()
+        },
+        0,
+    )
+    .unwrap();
+
+    let view = game.get_view(0);
+    assert_eq!(view.seats.len(), 2);
+    assert!(view.seats.iter().all(|s| s.player.is_none()));
+    assert_eq!(view.turn, 0);
+    assert!(view.waiting_for_player);
+    assert!(!view.pass_suggested);
+    assert_eq!(view.move_number, 0);
+}
+
+#[test]
+fn fresh_room_view_is_drawable() {
+    let game = Game::standard(
+        &[1, 2],
+        GroupVec::from(&[0, 15][..]),
+        (13, 9),
+        GameModifier::default(),
+        0,
+    )
+    .unwrap();
+
+    // What the board draws from on the first render: an empty board of the room's size,
+    // and a seat on turn that nobody sits in, so no ghost stone.
+    let view = game.get_view(0);
+    assert_eq!(view.size, (13, 9));
+    assert_eq!(view.board.len(), 13 * 9);
+    assert!(view.board.iter().all(|c| c.is_empty()));
+    let seat = view.seats.get(view.turn as usize).unwrap();
+    assert_eq!(seat.player, None);
+}
+
+#[test]
+fn dame() {
+    let mut game = seated_game((5, 5), GameModifier::default());
+
+    // Walls on columns 1 and 3 leave column 2 neutral.
+    let time = clock::Millisecond(0);
+    for y in 0..5 {
+        game.make_action(100, ActionKind::Place(1, y), time)
+            .unwrap();
+        game.make_action(200, ActionKind::Place(3, y), time)
+            .unwrap();
+    }
+    assert!(game.get_view(100).dame.is_empty());
+
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    let mut dame = game.get_view(100).dame;
+    dame.sort();
+    assert_eq!(dame, (0..5).map(|y| (2, y)).collect::<Vec<_>>());
+
+    // Filling them after going back to play leaves none.
+    game.make_action(100, ActionKind::Cancel, time).unwrap();
+    for y in 0..5 {
+        let player = if y % 2 == 0 { 100 } else { 200 };
+        game.make_action(player, ActionKind::Place(2, y), time)
+            .unwrap();
+    }
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    assert!(game.get_view(100).dame.is_empty());
+}
+
+#[test]
+fn capture_goal() {
+    let mods = GameModifier {
+        capture_goal: Some(2),
+        ..GameModifier::default()
+    };
+    let mut game = seated_game((9, 9), mods);
+
+    // Black captures two white stones in the corners, one at a time.
+    let time = clock::Millisecond(0);
+    let moves = [(1, 0), (0, 0), (0, 1), (8, 8), (7, 8)];
+    for (i, &(x, y)) in moves.iter().enumerate() {
+        let player = if i % 2 == 0 { 100 } else { 200 };
+        game.make_action(player, ActionKind::Place(x, y), time)
+            .unwrap();
+    }
+    let view = game.get_view(100);
+    assert_eq!(&view.prisoners[..], &[1, 0]);
+    assert_eq!(view.result, None);
+
+    game.make_action(200, ActionKind::Place(4, 4), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Place(8, 7), time)
+        .unwrap();
+    let view = game.get_view(100);
+    assert_eq!(&view.prisoners[..], &[2, 0]);
+    assert_eq!(view.result, Some(GameResult::CaptureGoal(Color(1))));
+    assert!(matches!(game.state, GameState::Done(_)));
+
+    assert!(Game::standard(
+        &[1, 2],
+        GroupVec::from(&[0, 15][..]),
+        (9, 9),
+        GameModifier {
+            capture_goal: Some(0),
+            ..GameModifier::default()
+        },
+        0,
+    )
+    .is_none());
+}
+
+#[test]
+fn stone_cap() {
+    let mods = GameModifier {
+        stone_cap: Some(StoneCap {
+            max_stones: Some(4),
+            max_group_size: Some(2),
+        }),
+        ..GameModifier::default()
+    };
+    let mut game = seated_game((9, 9), mods);
+
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(0, 0), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(5, 5), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Place(1, 0), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(7, 7), time)
+        .unwrap();
+
+    // A third stone in the group is rejected and the board is left untouched.
+    assert_eq!(
+        game.make_action(100, ActionKind::Place(2, 0), time),
+        Err(MakeActionError::GroupTooLarge)
+    );
+    assert!(game.shared.board.get_point((2, 0)).is_empty());
+
+    assert_eq!(
+        game.make_action(100, ActionKind::Place(3, 3), time),
+        Err(MakeActionError::TooManyStones)
+    );
+}
+
+#[test]
+fn review() {
+    let mut game = seated_game((5, 5), GameModifier::default());
+
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(1, 0), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(4, 4), time)
+        .unwrap();
+
+    let mut review = game.start_review();
+    assert_eq!(
+        game.make_review_action(&mut review, ReviewAction::Undo),
+        Err(MakeActionError::NotDone)
+    );
+
+    game.make_action(100, ActionKind::Resign, time).unwrap();
+    let board = game.shared.board.clone();
+
+    let mut review = game.start_review();
+    assert_eq!(review.move_number, 2);
+    assert_eq!(
+        game.make_review_action(&mut review, ReviewAction::Navigate(3)),
+        Err(MakeActionError::OutOfBounds)
+    );
+
+    // White is on turn after the first move, then colors alternate.
+    game.make_review_action(&mut review, ReviewAction::Navigate(1))
+        .unwrap();
+    game.make_review_action(&mut review, ReviewAction::Place(0, 0))
+        .unwrap();
+    game.make_review_action(&mut review, ReviewAction::Place(0, 1))
+        .unwrap();
+    let view = game.get_review_view(&review).unwrap();
+    assert_eq!(view.board[0], 0);
+    assert_eq!(view.board[5], 1);
+    assert_eq!(view.board[24], 0);
+
+    assert_eq!(
+        game.make_review_action(&mut review, ReviewAction::Place(0, 0)),
+        Err(MakeActionError::Suicide)
+    );
+    assert_eq!(review.variation, vec![(0, 0), (0, 1)]);
+
+    game.make_review_action(&mut review, ReviewAction::Undo)
+        .unwrap();
+    assert_eq!(review.variation, vec![(0, 0)]);
+
+    // The game itself never changes.
+    assert_eq!(game.shared.board, board);
+    assert_eq!(game.get_view(0).result, Some(GameResult::Finished));
+}
+
+#[test]
+fn one_color_captures() {
+    let mods = GameModifier {
+        visibility_mode: Some(VisibilityMode::OneColor),
+        ..GameModifier::default()
+    };
+    let mut game = seated_game((5, 5), mods);
+
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(1, 0), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(0, 0), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Place(0, 1), time)
+        .unwrap();
+
+    // Captures follow the real owners of the stones.
+    let board = &game.shared.board;
+    assert!(board.get_point((0, 0)).is_empty());
+    assert_eq!(board.get_point((1, 0)), Color(1));
+    assert_eq!(board.get_point((0, 1)), Color(1));
+
+    // Players and spectators only ever see one color.
+    for &user_id in &[100, 200, 300] {
+        let view = game.get_view(user_id);
+        assert!(view.board[0].is_empty());
+        assert_eq!(view.board[1], Color(2));
+        assert_eq!(view.board[5], Color(2));
+    }
+}
+
+#[test]
+fn setup_phase() {
+    let mods = GameModifier {
+        setup_phase: Some(SetupPhase {}),
+        ..GameModifier::default()
+    };
+    let mut game = Game::standard(&[1, 2], GroupVec::from(&[0, 15][..]), (5, 5), mods, 0).unwrap();
+    game.take_seat(100, 0).unwrap();
+    game.take_seat(100, 1).unwrap();
+
+    let time = clock::Millisecond(0);
+    assert_eq!(
+        game.make_action(100, ActionKind::Place(2, 2), time),
+        Err(MakeActionError::Illegal)
+    );
+    assert_eq!(
+        game.setup_stones(300, vec![(0, 0, 1)]),
+        Err(MakeActionError::NotPlayer)
+    );
+    assert_eq!(
+        game.setup_stones(100, vec![(0, 0, 1), (5, 0, 2)]),
+        Err(MakeActionError::OutOfBounds)
+    );
+    assert_eq!(
+        game.setup_stones(100, vec![(0, 0, 3)]),
+        Err(MakeActionError::Illegal)
+    );
+    assert!(game.shared.board.get_point((0, 0)).is_empty());
+
+    // No captures while editing, the white stone stays without liberties.
+    let stones = vec![(0, 0, 2), (1, 0, 1), (0, 1, 1), (4, 4, 1)];
+    game.setup_stones(100, stones).unwrap();
+    game.setup_stones(100, vec![(4, 4, 0)]).unwrap();
+
+    let view = game.get_view(100);
+    assert!(matches!(view.state, GameStateView::Setup(_)));
+    assert_eq!(view.board[0], Color(2));
+    assert_eq!(view.board[1], Color(1));
+    assert_eq!(view.board[5], Color(1));
+    assert!(view.board[24].is_empty());
+
+    // Passing starts the game from the constructed position.
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    let view = game.get_view(100);
+    assert!(matches!(view.state, GameStateView::Play(_)));
+    assert_eq!(view.move_number, 0);
+    assert_eq!(view.board[0], Color(2));
+    assert_eq!(
+        game.setup_stones(100, vec![(2, 2, 1)]),
+        Err(MakeActionError::Illegal)
+    );
+
+    // The setup survives a save and load.
+    let loaded = Game::load(&game.dump()).unwrap();
+    assert_eq!(loaded.shared.board, game.shared.board);
+}
+
+#[test]
+fn verify_replay() {
+    let mut game = seated_game((5, 5), GameModifier::default());
+
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(1, 1), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(3, 3), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Resign, time).unwrap();
+    assert_eq!(Game::verify_replay(&game.dump()), Ok(()));
+
+    // Losing the resignation is noticed.
+    let mut replay: GameReplay = serde_cbor::from_slice(&game.dump()).unwrap();
+    replay.actions.pop();
+    let mut dump = Vec::new();
+    replay
+        .serialize(&mut serde_cbor::Serializer::new(&mut dump).packed_format())
+        .unwrap();
+    assert_eq!(
+        Game::verify_replay(&dump),
+        Err(ReplayMismatch::Result {
+            stored: Some(GameResult::Finished),
+            replayed: None,
+        })
+    );
+
+    // Replays stored before the check was recorded have nothing to compare.
+    replay.check = None;
+    let mut dump = Vec::new();
+    replay
+        .serialize(&mut serde_cbor::Serializer::new(&mut dump).packed_format())
+        .unwrap();
+    assert_eq!(Game::verify_replay(&dump), Ok(()));
+
+    assert_eq!(
+        Game::verify_replay(&[1, 2, 3]),
+        Err(ReplayMismatch::Unplayable)
+    );
+}
+
+#[test]
+fn pie_rule() {
+    let mods = GameModifier {
+        pie_rule: true,
+        ..GameModifier::default()
+    };
+    assert!(Game::standard(
+        &[1, 2, 3],
+        GroupVec::from(&[0, 0, 0][..]),
+        (9, 9),
+        mods.clone(),
+        0
+    )
+    .is_none());
+
+    let mut game = seated_game((9, 9), mods.clone());
+
+    let time = clock::Millisecond(0);
+    assert!(!game.get_view(100).swap_available);
+    assert_eq!(
+        game.make_action(100, ActionKind::Swap, time),
+        Err(MakeActionError::Illegal)
+    );
+
+    game.make_action(100, ActionKind::Place(2, 2), time)
+        .unwrap();
+    assert!(game.get_view(200).swap_available);
+    // Only the second player gets to choose.
+    assert_eq!(
+        game.make_action(100, ActionKind::Swap, time),
+        Err(MakeActionError::NotTurn)
+    );
+    game.make_action(200, ActionKind::Swap, time).unwrap();
+
+    // The second player now owns the black stone and the first player answers it.
+    assert_eq!(game.shared.seats[0].player, Some(200));
+    assert_eq!(game.shared.seats[1].player, Some(100));
+    assert_eq!(game.shared.turn, 1);
+    assert!(!game.get_view(100).swap_available);
+    assert_eq!(
+        game.make_action(100, ActionKind::Swap, time),
+        Err(MakeActionError::Illegal)
+    );
+    game.make_action(100, ActionKind::Place(6, 6), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(2, 6), time)
+        .unwrap();
+
+    // The swap survives a reload.
+    let loaded = Game::load(&game.dump()).unwrap();
+    assert_eq!(loaded.shared.seats[0].player, Some(200));
+
+    // Undo keeps the swap and can't go back past it.
+    game.make_action(200, ActionKind::Cancel, time).unwrap();
+    game.make_action(100, ActionKind::Cancel, time).unwrap();
+    assert_eq!(game.shared.seats[0].player, Some(200));
+    assert!(!game.get_view(100).swap_available);
+    assert_eq!(
+        game.make_action(100, ActionKind::Cancel, time),
+        Err(MakeActionError::UndoDisabled)
+    );
+
+    // Too late after the second move.
+    let mut game = seated_game((9, 9), mods);
+    game.make_action(100, ActionKind::Place(2, 2), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(6, 6), time)
+        .unwrap();
+    assert_eq!(
+        game.make_action(100, ActionKind::Swap, time),
+        Err(MakeActionError::Illegal)
+    );
+}
+
+#[test]
+fn scoring_resumes_bounded() {
+    use crate::states::scoring::MAX_SCORING_RESUMES;
+
+    let mut game = seated_game((9, 9), GameModifier::default());
+
+    let time = clock::Millisecond(0);
+    for _ in 0..MAX_SCORING_RESUMES {
+        game.make_action(100, ActionKind::Pass, time).unwrap();
+        game.make_action(200, ActionKind::Pass, time).unwrap();
+        assert!(matches!(game.state, GameState::Scoring(_)));
+        game.make_action(200, ActionKind::Cancel, time).unwrap();
+        assert!(matches!(game.state, GameState::Play(_)));
+    }
+
+    // The next round of scoring has to be settled.
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    assert_eq!(
+        game.make_action(200, ActionKind::Cancel, time),
+        Err(MakeActionError::ResumeLimit)
+    );
+    assert_eq!(
+        game.make_action(100, ActionKind::Cancel, time),
+        Err(MakeActionError::ResumeLimit)
+    );
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    assert!(matches!(game.state, GameState::Done(_)));
+}
+
+#[test]
+fn scoring_resumes_bounded_across_undo() {
+    use crate::states::scoring::MAX_SCORING_RESUMES;
+
+    let mut game = seated_game((9, 9), GameModifier::default());
+
+    // Taking back the pass after resuming doesn't give back the round.
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    for _ in 0..MAX_SCORING_RESUMES {
+        game.make_action(200, ActionKind::Pass, time).unwrap();
+        assert!(matches!(game.state, GameState::Scoring(_)));
+        game.make_action(200, ActionKind::Cancel, time).unwrap();
+        game.make_action(200, ActionKind::Cancel, time).unwrap();
+        assert!(matches!(game.state, GameState::Play(_)));
+    }
+
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    assert_eq!(
+        game.make_action(200, ActionKind::Cancel, time),
+        Err(MakeActionError::ResumeLimit)
+    );
+}
+
+#[test]
+fn settle_scoring() {
+    use crate::states::scoring::MAX_SCORING_RESUMES;
+
+    let mut game = seated_game((9, 9), GameModifier::default());
+
+    let time = clock::Millisecond(0);
+    for y in 0..9 {
+        game.make_action(100, ActionKind::Place(3, y), time)
+            .unwrap();
+        game.make_action(200, ActionKind::Place(5, y), time)
+            .unwrap();
+    }
+    // A stone inside the other side's area for each of them.
+    game.make_action(100, ActionKind::Place(7, 4), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(1, 4), time)
+        .unwrap();
+
+    // Only the final round can be settled.
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    assert_eq!(game.settle_scoring(), Err(MakeActionError::Illegal));
+    game.make_action(200, ActionKind::Cancel, time).unwrap();
+    for _ in 1..MAX_SCORING_RESUMES {
+        game.make_action(100, ActionKind::Pass, time).unwrap();
+        game.make_action(200, ActionKind::Pass, time).unwrap();
+        game.make_action(200, ActionKind::Cancel, time).unwrap();
+    }
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+
+    // Marking the wall dead doesn't count, the estimate does.
+    game.make_action(100, ActionKind::Place(5, 0), time)
+        .unwrap();
+    game.settle_scoring().unwrap();
+
+    let scoring = match &game.state {
+        GameState::Done(scoring) => scoring.clone(),
+        _ => panic!("Game not settled"),
+    };
+    let alive = |point: Point| {
+        scoring
+            .groups
+            .iter()
+            .find(|g| g.points.contains(&point))
+            .unwrap()
+            .alive
+    };
+    assert!(alive((3, 0)));
+    assert!(alive((5, 0)));
+    assert!(!alive((7, 4)));
+    assert!(!alive((1, 4)));
+    assert_eq!(scoring.players_accepted, vec![false, false]);
+
+    let loaded = Game::load(&game.dump()).unwrap();
+    assert_eq!(loaded.state, game.state);
+}
+
+#[test]
+fn scoring_marks_record_seat() {
+    let mut game = seated_game((9, 9), GameModifier::default());
+
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(2, 2), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(6, 6), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+
+    let marked_by = |game: &Game, point: Point| {
+        let scoring = match &game.state {
+            GameState::Scoring(scoring) => scoring,
+            _ => panic!("not scoring"),
+        };
+        let idx = scoring
+            .groups
+            .iter()
+            .position(|g| g.points.contains(&point))
+            .unwrap();
+        (
+            scoring.groups[idx].alive,
+            scoring.marked_by.get(idx).copied().flatten(),
+        )
+    };
+
+    assert_eq!(marked_by(&game, (2, 2)), (true, None));
+    game.make_action(200, ActionKind::Place(2, 2), time)
+        .unwrap();
+    assert_eq!(marked_by(&game, (2, 2)), (false, Some(1)));
+    assert_eq!(marked_by(&game, (6, 6)), (true, None));
+
+    // The other seat overriding the mark is recorded too.
+    game.make_action(100, ActionKind::Place(2, 2), time)
+        .unwrap();
+    assert_eq!(marked_by(&game, (2, 2)), (true, Some(0)));
+    game.make_action(100, ActionKind::Place(6, 6), time)
+        .unwrap();
+    assert_eq!(marked_by(&game, (6, 6)), (false, Some(0)));
+
+    // Resuming play starts over.
+    game.make_action(100, ActionKind::Cancel, time).unwrap();
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    assert_eq!(marked_by(&game, (6, 6)), (true, None));
+}
+
+#[test]
+fn max_moves() {
+    let mods = GameModifier {
+        max_moves: Some(4),
+        ..GameModifier::default()
+    };
+    let mut game = seated_game((9, 9), mods);
+    assert_eq!(game.get_view(100).max_moves, 4);
+
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(2, 2), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(6, 6), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Place(2, 6), time)
+        .unwrap();
+    assert!(matches!(game.state, GameState::Play(_)));
+    game.make_action(200, ActionKind::Place(6, 2), time)
+        .unwrap();
+
+    // The cap sends the game to scoring for good.
+    let view = game.get_view(100);
+    assert_eq!(view.move_number, 4);
+    assert!(matches!(view.state, GameStateView::Scoring(_)));
+    assert_eq!(
+        game.make_action(100, ActionKind::Cancel, time),
+        Err(MakeActionError::ResumeLimit)
+    );
+
+    // Without an agreement in time it's counted for the players, not accepted for them.
+    let mut settled = game.clone();
+    settled.settle_scoring().unwrap();
+    match &settled.state {
+        GameState::Done(scoring) => assert_eq!(scoring.players_accepted, vec![false, false]),
+        _ => panic!("Game not settled"),
+    }
+    let loaded = Game::load(&settled.dump()).unwrap();
+    assert_eq!(loaded.state, settled.state);
+
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    assert!(matches!(game.state, GameState::Done(_)));
+
+    // Replaying ends up in the same place.
+    let loaded = Game::load(&game.dump()).unwrap();
+    assert!(matches!(loaded.state, GameState::Done(_)));
+}
+
+#[test]
+fn text_diagram() {
+    let mut board = Board::empty(3, 3, false);
+    *board.point_mut((0, 0)) = Color(1);
+    *board.point_mut((1, 1)) = Color(2);
+    *board.point_mut((2, 2)) = Color(3);
+
+    assert_eq!(
+        export::text_diagram(&board, &[(1, 1)], false),
+        "X . .\n. o .\n. . B\n"
+    );
+    assert_eq!(
+        export::text_diagram(&board, &[], true),
+        "  A B C\n3 X . . 3\n2 . O . 2\n1 . . B 1\n  A B C\n"
+    );
+}
+
+#[test]
+fn handicap() {
+    let handicap = |stones, keep_komi| GameModifier {
+        handicap: Some(Handicap { stones, keep_komi }),
+        ..GameModifier::default()
+    };
+    let komis = GroupVec::from(&[0, 15][..]);
+
+    let game = Game::standard(&[1, 2], komis.clone(), (19, 19), handicap(4, false), 0).unwrap();
+    let view = game.get_view(0);
+    assert_eq!(&view.komis[..], &[0, 1]);
+    assert_eq!(&view.points[..], &[0, 1]);
+    assert_eq!(view.turn, 1);
+    let stones = view.board.iter().filter(|c| **c == Color(1)).count();
+    assert_eq!(stones, 4);
+    for &point in &[(15, 3), (3, 15), (15, 15), (3, 3)] {
+        assert_eq!(game.shared.board.get_point(point), Color(1));
+    }
+
+    // A single stone handicap is only the lower komi, black still starts.
+    let game = Game::standard(&[1, 2], komis.clone(), (19, 19), handicap(1, false), 0).unwrap();
+    assert_eq!(&game.get_view(0).komis[..], &[0, 1]);
+    assert_eq!(game.shared.turn, 0);
+    assert!(game.shared.board.points.iter().all(|c| c.is_empty()));
+
+    // Komi given explicitly stays.
+    let game = Game::standard(&[1, 2], komis.clone(), (9, 9), handicap(5, true), 0).unwrap();
+    assert_eq!(&game.get_view(0).komis[..], &[0, 15]);
+    assert_eq!(game.shared.board.get_point((4, 4)), Color(1));
+
+    // Not for more teams or too many stones.
+    assert!(Game::standard(
+        &[1, 2, 3],
+        GroupVec::from(&[0, 0, 0][..]),
+        (19, 19),
+        handicap(2, false),
+        0
+    )
+    .is_none());
+    assert!(Game::standard(&[1, 2], komis, (19, 19), handicap(10, false), 0).is_none());
+}
+
+#[test]
+fn skip_absent() {
+    let mods = GameModifier {
+        skip_absent: Some(SkipAbsent { turn_seconds: 60 }),
+        ..GameModifier::default()
+    };
+    let komis = GroupVec::from(&[0, 0][..]);
+    assert!(Game::standard(&[1, 2], komis, (9, 9), mods.clone(), 0).is_none());
+
+    let mut game = seated_game_with(&[1, 2, 3], &[0, 0, 0], (9, 9), mods);
+
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(0, 0), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(1, 1), time)
+        .unwrap();
+
+    // Blue is away, the server skips it and black is on turn again.
+    game.skip_turn(time).unwrap();
+    assert_eq!(game.shared.turn, 0);
+    let view = game.get_view(100);
+    assert_eq!(view.move_number, 3);
+    assert_eq!(view.skips, vec![(3, 2)]);
+    game.make_action(100, ActionKind::Place(2, 2), time)
+        .unwrap();
+    assert_eq!(game.get_view(100).skips, vec![(3, 2)]);
+
+    // Skips are part of the replay.
+    let loaded = Game::load(&game.dump()).unwrap();
+    assert_eq!(loaded.get_view(100).skips, vec![(3, 2)]);
+    assert_eq!(loaded.shared.turn, 1);
+
+    // Only with the rule.
+    let mut plain = Game::standard(
+        &[1, 2, 3],
+        GroupVec::from(&[0, 0, 0][..]),
+        (9, 9),
+        GameModifier::default(),
+        0,
+    )
+    .unwrap();
+    assert_eq!(plain.skip_turn(time), Err(MakeActionError::Illegal));
+}
+
+#[test]
+fn game_status_round_trip() {
+    use crate::message::{Encoding, ServerMessage};
+
+    let mods = GameModifier {
+        capture_goal: Some(5),
+        ..GameModifier::default()
+    };
+    let mut game = seated_game((9, 9), mods);
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(2, 2), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(3, 2), time)
+        .unwrap();
+
+    // The view the server sends is the one the client reads, field for field.
+    let view = game.get_view(100);
+    for &encoding in &[Encoding::Cbor, Encoding::PackedCbor] {
+        let msg = ServerMessage::GameStatus {
+            room_id: 1,
+            owner: 100,
+            members: vec![100, 200],
+            view: view.clone(),
+        };
+        match serde_cbor::from_slice(&msg.pack_with(encoding)).unwrap() {
+            ServerMessage::GameStatus {
+                room_id,
+                members,
+                view: decoded,
+                ..
+            } => {
+                assert_eq!(room_id, 1);
+                assert_eq!(members, vec![100, 200]);
+                assert_eq!(decoded, view);
+            }
+            msg => panic!("Decoded {:?}", msg),
+        }
+    }
+}
+
+#[test]
+fn board_presentation_round_trip() {
+    use crate::message::{BoardPalette, BoardPresentation, Encoding, ServerMessage};
+
+    let presentation = BoardPresentation {
+        palette: Some(BoardPalette::Colorblind),
+        coordinates: Some(false),
+        rotation: Some(2),
+    };
+    assert!(presentation.check().is_ok());
+    let turned_too_far = BoardPresentation {
+        rotation: Some(4),
+        ..BoardPresentation::default()
+    };
+    assert!(turned_too_far.check().is_err());
+
+    // What the room relays to its members is what their boards get.
+    for &encoding in &[Encoding::Cbor, Encoding::PackedCbor] {
+        let msg = ServerMessage::Presentation {
+            room_id: 1,
+            presentation: presentation.clone(),
+        };
+        match serde_cbor::from_slice(&msg.pack_with(encoding)).unwrap() {
+            ServerMessage::Presentation {
+                room_id,
+                presentation: decoded,
+            } => {
+                assert_eq!(room_id, 1);
+                assert_eq!(decoded, presentation);
+            }
+            msg => panic!("Decoded {:?}", msg),
+        }
+    }
+}
+
+#[test]
+fn influence_map() {
+    use crate::states::scoring::{influence_map, INFLUENCE_RANGE};
+
+    let mut board = Board::empty(9, 9, false);
+    *board.point_mut((0, 4)) = Color(1);
+    *board.point_mut((8, 4)) = Color(2);
+    let influence = influence_map(&board);
+    let at = |(x, y): Point| influence[(y * 9 + x) as usize];
+
+    assert_eq!(at((1, 4)), (Color(1), INFLUENCE_RANGE));
+    assert_eq!(at((3, 4)), (Color(1), 2));
+    assert_eq!(at((7, 4)), (Color(2), INFLUENCE_RANGE));
+    // Halfway between is neither's, and so are stones and far away points.
+    assert_eq!(at((4, 4)), (Color::empty(), 0));
+    assert_eq!(at((0, 4)), (Color::empty(), 0));
+    assert_eq!(at((4, 0)), (Color::empty(), 0));
+
+    // Influence doesn't pass through stones.
+    *board.point_mut((1, 4)) = Color(2);
+    *board.point_mut((0, 3)) = Color(2);
+    *board.point_mut((0, 5)) = Color(2);
+    let influence = influence_map(&board);
+    assert_eq!(influence[(4 * 9 + 2) as usize], (Color(2), INFLUENCE_RANGE));
+}
+
+#[test]
+fn capture_tiebreak() {
+    let play_tied_game = |capture_tiebreak| {
+        let mods = GameModifier {
+            capture_tiebreak,
+            ..GameModifier::default()
+        };
+        let mut game = seated_game_with(&[1, 2], &[0, 0], (9, 9), mods);
+        let time = clock::Millisecond(0);
+
+        // Black captures a stone in the corner, then both wall off four columns with a
+        // neutral column between them.
+        let mut moves = vec![(1, 0), (0, 0), (0, 1)];
+        for y in 0..9 {
+            moves.push((5, y));
+            moves.push((3, y));
+        }
+        for (i, &(x, y)) in moves.iter().enumerate() {
+            let player = if i % 2 == 0 { 100 } else { 200 };
+            game.make_action(player, ActionKind::Place(x, y), time)
+                .unwrap();
+        }
+        for &player in &[200, 100, 200, 100] {
+            game.make_action(player, ActionKind::Pass, time).unwrap();
+        }
+
+        let view = game.get_view(100);
+        assert_eq!(&view.prisoners[..], &[1, 0]);
+        match &game.state {
+            GameState::Done(state) => assert_eq!(&state.scores[..], &[72, 72]),
+            state => panic!("Game not done: {:?}", state),
+        }
+        view.result
+    };
+
+    assert_eq!(play_tied_game(None), Some(GameResult::Finished));
+    assert_eq!(
+        play_tied_game(Some(CaptureTiebreak::MostCaptures)),
+        Some(GameResult::Tiebreak(Color(1)))
+    );
+    assert_eq!(
+        play_tied_game(Some(CaptureTiebreak::FewestCaptures)),
+        Some(GameResult::Tiebreak(Color(2)))
+    );
+}
+
+#[test]
+fn three_team_seat_order_tiebreak() {
+    let play_tied_game = |seat_order_tiebreak| {
+        let mods = GameModifier {
+            seat_order_tiebreak,
+            ..GameModifier::default()
+        };
+        // Half a point of komi for both later teams leaves them tied on an empty board.
+        let mut game = seated_game_with(&[1, 2, 3], &[0, 1, 1], (9, 9), mods);
+        let time = clock::Millisecond(0);
+
+        for &player in &[100, 200, 300, 100, 200, 300] {
+            game.make_action(player, ActionKind::Pass, time).unwrap();
+        }
+
+        match &game.state {
+            GameState::Done(state) => assert_eq!(&state.scores[..], &[0, 1, 1]),
+            state => panic!("Game not done: {:?}", state),
+        }
+        let view = game.get_view(100);
+        (view.places.to_vec(), view.result)
+    };
+
+    assert_eq!(
+        play_tied_game(false),
+        (vec![3, 1, 1], Some(GameResult::Finished))
+    );
+    assert_eq!(
+        play_tied_game(true),
+        (vec![3, 2, 1], Some(GameResult::Tiebreak(Color(3))))
+    );
+}
+
+#[test]
+fn pause_for_disconnect() {
+    let new_game = || {
+        let mut game = seated_game((9, 9), GameModifier::default());
+        game.make_action(100, ActionKind::Place(2, 2), clock::Millisecond(0))
+            .unwrap();
+        game
+    };
+    let time = clock::Millisecond(1000);
+    let grace_ends = clock::Millisecond(time.0 + RECONNECT_GRACE.0);
+
+    // Disconnect, reconnect and resume.
+    let mut game = new_game();
+    game.pause_for(200, time).unwrap();
+    assert_eq!(
+        game.get_view(100).pause.map(|p| p.grace_ends),
+        Some(grace_ends)
+    );
+    assert_eq!(
+        game.make_action(200, ActionKind::Place(3, 3), time),
+        Err(MakeActionError::Paused)
+    );
+    assert_eq!(game.resume(100, time), Err(MakeActionError::Paused));
+    game.player_returned(200);
+    assert_eq!(game.claim(100, time), Err(MakeActionError::Paused));
+    game.resume(100, time).unwrap();
+    assert_eq!(game.get_view(100).pause, None);
+    game.make_action(200, ActionKind::Place(3, 3), time)
+        .unwrap();
+
+    // Disconnect and stay away until the opponent claims the game.
+    let mut game = new_game();
+    game.pause_for(200, time).unwrap();
+    assert_eq!(game.claim(100, time), Err(MakeActionError::Paused));
+    assert_eq!(game.claim(200, grace_ends), Err(MakeActionError::NotPlayer));
+    game.claim(100, grace_ends).unwrap();
+    assert!(matches!(game.state, GameState::Done(_)));
+    assert!(game.shared.seats[1].resigned);
+    assert!(!game.shared.seats[0].resigned);
+
+    // The claim is part of the replay, the pause isn't.
+    let game = Game::load(&game.dump()).unwrap();
+    assert!(matches!(game.state, GameState::Done(_)));
+    assert_eq!(game.pause, None);
+}
+
+#[test]
+fn captured_stones_recorded() {
+    let mut game = seated_game((9, 9), GameModifier::default());
+
+    // White's corner stone is taken on move 3, then white passes.
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(1, 0), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(0, 0), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Place(0, 1), time)
+        .unwrap();
+    match &game.get_view(200).state {
+        GameStateView::Play(state) => assert_eq!(state.last_captured, vec![(0, 0)]),
+        state => panic!("Not playing: {:?}", state),
+    }
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+
+    assert_eq!(game.get_view_at(100, 3).unwrap().captured, vec![(0, 0)]);
+    assert!(game.get_view_at(100, 2).unwrap().captured.is_empty());
+    assert!(game.get_view_at(100, 4).unwrap().captured.is_empty());
+    assert_eq!(
+        export::capture_note(&[(0, 0), (3, 2)], 9),
+        Some("2 captured (A9 D7)".to_string())
+    );
+}
+
+#[test]
+fn rotated_clicks() {
+    // The top left corner as drawn, after each number of quarter turns clockwise.
+    let corners = [(0, 0), (0, 18), (18, 18), (18, 0)];
+    for (quarters, &corner) in corners.iter().enumerate() {
+        let quarters = quarters as u8;
+        assert_eq!(unrotate_point((0, 0), 19, quarters), corner);
+        assert_eq!(rotate_point(corner, 19, quarters), (0, 0));
+
+        // Every click lands back on the point drawn there.
+        for &point in &[(0, 0), (3, 15), (9, 9), (18, 2)] {
+            let drawn = rotate_point(point, 19, quarters);
+            assert_eq!(unrotate_point(drawn, 19, quarters), point);
+        }
+    }
+    // A quarter turn clockwise moves the right side to the bottom.
+    assert_eq!(rotate_point((18, 4), 19, 1), (14, 18));
+    assert_eq!(rotate_point((3, 15), 19, 4), (3, 15));
+}
+
+#[test]
+fn game_started_once() {
+    let mut game = seated_game((9, 9), GameModifier::default());
+    assert!(!game.has_started());
+
+    // The room announces the start on the first change that flips this, so count them.
+    let time = clock::Millisecond(0);
+    let mut starts = 0;
+    let mut started = game.has_started();
+    let mut step = |game: &Game| {
+        if !started && game.has_started() {
+            starts += 1;
+            started = true;
+        }
+    };
+    game.make_action(100, ActionKind::Place(2, 2), time)
+        .unwrap();
+    step(&game);
+    game.make_action(200, ActionKind::Place(3, 3), time)
+        .unwrap();
+    step(&game);
+    game.make_action(100, ActionKind::Cancel, time).unwrap();
+    step(&game);
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    step(&game);
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    step(&game);
+    assert_eq!(starts, 1);
+    assert!(game.has_started());
+}
+
+#[test]
+fn capture_bonus() {
+    let mods = GameModifier {
+        capture_bonus: Some(CaptureBonus {
+            min_stones: 2,
+            points: 5,
+        }),
+        ..GameModifier::default()
+    };
+    let mut game = seated_game_with(&[1, 2], &[0, 0], (9, 9), mods);
+    let time = clock::Millisecond(0);
+
+    // A single stone is too small for the bonus.
+    game.make_action(100, ActionKind::Place(8, 7), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(8, 8), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Place(7, 8), time)
+        .unwrap();
+    assert_eq!(&game.get_view(100).prisoners[..], &[1, 0]);
+    assert!(game.get_view(100).capture_bonuses.iter().all(|&b| b == 0));
+
+    // Two white stones on the edge taken with one move.
+    game.make_action(200, ActionKind::Place(0, 0), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Place(0, 1), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(1, 0), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Place(1, 1), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(5, 5), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Place(2, 0), time)
+        .unwrap();
+    assert_eq!(&game.get_view(100).capture_bonuses[..], &[10, 0]);
+    assert_eq!(&game.shared.points[..], &[10, 0]);
+
+    // Taking the move back takes the bonus with it.
+    game.make_action(200, ActionKind::Cancel, time).unwrap();
+    assert!(game.get_view(100).capture_bonuses.iter().all(|&b| b == 0));
+    assert_eq!(&game.shared.points[..], &[0, 0]);
+}
+
+#[test]
+fn load_rejects_broken_replays() {
+    let mut game = seated_game((5, 5), GameModifier::default());
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(1, 1), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(3, 3), time)
+        .unwrap();
+    assert!(Game::load_checked(&game.dump()).is_ok());
+
+    let with_last_move = |action: ActionKind| {
+        let mut replay: GameReplay = serde_cbor::from_slice(&game.dump()).unwrap();
+        replay.actions.last_mut().unwrap().action = ReplayActionKind::Play(action);
+        let mut dump = Vec::new();
+        replay
+            .serialize(&mut serde_cbor::Serializer::new(&mut dump).packed_format())
+            .unwrap();
+        dump
+    };
+
+    // The fourth action, after taking both seats and one move.
+    assert_eq!(
+        Game::load_checked(&with_last_move(ActionKind::Place(7, 2))).err(),
+        Some(LoadError::OutOfBounds {
+            index: 3,
+            point: (7, 2),
+        })
+    );
+    assert_eq!(
+        Game::load_checked(&with_last_move(ActionKind::Place(1, 1))).err(),
+        Some(LoadError::Action {
+            index: 3,
+            error: MakeActionError::PointOccupied,
+        })
+    );
+    assert!(Game::load(&with_last_move(ActionKind::Place(7, 2))).is_none());
+    assert_eq!(
+        Game::load_checked(&[1, 2, 3]).err(),
+        Some(LoadError::Malformed)
+    );
+}
+
+#[test]
+fn min_players_blocks_start() {
+    let new_game = |min_players| {
+        Game::standard(
+            &[1, 2, 1, 2],
+            GroupVec::from(&[0, 15][..]),
+            (9, 9),
+            GameModifier {
+                min_players,
+                ..Default::default()
+            },
+            0,
+        )
+    };
+    assert!(new_game(Some(5)).is_none());
+    assert!(new_game(Some(0)).is_none());
+
+    let mut game = new_game(Some(4)).unwrap();
+    let time = clock::Millisecond(0);
+    game.take_seat(100, 0).unwrap();
+    game.take_seat(200, 1).unwrap();
+    assert_eq!(game.players_missing(), 2);
+    assert_eq!(game.get_view(0).players_missing, 2);
+    assert!(!game.awaits_player(100));
+    assert_eq!(
+        game.make_action(100, ActionKind::Place(0, 0), time),
+        Err(MakeActionError::NotEnoughPlayers)
+    );
+
+    game.take_seat(300, 2).unwrap();
+    assert_eq!(
+        game.make_action(100, ActionKind::Place(0, 0), time),
+        Err(MakeActionError::NotEnoughPlayers)
+    );
+    game.take_seat(400, 3).unwrap();
+    assert_eq!(game.players_missing(), 0);
+    game.make_action(100, ActionKind::Place(0, 0), time)
+        .unwrap();
+
+    // Once started, a player leaving doesn't block the others.
+    game.leave_seat(400, 3).unwrap();
+    assert_eq!(game.players_missing(), 0);
+    game.make_action(200, ActionKind::Place(1, 1), time)
+        .unwrap();
+}
+
+#[test]
+fn seeded_games_play_alike() {
+    let play_out = |seed| {
+        let mut game = Game::standard(
+            &[1, 2],
+            GroupVec::from(&[0, 15][..]),
+            (9, 9),
+            GameModifier::default(),
+            seed,
+        )
+        .unwrap();
+        game.take_seat(100, 0).unwrap();
+        game.take_seat(200, 1).unwrap();
+        let mut bots = [
+            game.bot(bot::BotLevel::Random, 0),
+            game.bot(bot::BotLevel::Random, 1),
+        ];
+        let time = clock::Millisecond(0);
+        let mut moves = Vec::new();
+        for _ in 0..20 {
+            let seat = game.shared.turn;
+            let action = bots[seat].choose_move(&game, seat);
+            game.make_action(100 * (seat as u64 + 1), action.clone(), time)
+                .unwrap();
+            moves.push(action);
+        }
+        moves
+    };
+
+    assert_eq!(play_out(42), play_out(42));
+}
+
+#[test]
+fn toggling_dead_recounts() {
+    let mut game = seated_game((9, 9), GameModifier::default());
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(2, 2), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(6, 6), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+
+    let scores = |game: &Game| match game.get_view(0).state {
+        GameStateView::Scoring(scoring) => scoring.scores.to_vec(),
+        _ => panic!("not scoring"),
+    };
+
+    // Every empty point touches both stones, so only the stones count.
+    assert_eq!(scores(&game), vec![2, 17]);
+
+    // With the white stone dead the whole board is black's.
+    game.make_action(100, ActionKind::Place(6, 6), time)
+        .unwrap();
+    assert_eq!(scores(&game), vec![162, 15]);
+
+    game.make_action(200, ActionKind::Place(6, 6), time)
+        .unwrap();
+    assert_eq!(scores(&game), vec![2, 17]);
+}
+
+#[test]
+fn move_stats_follow_replay() {
+    let mut game = seated_game((9, 9), GameModifier::default());
+
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(1, 0), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(0, 0), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Place(0, 1), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+
+    let stats = export::move_stats(&game);
+    assert_eq!(stats.len(), 4);
+    for row in &stats {
+        let history = &game.shared.board_history[row.move_number as usize];
+        if let Some(point) = row.point {
+            assert_eq!(history.board.get_point(point), row.team);
+        }
+    }
+
+    let loaded = Game::load(&game.dump()).unwrap();
+    assert_eq!(export::move_stats(&loaded), stats);
+
+    assert_eq!(
+        export::stats_csv(&game),
+        "move,seat,color,point,captures,Black,White\n\
+         1,0,Black,B9,0,81,7.5\n\
+         2,1,White,A9,0,1,8.5\n\
+         3,0,Black,A8,1,81,7.5\n\
+         4,1,White,pass,0,81,7.5\n"
+    );
+}
+
+/// White to move on a 5x5 board. A white stone at (0, 0) leaves two white stones without
+/// liberties, one at (4, 4) leaves a single stone without liberties.
+fn suicide_game(suicide: SuicideRule, repetition: Option<Repetition>) -> Game {
+    let mods = GameModifier {
+        suicide,
+        repetition,
+        ..GameModifier::default()
+    };
+    let mut game = seated_game((5, 5), mods);
+    let black = [(1, 0), (1, 1), (0, 2), (3, 4), (4, 3)];
+    set_up_position(&mut game, &black, &[(0, 1)]);
+
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(2, 2), time)
+        .unwrap();
+    game
+}
+
+#[test]
+fn suicide_forbidden_by_default() {
+    let mut game = suicide_game(SuicideRule::default(), None);
+    let time = clock::Millisecond(0);
+    assert_eq!(
+        game.make_action(200, ActionKind::Place(0, 0), time),
+        Err(MakeActionError::Suicide)
+    );
+    assert_eq!(
+        game.make_action(200, ActionKind::Place(4, 4), time),
+        Err(MakeActionError::Suicide)
+    );
+    assert_eq!(game.shared.board.get_point((0, 1)), Color(2));
+}
+
+#[test]
+fn multi_stone_suicide() {
+    let suicide = SuicideRule {
+        single_stone: false,
+        multi_stone: true,
+    };
+    let mut game = suicide_game(suicide, None);
+    let time = clock::Millisecond(0);
+    assert_eq!(
+        game.make_action(200, ActionKind::Place(4, 4), time),
+        Err(MakeActionError::Suicide)
+    );
+
+    game.make_action(200, ActionKind::Place(0, 0), time)
+        .unwrap();
+    assert!(game.shared.board.get_point((0, 0)).is_empty());
+    assert!(game.shared.board.get_point((0, 1)).is_empty());
+    assert_eq!(game.shared.turn, 0);
+    // Own stones aren't prisoners for anyone.
+    assert_eq!(&game.get_view(100).prisoners[..], &[0, 0]);
+}
+
+#[test]
+fn single_stone_suicide() {
+    let suicide = SuicideRule {
+        single_stone: true,
+        multi_stone: false,
+    };
+    let time = clock::Millisecond(0);
+
+    // The position is the one before the move, which superko doesn't allow.
+    let mut game = suicide_game(suicide.clone(), None);
+    assert_eq!(
+        game.make_action(200, ActionKind::Place(4, 4), time),
+        Err(MakeActionError::Ko)
+    );
+    assert_eq!(
+        game.make_action(200, ActionKind::Place(0, 0), time),
+        Err(MakeActionError::Suicide)
+    );
+
+    let repetition = Some(Repetition {
+        no_result_after: None,
+    });
+    let mut game = suicide_game(suicide, repetition);
+    let board = game.shared.board.clone();
+    game.make_action(200, ActionKind::Place(4, 4), time)
+        .unwrap();
+    assert_eq!(game.shared.board, board);
+    assert_eq!(game.shared.turn, 0);
+}
+
+#[test]
+fn illegal_move_notice() {
+    let mut game = suicide_game(SuicideRule::default(), None);
+    let time = clock::Millisecond(0);
+    let err = game
+        .make_action(200, ActionKind::Place(4, 4), time)
+        .unwrap_err();
+    assert_eq!(game.illegal_move_notice(200, &err), None);
+
+    game.shared.mods.announce_illegal = true;
+    assert_eq!(
+        game.illegal_move_notice(200, &err).as_deref(),
+        Some("White attempted an illegal move")
+    );
+    // Only the player on turn, and only for moves against the rules.
+    assert_eq!(game.illegal_move_notice(100, &err), None);
+    let err = game
+        .make_action(100, ActionKind::Place(0, 0), time)
+        .unwrap_err();
+    assert_eq!(err, MakeActionError::NotTurn);
+    assert_eq!(game.illegal_move_notice(100, &err), None);
+}
+
+#[test]
+fn teacher_annotations() {
+    let mods = GameModifier {
+        teaching: Some(Teaching { teacher: 300 }),
+        ..GameModifier::default()
+    };
+    let game = seated_game((9, 9), mods);
+
+    let mut annotations = Vec::new();
+    let circle = AnnotationAction::Add(Annotation::Circle((2, 2)));
+    assert_eq!(
+        game.annotate(&mut annotations, 100, circle.clone()),
+        Err(MakeActionError::NotPlayer)
+    );
+    assert!(annotations.is_empty());
+
+    game.annotate(&mut annotations, 300, circle.clone())
+        .unwrap();
+    let arrow = AnnotationAction::Add(Annotation::Arrow((0, 0), (4, 4)));
+    game.annotate(&mut annotations, 300, arrow).unwrap();
+    assert_eq!(annotations.len(), 2);
+
+    let outside = AnnotationAction::Add(Annotation::Line((0, 0), (9, 0)));
+    assert_eq!(
+        game.annotate(&mut annotations, 300, outside),
+        Err(MakeActionError::OutOfBounds)
+    );
+    let letter = AnnotationAction::Add(Annotation::Letter((1, 1), '?'));
+    assert_eq!(
+        game.annotate(&mut annotations, 300, letter),
+        Err(MakeActionError::Illegal)
+    );
+
+    // Drawing the same mark again takes it away.
+    game.annotate(&mut annotations, 300, circle).unwrap();
+    assert_eq!(annotations, vec![Annotation::Arrow((0, 0), (4, 4))]);
+
+    game.annotate(&mut annotations, 300, AnnotationAction::Clear)
+        .unwrap();
+    assert!(annotations.is_empty());
+}
+
+#[test]
+fn default_komi_by_size() {
+    let game = Game::standard(
+        &[1, 2],
+        GroupVec::new(),
+        (19, 19),
+        GameModifier::default(),
+        0,
+    )
+    .unwrap();
+    assert_eq!(&game.shared.komis[..], &[0, 15]);
+
+    // Pass stones count like Japanese rules.
+    let mods = GameModifier {
+        pass_gives_prisoner: Some(PassGivesPrisoner {}),
+        ..GameModifier::default()
+    };
+    let game = Game::standard(&[1, 2], GroupVec::new(), (9, 9), mods, 0).unwrap();
+    assert_eq!(&game.shared.komis[..], &[0, 11]);
+    assert_eq!(&game.get_view(0).komis[..], &[0, 11]);
+
+    let game = Game::standard(
+        &[1, 2, 3],
+        GroupVec::new(),
+        (9, 9),
+        GameModifier::default(),
+        0,
+    )
+    .unwrap();
+    assert_eq!(&game.shared.komis[..], &[0, 0, 0]);
+
+    // Given komis are kept.
+    let game = Game::standard(
+        &[1, 2],
+        GroupVec::from(&[0, 1][..]),
+        (9, 9),
+        GameModifier::default(),
+        0,
+    )
+    .unwrap();
+    assert_eq!(&game.shared.komis[..], &[0, 1]);
+}
+
+/// Each side has a wall around a one point eye in the corner, with neutral points
+/// between them.
+fn seki_game(seki: SekiScoring) -> Game {
+    let mods = GameModifier {
+        seki,
+        ..GameModifier::default()
+    };
+    let mut game = seated_game_with(&[1, 2], &[0, 0], (7, 3), mods);
+
+    let black = [(1, 0), (0, 1), (1, 1), (2, 1), (0, 2), (1, 2), (2, 2)];
+    let white = black.map(|(x, y)| (6 - x, y));
+    set_up_position(&mut game, &black, &white);
+
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    game
+}
+
+#[test]
+fn seki_eyes() {
+    let game = seki_game(SekiScoring::CountEyes);
+    let scoring = match &game.state {
+        GameState::Scoring(scoring) => scoring,
+        _ => panic!("Not scoring"),
+    };
+    let mut eyes = scoring.seki_eyes.clone();
+    eyes.sort_unstable();
+    assert_eq!(eyes, vec![(0, 0), (6, 0)]);
+    assert_eq!(&scoring.scores[..], &[16, 16]);
+
+    let game = seki_game(SekiScoring::Neutral);
+    let scoring = match &game.state {
+        GameState::Scoring(scoring) => scoring,
+        _ => panic!("Not scoring"),
+    };
+    assert_eq!(scoring.seki_eyes.len(), 2);
+    assert_eq!(&scoring.scores[..], &[14, 14]);
+    assert!(scoring.points.get_point((0, 0)).is_empty());
+}
+
+#[test]
+fn start_game_preset_round_trip() {
+    use crate::message::StartGame;
+
+    let start = StartGame {
+        name: "Three color rengo".to_string(),
+        seats: vec![1, 2, 3, 1, 2, 3],
+        komis: vec![0, 1, 1],
+        size: (13, 13),
+        mods: GameModifier {
+            seat_order_tiebreak: true,
+            capture_tiebreak: Some(CaptureTiebreak::MostCaptures),
+            ..GameModifier::default()
+        },
+        seed: Some(5),
+        untimed: false,
+    };
+    let preset = start.to_preset();
+    let parsed = StartGame::from_preset(&format!("  {}\n", preset)).unwrap();
+    assert_eq!(parsed, start);
+
+    let komis = parsed.komis.as_slice().into();
+    let game = Game::standard(&parsed.seats, komis, parsed.size, parsed.mods, 5).unwrap();
+    let view = game.get_view(0);
+    assert_eq!(view.seats.len(), 6);
+    assert_eq!(&view.komis[..], &[0, 1, 1]);
+    assert_eq!(view.size, (13, 13));
+    assert!(view.mods.seat_order_tiebreak);
+
+    assert!(StartGame::from_preset("{\"name\": \"Broken\"}").is_err());
+}
+
+#[test]
+fn consent_requests() {
+    let mut game = seated_game_with(&[1, 2, 3], &[0, 0, 0], (9, 9), GameModifier::default());
+    let time = clock::Millisecond(0);
+    let pending = |game: &Game| game.get_view(100).consents;
+
+    game.make_action(100, ActionKind::Place(2, 2), time)
+        .unwrap();
+    assert!(pending(&game).is_empty());
+    for &player in &[200, 300, 100] {
+        game.make_action(player, ActionKind::Pass, time).unwrap();
+    }
+
+    // Scoring waits for every seat to accept.
+    assert_eq!(
+        pending(&game),
+        vec![ConsentRequest {
+            kind: ConsentKind::AcceptScore,
+            waiting: vec![0, 1, 2],
+        }]
+    );
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    assert_eq!(pending(&game)[0].waiting, vec![0, 2]);
+
+    // Resuming play drops the request, and scoring again asks everyone anew.
+    game.make_action(300, ActionKind::Cancel, time).unwrap();
+    assert!(pending(&game).is_empty());
+    for &player in &[200, 300, 100] {
+        game.make_action(player, ActionKind::Pass, time).unwrap();
+    }
+    assert_eq!(pending(&game)[0].waiting, vec![0, 1, 2]);
+
+    for &player in &[100, 200, 300] {
+        game.make_action(player, ActionKind::Pass, time).unwrap();
+    }
+    assert!(matches!(game.state, GameState::Done(_)));
+    assert!(pending(&game).is_empty());
+}
+
+#[test]
+fn hypothetical_count() {
+    use crate::states::scoring::hypothetical_count;
+
+    // Black walls off the left edge and white the right one, with a white stone
+    // invading black's side.
+    let mut board = Board::empty(5, 5, false);
+    for y in 0..5 {
+        *board.point_mut((1, y)) = Color(1);
+        *board.point_mut((3, y)) = Color(2);
+    }
+    *board.point_mut((0, 2)) = Color(2);
+
+    let count = hypothetical_count(&board, &[0, 1], SekiScoring::CountEyes);
+    let dead = count
+        .groups
+        .iter()
+        .filter(|g| !g.alive)
+        .map(|g| g.points.to_vec())
+        .collect::<Vec<_>>();
+    assert_eq!(dead, vec![vec![(0, 2)]]);
+    assert_eq!(count.points.get_point((0, 2)), Color(1));
+    assert!(count.points.get_point((2, 2)).is_empty());
+    assert_eq!(&count.scores[..], &[20, 21]);
+    assert!(count.players_accepted.is_empty());
+}
+
+#[test]
+fn rengo_turn_survives_reconnect() {
+    let mut game = Game::standard(
+        &[1, 2, 1, 2],
+        GroupVec::from(&[0, 15][..]),
+        (9, 9),
+        GameModifier::default(),
+        0,
+    )
+    .unwrap();
+    for (seat, player) in [100, 200, 300, 400].iter().enumerate() {
+        game.take_seat(*player, seat).unwrap();
+    }
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(2, 2), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(6, 6), time)
+        .unwrap();
+
+    // Black's second partner is on move and drops.
+    assert_eq!(game.get_view(0).turn, 2);
+    game.pause_for(300, time).unwrap();
+    assert_eq!(
+        game.make_action(100, ActionKind::Place(2, 6), time),
+        Err(MakeActionError::Paused)
+    );
+    game.player_returned(300);
+    game.resume(300, time).unwrap();
+    assert_eq!(game.get_view(0).turn, 2);
+    assert!(game.awaits_player(300));
+    assert!(!game.awaits_player(100));
+    assert_eq!(
+        game.make_action(100, ActionKind::Place(2, 6), time),
+        Err(MakeActionError::NotTurn)
+    );
+
+    // Losing the seat and taking it back, or the server reloading the game, doesn't
+    // hand the move to the partner either.
+    game.leave_seat(300, 2).unwrap();
+    game.take_seat(300, 2).unwrap();
+    let mut game = Game::load(&game.dump()).unwrap();
+    assert_eq!(game.get_view(0).turn, 2);
+    assert_eq!(
+        game.make_action(100, ActionKind::Place(2, 6), time),
+        Err(MakeActionError::NotTurn)
+    );
+    game.make_action(300, ActionKind::Place(2, 6), time)
+        .unwrap();
+    assert_eq!(game.get_view(0).turn, 3);
+}
+
+#[test]
+fn no_spectators_admits_players_and_invited() {
+    let new_game = |no_spectators| {
+        seated_game(
+            (9, 9),
+            GameModifier {
+                no_spectators,
+                ..GameModifier::default()
+            },
+        )
+    };
+
+    let game = new_game(true);
+    assert!(game.admits(100, false));
+    assert!(game.admits(200, false));
+    assert!(game.admits(300, true));
+    assert!(!game.admits(300, false));
+
+    // Leaving the seat leaves the room closed to the former player too.
+    let mut game = game;
+    game.leave_seat(200, 1).unwrap();
+    assert!(!game.admits(200, false));
+
+    let game = new_game(false);
+    assert!(game.admits(300, false));
+}
+
+#[test]
+fn claim_abandoned_game() {
+    let mut game = seated_game((9, 9), GameModifier::default());
+    let time = clock::Millisecond(1000);
+    game.make_action(100, ActionKind::Place(2, 2), time)
+        .unwrap();
+
+    game.pause_for(200, time).unwrap();
+    let grace_ends = clock::Millisecond(time.0 + RECONNECT_GRACE.0);
+    let pause = game.get_view(100).pause.unwrap();
+    assert!(!pause.claimable(clock::Millisecond(grace_ends.0 - 1)));
+    assert!(pause.claimable(grace_ends));
+    assert_eq!(
+        game.claim(100, clock::Millisecond(grace_ends.0 - 1)),
+        Err(MakeActionError::Paused)
+    );
+
+    game.claim(100, grace_ends).unwrap();
+    let view = game.get_view(0);
+    assert_eq!(view.result, Some(GameResult::Abandoned(Color(1))));
+
+    // The absent player coming back to their seat doesn't change how the game ended.
+    game.leave_seat(200, 1).unwrap();
+    game.take_seat(200, 1).unwrap();
+    let game = Game::load(&game.dump()).unwrap();
+    assert_eq!(game.result(), Some(GameResult::Abandoned(Color(1))));
+}
+
+#[test]
+fn variant_default_clock() {
+    use crate::message::StartGame;
+
+    let start = |mods, untimed| StartGame {
+        name: "Atari go".to_string(),
+        seats: vec![1, 2],
+        komis: vec![],
+        size: (9, 9),
+        mods,
+        seed: None,
+        untimed,
+    };
+    let atari_go = GameModifier {
+        capture_goal: Some(1),
+        ..GameModifier::default()
+    };
+
+    // Capture go is blitz, three minutes and five seconds a move.
+    let mods = start(atari_go.clone(), false).modifiers();
+    let blitz = clock::ClockRule::Fischer(clock::FischerClock {
+        main_time: clock::Millisecond(180_000),
+        increment: clock::Millisecond(5_000),
+    });
+    assert_eq!(mods.clock.as_ref().map(|c| &c.rule), Some(&blitz));
+    let game = Game::standard(&[1, 2], GroupVec::new(), (9, 9), mods, 0).unwrap();
+    assert!(game.get_view(0).clock.is_some());
+
+    // Standard go stays untimed, and so does a variant game that asks to.
+    assert_eq!(
+        start(GameModifier::default(), false).modifiers().clock,
+        None
+    );
+    assert_eq!(start(atari_go.clone(), true).modifiers().clock, None);
+
+    // A clock that was picked is kept.
+    let picked = Clock {
+        rule: clock::ClockRule::Fischer(clock::FischerClock {
+            main_time: clock::Millisecond(600_000),
+            increment: clock::Millisecond(0),
+        }),
+    };
+    let mods = GameModifier {
+        clock: Some(picked.clone()),
+        ..atari_go
+    };
+    assert_eq!(start(mods, false).modifiers().clock, Some(picked));
+}
+
+#[test]
+fn consecutive_passes_start_scoring() {
+    let time = clock::Millisecond(0);
+    let in_play = |game: &Game| matches!(game.state, GameState::Play(_));
+
+    // A stone between two passes starts the count over.
+    let mut game = seated_game((9, 9), GameModifier::default());
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    game.make_action(200, ActionKind::Place(4, 4), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    assert!(in_play(&game));
+    assert_eq!(game.get_view(0).pass_streak, 1);
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    assert!(!in_play(&game));
+    assert_eq!(game.get_view(0).pass_streak, 0);
+
+    // Every team has to pass in a row.
+    let mut game = seated_game_with(&[1, 2, 3], &[0, 0, 0], (9, 9), GameModifier::default());
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    game.make_action(300, ActionKind::Place(4, 4), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    assert!(in_play(&game));
+    assert_eq!(game.get_view(0).pass_streak, 2);
+    game.make_action(300, ActionKind::Pass, time).unwrap();
+    assert!(!in_play(&game));
+
+    // Rengo partners pass for their team, one pass per team is enough.
+    let mut game = seated_game_with(&[1, 2, 1, 2], &[0, 15], (9, 9), GameModifier::default());
+    game.make_action(100, ActionKind::Place(4, 4), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    assert!(in_play(&game));
+    game.make_action(300, ActionKind::Pass, time).unwrap();
+    assert!(!in_play(&game));
+
+    // Zen go seats all play the color on turn, so each player has to pass.
+    let mods = GameModifier {
+        zen_go: Some(ZenGo { color_count: 2 }),
+        ..GameModifier::default()
+    };
+    let mut game = seated_game_with(&[1, 2, 1], &[0, 15], (9, 9), mods);
+    game.make_action(100, ActionKind::Place(4, 4), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    assert!(in_play(&game));
+    game.make_action(300, ActionKind::Pass, time).unwrap();
+    assert!(in_play(&game));
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    assert!(!in_play(&game));
+}
+
+#[test]
+fn resume_gives_opponent_the_move() {
+    let time = clock::Millisecond(0);
+    let resumed = |mods: GameModifier, resumer: u64| {
+        let mut game = seated_game((9, 9), mods);
+        game.make_action(100, ActionKind::Place(4, 4), time)
+            .unwrap();
+        game.make_action(200, ActionKind::Pass, time).unwrap();
+        game.make_action(100, ActionKind::Pass, time).unwrap();
+        game.make_action(resumer, ActionKind::Cancel, time).unwrap();
+        assert!(matches!(game.state, GameState::Play(_)));
+        game.shared.turn
+    };
+
+    // By default the turn order goes on from the last pass, whoever resumes.
+    assert_eq!(resumed(GameModifier::default(), 100), 1);
+    assert_eq!(resumed(GameModifier::default(), 200), 1);
+
+    let mods = GameModifier {
+        resume_to_opponent: true,
+        ..GameModifier::default()
+    };
+    assert_eq!(resumed(mods.clone(), 100), 1);
+    assert_eq!(resumed(mods.clone(), 200), 0);
+
+    // The opponent has to move, the resumer can't.
+    let mut game = seated_game((9, 9), mods);
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+    game.make_action(100, ActionKind::Cancel, time).unwrap();
+    assert_eq!(
+        game.make_action(100, ActionKind::Place(2, 2), time),
+        Err(MakeActionError::NotTurn)
+    );
+    game.make_action(200, ActionKind::Place(2, 2), time)
+        .unwrap();
+}
+
+#[test]
+fn solving_advances_problem_progress() {
+    use problem_set::*;
+
+    let problem = |id, solution: Vec<(u32, u32)>| SetProblem {
+        id,
+        name: format!("Problem {}", id),
+        size: (9, 9),
+        problem: Problem {
+            setup: vec![(0, 1, 1), (1, 0, 1), (2, 1, 1), (1, 1, 2)],
+            solution,
+        },
+    };
+    let set = ProblemSet {
+        problems: vec![
+            problem(7, vec![(1, 2)]),
+            problem(3, vec![(4, 4), (1, 2), (5, 5)]),
+        ],
+    };
+    let mut progress = ProblemProgress::default();
+    assert_eq!(set.next(&progress).map(|p| p.id), Some(7));
+
+    // Play the served problem the way a problem room does.
+    let next = set.next(&progress).unwrap().clone();
+    let mods = GameModifier {
+        problem: Some(next.problem.clone()),
+        ..GameModifier::default()
+    };
+    let size = next.size;
+    let mut game = Game::standard(&[1, 2], GroupVec::from(&[0, 0][..]), size, mods, 0).unwrap();
+    game.take_seat(100, 0).unwrap();
+    let time = clock::Millisecond(0);
+    assert!(!game.problem_solved());
+    game.make_action(100, ActionKind::Place(1, 2), time)
+        .unwrap();
+    assert!(game.problem_solved());
+
+    assert!(progress.solve(next.id));
+    assert!(!progress.solve(next.id));
+    assert_eq!(set.solved_count(&progress), 1);
+    assert_eq!(set.next(&progress).map(|p| p.id), Some(3));
+
+    assert!(progress.solve(3));
+    assert_eq!(set.solved_count(&progress), 2);
+    assert_eq!(set.next(&progress), None);
+}
+
+#[test]
+fn resuming_keeps_stones_marked_dead() {
+    let mods = GameModifier::default();
+    let mut game = seated_game((9, 9), mods);
+    let time = clock::Millisecond(0);
+    game.make_action(100, ActionKind::Place(2, 2), time)
+        .unwrap();
+    game.make_action(200, ActionKind::Place(6, 6), time)
+        .unwrap();
+    game.make_action(100, ActionKind::Pass, time).unwrap();
+    game.make_action(200, ActionKind::Pass, time).unwrap();
+
+    // Marking the white stone dead only marks it.
+    game.make_action(100, ActionKind::Place(6, 6), time)
+        .unwrap();
+    let view = game.get_view(100);
+    let scoring = match &view.state {
+        GameStateView::Scoring(scoring) => scoring,
+        state => panic!("not scoring: {:?}", state),
+    };
+    assert!(scoring
+        .groups
+        .iter()
+        .any(|g| !g.alive && g.points.contains(&(6, 6))));
+    assert_eq!(view.board[6 * 9 + 6], Color(2));
+
+    // Clients preview the count on a copy of the view with the dead stones taken off.
+    let mut preview = view.board.clone();
+    for group in scoring.groups.iter().filter(|g| !g.alive) {
+        for &(x, y) in &group.points {
+            preview[y as usize * 9 + x as usize] = Color::empty();
+        }
+    }
+    assert!(preview[6 * 9 + 6].is_empty());
+
+    game.make_action(100, ActionKind::Cancel, time).unwrap();
+    assert!(matches!(game.state, GameState::Play(_)));
+    assert_eq!(game.shared.board.get_point((6, 6)), Color(2));
+    assert_eq!(game.get_view(100).board[6 * 9 + 6], Color(2));
+
+    // The stone is still there to be captured, not played over.
+    assert_eq!(
+        game.make_action(100, ActionKind::Place(6, 6), time),
+        Err(MakeActionError::PointOccupied)
+    );
+    game.make_action(100, ActionKind::Place(6, 5), time)
+        .unwrap();
+}
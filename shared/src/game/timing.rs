@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+/// Moves a player has to make after the opening before their timing is judged.
+pub const MIN_TIMED_MOVES: usize = 30;
+/// The opening is often played from memory at a steady pace, so it isn't judged.
+pub const SKIPPED_MOVES: usize = 10;
+/// Think times are machine-like below this standard deviation, relative to the mean.
+/// People take much longer on some moves than on others, usually well above 0.5.
+pub const MAX_SPREAD: f64 = 0.15;
+/// Moves faster than this, in milliseconds, are hard to make by hand.
+pub const FAST_MOVE_MS: u32 = 250;
+/// Share of fast moves from which a game gets flagged. Quick replies to forced moves
+/// are normal, most of a game isn't.
+pub const MAX_FAST_SHARE: f64 = 0.8;
+
+/// Why a player's move timing looks like they aren't playing by hand. Only a hint for
+/// moderators, see `suspicious_timing`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimingFlag {
+    /// Moves judged, the opening left out.
+    pub moves: u32,
+    pub mean_ms: u32,
+    /// Standard deviation relative to the mean.
+    pub spread: f64,
+    /// Moves faster than `FAST_MOVE_MS`.
+    pub fast_moves: u32,
+}
+
+/// Checks the time a player took for each of their moves, in milliseconds and in order.
+/// Flags near constant think times, like a bot with a fixed time per move relayed by
+/// hand, and games played mostly faster than a person clicks. Kept conservative: short
+/// games are never flagged.
+pub fn suspicious_timing(think_times: &[u32]) -> Option<TimingFlag> {
+    let times = think_times.get(SKIPPED_MOVES..)?;
+    if times.len() < MIN_TIMED_MOVES {
+        return None;
+    }
+
+    let count = times.len() as f64;
+    let mean = times.iter().map(|&t| t as f64).sum::<f64>() / count;
+    let variance = times
+        .iter()
+        .map(|&t| (t as f64 - mean).powi(2))
+        .sum::<f64>()
+        / count;
+    let spread = match mean > 0.0 {
+        true => variance.sqrt() / mean,
+        false => 0.0,
+    };
+    let fast_moves = times.iter().filter(|&&t| t < FAST_MOVE_MS).count();
+
+    if spread >= MAX_SPREAD && (fast_moves as f64) < count * MAX_FAST_SHARE {
+        return None;
+    }
+    Some(TimingFlag {
+        moves: times.len() as u32,
+        mean_ms: mean as u32,
+        spread,
+        fast_moves: fast_moves as u32,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steady_move_timing_is_flagged() {
+        // A few quick opening moves, then one move every three seconds give or take a tenth.
+        let mut steady = vec![500; SKIPPED_MOVES];
+        steady.extend((0..40).map(|idx| 3000 + (idx % 5) * 50));
+        let flag = suspicious_timing(&steady).unwrap();
+        assert_eq!(flag.moves, 40);
+        assert!(flag.spread < MAX_SPREAD);
+
+        // Clicking every move out faster than a person reacts.
+        let fast = vec![100; SKIPPED_MOVES + 40];
+        assert_eq!(suspicious_timing(&fast).unwrap().fast_moves, 40);
+
+        // People think longer about some moves than about others.
+        let varied: Vec<u32> = (0..50).map(|idx| 1000 + (idx * 7919 % 23) * 900).collect();
+        assert_eq!(suspicious_timing(&varied), None);
+
+        // Short games are never judged.
+        assert_eq!(suspicious_timing(&steady[..SKIPPED_MOVES + 20]), None);
+    }
+}
@@ -0,0 +1,386 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TournamentFormat {
+    /// Losers drop out. Byes fill the first round up to a power of two.
+    SingleElimination,
+    /// Everyone plays everyone once.
+    RoundRobin,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PairingResult {
+    Won(u64),
+    /// Only kept in round robins, a drawn elimination game is played again.
+    Draw,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pairing {
+    pub round: u32,
+    pub black: u64,
+    /// `None` for a bye, which `black` wins without playing.
+    pub white: Option<u64>,
+    pub result: Option<PairingResult>,
+}
+
+impl Pairing {
+    fn new(round: u32, black: u64, white: Option<u64>) -> Pairing {
+        Pairing {
+            round,
+            black,
+            white,
+            result: match white {
+                Some(_) => None,
+                None => Some(PairingResult::Won(black)),
+            },
+        }
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = u64> {
+        std::iter::once(self.black).chain(self.white)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TournamentError {
+    /// A tournament needs at least two participants.
+    TooFewPlayers,
+    DuplicatePlayer,
+    NoSuchPairing,
+    /// The pairing already has a result, or its round hasn't started.
+    NotOpen,
+    /// The winner didn't play in the pairing.
+    NotInPairing,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Standing {
+    pub player: u64,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Standing {
+    /// Two points for a win and one for a draw.
+    pub fn points(&self) -> u32 {
+        self.wins * 2 + self.draws
+    }
+}
+
+/// Pairings of a tournament and their results. Rooms for the games are kept by the server.
+///
+/// Pairings are never removed, `Tournament::record` only opens the next ones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tournament {
+    pub name: String,
+    pub format: TournamentFormat,
+    /// In seeding order.
+    pub participants: Vec<u64>,
+    pub pairings: Vec<Pairing>,
+    /// The round being played, counting from 0.
+    pub round: u32,
+}
+
+impl Tournament {
+    pub fn new(
+        name: String,
+        format: TournamentFormat,
+        participants: Vec<u64>,
+    ) -> Result<Tournament, TournamentError> {
+        if participants.len() < 2 {
+            return Err(TournamentError::TooFewPlayers);
+        }
+        for (idx, player) in participants.iter().enumerate() {
+            if participants[..idx].contains(player) {
+                return Err(TournamentError::DuplicatePlayer);
+            }
+        }
+
+        let pairings = match format {
+            TournamentFormat::SingleElimination => {
+                let size = participants.len().next_power_of_two();
+                // Top seeds meet the empty slots at the end, so they get the byes.
+                (0..size / 2)
+                    .map(|idx| {
+                        let white = participants.get(size - 1 - idx).copied();
+                        Pairing::new(0, participants[idx], white)
+                    })
+                    .collect()
+            }
+            TournamentFormat::RoundRobin => round_robin(&participants),
+        };
+
+        Ok(Tournament {
+            name,
+            format,
+            participants,
+            pairings,
+            round: 0,
+        })
+    }
+
+    /// Pairings of the current round still waiting for a result.
+    pub fn open(&self) -> Vec<usize> {
+        (0..self.pairings.len())
+            .filter(|&idx| {
+                let pairing = &self.pairings[idx];
+                pairing.round == self.round && pairing.result.is_none()
+            })
+            .collect()
+    }
+
+    /// Records the game of a pairing, `None` for a draw. Returns the pairings that can
+    /// be played now: a rematch after a drawn elimination game, or the next round
+    /// once this one is complete.
+    pub fn record(
+        &mut self,
+        pairing: usize,
+        winner: Option<u64>,
+    ) -> Result<Vec<usize>, TournamentError> {
+        let format = self.format;
+        let round = self.round;
+        let entry = self
+            .pairings
+            .get_mut(pairing)
+            .ok_or(TournamentError::NoSuchPairing)?;
+        if entry.round != round || entry.result.is_some() {
+            return Err(TournamentError::NotOpen);
+        }
+
+        match winner {
+            Some(winner) if entry.players().any(|p| p == winner) => {
+                entry.result = Some(PairingResult::Won(winner));
+            }
+            Some(_) => return Err(TournamentError::NotInPairing),
+            None if format == TournamentFormat::SingleElimination => {
+                // Swap colors for the rematch.
+                if let Some(white) = entry.white {
+                    entry.white = Some(entry.black);
+                    entry.black = white;
+                }
+                return Ok(vec![pairing]);
+            }
+            None => entry.result = Some(PairingResult::Draw),
+        }
+
+        if !self.open().is_empty() {
+            return Ok(Vec::new());
+        }
+        self.advance();
+        Ok(self.open())
+    }
+
+    /// Starts the next round after the current one is complete.
+    fn advance(&mut self) {
+        match self.format {
+            TournamentFormat::SingleElimination => {
+                let winners = self.round_winners(self.round);
+                if winners.len() < 2 {
+                    return;
+                }
+                self.round += 1;
+                for pair in winners.chunks(2) {
+                    let pairing = Pairing::new(self.round, pair[0], pair.get(1).copied());
+                    self.pairings.push(pairing);
+                }
+            }
+            TournamentFormat::RoundRobin => {
+                let last = self.pairings.iter().map(|p| p.round).max().unwrap_or(0);
+                if self.round < last {
+                    self.round += 1;
+                }
+            }
+        }
+    }
+
+    fn round_winners(&self, round: u32) -> Vec<u64> {
+        self.pairings
+            .iter()
+            .filter(|p| p.round == round)
+            .filter_map(|p| match p.result {
+                Some(PairingResult::Won(winner)) => Some(winner),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        if !self.open().is_empty() {
+            return false;
+        }
+        match self.format {
+            TournamentFormat::SingleElimination => self.round_winners(self.round).len() == 1,
+            TournamentFormat::RoundRobin => self.pairings.iter().all(|p| p.round <= self.round),
+        }
+    }
+
+    /// The tournament winner, `None` until it's finished. Round robins can end tied at the
+    /// top, see `Tournament::standings`.
+    pub fn winner(&self) -> Option<u64> {
+        if !self.is_finished() {
+            return None;
+        }
+        if self.format == TournamentFormat::SingleElimination {
+            return self.round_winners(self.round).first().copied();
+        }
+        let standings = self.standings();
+        match standings.get(1) {
+            Some(second) if second.points() == standings[0].points() => None,
+            _ => standings.first().map(|s| s.player),
+        }
+    }
+
+    /// Results of each participant, most points first. Ties keep the seeding order.
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<_> = self
+            .participants
+            .iter()
+            .map(|&player| Standing {
+                player,
+                wins: 0,
+                draws: 0,
+                losses: 0,
+            })
+            .collect();
+        for pairing in &self.pairings {
+            for standing in &mut standings {
+                if !pairing.players().any(|p| p == standing.player) {
+                    continue;
+                }
+                match pairing.result {
+                    Some(PairingResult::Won(winner)) if winner == standing.player => {
+                        standing.wins += 1
+                    }
+                    Some(PairingResult::Won(_)) => standing.losses += 1,
+                    Some(PairingResult::Draw) => standing.draws += 1,
+                    None => {}
+                }
+            }
+        }
+        standings.sort_by_key(|s| std::cmp::Reverse(s.points()));
+        standings
+    }
+}
+
+/// Every round of a round robin with the circle method: the first player stays put and
+/// the rest rotate. Players paired with the empty slot of an odd field sit the round out.
+fn round_robin(participants: &[u64]) -> Vec<Pairing> {
+    let mut slots: Vec<Option<u64>> = participants.iter().copied().map(Some).collect();
+    if slots.len() % 2 == 1 {
+        slots.push(None);
+    }
+    let count = slots.len();
+
+    let mut pairings = Vec::new();
+    for round in 0..count - 1 {
+        for idx in 0..count / 2 {
+            let (a, b) = (slots[idx], slots[count - 1 - idx]);
+            if let (Some(a), Some(b)) = (a, b) {
+                // Alternate colors between rounds.
+                let (black, white) = if round % 2 == 0 { (a, b) } else { (b, a) };
+                pairings.push(Pairing::new(round as u32, black, Some(white)));
+            }
+        }
+        slots[1..].rotate_right(1);
+    }
+    pairings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_elimination_advances() {
+        let format = TournamentFormat::SingleElimination;
+        assert_eq!(
+            Tournament::new("Cup".into(), format, vec![1]),
+            Err(TournamentError::TooFewPlayers)
+        );
+        assert_eq!(
+            Tournament::new("Cup".into(), format, vec![1, 2, 1]),
+            Err(TournamentError::DuplicatePlayer)
+        );
+
+        // Five players fill a bracket of eight, the top three seeds get a bye.
+        let mut cup = Tournament::new("Cup".into(), format, vec![1, 2, 3, 4, 5]).unwrap();
+        assert_eq!(cup.pairings.len(), 4);
+        assert_eq!(cup.open(), vec![3]);
+        assert_eq!(cup.pairings[3].players().collect::<Vec<_>>(), vec![4, 5]);
+        assert_eq!(cup.record(3, Some(1)), Err(TournamentError::NotInPairing));
+
+        assert_eq!(cup.record(3, Some(5)), Ok(vec![4, 5]));
+        assert_eq!(cup.round, 1);
+        assert_eq!(cup.pairings[4].players().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(cup.pairings[5].players().collect::<Vec<_>>(), vec![3, 5]);
+        assert_eq!(cup.record(3, Some(5)), Err(TournamentError::NotOpen));
+
+        // A draw is played again with colors swapped.
+        assert_eq!(cup.record(5, None), Ok(vec![5]));
+        assert_eq!(cup.pairings[5].players().collect::<Vec<_>>(), vec![5, 3]);
+        assert_eq!(cup.record(4, Some(2)), Ok(vec![]));
+        assert_eq!(cup.record(5, Some(5)), Ok(vec![6]));
+        assert!(!cup.is_finished());
+
+        assert_eq!(cup.record(6, Some(5)), Ok(vec![]));
+        assert!(cup.is_finished());
+        assert_eq!(cup.winner(), Some(5));
+        let standings = cup.standings();
+        assert_eq!(standings[0].player, 5);
+        assert_eq!((standings[0].wins, standings[0].losses), (3, 0));
+        assert_eq!(standings[1].player, 2);
+    }
+
+    #[test]
+    fn round_robin_advances() {
+        let format = TournamentFormat::RoundRobin;
+        let mut league = Tournament::new("League".into(), format, vec![1, 2, 3, 4]).unwrap();
+        assert_eq!(league.pairings.len(), 6);
+        for a in 1..=4 {
+            for b in a + 1..=4 {
+                let games = league.pairings.iter().filter(|p| {
+                    let players = p.players().collect::<Vec<_>>();
+                    players.contains(&a) && players.contains(&b)
+                });
+                assert_eq!(games.count(), 1);
+            }
+        }
+
+        // The lower id wins every game, except for a draw between 3 and 4.
+        while !league.is_finished() {
+            let round = league.round;
+            for idx in league.open() {
+                let pairing = &league.pairings[idx];
+                let players = pairing.players().collect::<Vec<_>>();
+                let winner = match players.contains(&3) && players.contains(&4) {
+                    true => None,
+                    false => players.iter().min().copied(),
+                };
+                league.record(idx, winner).unwrap();
+            }
+            assert!(league.round > round || league.is_finished());
+        }
+
+        // 1 wins all three games for 6 points and 2 wins two for 4. 3 and 4 lose to both and
+        // draw each other, a point each. The tie keeps the seeding order.
+        assert_eq!(league.round, 2);
+        let standings = league.standings();
+        let order = standings.iter().map(|s| s.player).collect::<Vec<_>>();
+        assert_eq!(order, vec![1, 2, 3, 4]);
+        let records = standings
+            .iter()
+            .map(|s| (s.wins, s.draws, s.losses))
+            .collect::<Vec<_>>();
+        assert_eq!(records, vec![(3, 0, 0), (2, 0, 1), (0, 1, 2), (0, 1, 2)]);
+        let points = standings.iter().map(|s| s.points()).collect::<Vec<_>>();
+        assert_eq!(points, vec![6, 4, 1, 1]);
+        assert_eq!(league.winner(), Some(1));
+
+        // An odd field sits one player out each round.
+        let league = Tournament::new("League".into(), format, vec![1, 2, 3]).unwrap();
+        assert_eq!(league.pairings.len(), 3);
+        assert_eq!(league.open().len(), 1);
+    }
+}
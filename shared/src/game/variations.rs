@@ -0,0 +1,216 @@
+use super::{place_review_stone, Board, Color, MakeActionError, Point};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariationNode {
+    pub parent: Option<usize>,
+    /// The first child continues the main line of the branch.
+    pub children: Vec<usize>,
+    /// The stone played to reach this node. `None` for the root.
+    pub stone: Option<(Point, Color)>,
+    pub comment: String,
+}
+
+/// Moves branching off a game position for studying it. Only kept by the client,
+/// the server only knows the shared review.
+///
+/// Nodes are never removed from `nodes`, pruning only detaches them from their parent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariationTree {
+    /// The position at the root.
+    pub board: Board,
+    /// Teams in turn order, starting with the one to play at the root.
+    pub teams: Vec<Color>,
+    pub nodes: Vec<VariationNode>,
+}
+
+impl VariationTree {
+    pub const ROOT: usize = 0;
+
+    pub fn new(board: Board, mut teams: Vec<Color>) -> Self {
+        if teams.is_empty() {
+            teams = vec![Color(1), Color(2)];
+        }
+        VariationTree {
+            board,
+            teams,
+            nodes: vec![VariationNode {
+                parent: None,
+                children: Vec::new(),
+                stone: None,
+                comment: String::new(),
+            }],
+        }
+    }
+
+    /// Nodes from the root to `node`, both included.
+    pub fn path(&self, node: usize) -> Vec<usize> {
+        let mut path = vec![node];
+        let mut current = node;
+        while let Some(parent) = self.nodes[current].parent {
+            path.push(parent);
+            current = parent;
+        }
+        path.reverse();
+        path
+    }
+
+    pub fn team_to_play(&self, node: usize) -> Color {
+        let depth = self.path(node).len() - 1;
+        self.teams[depth % self.teams.len()]
+    }
+
+    /// The position at `node`.
+    pub fn board_at(&self, node: usize) -> Board {
+        let mut board = self.board.clone();
+        for idx in self.path(node) {
+            if let Some((point, team)) = self.nodes[idx].stone {
+                // Stones are checked when they are added, so replaying them can't fail.
+                let _ = place_review_stone(&mut board, point, team);
+            }
+        }
+        board
+    }
+
+    /// Plays the next stone after `parent`. Playing a move that already exists
+    /// there returns the existing node instead of branching again.
+    pub fn play(&mut self, parent: usize, point: Point) -> Result<usize, MakeActionError> {
+        if parent >= self.nodes.len() {
+            return Err(MakeActionError::OutOfBounds);
+        }
+        let team = self.team_to_play(parent);
+        self.play_as(parent, point, team)
+    }
+
+    /// `VariationTree::play` with a stone of `team`, whoever's turn it is. For records
+    /// that don't alternate, like SGF files with passes left out.
+    pub fn play_as(
+        &mut self,
+        parent: usize,
+        point: Point,
+        team: Color,
+    ) -> Result<usize, MakeActionError> {
+        if parent >= self.nodes.len() {
+            return Err(MakeActionError::OutOfBounds);
+        }
+
+        let existing = self.nodes[parent]
+            .children
+            .iter()
+            .copied()
+            .find(|&child| self.nodes[child].stone == Some((point, team)));
+        if let Some(child) = existing {
+            return Ok(child);
+        }
+
+        let mut board = self.board_at(parent);
+        place_review_stone(&mut board, point, team)?;
+
+        let node = self.nodes.len();
+        self.nodes.push(VariationNode {
+            parent: Some(parent),
+            children: Vec::new(),
+            stone: Some((point, team)),
+            comment: String::new(),
+        });
+        self.nodes[parent].children.push(node);
+        Ok(node)
+    }
+
+    /// Grid cell of every node for drawing the tree as `(column, row)`, indexed by node.
+    /// The column is the move number and the main line takes row 0. A branch goes on the
+    /// first row below its branch point that is free from its column on, with the line
+    /// to it running down the column of the branch point. Detached nodes are `None`.
+    pub fn layout(&self) -> Vec<Option<(usize, usize)>> {
+        let mut cells = vec![None; self.nodes.len()];
+        // First free row of each column.
+        let mut free_rows: Vec<usize> = Vec::new();
+        // Depth first without recursion, long games would need deep stacks.
+        let mut stack = vec![(Self::ROOT, 0, 0)];
+        while let Some((node, column, parent_row)) = stack.pop() {
+            if free_rows.len() <= column {
+                free_rows.resize(column + 1, 0);
+            }
+            let row = parent_row.max(free_rows[column]);
+            free_rows[column] = row + 1;
+            if column > 0 {
+                // Keeps the line down from the branch point clear.
+                free_rows[column - 1] = free_rows[column - 1].max(row);
+            }
+            cells[node] = Some((column, row));
+            for &child in self.nodes[node].children.iter().rev() {
+                stack.push((child, column + 1, row));
+            }
+        }
+        cells
+    }
+
+    /// Detaches `node` and everything after it from the tree. The root stays.
+    pub fn prune(&mut self, node: usize) {
+        if let Some(parent) = self.nodes.get(node).and_then(|n| n.parent) {
+            self.nodes[parent].children.retain(|&child| child != node);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::export;
+
+    #[test]
+    fn variation_tree() {
+        let mut board = Board::empty(9, 9, false);
+        *board.point_mut((1, 0)) = Color(1);
+        let mut tree = VariationTree::new(board, vec![Color(2), Color(1)]);
+
+        // White plays into atari, black captures.
+        let a = tree.play(VariationTree::ROOT, (0, 0)).unwrap();
+        let b = tree.play(a, (0, 1)).unwrap();
+        assert!(tree.board_at(b).get_point((0, 0)).is_empty());
+        assert_eq!(tree.board_at(b).get_point((0, 1)), Color(1));
+        assert_eq!(tree.path(b), vec![VariationTree::ROOT, a, b]);
+
+        // Replaying a move reuses its node, a different one branches.
+        assert_eq!(tree.play(VariationTree::ROOT, (0, 0)), Ok(a));
+        let c = tree.play(VariationTree::ROOT, (4, 4)).unwrap();
+        assert_eq!(tree.nodes[VariationTree::ROOT].children, vec![a, c]);
+        assert_eq!(tree.play(a, (1, 0)), Err(MakeActionError::PointOccupied));
+
+        tree.nodes[b].comment = "Ponnuki [sort of]".to_string();
+        assert_eq!(
+            export::variation_sgf(&tree),
+            "(;FF[4]GM[1]SZ[9]AB[ba](;W[aa];B[ab]C[Ponnuki [sort of\\]])(;W[ee]))"
+        );
+
+        tree.prune(a);
+        assert_eq!(tree.nodes[VariationTree::ROOT].children, vec![c]);
+    }
+
+    #[test]
+    fn variation_tree_layout() {
+        let mut tree = VariationTree::new(Board::empty(9, 9, false), vec![Color(1), Color(2)]);
+        let a = tree.play(VariationTree::ROOT, (0, 0)).unwrap();
+        let b = tree.play(a, (1, 0)).unwrap();
+        let c = tree.play(b, (2, 0)).unwrap();
+        // Branches off the first move and off the second.
+        let d = tree.play(a, (3, 0)).unwrap();
+        let e = tree.play(d, (4, 0)).unwrap();
+        let f = tree.play(VariationTree::ROOT, (5, 0)).unwrap();
+        let g = tree.play(f, (6, 0)).unwrap();
+
+        let cells = tree.layout();
+        assert_eq!(cells[VariationTree::ROOT], Some((0, 0)));
+        assert_eq!(cells[c], Some((3, 0)));
+        assert_eq!(cells[d], Some((2, 1)));
+        assert_eq!(cells[e], Some((3, 1)));
+        // The second first move fits under the first one, its answer has to go below
+        // the branch taken earlier.
+        assert_eq!(cells[f], Some((1, 1)));
+        assert_eq!(cells[g], Some((2, 2)));
+
+        tree.prune(d);
+        let cells = tree.layout();
+        assert_eq!(cells[d], None);
+        assert_eq!(cells[g], Some((2, 1)));
+    }
+}
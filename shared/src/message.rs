@@ -4,6 +4,12 @@ use std::borrow::Cow;
 
 use crate::game;
 
+/// Bumped whenever the client and server messages change incompatibly.
+pub const PROTOCOL_VERSION: u32 = 3;
+
+/// Maximum size of the serialized user settings in bytes.
+pub const MAX_SETTINGS_SIZE: usize = 4096;
+
 ///////////////////////////////////////////////////////////////////////////////
 //                              Client messages                              //
 ///////////////////////////////////////////////////////////////////////////////
@@ -14,25 +20,172 @@ pub enum GameAction {
     Pass,
     Cancel,
     Resign,
+    /// Pie rule, only right after the first move.
+    Swap,
     BoardAt(u32, u32),
     TakeSeat(u32),
     LeaveSeat(u32),
     KickPlayer(u64),
-    RequestSGF,
+    /// Only once the game is over.
+    RequestSGF(game::export::SgfOptions),
+    /// Per move statistics as CSV, see `game::export::stats_csv`. Only once the game is over.
+    RequestStats,
+    /// Points the seat could play on its turn, answered with `LegalMoves`. Not available
+    /// in games with hidden stones.
+    RequestLegalMoves(u32),
+    /// Rough score of each team, answered with `ScoreEstimate` to the asking session only.
+    /// Open to spectators as well, nothing changes for the players.
+    EstimateScore,
+    /// Only allowed for the room owner, and not while they hold a seat in an unfinished
+    /// game. Admins use `AdminAction::DumpRoom`.
+    RequestStateDump,
+    Chat {
+        channel: ChatChannel,
+        text: String,
+    },
+    /// The user is writing in the channel. Relayed to the others as `UserTyping`, never stored.
+    Typing(ChatChannel),
+    /// Only allowed for players once the game is over. Shared with the whole room.
+    Review(game::ReviewAction),
+    /// Only allowed for the teacher of a teaching game, see `game::Game::annotate`.
+    Annotate(game::AnnotationAction),
+    /// Places or removes `(x, y, team)` stones during the setup phase. Team 0 removes.
+    SetupStones(Vec<(u32, u32, u8)>),
+    /// Ends a pause for a disconnected player, see `game::Game::resume`.
+    Resume,
+    /// Wins against a player who didn't return in time, see `game::Game::claim`.
+    Claim,
+    /// Seats the external engine the server is configured with. Only allowed for the room
+    /// owner, and only in games the engine understands, see `game::export::gtp_supported`.
+    SeatEngine(u32),
+    /// Shows the board to the whole room the way the owner picked. Only allowed for the room
+    /// owner, relayed as `ServerMessage::Presentation`.
+    Present(BoardPresentation),
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BoardPalette {
+    Normal,
+    Colorblind,
+}
+
+/// How the room owner wants the board drawn for everyone, for lessons and streams. Each
+/// `None` leaves the member's own setting, and members can ignore it altogether.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct BoardPresentation {
+    pub palette: Option<BoardPalette>,
+    pub coordinates: Option<bool>,
+    /// Quarter turns clockwise, 0 to 3.
+    pub rotation: Option<u8>,
+}
+
+impl BoardPresentation {
+    pub fn check(&self) -> Result<(), Error> {
+        match self.rotation {
+            Some(rotation) if rotation > 3 => Err(Error::other("Invalid rotation")),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ChatChannel {
+    /// Visible to everyone in the room.
+    Main,
+    /// Kibitz channel. Only visible to and writable by members who don't hold a seat.
+    Spectator,
+}
+
+/// Author of chat lines sent by the server itself.
+pub const SYSTEM_USER_ID: u64 = 0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChatLine {
+    pub user_id: u64,
+    pub channel: ChatChannel,
+    pub text: String,
+}
+
+/// Users whose chat and invites someone doesn't want, see `ClientMessage::Block`. Only
+/// the user who blocks ever sees it.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct BlockList(pub Vec<u64>);
+
+impl BlockList {
+    pub const MAX_LEN: usize = 200;
+
+    pub fn blocks(&self, user_id: u64) -> bool {
+        self.0.contains(&user_id)
+    }
+
+    pub fn set(&mut self, user_id: u64, blocked: bool) -> Result<(), Error> {
+        if user_id == SYSTEM_USER_ID {
+            return Err(Error::other("Can't block the server"));
+        }
+        if !blocked {
+            self.0.retain(|&id| id != user_id);
+        } else if !self.blocks(user_id) {
+            if self.0.len() >= Self::MAX_LEN {
+                return Err(Error::other("Too many blocked users"));
+            }
+            self.0.push(user_id);
+        }
+        Ok(())
+    }
+
+    /// Whether a chat line reaches the user.
+    pub fn delivers(&self, line: &ChatLine) -> bool {
+        !self.blocks(line.user_id)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct StartGame {
     pub name: String,
     pub seats: Vec<u8>,
+    /// In half points, indexed by team. Left empty for the usual komi of the size and
+    /// rules, see `game::default_komi`.
     pub komis: Vec<i32>,
     pub size: (u8, u8),
     pub mods: game::GameModifier,
+    /// Seeds all randomness of the game, so rooms created with the same seed play out
+    /// the same. A random seed is used if `None`.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Keeps a variant game without a clock instead of giving it `game::default_clock`.
+    #[serde(default)]
+    pub untimed: bool,
+}
+
+impl StartGame {
+    /// The rules the game is created with. A clock given in `mods` is kept, otherwise
+    /// the variant's default is filled in unless the game is `untimed`.
+    pub fn modifiers(&self) -> game::GameModifier {
+        let mut mods = self.mods.clone();
+        if mods.clock.is_none() && !self.untimed {
+            mods.clock = game::default_clock(self.size, &mods);
+        }
+        mods
+    }
+
+    /// The settings as a string that can be shared and pasted back with `from_preset`.
+    pub fn to_preset(&self) -> String {
+        serde_json::to_string(self).expect("Preset serialization failed")
+    }
+
+    /// Reads a preset made by `to_preset`. Only the format is checked here, the server
+    /// validates the rules when the room is created.
+    pub fn from_preset(preset: &str) -> Result<StartGame, Error> {
+        serde_json::from_str(preset.trim()).map_err(|_| Error::other("Not a valid preset"))
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum AdminAction {
     UnloadRoom(u32),
+    /// Restarts the game of a room from an empty board. Seats are vacated.
+    ResetRoom(u32),
+    DumpRoom(u32),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -41,6 +194,18 @@ pub enum ClientMode {
     Integration,
 }
 
+/// How the server encodes its messages to a client. Chosen by the client with
+/// `ClientMessage::Encoding`, available since protocol version 2.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// CBOR with field and variant names, easy to inspect.
+    #[default]
+    Cbor,
+    /// CBOR with fields and variants as indices. Smaller and faster to parse, but
+    /// only readable with the same message definitions.
+    PackedCbor,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, From)]
 pub enum ClientMessage {
     #[from(ignore)]
@@ -50,19 +215,55 @@ pub enum ClientMessage {
     },
     #[from(ignore)]
     GetGameList,
+    /// Lists one page of the rooms matching the filter, answered with `GameList`.
+    #[from(ignore)]
+    FindGames(RoomFilter),
     #[from(ignore)]
     JoinGame(u32),
+    /// Joins a random game in progress as a spectator. Answered with `RandomGame`.
+    #[from(ignore)]
+    SpectateRandomGame,
     /// `None` leaves all rooms
     #[from(ignore)]
     LeaveGame(Option<u32>),
+    /// Joins every room the user joins as a spectator, answered with `Followed` each time.
+    /// `None` stops following.
+    #[from(ignore)]
+    Follow(Option<u64>),
+    /// Asks another online user to join the room, delivered to them as `Invited`.
+    #[from(ignore)]
+    Invite {
+        user_id: u64,
+        room_id: u32,
+    },
     #[from(ignore)]
     GameAction {
         room_id: Option<u32>,
         action: GameAction,
     },
-    StartGame(StartGame),
+    /// Boxed, the modifiers make it much larger than the other messages.
+    StartGame(Box<StartGame>),
+    /// Creates a room from a preset string, see `StartGame::to_preset`.
+    #[from(ignore)]
+    StartGamePreset(String),
     Admin(AdminAction),
     Mode(ClientMode),
+    Encoding(Encoding),
+    #[from(ignore)]
+    GetInfo,
+    /// Replaces the stored client settings of the user. Must be a JSON object.
+    #[from(ignore)]
+    UpdateSettings(serde_json::Value),
+    /// Stops or resumes delivering chat and invites from the user, see `BlockList`.
+    #[from(ignore)]
+    Block {
+        user_id: u64,
+        blocked: bool,
+    },
+    /// Opens a room with the first problem of the server's problem set the user hasn't
+    /// solved, with the user in the solver's seat. Answered with `ProblemProgress`.
+    #[from(ignore)]
+    NextProblem,
 }
 
 impl std::convert::From<GameAction> for ClientMessage {
@@ -82,12 +283,165 @@ impl std::convert::From<GameAction> for ClientMessage {
 pub struct Profile {
     pub user_id: u64,
     pub nick: Option<String>,
+    /// Rating on the EGF scale, see `rank_label`. `None` while the player is unrated.
+    #[serde(default)]
+    pub rating: Option<f32>,
+}
+
+/// Lowest rating of 1 dan on the EGF scale. Every rank spans 100 points.
+pub const FIRST_DAN_RATING: f32 = 2050.0;
+
+/// Rank of a rating like `5k` or `2d`, between 30 kyu and 9 dan. Unrated players are `?`.
+pub fn rank_label(rating: Option<f32>) -> String {
+    let rating = match rating {
+        Some(rating) => rating,
+        None => return "?".to_string(),
+    };
+    if rating >= FIRST_DAN_RATING {
+        let dan = ((rating - FIRST_DAN_RATING) / 100.0).floor() as u32 + 1;
+        format!("{}d", dan.min(9))
+    } else {
+        let kyu = ((FIRST_DAN_RATING - rating) / 100.0).ceil() as u32;
+        format!("{}k", kyu.clamp(1, 30))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RoomInfo {
+    pub room_id: u32,
+    pub name: String,
+    #[serde(default)]
+    pub thumbnail: Option<game::BoardThumbnail>,
+    /// Users in the room without a seat.
+    #[serde(default)]
+    pub spectators: u32,
+    #[serde(default)]
+    pub size: (u8, u8),
+    /// See `GameModifier::variants`.
+    #[serde(default)]
+    pub variants: Vec<String>,
+    /// See `Game::in_progress`.
+    #[serde(default)]
+    pub in_progress: bool,
+    /// See `GameModifier::no_spectators`.
+    #[serde(default)]
+    pub no_spectators: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum RoomSort {
+    #[default]
+    Newest,
+    MostSpectators,
+    /// Largest board first.
+    BoardSize,
+}
+
+/// Which rooms a lobby listing shows and in what order. The default lists every room,
+/// newest first.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct RoomFilter {
+    /// Only rooms playing this variant, by its name in `GameModifier::variants`.
+    /// An empty name only matches standard go.
+    #[serde(default)]
+    pub variant: Option<String>,
+    #[serde(default)]
+    pub size: Option<(u8, u8)>,
+    /// `Some(true)` only lists games being played, `Some(false)` games that haven't
+    /// started or are over.
+    #[serde(default)]
+    pub in_progress: Option<bool>,
+    #[serde(default)]
+    pub sort: RoomSort,
+    /// Matching rooms skipped before the page starts.
+    #[serde(default)]
+    pub offset: u32,
+    /// Rooms on a page, `None` for all of them.
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+impl RoomFilter {
+    pub fn matches(&self, room: &RoomInfo) -> bool {
+        let variant = match self.variant.as_deref() {
+            None => true,
+            Some("") => room.variants.is_empty(),
+            Some(name) => room.variants.iter().any(|v| v == name),
+        };
+        variant
+            && self.size.is_none_or(|size| room.size == size)
+            && self.in_progress.is_none_or(|p| room.in_progress == p)
+    }
+
+    /// The page of matching `rooms` in order, and the number of rooms matching in total.
+    pub fn apply(&self, rooms: Vec<RoomInfo>) -> (Vec<RoomInfo>, u32) {
+        let mut rooms = rooms
+            .into_iter()
+            .filter(|room| self.matches(room))
+            .collect::<Vec<_>>();
+        let total = rooms.len() as u32;
+
+        // Ties stay newest first.
+        rooms.sort_unstable_by_key(|room| std::cmp::Reverse(room.room_id));
+        match self.sort {
+            RoomSort::Newest => {}
+            RoomSort::MostSpectators => {
+                rooms.sort_by_key(|room| std::cmp::Reverse(room.spectators));
+            }
+            RoomSort::BoardSize => {
+                let area = |room: &RoomInfo| room.size.0 as u32 * room.size.1 as u32;
+                rooms.sort_by_key(|room| std::cmp::Reverse(area(room)));
+            }
+        }
+
+        let limit = self.limit.unwrap_or(u32::MAX) as usize;
+        let page = rooms
+            .into_iter()
+            .skip(self.offset as usize)
+            .take(limit)
+            .collect();
+        (page, total)
+    }
+}
+
+/// A room where the user holds a seat in an unfinished game.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SeatedRoom {
+    pub room_id: u32,
+    pub name: String,
+    #[serde(default)]
+    pub thumbnail: Option<game::BoardThumbnail>,
+    /// The game is waiting on the user, for a move or to accept the score.
+    pub your_turn: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ServerInfo {
+    pub version: String,
+    pub protocol_version: u32,
+    pub uptime_secs: u64,
+    pub room_count: u32,
+    pub user_count: u32,
+}
+
+/// Everything needed to reproduce a room, sent as JSON in `ServerMessage::StateDump`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RoomDump {
+    pub room_id: u32,
+    pub name: String,
+    pub owner: Option<u64>,
+    pub members: Vec<u64>,
+    pub kicked_players: Vec<u64>,
+    pub game: game::GameDump,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, From)]
 pub enum GameError {
     TakeSeat(game::TakeSeatError),
     Action(game::MakeActionError),
+    /// The action is bound to a seat but the user doesn't hold one.
+    #[from(ignore)]
+    NoSeat,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -108,6 +462,58 @@ impl Error {
     }
 }
 
+/// Wording shown to users, kept here so every client says the same.
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::GameStartTimer(secs) => {
+                write!(f, "Wait {} seconds before creating another game", secs)
+            }
+            Error::Game { error, .. } => write!(f, "{}", error),
+            Error::RateLimit => write!(f, "Slow down, too many messages"),
+            Error::Other(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+impl std::fmt::Display for GameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use game::{MakeActionError::*, RestrictedArea, TakeSeatError};
+
+        let text = match self {
+            GameError::NoSeat => "Take a seat first",
+            GameError::TakeSeat(TakeSeatError::DoesNotExist) => "No such seat",
+            GameError::TakeSeat(TakeSeatError::NotOpen) => "The seat is taken",
+            GameError::TakeSeat(TakeSeatError::CanOnlyHoldOne) => "You can only hold one seat",
+            GameError::Action(error) => match error {
+                NotPlayer => "You're not playing in this game",
+                NotTurn => "Not your turn",
+                OutOfBounds => "Outside the board",
+                PointOccupied => "There's a stone there already",
+                Suicide => "Suicide isn't allowed",
+                Ko => "Ko, play elsewhere first",
+                Illegal => "Not allowed right now",
+                GameDone => "The game is over",
+                WrongMove => "Wrong move",
+                TooManyStones => "The board is full for this game",
+                GroupTooLarge => "The group would grow too large",
+                NotDone => "Only once the game is over",
+                UndoDisabled => "Undo is disabled in this game",
+                Restricted(RestrictedArea::Edge { lines }) => {
+                    return write!(f, "The first {} lines are closed this early", lines);
+                }
+                Restricted(RestrictedArea::Center { size }) => {
+                    return write!(f, "The center {0}x{0} is closed this early", size);
+                }
+                ResumeLimit => "Play can't be resumed any more",
+                Paused => "The game is paused for a disconnected player",
+                NotEnoughPlayers => "Waiting for more players to take a seat",
+            },
+        };
+        write!(f, "{}", text)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(clippy::large_enum_variant)]
 pub enum ServerMessage {
@@ -115,10 +521,33 @@ pub enum ServerMessage {
         token: String,
         nick: Option<String>,
         user_id: u64,
+        /// Client settings stored for the user, `Null` if none.
+        #[serde(default)]
+        settings: serde_json::Value,
+        /// Users this user blocked.
+        #[serde(default)]
+        blocked: BlockList,
     },
     AnnounceGame {
         room_id: u32,
         name: String,
+        #[serde(default)]
+        thumbnail: Option<game::BoardThumbnail>,
+        #[serde(default)]
+        spectators: u32,
+        /// See `GameModifier::no_spectators`.
+        #[serde(default)]
+        no_spectators: bool,
+    },
+    /// Answers `FindGames`. `total` counts the matching rooms on every page.
+    GameList {
+        rooms: Vec<RoomInfo>,
+        total: u32,
+    },
+    /// The spectator count of a listed room changed. Sent at most once per interval per room.
+    RoomUpdated {
+        room_id: u32,
+        spectators: u32,
     },
     CloseGame {
         room_id: u32,
@@ -127,28 +556,96 @@ pub enum ServerMessage {
         room_id: u32,
         owner: u64,
         members: Vec<u64>,
-        seats: Vec<(Option<u64>, u8, bool)>,
-        turn: u32,
-        // 19x19 vec, 0 = empty, 1 = black, 2 = white
-        board: Vec<u8>,
-        board_visibility: Option<Vec<u16>>,
-        hidden_stones_left: u32,
-        size: (u8, u8),
-        state: game::GameStateView,
-        mods: game::GameModifier,
-        points: Vec<i32>,
-        move_number: u32,
-        clock: Option<game::clock::GameClock>,
+        /// Sent as the game built it, so the server and the clients can't disagree on its fields.
+        view: game::GameView,
+    },
+    /// Play began in the room, see `Game::has_started`. Sent once per game.
+    GameStarted {
+        room_id: u32,
+        config: StartGame,
     },
     BoardAt {
         room_id: u32,
         view: game::GameHistory,
     },
+    Review {
+        room_id: u32,
+        view: game::ReviewView,
+    },
+    /// Everything the teacher has drawn in the room, sent whenever it changes.
+    Annotations {
+        room_id: u32,
+        annotations: Vec<game::Annotation>,
+    },
+    /// The board presentation of the room, sent whenever the owner changes it.
+    Presentation {
+        room_id: u32,
+        presentation: BoardPresentation,
+    },
     SGF {
         room_id: u32,
         sgf: String,
     },
+    Stats {
+        room_id: u32,
+        csv: String,
+    },
+    LegalMoves {
+        room_id: u32,
+        seat: u32,
+        moves: Vec<(u32, u32)>,
+    },
+    ScoreEstimate {
+        room_id: u32,
+        move_number: u32,
+        scores: game::GroupVec<i32>,
+    },
+    /// `RoomDump` serialized as JSON.
+    StateDump {
+        room_id: u32,
+        json: String,
+    },
+    Chat {
+        room_id: u32,
+        line: ChatLine,
+    },
+    /// Someone else is writing in a channel we can see. Show it until a few seconds pass
+    /// without another one.
+    UserTyping {
+        room_id: u32,
+        user_id: u64,
+        channel: ChatChannel,
+    },
+    /// Replaces the known chat of a room. Sent when joining.
+    ChatHistory {
+        room_id: u32,
+        lines: Vec<ChatLine>,
+    },
     Profile(Profile),
+    /// The room joined for `ClientMessage::SpectateRandomGame`.
+    RandomGame {
+        room_id: u32,
+    },
+    /// The room joined after the user of `ClientMessage::Follow`.
+    Followed {
+        user_id: u64,
+        room_id: u32,
+    },
+    /// Another user invited us to their room, see `ClientMessage::Invite`.
+    Invited {
+        from: u64,
+        room_id: u32,
+        room_name: String,
+    },
+    /// Rooms where the user holds a seat in an unfinished game, the ones waiting on
+    /// them first. Sent after `Identify`.
+    ActiveRooms(Vec<SeatedRoom>),
+    /// Problems of the server's set the user solved, sent when starting and solving one.
+    ProblemProgress {
+        solved: u32,
+        total: u32,
+    },
+    Info(ServerInfo),
     ServerTime(game::clock::Millisecond),
     MsgError(String),
     Error(Error),
@@ -156,6 +653,152 @@ pub enum ServerMessage {
 
 impl ServerMessage {
     pub fn pack(&self) -> Vec<u8> {
-        serde_cbor::to_vec(self).expect("cbor fail")
+        self.pack_with(Encoding::Cbor)
+    }
+
+    pub fn pack_with(&self, encoding: Encoding) -> Vec<u8> {
+        match encoding {
+            Encoding::Cbor => serde_cbor::to_vec(self).expect("cbor fail"),
+            Encoding::PackedCbor => {
+                let mut buf = Vec::new();
+                self.serialize(&mut serde_cbor::Serializer::new(&mut buf).packed_format())
+                    .expect("cbor fail");
+                buf
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_messages() {
+        let game_error = |error| Error::Game { room_id: 3, error };
+        let errors = vec![
+            (
+                game_error(GameError::Action(game::MakeActionError::Ko)),
+                "Ko, play elsewhere first",
+            ),
+            (
+                game_error(GameError::Action(game::MakeActionError::Restricted(
+                    game::RestrictedArea::Edge { lines: 2 },
+                ))),
+                "The first 2 lines are closed this early",
+            ),
+            (game_error(GameError::NoSeat), "Take a seat first"),
+            (
+                game_error(GameError::TakeSeat(game::TakeSeatError::NotOpen)),
+                "The seat is taken",
+            ),
+            (Error::RateLimit, "Slow down, too many messages"),
+            (
+                Error::GameStartTimer(30),
+                "Wait 30 seconds before creating another game",
+            ),
+            (Error::other("Not room owner"), "Not room owner"),
+        ];
+
+        for (error, text) in errors {
+            for &encoding in &[Encoding::Cbor, Encoding::PackedCbor] {
+                let bytes = ServerMessage::Error(error.clone()).pack_with(encoding);
+                match serde_cbor::from_slice(&bytes).unwrap() {
+                    ServerMessage::Error(decoded) => assert_eq!(decoded.to_string(), text),
+                    msg => panic!("Decoded {:?}", msg),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn room_filter_by_variant() {
+        use game::{GameModifier, TetrisGo};
+
+        let room = |room_id, mods: GameModifier| RoomInfo {
+            room_id,
+            name: format!("Room {}", room_id),
+            thumbnail: None,
+            spectators: 0,
+            size: (19, 19),
+            variants: mods.variants().into_iter().map(String::from).collect(),
+            in_progress: false,
+            no_spectators: false,
+        };
+        let pixel = GameModifier {
+            pixel: true,
+            ..GameModifier::default()
+        };
+        let pixel_tetris = GameModifier {
+            pixel: true,
+            tetris: Some(TetrisGo {}),
+            ..GameModifier::default()
+        };
+        let rooms = vec![
+            room(1, pixel.clone()),
+            room(2, GameModifier::default()),
+            room(3, pixel_tetris),
+            room(4, pixel),
+        ];
+
+        let filter = RoomFilter {
+            variant: Some("Pixel go".to_string()),
+            ..RoomFilter::default()
+        };
+        let (page, total) = filter.apply(rooms.clone());
+        assert_eq!(total, 3);
+        let ids = page.iter().map(|r| r.room_id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![4, 3, 1]);
+
+        let filter = RoomFilter {
+            offset: 1,
+            limit: Some(1),
+            ..filter
+        };
+        let (page, total) = filter.apply(rooms.clone());
+        assert_eq!(total, 3);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].room_id, 3);
+
+        let standard = RoomFilter {
+            variant: Some(String::new()),
+            ..RoomFilter::default()
+        };
+        let (page, total) = standard.apply(rooms);
+        assert_eq!(total, 1);
+        assert_eq!(page[0].room_id, 2);
+    }
+
+    #[test]
+    fn blocked_chat_is_not_delivered() {
+        let line = |user_id| ChatLine {
+            user_id,
+            channel: ChatChannel::Main,
+            text: "hi".to_string(),
+        };
+
+        let mut blocked = BlockList::default();
+        blocked.set(2, true).unwrap();
+        // Blocking twice keeps a single entry.
+        blocked.set(2, true).unwrap();
+        assert_eq!(blocked.0, vec![2]);
+        assert!(!blocked.delivers(&line(2)));
+        assert!(blocked.delivers(&line(3)));
+        assert!(blocked.set(SYSTEM_USER_ID, true).is_err());
+
+        blocked.set(2, false).unwrap();
+        assert!(blocked.delivers(&line(2)));
+    }
+
+    #[test]
+    fn rank_labels() {
+        assert_eq!(rank_label(None), "?");
+        assert_eq!(rank_label(Some(2100.0)), "1d");
+        assert_eq!(rank_label(Some(2050.0)), "1d");
+        assert_eq!(rank_label(Some(2049.0)), "1k");
+        assert_eq!(rank_label(Some(1550.0)), "5k");
+        assert_eq!(rank_label(Some(2250.0)), "3d");
+        assert_eq!(rank_label(Some(-1000.0)), "30k");
+        assert_eq!(rank_label(Some(3500.0)), "9d");
     }
 }
@@ -204,6 +204,7 @@ impl FreePlacement {
                 // We don't allow resigning in free placement
                 Ok(ActionChange::None)
             }
+            ActionKind::Swap => Err(MakeActionError::Illegal),
         }
     }
 }
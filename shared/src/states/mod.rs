@@ -1,13 +1,15 @@
 pub mod free_placement;
 pub mod play;
 pub mod scoring;
+pub mod setup;
 
 pub use self::free_placement::FreePlacement;
 pub use self::play::PlayState;
 pub use self::scoring::ScoringState;
+pub use self::setup::SetupState;
 
 use crate::assume::AssumeFrom;
-use crate::game::{Board, Seat};
+use crate::game::{Board, Seat, SekiScoring};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -16,6 +18,7 @@ pub enum GameState {
     Play(PlayState),
     Scoring(ScoringState),
     Done(ScoringState),
+    Setup(SetupState),
 }
 
 impl GameState {
@@ -37,8 +40,8 @@ impl GameState {
         GameState::Play(PlayState::new(seat_count))
     }
 
-    pub fn scoring(board: &Board, seats: &[Seat], scores: &[i32]) -> Self {
-        GameState::Scoring(ScoringState::new(board, seats, scores))
+    pub fn scoring(board: &Board, seats: &[Seat], scores: &[i32], seki: SekiScoring) -> Self {
+        GameState::Scoring(ScoringState::new(board, seats, scores, seki))
     }
 }
 
@@ -46,3 +49,4 @@ assume!(GameState);
 assume!(GameState, Play(x) => x, PlayState);
 assume!(GameState, Scoring(x) => x, ScoringState);
 assume!(GameState, FreePlacement(x) => x, FreePlacement);
+assume!(GameState, Setup(x) => x, SetupState);
@@ -1,17 +1,18 @@
 mod n_plus_one;
+mod problem;
 mod tetris;
 pub(crate) mod traitor;
 
 use crate::game::{
     find_groups, ActionChange, ActionKind, Board, BoardHistory, Color, GameState, Group, GroupVec,
-    MakeActionError, MakeActionResult, Point, SharedState, VisibilityBoard,
+    MakeActionError, MakeActionResult, Point, SharedState, StoneCap, VisibilityBoard,
 };
 use serde::{Deserialize, Serialize};
 
 use bitmaps::Bitmap;
 use tinyvec::tiny_vec;
 
-use super::ScoringState;
+use super::scoring::{ScoringState, MAX_SCORING_RESUMES};
 
 type Revealed = bool;
 
@@ -20,8 +21,34 @@ pub struct PlayState {
     // TODO: use smallvec?
     pub players_passed: Vec<bool>,
     pub last_stone: Option<GroupVec<(u32, u32)>>,
+    /// Stones removed by the last move in the order they were taken. Empty after a pass.
+    #[serde(default)]
+    pub last_captured: Vec<Point>,
     /// Optimization for superko
     pub capture_count: usize,
+    /// Prisoners received from opponent passes, indexed by team.
+    #[serde(default)]
+    pub pass_stones: GroupVec<i32>,
+    /// Stones captured by each team, indexed by team. Tracked whether or not the
+    /// scoring uses them.
+    #[serde(default)]
+    pub prisoners: GroupVec<u32>,
+    /// The players already swapped seats under the pie rule.
+    #[serde(default)]
+    pub swapped: bool,
+    /// Times the players passed into scoring.
+    #[serde(default)]
+    pub scoring_rounds: u32,
+    /// Passes made for absent seats as `(move number, seat)`, see `SkipAbsent`.
+    #[serde(default)]
+    pub skips: Vec<(u32, u32)>,
+    /// Passes in a row since the last stone was placed.
+    #[serde(default)]
+    pub pass_streak: u32,
+    /// Points in half points earned by each team under `CaptureBonus`, indexed by team.
+    /// Already part of the shared points.
+    #[serde(default)]
+    pub capture_bonuses: GroupVec<i32>,
 }
 
 impl PlayState {
@@ -29,7 +56,15 @@ impl PlayState {
         PlayState {
             players_passed: vec![false; seat_count],
             last_stone: None,
+            last_captured: Vec::new(),
             capture_count: 0,
+            pass_stones: GroupVec::new(),
+            prisoners: GroupVec::new(),
+            swapped: false,
+            scoring_rounds: 0,
+            skips: Vec::new(),
+            pass_streak: 0,
+            capture_bonuses: GroupVec::new(),
         }
     }
 
@@ -77,6 +112,7 @@ impl PlayState {
             if !any_placed {
                 if any_revealed {
                     self.last_stone = Some(points_played);
+                    self.last_captured.clear();
                     return Ok(GroupVec::new());
                 }
                 return Err(MakeActionError::PointOccupied);
@@ -98,6 +134,7 @@ impl PlayState {
             if !point.is_empty() {
                 if revealed {
                     self.last_stone = Some(tiny_vec![[Point; 8] => (x, y)]);
+                    self.last_captured.clear();
                     return Ok(points_played);
                 }
                 return Err(MakeActionError::PointOccupied);
@@ -115,9 +152,10 @@ impl PlayState {
         shared: &mut SharedState,
         points_played: &mut GroupVec<Point>,
         color_placed: Color,
-    ) -> (usize, Revealed) {
+    ) -> (Vec<Point>, usize, Revealed) {
         let active_seat = shared.get_active_seat();
-        let mut captures = 0;
+        let mut captured = Vec::new();
+        let mut suicided = 0;
         let mut revealed = false;
 
         if shared.mods.phantom.is_some() {
@@ -134,7 +172,7 @@ impl PlayState {
             let board = &mut shared.board;
             for point in &group.points {
                 *board.point_mut(*point) = Color::empty();
-                captures += 1;
+                captured.push(*point);
             }
             let reveals = reveal_group(shared.board_visibility.as_mut(), group, board);
 
@@ -174,6 +212,20 @@ impl PlayState {
             .filter(|g| g.liberties == 0 && g.team == color_placed);
 
         for group in dead_own {
+            let played = group.points.iter().any(|p| points_played.contains(p));
+            if played
+                && color_placed == active_seat.team
+                && shared.mods.suicide.allows(group.points.len())
+            {
+                let reveals = reveal_group(shared.board_visibility.as_mut(), group, &shared.board);
+                revealed = revealed || reveals;
+                for point in &group.points {
+                    *shared.board.point_mut(*point) = Color::empty();
+                }
+                suicided += group.points.len();
+                continue;
+            }
+
             let mut removed_move = false;
             for point in &group.points {
                 if points_played.contains(point) && color_placed == active_seat.team {
@@ -194,10 +246,85 @@ impl PlayState {
         }
 
         if shared.mods.captures_give_points.is_some() {
-            shared.points[active_seat.team.0 as usize - 1] += captures as i32 * 2;
+            shared.points[active_seat.team.0 as usize - 1] += captured.len() as i32 * 2;
         }
 
-        (captures, revealed)
+        (captured, suicided, revealed)
+    }
+
+    /// True if the seat on turn can place a stone at `point`. Leaves `shared` as it was.
+    pub fn is_legal(&self, shared: &mut SharedState, point: Point) -> bool {
+        let board = shared.board.clone();
+        let board_visibility = shared.board_visibility.clone();
+        let points = shared.points.clone();
+        let turn = shared.turn;
+        let traitor = shared.traitor.clone();
+        let history_len = shared.board_history.len();
+
+        let color = shared.get_active_seat().team;
+        let res = self.clone().make_action_place(shared, point, color);
+        let legal = res.is_ok() && shared.board_history.len() > history_len;
+
+        shared.board = board;
+        shared.board_visibility = board_visibility;
+        shared.points = points;
+        shared.turn = turn;
+        shared.traitor = traitor;
+        shared.board_history.truncate(history_len);
+
+        legal
+    }
+
+    /// True if the seat on turn placing a stone on `point` recreates an earlier position,
+    /// checked like `basic_ko` and `superko`. Only captures are played out, so variant
+    /// rules that change the board otherwise aren't considered.
+    pub fn repeats_position(&self, shared: &SharedState, point: Point) -> bool {
+        let color = shared.get_active_seat().team;
+        let mut board = shared.board.clone();
+        *board.point_mut(point) = color;
+
+        let mut captures = 0;
+        for group in find_groups(&board) {
+            if group.liberties == 0 && group.team != color {
+                captures += group.points.len();
+                for &p in &group.points {
+                    *board.point_mut(p) = Color::empty();
+                }
+            }
+        }
+        // Without a capture the position has one stone more than any earlier one.
+        if captures == 0 {
+            return false;
+        }
+
+        let hash = board.hash();
+        let repeats = |old: &BoardHistory| old.hash == hash && old.board == board;
+        let history = &shared.board_history;
+        if shared.mods.repetition.is_some() {
+            history.len() >= 2 && repeats(&history[history.len() - 2])
+        } else {
+            history
+                .iter()
+                .rev()
+                .take(self.capture_count + captures)
+                .any(repeats)
+        }
+    }
+
+    /// The position after the seat on turn places a stone on `point`, `None` if that's
+    /// illegal. Lets bots look a move ahead without touching the game.
+    pub fn after_place(&self, shared: &SharedState, point: Point) -> Option<(Self, SharedState)> {
+        let mut shared = shared.clone();
+        let mut state = self.clone();
+        let history_len = shared.board_history.len();
+
+        let color = shared.get_active_seat().team;
+        let res = state.make_action_place(&mut shared, point, color);
+        if res.is_ok() && shared.board_history.len() > history_len {
+            Some((state, shared))
+        } else {
+            None
+        }
     }
 
     /// Superko
@@ -220,17 +347,7 @@ impl PlayState {
             .take(self.capture_count + captures)
         {
             if *old_hash == hash && old_board == &shared.board {
-                let BoardHistory {
-                    board: old_board,
-                    points: old_points,
-                    ..
-                } = shared
-                    .board_history
-                    .last()
-                    .expect("board_history.last() shouldn't be None")
-                    .clone();
-                shared.board = old_board;
-                shared.points = old_points;
+                Self::restore_position(shared);
                 return Err(MakeActionError::Ko);
             }
         }
@@ -238,12 +355,48 @@ impl PlayState {
         Ok(())
     }
 
+    /// Only forbids recreating the position from before the previous move.
+    fn basic_ko(&self, shared: &mut SharedState, hash: u64) -> MakeActionResult<()> {
+        let len = shared.board_history.len();
+        if len < 2 {
+            return Ok(());
+        }
+        let previous = &shared.board_history[len - 2];
+        if previous.hash == hash && previous.board == shared.board {
+            Self::restore_position(shared);
+            return Err(MakeActionError::Ko);
+        }
+
+        Ok(())
+    }
+
+    fn restore_position(shared: &mut SharedState) {
+        let BoardHistory {
+            board: old_board,
+            points: old_points,
+            ..
+        } = shared
+            .board_history
+            .last()
+            .expect("board_history.last() shouldn't be None")
+            .clone();
+        shared.board = old_board;
+        shared.points = old_points;
+    }
+
     fn make_action_place(
         &mut self,
         shared: &mut SharedState,
         (x, y): (u32, u32),
         color_placed: Color,
     ) -> MakeActionResult {
+        if let Some(rules) = &shared.mods.opening_restrictions {
+            let move_number = shared.board_history.len() as u32 - 1;
+            if let Some(area) = rules.forbidding(&shared.board, (x, y), move_number) {
+                return Err(MakeActionError::Restricted(area));
+            }
+        }
+
         // TODO: should use some kind of set to make suicide prevention faster
         let mut points_played = self.place_stone(shared, (x, y), color_placed)?;
         if points_played.is_empty() {
@@ -281,7 +434,8 @@ impl PlayState {
             }
         }
 
-        let (captures, revealed) = self.capture(shared, &mut points_played, color_placed);
+        let (captured, suicided, revealed) = self.capture(shared, &mut points_played, color_placed);
+        let captures = captured.len();
 
         if points_played.is_empty() {
             let BoardHistory { board, points, .. } = shared
@@ -300,7 +454,19 @@ impl PlayState {
 
         let hash = shared.board.hash();
 
-        self.superko(shared, captures, hash)?;
+        if shared.mods.repetition.is_some() {
+            self.basic_ko(shared, hash)?;
+        } else {
+            // Suicide removes stones too, so the position may repeat an older one.
+            self.superko(shared, captures + suicided, hash)?;
+        }
+
+        if let Some(cap) = shared.mods.stone_cap.clone() {
+            if let Err(e) = check_stone_cap(&shared.board, &cap) {
+                Self::restore_position(shared);
+                return Err(e);
+            }
+        }
 
         let new_turn = if let Some(rule) = &shared.mods.n_plus_one {
             use n_plus_one::NPlusOneResult::*;
@@ -318,6 +484,7 @@ impl PlayState {
         };
 
         self.last_stone = Some(points_played);
+        self.last_captured = captured;
 
         // TODO: Handle this at the view layer instead to have the marker visible for your own stones.
         if shared.mods.phantom.is_some() {
@@ -327,9 +494,43 @@ impl PlayState {
         for passed in &mut self.players_passed {
             *passed = false;
         }
+        self.pass_streak = 0;
 
+        self.add_prisoners(shared, captures as u32);
+        self.add_capture_bonus(shared, captures as u32);
+        let capture_winner = self.capture_goal_reached(shared);
+        // Before the history entry is made, undo restores the state from it.
+        self.capture_count += captures + suicided;
         self.next_turn(shared, new_turn);
-        self.capture_count += captures;
+
+        if let Some(team) = capture_winner {
+            let mut state = ScoringState::new(
+                &shared.board,
+                &shared.seats,
+                &shared.points,
+                shared.mods.seki,
+            );
+            state.capture_winner = Some(team);
+            return Ok(ActionChange::PushState(GameState::Done(state)));
+        }
+
+        if let Some(limit) = shared
+            .mods
+            .repetition
+            .as_ref()
+            .and_then(|r| r.no_result_after)
+        {
+            if shared.position_count() >= limit as usize {
+                let mut state = ScoringState::new(
+                    &shared.board,
+                    &shared.seats,
+                    &shared.points,
+                    shared.mods.seki,
+                );
+                state.no_result = true;
+                return Ok(ActionChange::PushState(GameState::Done(state)));
+            }
+        }
 
         Ok(ActionChange::None)
     }
@@ -337,28 +538,145 @@ impl PlayState {
     fn make_action_pass(&mut self, shared: &mut SharedState) -> MakeActionResult {
         let active_seat = shared.get_active_seat();
 
-        for (seat, passed) in shared.seats.iter().zip(self.players_passed.iter_mut()) {
-            if seat.team == active_seat.team {
+        // A pass speaks for the whole team, but in zen go every seat plays the color on
+        // turn, so there it only counts for the seat that passed.
+        let team_pass = shared.mods.zen_go.is_none();
+        let seats = shared.seats.iter().zip(self.players_passed.iter_mut());
+        for (idx, (seat, passed)) in seats.enumerate() {
+            if idx == shared.turn || (team_pass && seat.team == active_seat.team) {
                 *passed = true;
             }
         }
+        self.pass_streak += 1;
 
-        self.next_turn(shared, false);
+        if shared.mods.pass_gives_prisoner.is_some() {
+            self.give_pass_stones(shared, active_seat.team);
+        }
+        self.last_captured.clear();
 
-        if shared
+        let all_passed = shared
             .seats
             .iter()
             .zip(&self.players_passed)
-            .all(|(s, &pass)| s.resigned || pass)
-        {
+            .all(|(s, &pass)| s.resigned || pass);
+        if all_passed {
+            // Counted before the turn is recorded, so the history holds the new count.
+            self.scoring_rounds += 1;
+        }
+
+        self.next_turn(shared, false);
+
+        if all_passed {
             for passed in &mut self.players_passed {
                 *passed = false;
             }
-            return Ok(ActionChange::PushState(GameState::scoring(
+            self.pass_streak = 0;
+            let mut scoring = ScoringState::new(
                 &shared.board,
                 &shared.seats,
                 &shared.points,
-            )));
+                shared.mods.seki,
+            );
+            scoring.pass_stones = self.pass_stones.clone();
+            scoring.final_round = self.scoring_rounds > MAX_SCORING_RESUMES;
+            return Ok(ActionChange::PushState(GameState::Scoring(scoring)));
+        }
+
+        Ok(ActionChange::None)
+    }
+
+    /// Passes for the seat on turn without checking who holds it.
+    pub fn skip_turn(&mut self, shared: &mut SharedState) -> MakeActionResult {
+        let move_number = shared.board_history.len() as u32;
+        self.skips.push((move_number, shared.turn as u32));
+        let res = self.make_action_pass(shared);
+        self.set_zen_teams(shared);
+        res
+    }
+
+    /// Credits the stones captured by the move being made to the team on turn.
+    fn add_prisoners(&mut self, shared: &SharedState, captures: u32) {
+        if captures == 0 {
+            return;
+        }
+        let team_count = shared.komis.len();
+        if self.prisoners.len() < team_count {
+            self.prisoners.resize(team_count, 0);
+        }
+        self.prisoners[shared.get_active_seat().team.as_usize() - 1] += captures;
+    }
+
+    /// Awards `CaptureBonus` to the team on turn if the move captured enough stones.
+    fn add_capture_bonus(&mut self, shared: &mut SharedState, captures: u32) {
+        let bonus = match &shared.mods.capture_bonus {
+            Some(rule) if captures >= rule.min_stones.max(1) => rule.points * 2,
+            _ => return,
+        };
+        let team_count = shared.komis.len();
+        if self.capture_bonuses.len() < team_count {
+            self.capture_bonuses.resize(team_count, 0);
+        }
+        let team = shared.get_active_seat().team.as_usize() - 1;
+        self.capture_bonuses[team] += bonus;
+        shared.points[team] += bonus;
+    }
+
+    /// The team on turn if its prisoners reached the capture goal.
+    fn capture_goal_reached(&self, shared: &SharedState) -> Option<Color> {
+        let goal = shared.mods.capture_goal?;
+        let team = shared.get_active_seat().team;
+        let prisoners = self
+            .prisoners
+            .get(team.as_usize() - 1)
+            .copied()
+            .unwrap_or(0);
+        if prisoners >= goal {
+            Some(team)
+        } else {
+            None
+        }
+    }
+
+    /// Hands a prisoner to each team other than the passing one.
+    fn give_pass_stones(&mut self, shared: &mut SharedState, passing_team: Color) {
+        let team_count = shared.komis.len();
+        if self.pass_stones.len() < team_count {
+            self.pass_stones.resize(team_count, 0);
+        }
+
+        for team in 1..=team_count {
+            if team == passing_team.as_usize() {
+                continue;
+            }
+            self.pass_stones[team - 1] += 1;
+            // Points are stored as half points
+            shared.points[team - 1] += 2;
+        }
+    }
+
+    /// True while the second seat may swap under the pie rule, right after the first move.
+    pub fn swap_available(&self, shared: &SharedState) -> bool {
+        shared.mods.pie_rule
+            && !self.swapped
+            && shared.seats.len() == 2
+            && shared.turn == 1
+            && shared.board_history.len() == 2
+    }
+
+    /// Trades the players of the two seats. The seat on turn stays, so the player who
+    /// made the first move answers it.
+    fn make_action_swap(&mut self, shared: &mut SharedState) -> MakeActionResult {
+        if !self.swap_available(shared) {
+            return Err(MakeActionError::Illegal);
+        }
+
+        let first = shared.seats[0].player;
+        shared.seats[0].player = shared.seats[1].player;
+        shared.seats[1].player = first;
+        self.swapped = true;
+        // Undo restores the state of this snapshot, which has to remember the swap.
+        if let Some(history) = shared.board_history.last_mut() {
+            history.state = GameState::Play(self.clone());
         }
 
         Ok(ActionChange::None)
@@ -371,12 +689,28 @@ impl PlayState {
         }
 
         if shared.mods.no_undo {
-            return Err(MakeActionError::Illegal);
+            return Err(MakeActionError::UndoDisabled);
+        }
+
+        // The seats were traded after the first move, undoing it wouldn't give them back.
+        if self.swapped && shared.board_history.len() <= 2 {
+            return Err(MakeActionError::UndoDisabled);
         }
 
         self.rollback_turn(shared, true)
     }
 
+    /// True if the player made the latest move, which they may take back before anyone
+    /// replies. The seat on turn can always undo the move before theirs.
+    fn made_last_move(shared: &SharedState, player_id: u64) -> bool {
+        let len = shared.board_history.len();
+        if len < 2 {
+            return false;
+        }
+        let seat = shared.board_history[len - 2].turn;
+        shared.seats[seat].player == Some(player_id)
+    }
+
     fn rollback_turn(
         &mut self,
         shared: &mut SharedState,
@@ -399,7 +733,10 @@ impl PlayState {
         shared.turn = history.turn;
         shared.traitor = history.traitor.clone();
 
+        // Undoing the passes doesn't give back the rounds of scoring already spent.
+        let scoring_rounds = self.scoring_rounds;
         *self = history.state.assume::<PlayState>().clone();
+        self.scoring_rounds = scoring_rounds;
 
         Ok(ActionChange::None)
     }
@@ -411,23 +748,40 @@ impl PlayState {
             .expect("Game turn number invalid");
 
         active_seat.resigned = true;
+        Self::after_resign(shared)
+    }
 
+    /// Resigns every seat of a player who left the game, whoever's turn it is.
+    pub fn forfeit(&mut self, shared: &mut SharedState, player_id: u64) -> MakeActionResult {
+        let mut held = false;
+        for seat in shared.seats.iter_mut() {
+            if seat.player == Some(player_id) {
+                seat.resigned = true;
+                held = true;
+            }
+        }
+        if !held {
+            return Err(MakeActionError::NotPlayer);
+        }
+        Self::after_resign(shared)
+    }
+
+    /// Ends the game once a single seat is left, otherwise moves the turn past resigned seats.
+    fn after_resign(shared: &mut SharedState) -> MakeActionResult {
         if shared.seats.iter().filter(|s| !s.resigned).count() <= 1 {
             return Ok(ActionChange::PushState(GameState::Done(ScoringState::new(
                 &shared.board,
                 &shared.seats,
                 &shared.points,
+                shared.mods.seki,
             ))));
         }
 
-        loop {
+        while shared.get_active_seat().resigned {
             shared.turn += 1;
             if shared.turn >= shared.seats.len() {
                 shared.turn = 0;
             }
-            if !shared.get_active_seat().resigned {
-                break;
-            }
         }
 
         Ok(ActionChange::None)
@@ -440,10 +794,20 @@ impl PlayState {
         action: ActionKind,
     ) -> MakeActionResult {
         let active_seat = shared.get_active_seat();
-        if active_seat.player != Some(player_id) {
+        let teaching = shared.is_teacher(player_id) && !matches!(action, ActionKind::Resign);
+        let own_undo =
+            matches!(action, ActionKind::Cancel) && Self::made_last_move(shared, player_id);
+        if active_seat.player != Some(player_id) && !teaching && !own_undo {
             return Err(MakeActionError::NotTurn);
         }
 
+        let problem = shared.mods.problem.clone();
+        if let Some(problem) = &problem {
+            problem::check(problem, shared, &action)?;
+        }
+        let is_place = matches!(action, ActionKind::Place(..));
+        let history_len = shared.board_history.len();
+
         let res = match action {
             ActionKind::Place(x, y) => {
                 let depth = shared.board_history.len();
@@ -482,10 +846,17 @@ impl PlayState {
             ActionKind::Pass => self.make_action_pass(shared),
             ActionKind::Cancel => self.make_action_cancel(shared),
             ActionKind::Resign => self.make_action_resign(shared),
+            ActionKind::Swap => self.make_action_swap(shared),
         };
 
         let res = res?;
 
+        if let Some(problem) = &problem {
+            if is_place && shared.board_history.len() > history_len {
+                problem::respond(self, shared, problem);
+            }
+        }
+
         self.set_zen_teams(shared);
 
         Ok(res)
@@ -504,6 +875,8 @@ impl PlayState {
             }
         }
 
+        self.expand_board(shared);
+
         shared.board_history.push(BoardHistory {
             hash: shared.board.hash(),
             board: shared.board.clone(),
@@ -515,6 +888,42 @@ impl PlayState {
         });
     }
 
+    /// Grows the board if the move being recorded is on the expanding board schedule.
+    fn expand_board(&mut self, shared: &mut SharedState) {
+        let rule = match &shared.mods.expanding_board {
+            Some(rule) => rule,
+            None => return,
+        };
+
+        let move_number = shared.board_history.len() as u32;
+        if !move_number.is_multiple_of(rule.interval) {
+            return;
+        }
+
+        let (old_width, old_height) = (shared.board.width, shared.board.height);
+        let width = (old_width + rule.growth).min(rule.max_size).max(old_width);
+        let height = (old_height + rule.growth)
+            .min(rule.max_size)
+            .max(old_height);
+        if (width, height) == (old_width, old_height) {
+            return;
+        }
+
+        let offset = ((width - old_width) / 2, (height - old_height) / 2);
+        shared.board = shared.board.expanded((width, height), offset);
+        if let Some(visibility) = &mut shared.board_visibility {
+            *visibility = visibility.expanded((width, height), offset);
+        }
+        if let Some(last_stone) = &mut self.last_stone {
+            for point in last_stone {
+                *point = (point.0 + offset.0, point.1 + offset.1);
+            }
+        }
+        for point in &mut self.last_captured {
+            *point = (point.0 + offset.0, point.1 + offset.1);
+        }
+    }
+
     fn set_zen_teams(&mut self, shared: &mut SharedState) {
         let move_number = shared.board_history.len() - 1;
         if let Some(zen) = &shared.mods.zen_go {
@@ -525,7 +934,26 @@ impl PlayState {
     }
 }
 
-pub(self) fn reveal_group(
+fn check_stone_cap(board: &Board, cap: &StoneCap) -> MakeActionResult<()> {
+    // The group is the more specific reason when both caps are hit.
+    if let Some(max) = cap.max_group_size {
+        if find_groups(board)
+            .iter()
+            .any(|g| g.points.len() > max as usize)
+        {
+            return Err(MakeActionError::GroupTooLarge);
+        }
+    }
+    if let Some(max) = cap.max_stones {
+        let count = board.points.iter().filter(|c| !c.is_empty()).count();
+        if count > max as usize {
+            return Err(MakeActionError::TooManyStones);
+        }
+    }
+    Ok(())
+}
+
+fn reveal_group(
     visibility: Option<&mut VisibilityBoard>,
     group: &Group,
     board: &Board,
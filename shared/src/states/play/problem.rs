@@ -0,0 +1,28 @@
+use crate::game::{ActionKind, MakeActionError, MakeActionResult, Problem, SharedState};
+
+use super::PlayState;
+
+/// Checks the solver's action against the solution before it's played.
+pub fn check(problem: &Problem, shared: &SharedState, action: &ActionKind) -> MakeActionResult<()> {
+    let move_number = shared.board_history.len() - 1;
+    match *action {
+        ActionKind::Place(x, y) => match problem.expected_move(move_number) {
+            Some(expected) if expected == (x, y) => Ok(()),
+            Some(_) => Err(MakeActionError::WrongMove),
+            None => Err(MakeActionError::GameDone),
+        },
+        ActionKind::Resign => Ok(()),
+        // Passing is never part of a solution and undo would leave the reply seat on turn.
+        ActionKind::Pass | ActionKind::Cancel | ActionKind::Swap => Err(MakeActionError::Illegal),
+    }
+}
+
+/// Plays the reply from the solution, if there is one left.
+/// A reply that turns out illegal just leaves the problem unsolvable.
+pub fn respond(state: &mut PlayState, shared: &mut SharedState, problem: &Problem) {
+    let move_number = shared.board_history.len() - 1;
+    if let Some(point) = problem.expected_move(move_number) {
+        let team = shared.get_active_seat().team;
+        let _ = state.make_action_place(shared, point, team);
+    }
+}
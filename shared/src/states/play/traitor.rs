@@ -47,7 +47,7 @@ impl TraitorState {
                 .choose(&mut self.rng_state)
                 .expect("Empty color choices in TraitorState::next_color");
 
-            Color(color as u8)
+            Color(color)
         } else {
             team_color
         }
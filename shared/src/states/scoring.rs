@@ -1,10 +1,17 @@
 use crate::game::{
     find_groups, ActionChange, ActionKind, Board, Color, GameState, Group, GroupVec,
-    MakeActionResult, Point, Seat, SharedState,
+    MakeActionError, MakeActionResult, Point, Seat, SekiScoring, SharedState,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::{HashSet, VecDeque};
 
+/// Territories up to this size can be the eye of a group in seki, see `seki_eyes`.
+const MAX_SEKI_EYE: usize = 6;
+
+/// How many times play can go back from scoring to the board. Passing into scoring
+/// after that starts a final round that has to be settled.
+pub const MAX_SCORING_RESUMES: u32 = 3;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ScoringState {
     pub groups: Vec<Group>,
@@ -13,47 +20,92 @@ pub struct ScoringState {
     pub scores: GroupVec<i32>,
     // TODO: use smallvec?
     pub players_accepted: Vec<bool>,
+    /// Prisoners each team received from opponent passes. Already included in `scores`.
+    #[serde(default)]
+    pub pass_stones: GroupVec<i32>,
+    /// The game was voided by a repetition cycle. Scores are informational only.
+    #[serde(default)]
+    pub no_result: bool,
+    /// The game ended when this team reached the capture goal. Scores are informational only.
+    #[serde(default)]
+    pub capture_winner: Option<Color>,
+    /// Play can't be resumed anymore, see `MAX_SCORING_RESUMES`.
+    #[serde(default)]
+    pub final_round: bool,
+    /// The seat that last toggled each group, indexed like `groups`. Starts over with
+    /// every round of scoring.
+    #[serde(default)]
+    pub marked_by: Vec<Option<u32>>,
+    /// Eyes of groups that look like they're in seki. Counted or left neutral by
+    /// `GameModifier::seki`.
+    #[serde(default)]
+    pub seki_eyes: Vec<Point>,
 }
 
 impl ScoringState {
-    pub fn new(board: &Board, seats: &[Seat], scores: &[i32]) -> Self {
+    pub fn new(board: &Board, seats: &[Seat], scores: &[i32], seki: SekiScoring) -> Self {
         let groups = find_groups(board);
-        let points = score_board(board, &groups);
-        let mut scores: GroupVec<i32> = scores.into();
-        for color in &points.points {
-            if !color.is_empty() {
-                scores[color.0 as usize - 1] += 2;
-            }
-        }
+        let (points, seki_eyes) = count_board(board, &groups, seki);
+        let scores = add_area(&points, scores);
         ScoringState {
             groups,
             points,
             scores,
             players_accepted: seats.iter().map(|s| s.resigned).collect(),
+            pass_stones: GroupVec::new(),
+            no_result: false,
+            capture_winner: None,
+            final_round: false,
+            marked_by: Vec::new(),
+            seki_eyes,
         }
     }
 
+    /// Neutral points next to a living stone, ie. empty points between teams. Under area
+    /// scoring they're worth a point to whoever fills them, so they should be filled first.
+    pub fn dame(&self) -> Vec<Point> {
+        let board = &self.points;
+        board
+            .points
+            .iter()
+            .enumerate()
+            .filter(|(_, color)| color.is_empty())
+            .filter_map(|(idx, _)| board.idx_to_coord(idx))
+            .filter(|&p| {
+                board
+                    .surrounding_points(p)
+                    .any(|q| !board.get_point(q).is_empty())
+            })
+            .collect()
+    }
+
     pub fn make_action_place(
         &mut self,
         shared: &mut SharedState,
+        player_id: u64,
         point: Point,
     ) -> MakeActionResult {
-        let group = self.groups.iter_mut().find(|g| g.points.contains(&point));
+        let group_idx = self.groups.iter().position(|g| g.points.contains(&point));
 
-        let group = match group {
-            Some(g) => g,
+        let group_idx = match group_idx {
+            Some(idx) => idx,
             None => return Ok(ActionChange::None),
         };
 
-        group.alive = !group.alive;
+        self.groups[group_idx].alive = !self.groups[group_idx].alive;
 
-        self.points = score_board(&shared.board, &self.groups);
-        self.scores = shared.points.clone();
-        for color in &self.points.points {
-            if !color.is_empty() {
-                self.scores[color.0 as usize - 1] += 2;
-            }
-        }
+        let seat_idx = shared
+            .seats
+            .iter()
+            .position(|s| s.player == Some(player_id));
+        self.marked_by.resize(self.groups.len(), None);
+        self.marked_by[group_idx] = seat_idx.map(|idx| idx as u32);
+
+        // Count again right away, so everyone sees what the toggle is worth.
+        let (points, seki_eyes) = count_board(&shared.board, &self.groups, shared.mods.seki);
+        self.points = points;
+        self.seki_eyes = seki_eyes;
+        self.scores = add_area(&self.points, &shared.points);
 
         for (idx, accept) in self.players_accepted.iter_mut().enumerate() {
             *accept = shared.seats[idx].resigned;
@@ -104,6 +156,23 @@ impl ScoringState {
         }
     }
 
+    /// Counts the position with the groups `likely_dead` picks as dead, whatever the
+    /// players marked. Nobody's acceptance is assumed, the game just ends with this count.
+    pub fn settle(&mut self, shared: &SharedState) {
+        for group in &mut self.groups {
+            group.alive = true;
+        }
+        for idx in likely_dead(&shared.board, &self.groups) {
+            self.groups[idx].alive = false;
+        }
+        self.marked_by.clear();
+
+        let (points, seki_eyes) = count_board(&shared.board, &self.groups, shared.mods.seki);
+        self.points = points;
+        self.seki_eyes = seki_eyes;
+        self.scores = add_area(&self.points, &shared.points);
+    }
+
     pub fn make_action(
         &mut self,
         shared: &mut SharedState,
@@ -111,16 +180,175 @@ impl ScoringState {
         action: ActionKind,
     ) -> MakeActionResult {
         match action {
-            ActionKind::Place(x, y) => self.make_action_place(shared, (x, y)),
+            ActionKind::Place(x, y) => self.make_action_place(shared, player_id, (x, y)),
             ActionKind::Pass => self.make_action_pass(shared, player_id),
+            ActionKind::Cancel if self.final_round => Err(MakeActionError::ResumeLimit),
             ActionKind::Cancel => Ok(ActionChange::PopState),
             ActionKind::Resign => self.make_action_resign(shared, player_id),
+            ActionKind::Swap => Err(MakeActionError::Illegal),
+        }
+    }
+}
+
+/// Adds a point for every point of `owners` a team owns to the `scores` from play.
+fn add_area(owners: &Board, scores: &[i32]) -> GroupVec<i32> {
+    let mut scores: GroupVec<i32> = scores.into();
+    for color in &owners.points {
+        if !color.is_empty() {
+            scores[color.0 as usize - 1] += 2;
         }
     }
+    scores
+}
+
+/// Area score estimate in half points, counting every group as alive. Komi and prisoners
+/// are not included.
+pub fn estimate_score(board: &Board, team_count: usize) -> GroupVec<i32> {
+    let points = estimate_territory(board);
+    let mut scores = GroupVec::new();
+    scores.resize(team_count, 0);
+    for color in &points.points {
+        if !color.is_empty() {
+            scores[color.0 as usize - 1] += 2;
+        }
+    }
+    scores
+}
+
+/// Marks the owner of each point counting every group as alive. Neutral points are empty.
+pub fn estimate_territory(board: &Board) -> Board {
+    score_board(board, &find_groups(board)).0
+}
+
+/// Counts a position still in play as if the game ended now, for commentary. Groups
+/// `likely_dead` calls dead are marked like a player would, the rest is counted the
+/// same as in scoring. Nothing of it reaches the game.
+pub fn hypothetical_count(board: &Board, scores: &[i32], seki: SekiScoring) -> ScoringState {
+    let mut state = ScoringState::new(board, &[], scores, seki);
+    for idx in likely_dead(board, &state.groups) {
+        state.groups[idx].alive = false;
+    }
+    let (points, seki_eyes) = count_board(board, &state.groups, seki);
+    state.points = points;
+    state.seki_eyes = seki_eyes;
+    state.scores = add_area(&state.points, scores);
+    state
 }
 
-/// Scores a board by filling in fully surrounded empty spaces based on chinese rules
-fn score_board(board: &Board, groups: &[Group]) -> Board {
+/// Groups without an eye of their own that end up inside the area of a single other
+/// team once taken off. Smaller groups go first, so the stones of an invasion don't
+/// spoil the eyes of the group around them. A heuristic, it doesn't read whether a
+/// group could still live.
+fn likely_dead(board: &Board, groups: &[Group]) -> Vec<usize> {
+    let mut groups = groups.to_vec();
+    let mut order = (0..groups.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&idx| groups[idx].points.len());
+
+    let mut dead = Vec::new();
+    for idx in order {
+        let (team, points) = (groups[idx].team, groups[idx].points.clone());
+        let (owners, _) = score_board(board, &groups);
+        let has_eye = points.iter().any(|&p| {
+            board
+                .surrounding_points(p)
+                .any(|q| board.get_point(q).is_empty() && owners.get_point(q) == team)
+        });
+        if has_eye {
+            continue;
+        }
+
+        groups[idx].alive = false;
+        let (owners, _) = score_board(board, &groups);
+        let taker = owners.get_point(points[0]);
+        let surrounded = !taker.is_empty()
+            && taker != team
+            && points.iter().all(|&p| owners.get_point(p) == taker);
+        if surrounded {
+            dead.push(idx);
+        } else {
+            groups[idx].alive = true;
+        }
+    }
+    dead
+}
+
+/// Empty points further than this from every stone have no influence.
+pub const INFLUENCE_RANGE: u8 = 4;
+
+/// Rough influence on each point as `(team, strength)`: the team with the nearest stones,
+/// from `INFLUENCE_RANGE` next to a stone down to 1 at the edge of the range. Stones,
+/// points out of range and points as close to two teams are `(empty, 0)`. Distances are
+/// walked around stones, so the work is linear in the board size. A visual aid for
+/// beginners, not how scoring works.
+pub fn influence_map(board: &Board) -> Vec<(Color, u8)> {
+    let mut influence = vec![(Color::empty(), 0); board.points.len()];
+    let mut distance = vec![u8::MAX; board.points.len()];
+    let idx = |(x, y): Point| (y * board.width + x) as usize;
+
+    let mut frontier = Vec::new();
+    for y in 0..board.height {
+        for x in 0..board.width {
+            let color = board.get_point((x, y));
+            if !color.is_empty() {
+                distance[idx((x, y))] = 0;
+                influence[idx((x, y))] = (color, 0);
+                frontier.push((x, y));
+            }
+        }
+    }
+
+    for step in 1..=INFLUENCE_RANGE {
+        let strength = INFLUENCE_RANGE + 1 - step;
+        let mut next = Vec::new();
+        for &point in &frontier {
+            let team = influence[idx(point)].0;
+            // Contested points don't spread further.
+            if team.is_empty() {
+                continue;
+            }
+            for p in board.surrounding_points(point) {
+                let i = idx(p);
+                if distance[i] < step {
+                    continue;
+                }
+                if distance[i] == step {
+                    if influence[i].0 != team {
+                        influence[i] = (Color::empty(), 0);
+                    }
+                    continue;
+                }
+                distance[i] = step;
+                influence[i] = (team, strength);
+                next.push(p);
+            }
+        }
+        frontier = next;
+    }
+
+    // Stones were only marked to spread from.
+    for (i, &color) in board.points.iter().enumerate() {
+        if !color.is_empty() {
+            influence[i] = (Color::empty(), 0);
+        }
+    }
+    influence
+}
+
+/// `score_board` with the seki eyes left neutral if `seki` asks for it. The eyes are
+/// returned either way.
+fn count_board(board: &Board, groups: &[Group], seki: SekiScoring) -> (Board, Vec<Point>) {
+    let (mut owners, eyes) = score_board(board, groups);
+    if seki == SekiScoring::Neutral {
+        for &point in &eyes {
+            *owners.point_mut(point) = Color::empty();
+        }
+    }
+    (owners, eyes)
+}
+
+/// Scores a board by filling in fully surrounded empty spaces based on chinese rules.
+/// Also returns the points of `seki_eyes`, which are counted here.
+fn score_board(board: &Board, groups: &[Group]) -> (Board, Vec<Point>) {
     let &Board {
         width,
         height,
@@ -139,6 +367,11 @@ fn score_board(board: &Board, groups: &[Group]) -> Board {
         }
     }
 
+    // Without living stones the whole board is neutral and komi alone decides.
+    if groups.iter().all(|g| !g.alive) {
+        return (board, Vec::new());
+    }
+
     // Find empty points
     let mut legal_points = board
         .points
@@ -164,6 +397,7 @@ fn score_board(board: &Board, groups: &[Group]) -> Board {
     let mut seen = HashSet::new();
     let mut stack = VecDeque::new();
     let mut marked = Vec::new();
+    let mut territories = Vec::new();
 
     while let Some(point) = legal_points.pop() {
         stack.push_back(point);
@@ -196,14 +430,65 @@ fn score_board(board: &Board, groups: &[Group]) -> Board {
 
         // The floodfill touched only a single color -> this must be their territory
         if let One(color) = collisions {
-            for point in marked.drain(..) {
+            for &point in &marked {
                 *board.point_mut(point) = color;
             }
+            let mut territory = marked.clone();
+            territory.sort_unstable();
+            territory.dedup();
+            territories.push((color, territory));
         }
 
         seen.clear();
         marked.clear();
     }
 
-    board
+    let eyes = seki_eyes(&board, groups, &territories);
+    (board, eyes)
+}
+
+/// Small territories whose every bordering group has no other territory and shares a
+/// neutral point with a living group of another team. That's how a seki looks once the
+/// game is over, but it's only a heuristic: nothing is read out.
+fn seki_eyes(owners: &Board, groups: &[Group], territories: &[(Color, Vec<Point>)]) -> Vec<Point> {
+    let borders = |group: &Group, territory: &[Point]| {
+        territory.iter().any(|&p| {
+            owners
+                .surrounding_points(p)
+                .any(|q| group.points.contains(&q))
+        })
+    };
+
+    let in_seki = |group: &Group| {
+        let eyes = territories
+            .iter()
+            .filter(|(color, territory)| *color == group.team && borders(group, territory))
+            .count();
+        // Colored points next to a neutral one are always living stones.
+        let shares_liberty = group
+            .points
+            .iter()
+            .flat_map(|&p| owners.surrounding_points(p))
+            .filter(|&p| owners.get_point(p).is_empty())
+            .any(|p| {
+                owners.surrounding_points(p).any(|q| {
+                    let color = owners.get_point(q);
+                    !color.is_empty() && color != group.team
+                })
+            });
+        eyes <= 1 && shares_liberty
+    };
+
+    territories
+        .iter()
+        .filter(|(_, territory)| territory.len() <= MAX_SEKI_EYE)
+        .filter(|(color, territory)| {
+            let mut bordering = groups
+                .iter()
+                .filter(|g| g.alive && g.team == *color && borders(g, territory))
+                .peekable();
+            bordering.peek().is_some() && bordering.all(in_seki)
+        })
+        .flat_map(|(_, territory)| territory.iter().copied())
+        .collect()
 }
@@ -0,0 +1,73 @@
+use crate::game::{
+    ActionChange, ActionKind, BoardHistory, Color, GameState, MakeActionError, MakeActionResult,
+    SharedState,
+};
+use serde::{Deserialize, Serialize};
+
+/// Most stones a single `SetupStones` action can change. Enough for a full 25x25 board.
+pub const MAX_SETUP_STONES: usize = 625;
+
+/// Edit phase for authoring positions. Stones are placed and removed directly,
+/// without turns, captures or ko.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SetupState {}
+
+impl SetupState {
+    /// Applies `(x, y, team)` changes, team 0 clears the point. Either every change
+    /// is applied or none.
+    pub fn setup_stones(
+        &mut self,
+        shared: &mut SharedState,
+        stones: &[(u32, u32, u8)],
+    ) -> MakeActionResult {
+        if stones.len() > MAX_SETUP_STONES {
+            return Err(MakeActionError::Illegal);
+        }
+
+        let team_count = shared.komis.len() as u8;
+        for &(x, y, team) in stones {
+            if !shared.board.point_within((x, y)) {
+                return Err(MakeActionError::OutOfBounds);
+            }
+            if team > team_count {
+                return Err(MakeActionError::Illegal);
+            }
+        }
+
+        for &(x, y, team) in stones {
+            *shared.board.point_mut((x, y)) = Color(team);
+        }
+
+        Ok(ActionChange::None)
+    }
+
+    /// Any seat holder passing finishes the setup. The position becomes the start of the game.
+    fn make_action_pass(&mut self, shared: &mut SharedState) -> MakeActionResult {
+        let state = GameState::play(shared.seats.len());
+
+        shared.board_history = vec![BoardHistory {
+            hash: shared.board.hash(),
+            board: shared.board.clone(),
+            board_visibility: shared.board_visibility.clone(),
+            state: state.clone(),
+            points: shared.points.clone(),
+            turn: 0,
+            traitor: shared.traitor.clone(),
+        }];
+
+        Ok(ActionChange::SwapState(state))
+    }
+
+    pub fn make_action(
+        &mut self,
+        shared: &mut SharedState,
+        action: ActionKind,
+    ) -> MakeActionResult {
+        match action {
+            ActionKind::Pass => self.make_action_pass(shared),
+            ActionKind::Place(..) | ActionKind::Cancel | ActionKind::Resign | ActionKind::Swap => {
+                Err(MakeActionError::Illegal)
+            }
+        }
+    }
+}